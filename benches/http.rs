@@ -0,0 +1,79 @@
+//! HTTP connector performance benchmark
+//!
+//! Sends echo commands through a `yubihsm-connector` compatible HTTP server
+//! (`yubihsm::connector::http::Server`) fronting a `MockHsm` on localhost,
+//! comparing a persistent (keep-alive) connection against opening a new one
+//! for every command, and the effect of the read buffer size.
+//!
+//! The `MockHsm` refuses to build without debug assertions, which the
+//! `bench` profile disables, so run this with them turned back on:
+//!
+//! ```text
+//! RUSTFLAGS="-C debug-assertions" cargo bench --features http-server,mockhsm --bench http
+//! ```
+
+#![deny(warnings)]
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::{net::TcpListener, thread};
+use yubihsm::{connector::http::Server, Client, Connector, HttpConfig};
+
+/// Message sent in each echo command. This is kept small so responses fit
+/// in the server's 1 KiB write buffer: larger ones are sent in two writes,
+/// and the second is held back by Nagle's algorithm until the client's
+/// delayed ACK (~40ms), which would swamp the rest of the measurement.
+const ECHO_MESSAGE: &[u8] = &[0x42; 64];
+
+/// Start an HTTP server fronting a `MockHsm`, returning its configuration
+fn start_server() -> HttpConfig {
+    let port = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+
+    let config = HttpConfig {
+        port,
+        ..HttpConfig::default()
+    };
+
+    let server = Server::new(&config, Connector::mockhsm()).unwrap();
+    thread::spawn(move || server.run().unwrap());
+    config
+}
+
+fn echo(c: &mut Criterion) {
+    let server_config = start_server();
+    let mut group = c.benchmark_group("http echo");
+
+    let configs = [
+        ("keep-alive", server_config.clone()),
+        (
+            "new connection per command",
+            HttpConfig {
+                idle_timeout_ms: 0,
+                ..server_config.clone()
+            },
+        ),
+        (
+            "keep-alive, 256 byte buffer",
+            HttpConfig {
+                buffer_size: 256,
+                ..server_config
+            },
+        ),
+    ];
+
+    for (name, config) in configs.iter() {
+        let hsm = Client::open(Connector::http(config), Default::default(), true).unwrap();
+
+        group.bench_with_input(BenchmarkId::from_parameter(name), &hsm, |b, hsm| {
+            b.iter(|| hsm.echo(ECHO_MESSAGE).unwrap())
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(http, echo);
+criterion_main!(http);
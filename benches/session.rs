@@ -0,0 +1,78 @@
+//! Encrypted session command performance benchmark
+//!
+//! Commands are sent to the `MockHsm`, so this measures the host side of the
+//! session protocol without any I/O. Also reports the number of heap
+//! allocations made per command (serializing, encrypting, and MACing the
+//! command, sending it, and then decrypting the response), which includes
+//! those made by the `MockHsm` to process it.
+//!
+//! The `MockHsm` refuses to build without debug assertions, which the
+//! `bench` profile disables, so run this with them turned back on:
+//!
+//! ```text
+//! RUSTFLAGS="-C debug-assertions" cargo bench --features mockhsm --bench session
+//! ```
+
+#![deny(warnings)]
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Number of commands sent when counting allocations
+const ALLOCATION_SAMPLE_COMMANDS: usize = 1000;
+
+/// Message sent in each echo command
+const ECHO_MESSAGE: &[u8] = &[0x42; 1024];
+
+/// Global allocator which counts the allocations it makes
+struct CountingAllocator;
+
+/// Number of allocations made so far
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn echo(c: &mut Criterion) {
+    let connector = yubihsm::Connector::mockhsm();
+    let hsm = yubihsm::Client::open(connector, Default::default(), true).unwrap();
+
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+
+    for _ in 0..ALLOCATION_SAMPLE_COMMANDS {
+        hsm.echo(ECHO_MESSAGE).unwrap();
+    }
+
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+
+    eprintln!(
+        "session echo: {:.1} allocations per command",
+        allocations as f64 / ALLOCATION_SAMPLE_COMMANDS as f64
+    );
+
+    c.bench_function("session echo", move |b| {
+        b.iter(|| hsm.echo(ECHO_MESSAGE).unwrap())
+    });
+}
+
+criterion_group!(session, echo);
+criterion_main!(session);
@@ -8,7 +8,14 @@
 
 mod algorithm;
 pub(crate) mod commands;
+mod import_layout;
+mod point_format;
 mod public_key;
 
-pub use self::{algorithm::Algorithm, public_key::PublicKey};
+pub use self::{
+    algorithm::Algorithm,
+    import_layout::{ByteOrder, ImportField, ImportLayout},
+    point_format::PointFormat,
+    public_key::PublicKey,
+};
 pub use signature;
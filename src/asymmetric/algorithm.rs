@@ -90,6 +90,40 @@ impl Algorithm {
             Algorithm::EcBp512 => 64,
         }
     }
+
+    /// Size of the modulus (and so of ciphertexts and signatures) of RSA
+    /// keys of this algorithm. Returns `None` for non-RSA algorithms.
+    pub fn rsa_modulus_len(self) -> Option<usize> {
+        match self {
+            Algorithm::Rsa2048 | Algorithm::Rsa3072 | Algorithm::Rsa4096 => Some(self.key_len()),
+            _ => None,
+        }
+    }
+
+    /// Length of the uncompressed (i.e. `0x04 || x || y`) public points of
+    /// elliptic curve keys of this algorithm, as used by ECDH. Returns `None`
+    /// for non-ECDH algorithms.
+    pub fn ecdh_point_len(self) -> Option<usize> {
+        match self {
+            Algorithm::Rsa2048 | Algorithm::Rsa3072 | Algorithm::Rsa4096 | Algorithm::Ed25519 => {
+                None
+            }
+            alg => Some(1 + 2 * alg.key_len()),
+        }
+    }
+
+    /// Length of the digests signed by ECDSA keys of this algorithm: the
+    /// size of the curve's order, except for NIST P-521 where it's 64 bytes
+    /// (i.e. SHA-512). Returns `None` for non-ECDSA algorithms.
+    pub fn ecdsa_digest_len(self) -> Option<usize> {
+        match self {
+            Algorithm::Rsa2048 | Algorithm::Rsa3072 | Algorithm::Rsa4096 | Algorithm::Ed25519 => {
+                None
+            }
+            Algorithm::EcP521 => Some(64),
+            alg => Some(alg.key_len()),
+        }
+    }
 }
 
 impl_algorithm_serializers!(Algorithm);
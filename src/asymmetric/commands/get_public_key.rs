@@ -19,6 +19,14 @@ pub(crate) struct GetPublicKeyCommand {
 
 impl Command for GetPublicKeyCommand {
     type ResponseType = GetPublicKeyResponse;
+
+    fn object_id(&self) -> Option<object::Id> {
+        Some(self.key_id)
+    }
+
+    fn object_type(&self) -> Option<object::Type> {
+        Some(object::Type::AsymmetricKey)
+    }
 }
 
 /// Response from `command::get_public_key`
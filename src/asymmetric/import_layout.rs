@@ -0,0 +1,192 @@
+//! Expected layout of raw private keys imported with `put_asymmetric_key`
+
+use super::Algorithm;
+use std::fmt;
+
+/// How the bytes of an `ImportField` are interpreted
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ByteOrder {
+    /// Unsigned integer, most significant byte first, left-padded with zeroes
+    /// to the field's length
+    BigEndian,
+
+    /// Opaque byte string used as-is (e.g. an Ed25519 seed)
+    Octets,
+}
+
+impl fmt::Display for ByteOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ByteOrder::BigEndian => "big-endian unsigned integer",
+            ByteOrder::Octets => "byte string",
+        })
+    }
+}
+
+/// A single field of a raw private key
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ImportField {
+    /// Name of the field (e.g. `p`, `d`)
+    pub name: &'static str,
+
+    /// Length of the field in bytes
+    pub len: usize,
+
+    /// Interpretation of the field's bytes
+    pub byte_order: ByteOrder,
+}
+
+impl fmt::Display for ImportField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} bytes ({})", self.name, self.len, self.byte_order)
+    }
+}
+
+/// Layout of the raw private key bytes expected by `put_asymmetric_key` for
+/// a given algorithm: the concatenation of `fields`, in order
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ImportLayout {
+    /// Fields of the private key
+    pub fields: Vec<ImportField>,
+}
+
+impl ImportLayout {
+    /// Total length of the private key in bytes
+    pub fn len(&self) -> usize {
+        self.fields.iter().map(|field| field.len).sum()
+    }
+
+    /// Does this layout have no fields?
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+}
+
+impl fmt::Display for ImportLayout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, field) in self.fields.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" || ")?;
+            }
+
+            write!(f, "{}", field)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Algorithm {
+    /// Get the layout of raw private keys for this algorithm, as expected by
+    /// `Client::put_asymmetric_key`:
+    ///
+    /// - RSA: the primes `p || q`, each half the modulus size, big-endian
+    /// - ECC: the private scalar `d`, big-endian, the size of the curve order
+    /// - Ed25519: the 32-byte seed (RFC 8032 private key)
+    pub fn expected_import_layout(self) -> ImportLayout {
+        let field = |name, len, byte_order| ImportField {
+            name,
+            len,
+            byte_order,
+        };
+
+        let fields = match self {
+            Algorithm::Rsa2048 | Algorithm::Rsa3072 | Algorithm::Rsa4096 => {
+                let prime_len = self.key_len() / 2;
+
+                vec![
+                    field("p", prime_len, ByteOrder::BigEndian),
+                    field("q", prime_len, ByteOrder::BigEndian),
+                ]
+            }
+            Algorithm::Ed25519 => vec![field("seed", self.key_len(), ByteOrder::Octets)],
+            _ => vec![field("d", self.key_len(), ByteOrder::BigEndian)],
+        };
+
+        ImportLayout { fields }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALGORITHMS: &[Algorithm] = &[
+        Algorithm::Rsa2048,
+        Algorithm::Rsa3072,
+        Algorithm::Rsa4096,
+        Algorithm::Ed25519,
+        Algorithm::EcP224,
+        Algorithm::EcP256,
+        Algorithm::EcP384,
+        Algorithm::EcP521,
+        Algorithm::EcK256,
+        Algorithm::EcBp256,
+        Algorithm::EcBp384,
+        Algorithm::EcBp512,
+    ];
+
+    #[test]
+    fn layout_matches_key_len() {
+        for &algorithm in ALGORITHMS {
+            assert_eq!(
+                algorithm.expected_import_layout().len(),
+                algorithm.key_len(),
+                "{:?}",
+                algorithm
+            );
+        }
+    }
+
+    #[test]
+    fn rsa_layout() {
+        let layout = Algorithm::Rsa2048.expected_import_layout();
+        let field = |name| ImportField {
+            name,
+            len: 128,
+            byte_order: ByteOrder::BigEndian,
+        };
+
+        assert_eq!(layout.fields, [field("p"), field("q")]);
+        assert_eq!(
+            layout.to_string(),
+            "p: 128 bytes (big-endian unsigned integer) || \
+             q: 128 bytes (big-endian unsigned integer)"
+        );
+    }
+
+    #[test]
+    fn ecc_layouts() {
+        for &(algorithm, len) in &[
+            (Algorithm::EcP224, 28),
+            (Algorithm::EcP256, 32),
+            (Algorithm::EcP384, 48),
+            (Algorithm::EcP521, 66),
+            (Algorithm::EcK256, 32),
+            (Algorithm::EcBp256, 32),
+            (Algorithm::EcBp384, 48),
+            (Algorithm::EcBp512, 64),
+        ] {
+            assert_eq!(
+                algorithm.expected_import_layout().fields,
+                [ImportField {
+                    name: "d",
+                    len,
+                    byte_order: ByteOrder::BigEndian,
+                }]
+            );
+        }
+    }
+
+    #[test]
+    fn ed25519_layout() {
+        assert_eq!(
+            Algorithm::Ed25519.expected_import_layout().fields,
+            [ImportField {
+                name: "seed",
+                len: 32,
+                byte_order: ByteOrder::Octets,
+            }]
+        );
+    }
+}
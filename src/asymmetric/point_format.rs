@@ -0,0 +1,197 @@
+//! Encodings for public keys (i.e. elliptic curve points)
+
+use super::{Algorithm, PublicKey};
+
+/// `id-ecPublicKey` (1.2.840.10045.2.1) OID
+const EC_PUBLIC_KEY_OID: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01];
+
+/// `id-Ed25519` (1.3.101.112) OID
+const ED25519_OID: &[u8] = &[0x2b, 0x65, 0x70];
+
+/// Formats in which a public key can be encoded
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PointFormat {
+    /// SEC1 uncompressed point: `0x04 || X || Y`
+    Uncompressed,
+
+    /// SEC1 compressed point: `0x02 | (Y & 1) || X` (for Ed25519, the
+    /// standard 32-byte encoding, which is always compressed)
+    Compressed,
+
+    /// DER-encoded X.509 `SubjectPublicKeyInfo`
+    SpkiDer,
+}
+
+// `#[default]` on enum variants requires a newer Rust than our MSRV
+#[allow(clippy::derivable_impls)]
+impl Default for PointFormat {
+    fn default() -> Self {
+        PointFormat::Uncompressed
+    }
+}
+
+impl PointFormat {
+    /// Can public keys of the given algorithm be encoded in this format?
+    pub fn is_supported(self, algorithm: Algorithm) -> bool {
+        match algorithm {
+            Algorithm::Ed25519 => self != PointFormat::Uncompressed,
+            alg => curve_oid(alg).is_some(),
+        }
+    }
+
+    /// Encode the given public key in this format, if it's supported for the
+    /// key's algorithm
+    pub fn encode(self, public_key: &PublicKey) -> Option<Vec<u8>> {
+        if !self.is_supported(public_key.algorithm) {
+            return None;
+        }
+
+        let bytes = public_key.as_slice();
+
+        if public_key.algorithm == Algorithm::Ed25519 {
+            return match self {
+                PointFormat::Compressed => Some(bytes.to_vec()),
+                _ => Some(spki(&der(0x30, &der(0x06, ED25519_OID)), bytes)),
+            };
+        }
+
+        // ECC public keys are the untagged concatenation of X and Y
+        if bytes.len() != public_key.algorithm.key_len() * 2 {
+            return None;
+        }
+
+        let (x, y) = bytes.split_at(bytes.len() / 2);
+
+        let mut uncompressed = Vec::with_capacity(bytes.len() + 1);
+        uncompressed.push(0x04);
+        uncompressed.extend_from_slice(bytes);
+
+        match self {
+            PointFormat::Uncompressed => Some(uncompressed),
+            PointFormat::Compressed => {
+                let mut compressed = Vec::with_capacity(x.len() + 1);
+                compressed.push(0x02 | (y[y.len() - 1] & 1));
+                compressed.extend_from_slice(x);
+                Some(compressed)
+            }
+            PointFormat::SpkiDer => {
+                let mut params = der(0x06, EC_PUBLIC_KEY_OID);
+                params.extend(der(0x06, curve_oid(public_key.algorithm)?));
+                Some(spki(&der(0x30, &params), &uncompressed))
+            }
+        }
+    }
+}
+
+impl PublicKey {
+    /// Encode this public key in the given format, if it's supported for
+    /// the key's algorithm
+    pub fn encode(&self, format: PointFormat) -> Option<Vec<u8>> {
+        format.encode(self)
+    }
+}
+
+/// OID of the named curve for the given ECC algorithm
+fn curve_oid(algorithm: Algorithm) -> Option<&'static [u8]> {
+    let oid: &[u8] = match algorithm {
+        Algorithm::EcP224 => &[0x2b, 0x81, 0x04, 0x00, 0x21],
+        Algorithm::EcP256 => &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07],
+        Algorithm::EcP384 => &[0x2b, 0x81, 0x04, 0x00, 0x22],
+        Algorithm::EcP521 => &[0x2b, 0x81, 0x04, 0x00, 0x23],
+        Algorithm::EcK256 => &[0x2b, 0x81, 0x04, 0x00, 0x0a],
+        Algorithm::EcBp256 => &[0x2b, 0x24, 0x03, 0x03, 0x02, 0x08, 0x01, 0x01, 0x07],
+        Algorithm::EcBp384 => &[0x2b, 0x24, 0x03, 0x03, 0x02, 0x08, 0x01, 0x01, 0x0b],
+        Algorithm::EcBp512 => &[0x2b, 0x24, 0x03, 0x03, 0x02, 0x08, 0x01, 0x01, 0x0d],
+        _ => return None,
+    };
+
+    Some(oid)
+}
+
+/// Encode a `SubjectPublicKeyInfo` from a DER-encoded `AlgorithmIdentifier`
+/// and the raw public key
+fn spki(algorithm_identifier: &[u8], public_key: &[u8]) -> Vec<u8> {
+    // BIT STRING prefixed with the number of unused bits (none)
+    let mut bit_string = Vec::with_capacity(public_key.len() + 1);
+    bit_string.push(0);
+    bit_string.extend_from_slice(public_key);
+
+    let mut body = algorithm_identifier.to_vec();
+    body.extend(der(0x03, &bit_string));
+    der(0x30, &body)
+}
+
+/// Encode a DER tag-length-value
+fn der(tag: u8, value: &[u8]) -> Vec<u8> {
+    let mut encoded = Vec::with_capacity(value.len() + 4);
+    encoded.push(tag);
+
+    match value.len() {
+        len if len < 0x80 => encoded.push(len as u8),
+        len if len <= 0xff => encoded.extend_from_slice(&[0x81, len as u8]),
+        len => {
+            debug_assert!(len <= 0xffff);
+            encoded.extend_from_slice(&[0x82, (len >> 8) as u8, len as u8]);
+        }
+    }
+
+    encoded.extend_from_slice(value);
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn p256_key() -> PublicKey {
+        PublicKey {
+            algorithm: Algorithm::EcP256,
+            bytes: (1..=64).collect(),
+        }
+    }
+
+    #[test]
+    fn ecdsa_point_formats() {
+        let key = p256_key();
+
+        let uncompressed = key.encode(PointFormat::Uncompressed).unwrap();
+        assert_eq!(uncompressed[0], 0x04);
+        assert_eq!(&uncompressed[1..], key.as_slice());
+
+        // Y ends in 64 (even)
+        let compressed = key.encode(PointFormat::Compressed).unwrap();
+        assert_eq!(compressed[0], 0x02);
+        assert_eq!(&compressed[1..], &key.as_slice()[..32]);
+
+        let spki = key.encode(PointFormat::SpkiDer).unwrap();
+        let prefix: &[u8] = &[
+            0x30, 0x59, 0x30, 0x13, 0x06, 0x07, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01, 0x06,
+            0x08, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07, 0x03, 0x42, 0x00,
+        ];
+        assert_eq!(&spki[..prefix.len()], prefix);
+        assert_eq!(&spki[prefix.len()..], uncompressed.as_slice());
+    }
+
+    #[test]
+    fn ed25519_point_formats() {
+        let key = PublicKey {
+            algorithm: Algorithm::Ed25519,
+            bytes: vec![0x42; 32],
+        };
+
+        assert_eq!(key.encode(PointFormat::Uncompressed), None);
+        assert_eq!(key.encode(PointFormat::Compressed).unwrap(), key.bytes);
+
+        let spki = key.encode(PointFormat::SpkiDer).unwrap();
+        let prefix: &[u8] = &[
+            0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00,
+        ];
+        assert_eq!(&spki[..prefix.len()], prefix);
+        assert_eq!(&spki[prefix.len()..], key.as_slice());
+    }
+
+    #[test]
+    fn rsa_is_unsupported() {
+        assert!(!PointFormat::SpkiDer.is_supported(Algorithm::Rsa2048));
+    }
+}
@@ -5,3 +5,9 @@ mod certificate;
 pub(crate) mod commands;
 
 pub use self::certificate::Certificate;
+
+use crate::{client, object};
+
+/// ID of a key, and either its attestation certificate or the error which
+/// occurred attesting it (see `Client::attest_all`)
+pub type KeyAttestation = (object::Id, Result<Certificate, client::Error>);
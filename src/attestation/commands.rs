@@ -22,6 +22,14 @@ pub(crate) struct SignAttestationCertificateCommand {
 
 impl Command for SignAttestationCertificateCommand {
     type ResponseType = Certificate;
+
+    fn object_id(&self) -> Option<object::Id> {
+        Some(self.key_id)
+    }
+
+    fn object_type(&self) -> Option<object::Type> {
+        Some(object::Type::AsymmetricKey)
+    }
 }
 
 /// DER encoded X.509 attestation certificate
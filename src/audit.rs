@@ -1,9 +1,19 @@
 //! Auditing options (for use with the `get_option` and `put_option` command)
 
+#[cfg(feature = "sha2")]
+mod archive;
 pub(crate) mod commands;
 mod error;
+mod state;
 
-pub use self::error::{Error, ErrorKind};
+pub use self::{
+    commands::{LogDigest, LogEntries, LogEntry, LOG_DIGEST_SIZE},
+    error::{Error, ErrorKind},
+    state::AuditState,
+};
+
+#[cfg(feature = "sha2")]
+pub use self::archive::{LogArchive, LogChainHead, LOG_ENTRY_SIZE};
 
 use crate::command;
 use anomaly::fail;
@@ -98,6 +108,7 @@ impl<'de> Deserialize<'de> for AuditOption {
 pub(crate) enum AuditTag {
     Force = 0x01,
     Command = 0x03,
+    Algorithm = 0x04,
 }
 
 impl AuditTag {
@@ -106,6 +117,7 @@ impl AuditTag {
         Ok(match byte {
             0x01 => AuditTag::Force,
             0x03 => AuditTag::Command,
+            0x04 => AuditTag::Algorithm,
             _ => fail!(ErrorKind::TagInvalid, "invalid audit tag value: {}", byte),
         })
     }
@@ -130,7 +142,7 @@ impl<'de> Deserialize<'de> for AuditTag {
             type Value = AuditTag;
 
             fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
-                formatter.write_str("an unsigned byte with values 0x01 or 0x03")
+                formatter.write_str("an unsigned byte with values 0x01, 0x03, or 0x04")
             }
 
             fn visit_u8<E: de::Error>(self, value: u8) -> Result<AuditTag, E> {
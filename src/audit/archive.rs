@@ -0,0 +1,276 @@
+//! Archiving audit log entries drained from the HSM, in a form which can be
+//! independently verified later.
+//!
+//! Each log entry carries a digest computed over the entry and the digest of
+//! the entry before it, forming a hash chain. An archive stores entries in
+//! the HSM's own 32-byte wire format, so the digests are preserved exactly.
+//!
+//! Logs are typically drained in segments (`Client::get_log_entries`
+//! followed by `Client::set_log_index`). To check that consecutive segments
+//! form a continuous chain, keep the `LogChainHead` returned by verifying
+//! one segment and pass it when verifying the next. A segment may begin
+//! with entries already archived in the previous one (e.g. if the log index
+//! wasn't advanced before draining again); `LogArchive::append` skips these
+//! after checking they match.
+//!
+//! If the HSM's log buffer fills up without audit enforcement enabled, old
+//! entries are overwritten before they can be drained. Verification reports
+//! this as a gap in the chain, since the lost entries can't be recovered.
+
+use super::{Error, ErrorKind, LogDigest, LogEntries, LogEntry, LOG_DIGEST_SIZE};
+use crate::serialization::{deserialize, serialize};
+use anomaly::{ensure, format_err};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Size of a serialized log entry
+pub const LOG_ENTRY_SIZE: usize = 16 + LOG_DIGEST_SIZE;
+
+/// Most recent entry in a verified log chain
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct LogChainHead {
+    /// Entry number
+    pub item: u16,
+
+    /// Digest of the entry
+    pub digest: LogDigest,
+}
+
+impl From<&LogEntry> for LogChainHead {
+    fn from(entry: &LogEntry) -> Self {
+        Self {
+            item: entry.item,
+            digest: entry.digest.clone(),
+        }
+    }
+}
+
+/// Audit log entries accumulated across one or more drains of the HSM's
+/// log buffer
+#[derive(Clone, Debug, Default)]
+pub struct LogArchive {
+    /// Archived entries, in order
+    pub entries: Vec<LogEntry>,
+}
+
+impl LogArchive {
+    /// Create a new, empty archive
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append entries drained from the HSM, skipping any at the start which
+    /// have already been archived. Returns an error if an overlapping entry
+    /// doesn't match the archived one.
+    pub fn append(&mut self, log: LogEntries) -> Result<(), Error> {
+        for entry in log.entries {
+            let last = match self.entries.last() {
+                Some(last) => last,
+                None => {
+                    self.entries.push(entry);
+                    continue;
+                }
+            };
+
+            if is_after(entry.item, last.item) {
+                self.entries.push(entry);
+                continue;
+            }
+
+            if let Some(archived) = self.entries.iter().rev().find(|e| e.item == entry.item) {
+                ensure!(
+                    archived.digest == entry.digest,
+                    ErrorKind::ChainInvalid,
+                    "entry {} doesn't match archived entry",
+                    entry.item
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Verify the archived entries form a continuous chain, returning the
+    /// new head of the chain.
+    ///
+    /// If `previous` is given, the first entry must directly follow it.
+    /// Otherwise the first entry is trusted as the start of the chain.
+    pub fn verify(&self, previous: Option<&LogChainHead>) -> Result<LogChainHead, Error> {
+        let mut head = previous.cloned();
+
+        for entry in &self.entries {
+            if let Some(head) = &head {
+                ensure!(
+                    entry.item == head.item.wrapping_add(1),
+                    ErrorKind::ChainInvalid,
+                    "gap in audit log: expected entry {}, got {}",
+                    head.item.wrapping_add(1),
+                    entry.item
+                );
+
+                ensure!(
+                    entry.compute_digest(&head.digest) == entry.digest,
+                    ErrorKind::ChainInvalid,
+                    "digest mismatch for entry {}",
+                    entry.item
+                );
+            }
+
+            head = Some(entry.into());
+        }
+
+        head.ok_or_else(|| format_err!(ErrorKind::ChainInvalid, "empty audit log").into())
+    }
+
+    /// Serialize the archive (entries in the HSM's wire format)
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.entries.len() * LOG_ENTRY_SIZE);
+
+        for entry in &self.entries {
+            bytes.extend_from_slice(&serialize(entry).expect("error serializing log entry"));
+        }
+
+        bytes
+    }
+
+    /// Parse an archive serialized with `LogArchive::to_bytes`
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let entries = bytes
+            .chunks(LOG_ENTRY_SIZE)
+            .map(|chunk| {
+                ensure!(
+                    chunk.len() == LOG_ENTRY_SIZE,
+                    ErrorKind::ArchiveInvalid,
+                    "archive length {} is not a multiple of {}",
+                    bytes.len(),
+                    LOG_ENTRY_SIZE
+                );
+
+                deserialize(chunk).map_err(|e| ErrorKind::ArchiveInvalid.context(e).into())
+            })
+            .collect::<Result<_, Error>>()?;
+
+        Ok(Self { entries })
+    }
+}
+
+impl LogEntry {
+    /// Compute the digest of this entry given the digest of the entry before
+    /// it, i.e. the first 16 bytes of the SHA-256 digest of this entry's
+    /// fields followed by the previous digest
+    pub fn compute_digest(&self, previous: &LogDigest) -> LogDigest {
+        let mut hasher = Sha256::new();
+        hasher.update(self.item.to_be_bytes());
+        hasher.update([self.cmd.to_u8()]);
+        hasher.update(self.length.to_be_bytes());
+        hasher.update(self.session_key.to_be_bytes());
+        hasher.update(self.target_key.to_be_bytes());
+        hasher.update(self.second_key.to_be_bytes());
+        hasher.update([self.result.to_u8()]);
+        hasher.update(self.tick.to_be_bytes());
+        hasher.update(previous.as_ref());
+
+        let mut digest = [0u8; LOG_DIGEST_SIZE];
+        digest.copy_from_slice(&hasher.finalize()[..LOG_DIGEST_SIZE]);
+        LogDigest(digest)
+    }
+}
+
+/// Does entry number `a` come after `b`, allowing for wraparound?
+fn is_after(a: u16, b: u16) -> bool {
+    let distance = a.wrapping_sub(b);
+    distance != 0 && distance < 0x8000
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{command, response};
+
+    /// Create a chain of `count` entries following `previous`
+    fn chain(previous: &LogChainHead, count: u16) -> Vec<LogEntry> {
+        let mut digest = previous.digest.clone();
+
+        (1..=count)
+            .map(|i| {
+                let mut entry = LogEntry {
+                    item: previous.item.wrapping_add(i),
+                    cmd: command::Code::SignEddsa,
+                    length: 4,
+                    session_key: 1,
+                    target_key: 100,
+                    second_key: 0xffff,
+                    result: response::Code::Success(command::Code::SignEddsa),
+                    tick: u32::from(i),
+                    digest: LogDigest([0; LOG_DIGEST_SIZE]),
+                };
+
+                entry.digest = entry.compute_digest(&digest);
+                digest = entry.digest.clone();
+                entry
+            })
+            .collect()
+    }
+
+    fn log_entries(entries: Vec<LogEntry>) -> LogEntries {
+        LogEntries {
+            unlogged_boot_events: 0,
+            unlogged_auth_events: 0,
+            num_entries: entries.len() as u8,
+            entries,
+        }
+    }
+
+    fn genesis() -> LogChainHead {
+        LogChainHead {
+            item: 0xfffe,
+            digest: LogDigest([0x42; LOG_DIGEST_SIZE]),
+        }
+    }
+
+    #[test]
+    fn verifies_across_drains() {
+        let entries = chain(&genesis(), 6);
+
+        // Second drain overlaps the first, and item numbers wrap around
+        let mut archive = LogArchive::new();
+        archive.append(log_entries(entries[..4].to_vec())).unwrap();
+        archive.append(log_entries(entries[2..].to_vec())).unwrap();
+        assert_eq!(archive.entries.len(), 6);
+
+        let head = archive.verify(Some(&genesis())).unwrap();
+        assert_eq!(head, LogChainHead::from(&entries[5]));
+
+        let bytes = archive.to_bytes();
+        assert_eq!(bytes.len(), 6 * LOG_ENTRY_SIZE);
+        assert!(LogArchive::from_bytes(&bytes[1..]).is_err());
+
+        let restored = LogArchive::from_bytes(&bytes).unwrap();
+        assert_eq!(restored.verify(Some(&genesis())).unwrap(), head);
+
+        // Next segment continues from the previous head
+        let mut next = LogArchive::new();
+        next.append(log_entries(chain(&head, 2))).unwrap();
+        assert!(next.verify(Some(&head)).is_ok());
+    }
+
+    #[test]
+    fn detects_tampering_and_gaps() {
+        let mut entries = chain(&genesis(), 4);
+
+        let gap = LogArchive {
+            entries: vec![entries[0].clone(), entries[2].clone()],
+        };
+        assert_eq!(
+            *gap.verify(None).unwrap_err().kind(),
+            ErrorKind::ChainInvalid
+        );
+
+        entries[1].target_key = 101;
+        let tampered = LogArchive { entries };
+        assert_eq!(
+            *tampered.verify(Some(&genesis())).unwrap_err().kind(),
+            ErrorKind::ChainInvalid
+        );
+    }
+}
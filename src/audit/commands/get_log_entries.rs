@@ -39,7 +39,7 @@ impl Response for LogEntries {
 }
 
 /// Entry in the log response
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct LogEntry {
     /// Entry number
     pub item: u16,
@@ -69,11 +69,18 @@ pub struct LogEntry {
     pub digest: LogDigest,
 }
 
+impl LogEntry {
+    /// Get the name of the command this entry records (e.g. "Sign EdDSA")
+    pub fn command_name(&self) -> &'static str {
+        self.cmd.name()
+    }
+}
+
 /// Size of a truncated digest in the log
 pub const LOG_DIGEST_SIZE: usize = 16;
 
 /// Truncated SHA-256 digest of a log entry and the previous log digest
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone, Eq, PartialEq)]
 pub struct LogDigest(pub [u8; LOG_DIGEST_SIZE]);
 
 impl AsRef<[u8]> for LogDigest {
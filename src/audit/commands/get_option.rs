@@ -5,6 +5,7 @@
 //! - [get_command_audit_option()]: get audit setting for a particular command
 //! - [get_commands_audit_options()]: get audit settings for all command
 //! - [get_force_audit_option()]: get option for forced auditing (ensure events are logged)
+//! - [get_algorithm_toggle()]: get the algorithms which are enabled
 //!
 //! For more information, see:
 //! <https://developers.yubico.com/YubiHSM2/Commands/Get_Option.html>
@@ -13,6 +14,7 @@
 //! [get_command_audit_option()]: https://docs.rs/yubihsm/latest/yubihsm/client/struct.Client.html#method.get_command_audit_option
 //! [get_commands_audit_options()]: https://docs.rs/yubihsm/latest/yubihsm/client/struct.Client.html#method.get_commands_audit_options
 //! [get_force_audit_option()]: https://docs.rs/yubihsm/latest/yubihsm/client/struct.Client.html#method.get_force_audit_option
+//! [get_algorithm_toggle()]: https://docs.rs/yubihsm/latest/yubihsm/client/struct.Client.html#method.get_algorithm_toggle
 
 use crate::{
     audit::AuditTag,
@@ -24,7 +26,7 @@ use serde::{Deserialize, Serialize};
 /// Request parameters for `command::get_option`
 #[derive(Serialize, Deserialize, Debug)]
 pub(crate) struct GetOptionCommand {
-    /// Tag byte for `Force` vs `Command` options
+    /// Tag byte for `Force`, `Command`, or `Algorithm` options
     pub tag: AuditTag,
 }
 
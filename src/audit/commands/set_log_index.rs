@@ -27,4 +27,5 @@ pub(crate) struct SetLogIndexResponse {}
 
 impl Response for SetLogIndexResponse {
     const COMMAND_CODE: command::Code = command::Code::SetLogIndex;
+    const MAX_SIZE: usize = 0;
 }
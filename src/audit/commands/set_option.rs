@@ -13,7 +13,7 @@ use serde::{Deserialize, Serialize};
 /// Request parameters for `command::put_option`
 #[derive(Serialize, Deserialize, Debug)]
 pub(crate) struct SetOptionCommand {
-    /// Tag byte for `Force` vs `Command` options
+    /// Tag byte for `Force`, `Command`, or `Algorithm` options
     pub tag: AuditTag,
 
     /// Length of the option-specific data
@@ -33,4 +33,5 @@ pub(crate) struct PutOptionResponse {}
 
 impl Response for PutOptionResponse {
     const COMMAND_CODE: command::Code = command::Code::SetOption;
+    const MAX_SIZE: usize = 0;
 }
@@ -9,6 +9,14 @@ pub type Error = crate::Error<ErrorKind>;
 /// Kinds of audit-related errors
 #[derive(Copy, Clone, Debug, Eq, Error, PartialEq)]
 pub enum ErrorKind {
+    /// Archived audit log couldn't be parsed
+    #[error("invalid audit log archive")]
+    ArchiveInvalid,
+
+    /// Audit log entries don't form a continuous hash chain
+    #[error("audit log chain invalid")]
+    ChainInvalid,
+
     /// Invalid option
     #[error("invalid option")]
     OptionInvalid,
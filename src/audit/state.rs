@@ -0,0 +1,80 @@
+//! Whether the HSM is actually writing an audit log
+
+use super::{commands::LogEntries, AuditOption};
+
+/// State of the HSM's audit log, as returned by `Client::get_audit_state`.
+///
+/// An empty log is ambiguous on its own: it may mean all entries have been
+/// consumed, or that nothing is being audited in the first place.
+/// `AuditState` distinguishes the two by cross-checking the HSM's force and
+/// command audit options.
+///
+/// Every variant carries the `LogEntries`, including their
+/// `unlogged_boot_events` and `unlogged_auth_events` counters.
+#[derive(Debug)]
+pub enum AuditState {
+    /// Forced auditing is off and no commands are audited, so the log isn't
+    /// being written (aside from any entries which were recorded before
+    /// auditing was disabled)
+    Disabled(LogEntries),
+
+    /// Auditing is enabled, and there are no unconsumed log entries
+    EnabledEmpty {
+        /// Force audit option (i.e. whether the HSM refuses commands
+        /// once the log is full)
+        force: AuditOption,
+
+        /// Log entries (with no `entries`)
+        log_entries: LogEntries,
+    },
+
+    /// Auditing is enabled, and the log contains entries
+    EnabledWithEntries {
+        /// Force audit option (i.e. whether the HSM refuses commands
+        /// once the log is full)
+        force: AuditOption,
+
+        /// Log entries
+        log_entries: LogEntries,
+    },
+}
+
+impl AuditState {
+    /// Determine the audit state from the force audit option, whether any
+    /// commands are audited, and the current log entries
+    pub(crate) fn new(force: AuditOption, commands_audited: bool, log_entries: LogEntries) -> Self {
+        if force == AuditOption::Off && !commands_audited {
+            AuditState::Disabled(log_entries)
+        } else if log_entries.entries.is_empty() {
+            AuditState::EnabledEmpty { force, log_entries }
+        } else {
+            AuditState::EnabledWithEntries { force, log_entries }
+        }
+    }
+
+    /// Is forced auditing or auditing for at least one command enabled?
+    pub fn is_enabled(&self) -> bool {
+        match self {
+            AuditState::Disabled(_) => false,
+            AuditState::EnabledEmpty { .. } | AuditState::EnabledWithEntries { .. } => true,
+        }
+    }
+
+    /// Get the force audit option
+    pub fn force(&self) -> AuditOption {
+        match self {
+            AuditState::Disabled(_) => AuditOption::Off,
+            AuditState::EnabledEmpty { force, .. }
+            | AuditState::EnabledWithEntries { force, .. } => *force,
+        }
+    }
+
+    /// Get the log entries
+    pub fn log_entries(&self) -> &LogEntries {
+        match self {
+            AuditState::Disabled(log_entries)
+            | AuditState::EnabledEmpty { log_entries, .. }
+            | AuditState::EnabledWithEntries { log_entries, .. } => log_entries,
+        }
+    }
+}
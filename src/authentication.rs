@@ -6,6 +6,9 @@ pub mod commands;
 mod credentials;
 mod error;
 pub mod key;
+#[cfg(feature = "keystore")]
+#[cfg_attr(docsrs, doc(cfg(feature = "keystore")))]
+pub mod keystore;
 
 pub use self::{
     algorithm::Algorithm,
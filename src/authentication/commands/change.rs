@@ -0,0 +1,47 @@
+//! Change the authentication key used to establish the current session
+//!
+//! <https://developers.yubico.com/YubiHSM2/Commands/Change_Authentication_Key.html>
+
+use crate::{
+    authentication,
+    command::{self, Command},
+    object,
+    response::Response,
+};
+use serde::{Deserialize, Serialize};
+
+/// Request parameters for `command::change_authentication_key`
+#[derive(Serialize, Deserialize, Debug)]
+pub(crate) struct ChangeAuthenticationKeyCommand {
+    /// ID of the session's authentication key
+    pub key_id: object::Id,
+
+    /// Authentication algorithm of the new key
+    pub algorithm: authentication::Algorithm,
+
+    /// New authentication key
+    pub authentication_key: authentication::Key,
+}
+
+impl Command for ChangeAuthenticationKeyCommand {
+    type ResponseType = ChangeAuthenticationKeyResponse;
+
+    fn object_id(&self) -> Option<object::Id> {
+        Some(self.key_id)
+    }
+
+    fn object_type(&self) -> Option<object::Type> {
+        Some(object::Type::AuthenticationKey)
+    }
+}
+
+/// Response from `command::change_authentication_key`
+#[derive(Serialize, Deserialize, Debug)]
+pub(crate) struct ChangeAuthenticationKeyResponse {
+    /// ID of the key
+    pub key_id: object::Id,
+}
+
+impl Response for ChangeAuthenticationKeyResponse {
+    const COMMAND_CODE: command::Code = command::Code::ChangeAuthenticationKey;
+}
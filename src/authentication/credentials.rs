@@ -1,6 +1,8 @@
 //! Credentials used to authenticate to the HSM (key ID + `authentication::Key`).
 
 use crate::{authentication, object};
+#[cfg(feature = "keystore")]
+use std::path::Path;
 
 /// Default auth key ID slot
 pub const DEFAULT_AUTHENTICATION_KEY_ID: object::Id = 1;
@@ -36,6 +38,34 @@ impl Credentials {
             authentication::Key::derive_from_password(password),
         )
     }
+
+    /// Load credentials from a keystore file created with
+    /// `to_encrypted_file`, decrypting them with the given passphrase.
+    ///
+    /// See the `authentication::keystore` module for the file format.
+    #[cfg(feature = "keystore")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "keystore")))]
+    pub fn from_encrypted_file(
+        path: impl AsRef<Path>,
+        passphrase: &[u8],
+    ) -> Result<Self, authentication::Error> {
+        authentication::keystore::read_file(path.as_ref(), passphrase)
+    }
+
+    /// Save these credentials to a keystore file, encrypted under a key
+    /// derived from the given passphrase. On Unix, newly created files are
+    /// only readable by their owner.
+    ///
+    /// See the `authentication::keystore` module for the file format.
+    #[cfg(feature = "keystore")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "keystore")))]
+    pub fn to_encrypted_file(
+        &self,
+        path: impl AsRef<Path>,
+        passphrase: &[u8],
+    ) -> Result<(), authentication::Error> {
+        authentication::keystore::write_file(self, path.as_ref(), passphrase)
+    }
 }
 
 #[cfg(feature = "passwords")]
@@ -9,9 +9,21 @@ pub type Error = crate::Error<ErrorKind>;
 /// Kinds of authentication errors
 #[derive(Copy, Clone, Debug, Eq, Error, PartialEq)]
 pub enum ErrorKind {
+    /// Error reading or writing a keystore file
+    #[error("I/O error")]
+    IoError,
+
     /// Key size is invalid
     #[error("invalid key size")]
     KeySizeInvalid,
+
+    /// Keystore couldn't be decrypted (e.g. the passphrase is wrong)
+    #[error("couldn't decrypt keystore")]
+    KeystoreDecryptFailed,
+
+    /// Keystore is malformed or uses an unsupported format
+    #[error("invalid keystore")]
+    KeystoreInvalid,
 }
 
 impl ErrorKind {
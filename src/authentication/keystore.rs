@@ -0,0 +1,427 @@
+//! Passphrase-encrypted keystore files for `Credentials`.
+//!
+//! Keystores let services persist their HSM credentials between restarts
+//! without storing the raw authentication key (or the password it was
+//! derived from) on disk.
+//!
+//! ## Format
+//!
+//! Keystores are binary files with the following layout (version 1). All
+//! integers are big endian. `k` is the size of the KDF parameters, which
+//! depends on the KDF (see below).
+//!
+//! | Offset  | Size | Field                                                 |
+//! |---------|------|-------------------------------------------------------|
+//! | 0       | 8    | Magic: `yhsmkey` followed by a zero byte              |
+//! | 8       | 1    | Format version: `1`                                   |
+//! | 9       | 1    | KDF identifier                                        |
+//! | 10      | k    | KDF parameters                                        |
+//! | 10 + k  | 16   | KDF salt                                              |
+//! | 26 + k  | 13   | AES-CCM nonce                                         |
+//! | 39 + k  | 2    | Authentication key ID                                 |
+//! | 41 + k  | 48   | Authentication key (32 bytes) and tag (16 bytes)      |
+//!
+//! The following KDFs are supported:
+//!
+//! | ID | KDF                | k | Parameters                                 |
+//! |----|--------------------|---|--------------------------------------------|
+//! | 1  | PBKDF2-HMAC-SHA256 | 4 | Iterations (4 bytes)                       |
+//! | 2  | scrypt             | 9 | log2(N) (1 byte), r (4 bytes), p (4 bytes) |
+//!
+//! New keystores are created with scrypt (N = 2^15, r = 8, p = 1). Keystores
+//! using PBKDF2 can still be read.
+//!
+//! The authentication key is encrypted with AES-256-CCM (16-byte tag) under
+//! a key derived from the passphrase with the given KDF, parameters and
+//! salt. All preceding bytes are authenticated as associated data, so
+//! tampering with any field (e.g. the key ID or KDF parameters) is detected.
+//!
+//! Readers must reject files with an unknown magic, version, or KDF, so the
+//! format can be extended with new KDFs or versions later.
+
+use super::{Credentials, Error, ErrorKind, Key};
+use aes::{
+    cipher::consts::{U13, U16},
+    Aes256,
+};
+use anomaly::{ensure, fail, format_err};
+use ccm::aead::{generic_array::GenericArray, AeadInPlace, NewAead};
+use hmac::Hmac;
+use pbkdf2::pbkdf2;
+use rand_core::{OsRng, RngCore};
+use scrypt::{scrypt, ScryptParams};
+use sha2::Sha256;
+use std::{convert::TryInto, fs, io::Write, path::Path};
+use zeroize::Zeroizing;
+
+/// Magic bytes at the start of every keystore
+pub const MAGIC: &[u8; 8] = b"yhsmkey\0";
+
+/// Current keystore format version
+pub const VERSION: u8 = 1;
+
+/// Identifier for PBKDF2-HMAC-SHA256
+pub const KDF_PBKDF2_SHA256: u8 = 1;
+
+/// Identifier for scrypt
+pub const KDF_SCRYPT: u8 = 2;
+
+/// scrypt cost parameter (log2 of N) used when creating keystores
+pub const SCRYPT_LOG_N: u8 = 15;
+
+/// scrypt block size used when creating keystores
+pub const SCRYPT_R: u32 = 8;
+
+/// scrypt parallelization parameter used when creating keystores
+pub const SCRYPT_P: u32 = 1;
+
+/// Size of the KDF salt
+pub const SALT_SIZE: usize = 16;
+
+/// Size of the AES-CCM nonce
+pub const NONCE_SIZE: usize = 13;
+
+/// Size of the AES-CCM tag
+pub const TAG_SIZE: usize = 16;
+
+/// Size of the magic, version and KDF identifier
+const PREFIX_SIZE: usize = 8 + 1 + 1;
+
+/// Size of the header fields following the KDF parameters
+const SUFFIX_SIZE: usize = SALT_SIZE + NONCE_SIZE + 2;
+
+/// AES-256-CCM with a 16-byte tag and 13-byte nonce
+type Aes256Ccm = ccm::Ccm<Aes256, U16, U13>;
+
+/// Key derivation functions (and their parameters) used by keystores
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Kdf {
+    /// PBKDF2-HMAC-SHA256
+    Pbkdf2Sha256 { iterations: u32 },
+
+    /// scrypt
+    Scrypt { log_n: u8, r: u32, p: u32 },
+}
+
+impl Kdf {
+    /// KDF used when creating new keystores
+    fn default_for_new_files() -> Self {
+        Kdf::Scrypt {
+            log_n: SCRYPT_LOG_N,
+            r: SCRYPT_R,
+            p: SCRYPT_P,
+        }
+    }
+
+    /// Identifier for this KDF in the keystore header
+    fn id(self) -> u8 {
+        match self {
+            Kdf::Pbkdf2Sha256 { .. } => KDF_PBKDF2_SHA256,
+            Kdf::Scrypt { .. } => KDF_SCRYPT,
+        }
+    }
+
+    /// Size of the serialized parameters for the KDF with the given ID
+    fn params_size(id: u8) -> Result<usize, Error> {
+        match id {
+            KDF_PBKDF2_SHA256 => Ok(4),
+            KDF_SCRYPT => Ok(9),
+            other => fail!(
+                ErrorKind::KeystoreInvalid,
+                "unsupported keystore KDF: {}",
+                other
+            ),
+        }
+    }
+
+    /// Parse KDF parameters from the keystore header
+    fn parse(id: u8, params: &[u8]) -> Result<Self, Error> {
+        match id {
+            KDF_PBKDF2_SHA256 => Ok(Kdf::Pbkdf2Sha256 {
+                iterations: u32::from_be_bytes(params.try_into().unwrap()),
+            }),
+            KDF_SCRYPT => Ok(Kdf::Scrypt {
+                log_n: params[0],
+                r: u32::from_be_bytes(params[1..5].try_into().unwrap()),
+                p: u32::from_be_bytes(params[5..9].try_into().unwrap()),
+            }),
+            other => fail!(
+                ErrorKind::KeystoreInvalid,
+                "unsupported keystore KDF: {}",
+                other
+            ),
+        }
+    }
+
+    /// Serialize KDF parameters into the keystore header
+    fn write_params(self, keystore: &mut Vec<u8>) {
+        match self {
+            Kdf::Pbkdf2Sha256 { iterations } => {
+                keystore.extend_from_slice(&iterations.to_be_bytes());
+            }
+            Kdf::Scrypt { log_n, r, p } => {
+                keystore.push(log_n);
+                keystore.extend_from_slice(&r.to_be_bytes());
+                keystore.extend_from_slice(&p.to_be_bytes());
+            }
+        }
+    }
+
+    /// Derive the keystore cipher from the given passphrase
+    fn cipher(self, passphrase: &[u8], salt: &[u8]) -> Result<Aes256Ccm, Error> {
+        let mut key = Zeroizing::new([0u8; 32]);
+
+        match self {
+            Kdf::Pbkdf2Sha256 { iterations } => {
+                pbkdf2::<Hmac<Sha256>>(passphrase, salt, iterations, key.as_mut());
+            }
+            Kdf::Scrypt { log_n, r, p } => {
+                let params = ScryptParams::new(log_n, r, p).map_err(|_| {
+                    format_err!(
+                        ErrorKind::KeystoreInvalid,
+                        "invalid scrypt parameters: log_n={}, r={}, p={}",
+                        log_n,
+                        r,
+                        p
+                    )
+                })?;
+
+                scrypt(passphrase, salt, &params, key.as_mut())
+                    .expect("keystore key length is valid for scrypt");
+            }
+        }
+
+        Ok(Aes256Ccm::new(GenericArray::from_slice(key.as_ref())))
+    }
+}
+
+/// Write the given credentials to a keystore file
+pub(super) fn write_file(
+    credentials: &Credentials,
+    path: &Path,
+    passphrase: &[u8],
+) -> Result<(), Error> {
+    let keystore = encrypt(credentials, passphrase, Kdf::default_for_new_files())?;
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    options
+        .open(path)
+        .and_then(|mut file| file.write_all(&keystore))
+        .map_err(|e| {
+            format_err!(
+                ErrorKind::IoError,
+                "couldn't write keystore {}: {}",
+                path.display(),
+                e
+            )
+            .into()
+        })
+}
+
+/// Read credentials from a keystore file
+pub(super) fn read_file(path: &Path, passphrase: &[u8]) -> Result<Credentials, Error> {
+    let keystore = fs::read(path).map_err(|e| {
+        format_err!(
+            ErrorKind::IoError,
+            "couldn't read keystore {}: {}",
+            path.display(),
+            e
+        )
+    })?;
+
+    decrypt(&keystore, passphrase)
+}
+
+/// Encrypt the given credentials, returning a serialized keystore
+fn encrypt(credentials: &Credentials, passphrase: &[u8], kdf: Kdf) -> Result<Vec<u8>, Error> {
+    let mut salt = [0u8; SALT_SIZE];
+    let mut nonce = [0u8; NONCE_SIZE];
+    OsRng.fill_bytes(&mut salt);
+    OsRng.fill_bytes(&mut nonce);
+
+    let mut keystore = Vec::new();
+    keystore.extend_from_slice(MAGIC);
+    keystore.push(VERSION);
+    keystore.push(kdf.id());
+    kdf.write_params(&mut keystore);
+    keystore.extend_from_slice(&salt);
+    keystore.extend_from_slice(&nonce);
+    keystore.extend_from_slice(&credentials.authentication_key_id.to_be_bytes());
+
+    let mut buffer = Zeroizing::new(Vec::with_capacity(super::key::SIZE + TAG_SIZE));
+    buffer.extend_from_slice(credentials.authentication_key.as_secret_slice());
+
+    kdf.cipher(passphrase, &salt)?
+        .encrypt_in_place(GenericArray::from_slice(&nonce), &keystore, &mut *buffer)
+        .map_err(|_| format_err!(ErrorKind::KeystoreInvalid, "error encrypting keystore"))?;
+
+    keystore.extend_from_slice(&buffer);
+    Ok(keystore)
+}
+
+/// Decrypt credentials from a serialized keystore
+fn decrypt(keystore: &[u8], passphrase: &[u8]) -> Result<Credentials, Error> {
+    ensure!(
+        keystore.len() >= PREFIX_SIZE,
+        ErrorKind::KeystoreInvalid,
+        "keystore too short: {} bytes",
+        keystore.len()
+    );
+
+    ensure!(
+        keystore[..8] == MAGIC[..],
+        ErrorKind::KeystoreInvalid,
+        "not a keystore (bad magic)"
+    );
+
+    ensure!(
+        keystore[8] == VERSION,
+        ErrorKind::KeystoreInvalid,
+        "unsupported keystore version: {}",
+        keystore[8]
+    );
+
+    let kdf_id = keystore[9];
+    let header_size = PREFIX_SIZE + Kdf::params_size(kdf_id)? + SUFFIX_SIZE;
+    let expected_size = header_size + super::key::SIZE + TAG_SIZE;
+
+    ensure!(
+        keystore.len() == expected_size,
+        ErrorKind::KeystoreInvalid,
+        "expected {}-byte keystore, got {}",
+        expected_size,
+        keystore.len()
+    );
+
+    let (header, ciphertext) = keystore.split_at(header_size);
+    let kdf = Kdf::parse(kdf_id, &header[PREFIX_SIZE..header_size - SUFFIX_SIZE])?;
+
+    let suffix = &header[header_size - SUFFIX_SIZE..];
+    let salt = &suffix[..SALT_SIZE];
+    let nonce = &suffix[SALT_SIZE..SALT_SIZE + NONCE_SIZE];
+    let key_id = u16::from_be_bytes(suffix[SALT_SIZE + NONCE_SIZE..].try_into().unwrap());
+
+    let mut buffer = Zeroizing::new(ciphertext.to_vec());
+
+    kdf.cipher(passphrase, salt)?
+        .decrypt_in_place(GenericArray::from_slice(nonce), header, &mut *buffer)
+        .map_err(|_| {
+            format_err!(
+                ErrorKind::KeystoreDecryptFailed,
+                "couldn't decrypt keystore (wrong passphrase or corrupted file)"
+            )
+        })?;
+
+    Ok(Credentials::new(key_id, Key::from_slice(&buffer)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Keep tests fast: the KDF cost doesn't affect the format
+    const TEST_SCRYPT: Kdf = Kdf::Scrypt {
+        log_n: 4,
+        r: 1,
+        p: 1,
+    };
+
+    /// Legacy PBKDF2 keystores must remain readable
+    const TEST_PBKDF2: Kdf = Kdf::Pbkdf2Sha256 { iterations: 10 };
+
+    /// Size of a scrypt keystore
+    const SCRYPT_KEYSTORE_SIZE: usize =
+        PREFIX_SIZE + 9 + SUFFIX_SIZE + crate::authentication::key::SIZE + TAG_SIZE;
+
+    fn credentials() -> Credentials {
+        Credentials::new(0x1234, Key::new([0x42; crate::authentication::key::SIZE]))
+    }
+
+    fn assert_round_trip(kdf: Kdf) {
+        let keystore = encrypt(&credentials(), b"passphrase", kdf).unwrap();
+        assert_eq!(keystore[9], kdf.id());
+
+        let decrypted = decrypt(&keystore, b"passphrase").unwrap();
+        assert_eq!(decrypted.authentication_key_id, 0x1234);
+        assert_eq!(
+            decrypted.authentication_key.as_secret_slice(),
+            credentials().authentication_key.as_secret_slice()
+        );
+    }
+
+    #[test]
+    fn round_trip_scrypt() {
+        assert_round_trip(TEST_SCRYPT);
+    }
+
+    #[test]
+    fn round_trip_pbkdf2() {
+        assert_round_trip(TEST_PBKDF2);
+    }
+
+    #[test]
+    fn scrypt_parameters_are_stored_in_header() {
+        let keystore = encrypt(&credentials(), b"passphrase", TEST_SCRYPT).unwrap();
+        assert_eq!(keystore.len(), SCRYPT_KEYSTORE_SIZE);
+        assert_eq!(
+            Kdf::parse(KDF_SCRYPT, &keystore[PREFIX_SIZE..PREFIX_SIZE + 9]).unwrap(),
+            TEST_SCRYPT
+        );
+    }
+
+    #[test]
+    fn new_files_use_scrypt() {
+        assert_eq!(Kdf::default_for_new_files().id(), KDF_SCRYPT);
+    }
+
+    #[test]
+    fn wrong_passphrase_is_rejected() {
+        let keystore = encrypt(&credentials(), b"passphrase", TEST_SCRYPT).unwrap();
+        let err = decrypt(&keystore, b"wrong").unwrap_err();
+        assert_eq!(*err.kind(), ErrorKind::KeystoreDecryptFailed);
+    }
+
+    #[test]
+    fn tampered_key_id_is_rejected() {
+        let mut keystore = encrypt(&credentials(), b"passphrase", TEST_SCRYPT).unwrap();
+        keystore[PREFIX_SIZE + 9 + SUFFIX_SIZE - 1] ^= 1;
+
+        let err = decrypt(&keystore, b"passphrase").unwrap_err();
+        assert_eq!(*err.kind(), ErrorKind::KeystoreDecryptFailed);
+    }
+
+    #[test]
+    fn tampered_scrypt_parameters_are_rejected() {
+        let mut keystore = encrypt(&credentials(), b"passphrase", TEST_SCRYPT).unwrap();
+        keystore[PREFIX_SIZE + 4] ^= 2;
+
+        let err = decrypt(&keystore, b"passphrase").unwrap_err();
+        assert_eq!(*err.kind(), ErrorKind::KeystoreDecryptFailed);
+    }
+
+    #[test]
+    fn unknown_version_is_rejected() {
+        let mut keystore = encrypt(&credentials(), b"passphrase", TEST_SCRYPT).unwrap();
+        keystore[8] = VERSION + 1;
+
+        let err = decrypt(&keystore, b"passphrase").unwrap_err();
+        assert_eq!(*err.kind(), ErrorKind::KeystoreInvalid);
+    }
+
+    #[test]
+    fn unknown_kdf_is_rejected() {
+        let mut keystore = encrypt(&credentials(), b"passphrase", TEST_SCRYPT).unwrap();
+        keystore[9] = 0xff;
+
+        let err = decrypt(&keystore, b"passphrase").unwrap_err();
+        assert_eq!(*err.kind(), ErrorKind::KeystoreInvalid);
+    }
+}
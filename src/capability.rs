@@ -1,14 +1,16 @@
 //! Object attributes specifying which operations are allowed to be performed
 
+#[cfg(feature = "std")]
+use crate::object;
 use bitflags::bitflags;
+use core::{
+    fmt::{self, Display},
+    str::{self, FromStr},
+};
 use serde::{
     de::{self, Deserialize, Deserializer, Visitor},
     ser::{Serialize, Serializer},
 };
-use std::{
-    fmt::{self, Display},
-    str::{self, FromStr},
-};
 
 bitflags! {
     /// Object attributes specifying which operations are allowed to be performed
@@ -210,6 +212,33 @@ bitflags! {
     }
 }
 
+impl Capability {
+    /// Compute the union of the capabilities and delegated capabilities of
+    /// the given objects.
+    ///
+    /// This is the minimal set of delegated capabilities a wrap key or
+    /// authentication key needs in order to manage (e.g. import) all of the
+    /// given objects.
+    #[cfg(feature = "std")]
+    pub fn union_of(objects: &[object::Info]) -> Self {
+        objects.iter().fold(Capability::empty(), |union, info| {
+            union | info.capabilities | info.delegated_capabilities
+        })
+    }
+
+    /// Look up a capability by its official name (as used by `yubihsm-shell`
+    /// and in the Yubico documentation), e.g. `sign-ecdsa`.
+    ///
+    /// Also accepts `put-mac-key`, the name `yubihsm-shell` uses for
+    /// `PUT_HMAC_KEY`.
+    pub fn from_name(name: &str) -> Option<Capability> {
+        match name {
+            "put-mac-key" => Some(Capability::PUT_HMAC_KEY),
+            _ => name.parse().ok(),
+        }
+    }
+}
+
 impl Default for Capability {
     fn default() -> Self {
         Capability::empty()
@@ -364,3 +393,137 @@ impl<'de> Deserialize<'de> for Capability {
         deserializer.deserialize_u64(CapabilityVisitor)
     }
 }
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::{asymmetric, Domain};
+
+    fn object_info(capabilities: Capability, delegated_capabilities: Capability) -> object::Info {
+        object::Info {
+            capabilities,
+            object_id: 1,
+            length: 32,
+            domains: Domain::DOM1,
+            object_type: object::Type::AsymmetricKey,
+            algorithm: asymmetric::Algorithm::Ed25519.into(),
+            sequence: 0,
+            origin: object::Origin::Generated,
+            label: Default::default(),
+            delegated_capabilities,
+        }
+    }
+
+    #[test]
+    fn union_of_overlapping_capabilities() {
+        let objects = [
+            object_info(
+                Capability::SIGN_EDDSA | Capability::EXPORTABLE_UNDER_WRAP,
+                Capability::empty(),
+            ),
+            object_info(
+                Capability::SIGN_ECDSA | Capability::EXPORTABLE_UNDER_WRAP,
+                Capability::empty(),
+            ),
+        ];
+
+        assert_eq!(
+            Capability::union_of(&objects),
+            Capability::SIGN_EDDSA | Capability::SIGN_ECDSA | Capability::EXPORTABLE_UNDER_WRAP
+        );
+    }
+
+    #[test]
+    fn union_of_disjoint_capabilities() {
+        let objects = [
+            object_info(Capability::SIGN_HMAC, Capability::empty()),
+            object_info(Capability::PUT_ASYMMETRIC_KEY, Capability::SIGN_EDDSA),
+        ];
+
+        assert_eq!(
+            Capability::union_of(&objects),
+            Capability::SIGN_HMAC | Capability::PUT_ASYMMETRIC_KEY | Capability::SIGN_EDDSA
+        );
+        assert_eq!(Capability::union_of(&[]), Capability::empty());
+    }
+
+    /// Capability names and bits from the official `yubihsm.h` capability list
+    const OFFICIAL_CAPABILITIES: &[(&str, u64)] = &[
+        ("change-authentication-key", 0x0000_4000_0000_0000),
+        ("create-otp-aead", 0x0000_0000_4000_0000),
+        ("decrypt-oaep", 0x0000_0000_0000_0400),
+        ("decrypt-otp", 0x0000_0000_2000_0000),
+        ("decrypt-pkcs", 0x0000_0000_0000_0200),
+        ("delete-asymmetric-key", 0x0000_0200_0000_0000),
+        ("delete-authentication-key", 0x0000_0100_0000_0000),
+        ("delete-hmac-key", 0x0000_0800_0000_0000),
+        ("delete-opaque", 0x0000_0080_0000_0000),
+        ("delete-otp-aead-key", 0x0000_2000_0000_0000),
+        ("delete-template", 0x0000_1000_0000_0000),
+        ("delete-wrap-key", 0x0000_0400_0000_0000),
+        ("derive-ecdh", 0x0000_0000_0000_0800),
+        ("export-wrapped", 0x0000_0000_0000_1000),
+        ("exportable-under-wrap", 0x0000_0000_0001_0000),
+        ("generate-asymmetric-key", 0x0000_0000_0000_0010),
+        ("generate-hmac-key", 0x0000_0000_0020_0000),
+        ("generate-otp-aead-key", 0x0000_0010_0000_0000),
+        ("generate-wrap-key", 0x0000_0000_0000_8000),
+        ("get-log-entries", 0x0000_0000_0100_0000),
+        ("get-opaque", 0x0000_0000_0000_0001),
+        ("get-option", 0x0000_0000_0004_0000),
+        ("get-pseudo-random", 0x0000_0000_0008_0000),
+        ("get-template", 0x0000_0000_0400_0000),
+        ("import-wrapped", 0x0000_0000_0000_2000),
+        ("put-asymmetric-key", 0x0000_0000_0000_0008),
+        ("put-authentication-key", 0x0000_0000_0000_0004),
+        ("put-mac-key", 0x0000_0000_0010_0000),
+        ("put-opaque", 0x0000_0000_0000_0002),
+        ("put-otp-aead-key", 0x0000_0008_0000_0000),
+        ("put-template", 0x0000_0000_0800_0000),
+        ("put-wrap-key", 0x0000_0000_0000_4000),
+        ("randomize-otp-aead", 0x0000_0000_8000_0000),
+        ("reset-device", 0x0000_0000_1000_0000),
+        ("rewrap-from-otp-aead-key", 0x0000_0001_0000_0000),
+        ("rewrap-to-otp-aead-key", 0x0000_0002_0000_0000),
+        ("set-option", 0x0000_0000_0002_0000),
+        ("sign-attestation-certificate", 0x0000_0004_0000_0000),
+        ("sign-ecdsa", 0x0000_0000_0000_0080),
+        ("sign-eddsa", 0x0000_0000_0000_0100),
+        ("sign-hmac", 0x0000_0000_0040_0000),
+        ("sign-pkcs", 0x0000_0000_0000_0020),
+        ("sign-pss", 0x0000_0000_0000_0040),
+        ("sign-ssh-certificate", 0x0000_0000_0200_0000),
+        ("unwrap-data", 0x0000_0040_0000_0000),
+        ("verify-hmac", 0x0000_0000_0080_0000),
+        ("wrap-data", 0x0000_0020_0000_0000),
+    ];
+
+    #[test]
+    fn capability_bits_match_official_list() {
+        let mut all_bits = 0;
+
+        for &(name, bits) in OFFICIAL_CAPABILITIES {
+            let capability = Capability::from_name(name)
+                .unwrap_or_else(|| panic!("unknown capability name: {}", name));
+
+            assert_eq!(capability.bits(), bits, "wrong bit for {}", name);
+            all_bits |= bits;
+        }
+
+        // Bits 47-63 aren't assigned to any capability (yet)
+        assert_eq!(all_bits, (1 << 47) - 1);
+    }
+
+    #[test]
+    fn capability_names_round_trip() {
+        for &(_, bits) in OFFICIAL_CAPABILITIES {
+            let capability = Capability::from_bits(bits).unwrap();
+            assert_eq!(
+                Capability::from_name(&capability.to_string()),
+                Some(capability)
+            );
+        }
+
+        assert_eq!(Capability::from_name("sign-ecdsaa"), None);
+    }
+}
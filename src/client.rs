@@ -9,9 +9,27 @@
 
 #[macro_use]
 mod error;
-
-pub use self::error::{Error, ErrorKind};
+pub mod cluster;
+pub mod diagnosis;
+pub mod pool;
+mod reconnect;
+pub mod self_test;
+#[cfg(feature = "threaded")]
+#[cfg_attr(docsrs, doc(cfg(feature = "threaded")))]
+pub mod threaded;
+
+#[cfg(feature = "threaded")]
+pub use self::threaded::{CommandFuture, ThreadedClient};
+pub use self::{
+    cluster::HsmCluster,
+    diagnosis::OperationDiagnosis,
+    error::{Error, ErrorKind},
+    pool::{ClientPool, PooledClient},
+    reconnect::ReconnectPolicy,
+    self_test::SelfTestReport,
+};
 use crate::{
+    algorithm::Algorithm,
     asymmetric::{self, commands::*, PublicKey},
     attestation::{self, commands::*},
     audit::{commands::*, *},
@@ -34,28 +52,47 @@ use crate::{
     wrap::{self, commands::*},
 };
 use anomaly::{ensure, fail, format_err};
+use rand_core::{OsRng, RngCore};
 use std::{
+    collections::{BTreeMap, BTreeSet},
+    mem,
+    path::Path,
     sync::{Arc, Mutex},
+    thread,
     time::{Duration, Instant},
 };
 
 #[cfg(feature = "passwords")]
-use std::{thread, time::SystemTime};
+use std::time::SystemTime;
 
 #[cfg(feature = "untested")]
 use {
     crate::{
-        algorithm::Algorithm,
         ecdh::{self, commands::*},
-        rsa::{self, pkcs1::commands::*, pss::commands::*},
+        rsa::{self, oaep::commands::*, pkcs1::commands::*, pss::commands::*},
         ssh::{self, commands::*},
     },
-    sha2::{Digest, Sha256},
+    sha2::Sha256,
 };
 
+#[cfg(feature = "sha2")]
+use sha2::{Digest, Sha384, Sha512};
+
 #[cfg(docsrs)]
 use crate::ecdsa;
 
+/// Maximum (and default) number of messages after which sessions are
+/// rotated. One message below the per-session limit is left for closing the
+/// session.
+const MAX_SESSION_ROTATION_THRESHOLD: usize = session::MAX_COMMANDS_PER_SESSION as usize - 1;
+
+/// Number of times a command is retried (in a new session) after its session
+/// is closed, so an HSM which keeps closing sessions doesn't loop forever
+const MAX_COMMAND_RETRIES: usize = 1;
+
+/// Callback invoked after each attempt to open a session
+type EstablishmentHook = Arc<dyn Fn(&session::Establishment) + Send + Sync>;
+
 /// YubiHSM client: main API in this crate for accessing functions of the
 /// HSM hardware device.
 #[derive(Clone)]
@@ -68,6 +105,45 @@ pub struct Client {
 
     /// Cached `Credentials` for reconnecting closed sessions
     credentials: Option<Credentials>,
+
+    /// Policy for logging commands sent to the HSM
+    log_policy: session::LogPolicy,
+
+    /// Source of host-side randomness (e.g. session host challenges)
+    rng: Arc<Mutex<Box<dyn RngCore + Send>>>,
+
+    /// Maximum amount of time a command (including reconnecting) may take
+    deadline: Option<Duration>,
+
+    /// Number of commands to record in each session's transcript
+    transcript_capacity: usize,
+
+    /// Policy for retrying failed attempts to open a session
+    reconnect_policy: ReconnectPolicy,
+
+    /// Callback invoked after each attempt to open a session
+    establishment_hook: Option<EstablishmentHook>,
+
+    /// Reject commands which modify the HSM
+    read_only: bool,
+
+    /// Check the session shares a domain with objects before operating on them
+    domain_checks: bool,
+
+    /// Refuse to delete the last authentication key able to create new ones
+    lockout_protection: bool,
+
+    /// Number of messages after which sessions are closed and reopened
+    session_rotation_threshold: usize,
+
+    /// Inactivity timeout after which sessions are reopened
+    session_timeout: session::Timeout,
+
+    /// Cached object info of keys used for signing and verification
+    key_info: Arc<Mutex<BTreeMap<object::Handle, object::Info>>>,
+
+    /// Cached device info used to check which commands are supported
+    device_info: Arc<Mutex<Option<device::Info>>>,
 }
 
 impl Client {
@@ -82,8 +158,35 @@ impl Client {
         connector: Connector,
         credentials: Credentials,
         reconnect: bool,
+    ) -> Result<Self, Error> {
+        Self::with_timeout(
+            connector,
+            credentials,
+            reconnect,
+            session::Timeout::default(),
+        )
+    }
+
+    /// Open a connection to a YubiHSM like `Client::open`, considering
+    /// sessions timed out (and opening new ones) after they've been inactive
+    /// for the given timeout, less its skew.
+    ///
+    /// The HSM itself closes sessions after 30 seconds of inactivity
+    /// (`session::SESSION_INACTIVITY_TIMEOUT`), which isn't configurable:
+    /// shorter timeouts make the client replace sessions earlier, while
+    /// longer ones only mean commands are more likely to be sent in a session
+    /// the HSM has already closed, and retried in a new one.
+    ///
+    /// Fails with `ErrorKind::CreateFailed` if the timeout isn't longer than
+    /// its skew.
+    pub fn with_timeout(
+        connector: Connector,
+        credentials: Credentials,
+        reconnect: bool,
+        timeout: session::Timeout,
     ) -> Result<Self, Error> {
         let mut client = Self::create(connector, credentials)?;
+        client.session_timeout = timeout;
         client.connect()?;
 
         // Clear credentials if reconnecting has been disabled
@@ -94,12 +197,73 @@ impl Client {
         Ok(client)
     }
 
+    /// Open a connection via a [Connector] to a YubiHSM, trying each of the
+    /// given `Credentials` in order until one of them authenticates.
+    ///
+    /// This is useful while rotating authentication keys, when it may not be
+    /// known which of several keys is currently active on the device. Only
+    /// authentication failures cause the next set of credentials to be
+    /// tried: any other error (e.g. the connector being unreachable) is
+    /// returned immediately.
+    ///
+    /// On success, returns the client along with the index of the
+    /// credentials which authenticated. Only those credentials are cached
+    /// for reconnecting.
+    ///
+    /// [Connector]: https://docs.rs/yubihsm/latest/yubihsm/connector/index.html
+    pub fn open_with_fallback<I>(
+        connector: Connector,
+        credentials: I,
+        reconnect: bool,
+    ) -> Result<(Self, usize), Error>
+    where
+        I: IntoIterator<Item = Credentials>,
+    {
+        let mut last_error = None;
+
+        for (index, creds) in credentials.into_iter().enumerate() {
+            let authentication_key_id = creds.authentication_key_id;
+
+            match Self::open(connector.clone(), creds, reconnect) {
+                Ok(client) => return Ok((client, index)),
+                Err(e)
+                    if *e.kind() == ErrorKind::AuthenticationError
+                        || e.device_error() == Some(device::ErrorKind::AuthenticationFailed) =>
+                {
+                    warn!(
+                        "authentication with key 0x{:04x} failed, trying next credentials: {}",
+                        authentication_key_id, e
+                    );
+                    last_error = Some(e);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| {
+            format_err!(ErrorKind::AuthenticationError, "no credentials provided").into()
+        }))
+    }
+
     /// Create a `yubihsm::Client`, but defer connecting until `connect()` is called.
     pub fn create(connector: Connector, credentials: Credentials) -> Result<Self, Error> {
         let client = Self {
             connector,
             session: Arc::new(Mutex::new(None)),
             credentials: Some(credentials),
+            log_policy: session::LogPolicy::default(),
+            rng: Arc::new(Mutex::new(Box::new(OsRng))),
+            deadline: None,
+            transcript_capacity: 0,
+            reconnect_policy: ReconnectPolicy::default(),
+            establishment_hook: None,
+            read_only: false,
+            domain_checks: false,
+            lockout_protection: true,
+            session_rotation_threshold: MAX_SESSION_ROTATION_THRESHOLD,
+            session_timeout: session::Timeout::default(),
+            key_info: Arc::new(Mutex::new(BTreeMap::new())),
+            device_info: Arc::new(Mutex::new(None)),
         };
 
         Ok(client)
@@ -110,41 +274,441 @@ impl Client {
         &self.connector
     }
 
+    /// Set the policy used to redact or suppress log lines about commands
+    /// sent to the HSM (default: log all commands).
+    pub fn set_log_policy(&mut self, log_policy: session::LogPolicy) {
+        if let Some(session) = self.session.lock().unwrap().as_mut() {
+            session.set_log_policy(log_policy.clone());
+        }
+
+        self.log_policy = log_policy;
+    }
+
+    /// Set the RNG used for host-side randomness, i.e. the host challenges
+    /// used when opening sessions and nonces from `generate_wrap_nonce`
+    /// (default: `OsRng`).
+    ///
+    /// Randomness generated by the HSM itself (e.g. `get_pseudo_random`,
+    /// key generation) is unaffected.
+    pub fn set_rng<R>(&mut self, rng: R)
+    where
+        R: RngCore + Send + 'static,
+    {
+        self.rng = Arc::new(Mutex::new(Box::new(rng)));
+    }
+
+    /// Set a deadline for sending commands to the HSM (default: none).
+    ///
+    /// The deadline covers reconnecting to the HSM (i.e. opening a new
+    /// session, if needed) and executing the command collectively. If the
+    /// deadline elapses before a response is received, an
+    /// `ErrorKind::TimeoutError` is returned rather than blocking on a
+    /// connector which has stopped responding.
+    ///
+    /// The deadline is enforced through the connection's own I/O timeouts
+    /// (the socket timeouts of HTTP connections, and the `UsbTimeout` of USB
+    /// ones), which are set to the time remaining before each message is
+    /// sent. A command which times out is abandoned along with its session
+    /// and connection, so it can't interfere with subsequent commands.
+    pub fn set_deadline(&mut self, deadline: Option<Duration>) {
+        self.deadline = deadline;
+    }
+
+    /// Check the current session's authentication key shares at least one
+    /// domain with the object a command operates on before sending it
+    /// (default: `false`).
+    ///
+    /// Without this, the HSM rejects such commands with a permission error
+    /// which doesn't say what's wrong. With it, they're rejected with
+    /// `ErrorKind::NoSharedDomain` instead. The domains of the object are
+    /// looked up with `Get Object Info` (and cached) the first time it's
+    /// operated on.
+    pub fn set_domain_checks(&mut self, enabled: bool) {
+        self.domain_checks = enabled;
+    }
+
+    /// Refuse to delete the last authentication key with the
+    /// `put-authentication-key` capability (default: `true`).
+    ///
+    /// Without such a key, no new authentication keys can be created, and
+    /// deleting the last authentication key of all makes it impossible to
+    /// open sessions: either way, the HSM can only be recovered by a
+    /// physical reset. Disable this for scripts which genuinely intend it,
+    /// e.g. during a controlled migration.
+    pub fn set_lockout_protection(&mut self, enabled: bool) {
+        self.lockout_protection = enabled;
+    }
+
+    /// Put this client in read-only mode (default: `false`).
+    ///
+    /// In read-only mode, commands which modify the HSM (i.e. putting,
+    /// generating, importing or deleting objects, changing options or the
+    /// log index, and resetting the device) are rejected with
+    /// `ErrorKind::ReadOnly` before they're sent. This complements, but
+    /// doesn't replace, limiting the authentication key's capabilities.
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+    }
+
+    /// Set the policy for retrying failed attempts to open a session with the
+    /// HSM (default: `ReconnectPolicy::none()`, i.e. don't retry).
+    ///
+    /// This only governs the delays between attempts to (re)open a session.
+    /// Use `set_deadline` to bound how long each command may take overall.
+    pub fn set_reconnect_policy(&mut self, reconnect_policy: ReconnectPolicy) {
+        self.reconnect_policy = reconnect_policy;
+    }
+
+    /// Set a callback which is invoked with a `session::Establishment` record
+    /// after every attempt to open a session, successful or not (default:
+    /// none).
+    ///
+    /// This is intended for emitting host-side records of authentication
+    /// attempts to e.g. a SIEM. The callback is invoked while the session
+    /// lock is held, so it shouldn't use this `Client`.
+    pub fn set_session_establishment_hook<F>(&mut self, hook: F)
+    where
+        F: Fn(&session::Establishment) + Send + Sync + 'static,
+    {
+        self.establishment_hook = Some(Arc::new(hook));
+    }
+
+    /// Close the current session and open a new one (with fresh session keys)
+    /// once it has sent `threshold` messages (default and maximum: one less
+    /// than `session::MAX_COMMANDS_PER_SESSION`).
+    ///
+    /// SCP03 message counters (and the birthday bound of its 8-byte MACs)
+    /// limit how many messages a session can safely carry. Rotating sessions
+    /// before reaching that limit avoids commands failing with
+    /// `session::ErrorKind::CommandLimitExceeded` on very busy clients.
+    /// Thresholds above the maximum are clamped to it. Rotation requires
+    /// reconnecting to be enabled.
+    pub fn set_session_rotation_threshold(&mut self, threshold: usize) {
+        self.session_rotation_threshold = threshold.min(MAX_SESSION_ROTATION_THRESHOLD);
+    }
+
+    /// Record metadata about up to `capacity` of the most recent commands
+    /// sent during each session (default: 0, i.e. disabled).
+    ///
+    /// Transcripts record the command, time, object ID (if applicable) and
+    /// outcome of each command, but not their payloads. They can be obtained
+    /// with `session_transcript`.
+    pub fn set_transcript_capacity(&mut self, capacity: usize) {
+        if let Some(session) = self.session.lock().unwrap().as_mut() {
+            session.set_transcript_capacity(capacity);
+        }
+
+        self.transcript_capacity = capacity;
+    }
+
+    /// Get the transcript of commands sent during the current session,
+    /// oldest first. Empty if transcripts are disabled or no session is open.
+    pub fn session_transcript(&self) -> Vec<session::TranscriptEntry> {
+        self.session
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(Session::transcript)
+            .unwrap_or_default()
+    }
+
     /// Connect to the HSM (idempotently, i.e. returns success if we have
-    /// an open connection already)
+    /// an open connection already).
+    ///
+    /// Fails with `ErrorKind::ConnectorError` if the connector reports a
+    /// protocol version this crate doesn't support.
     pub fn connect(&self) -> Result<(), Error> {
+        self.connector.check_protocol_version()?;
         self.session()?;
         Ok(())
     }
 
     /// Get current `Session` (either opening a new one or returning an already
     /// open one).
+    ///
+    /// Commands sent through the returned guard are subject to the deadline
+    /// set with `set_deadline`, counted from when this is called.
     pub fn session(&self) -> Result<session::Guard<'_>, Error> {
+        self.session_with_deadline(self.deadline.map(|d| Instant::now() + d))
+    }
+
+    /// Get the current `Session`, opening a new one if needed, and apply the
+    /// given deadline to it
+    fn session_with_deadline(
+        &self,
+        deadline: Option<Instant>,
+    ) -> Result<session::Guard<'_>, Error> {
         // TODO(tarcieri): handle PoisonError better?
         let mut session_mutex_guard = self.session.lock().unwrap();
 
-        if let Some(session) = session_mutex_guard.as_ref() {
+        if let Some(session) = session_mutex_guard.as_mut() {
             if session.is_open() {
-                return Ok(session::Guard::new(session_mutex_guard));
+                let messages_sent = session.messages_sent()?;
+
+                if messages_sent < self.session_rotation_threshold || self.credentials.is_none() {
+                    session.set_deadline(deadline);
+                    return Ok(session::Guard::new(session_mutex_guard));
+                }
+
+                info!(
+                    "session={} rotating session after {} messages",
+                    session.id().to_u8(),
+                    messages_sent
+                );
             }
         }
 
+        let credentials = self.credentials.as_ref().ok_or_else(|| {
+            format_err!(
+                ErrorKind::AuthenticationError,
+                "session reconnection disabled"
+            )
+        })?;
+
+        // Close the current session (if any) before opening its replacement,
+        // without letting the new command's deadline cut the close short
+        if let Some(session) = session_mutex_guard.as_mut() {
+            session.set_deadline(None);
+        }
+
+        *session_mutex_guard = None;
+
         // If we don't have an open session, create a new one
-        let session = Session::open(
-            self.connector.clone(),
-            self.credentials.as_ref().ok_or_else(|| {
-                format_err!(
-                    ErrorKind::AuthenticationError,
-                    "session reconnection disabled"
-                )
-            })?,
-            session::Timeout::default(),
-        )?;
+        let mut session = self.open_session_with_backoff(credentials, deadline)?;
 
+        session.set_transcript_capacity(self.transcript_capacity);
         *session_mutex_guard = Some(session);
         Ok(session::Guard::new(session_mutex_guard))
     }
 
+    /// Open a new session, retrying according to the reconnect policy
+    fn open_session_with_backoff(
+        &self,
+        credentials: &Credentials,
+        deadline: Option<Instant>,
+    ) -> Result<Session, Error> {
+        let mut attempt = 0;
+
+        loop {
+            let result = Session::open(
+                self.connector.clone(),
+                credentials,
+                self.session_timeout,
+                self.log_policy.clone(),
+                &mut **self.rng.lock().unwrap(),
+                deadline,
+            );
+
+            if let Some(hook) = &self.establishment_hook {
+                hook(&session::Establishment::new(
+                    credentials.authentication_key_id,
+                    &result,
+                ));
+            }
+
+            let err = match result {
+                Ok(session) => return Ok(session),
+                Err(e) => e,
+            };
+
+            let retryable = !matches!(
+                err.kind(),
+                session::ErrorKind::AuthenticationError
+                    | session::ErrorKind::DeviceError
+                    | session::ErrorKind::VerifyFailed
+            );
+
+            let delay = match self.reconnect_policy.delay(attempt) {
+                Some(delay) if retryable => delay,
+                _ => return Err(err.into()),
+            };
+
+            if let Some(deadline) = deadline {
+                ensure!(
+                    Instant::now() + delay < deadline,
+                    ErrorKind::TimeoutError,
+                    "deadline elapses before next attempt to open session: {}",
+                    err
+                );
+            }
+
+            warn!(
+                "error opening session (attempt {}), retrying in {:?}: {}",
+                attempt + 1,
+                delay,
+                err
+            );
+
+            thread::sleep(delay);
+            attempt = attempt.saturating_add(1);
+        }
+    }
+
+    /// Check the connector, session, and HSM all work end to end, by
+    /// performing the steps selected by the given options (e.g. an echo and
+    /// fetching random bytes).
+    ///
+    /// Fails if a session can't be opened. Otherwise, each step is performed
+    /// regardless of whether earlier ones failed, and the outcome and
+    /// latency of each is returned in the report.
+    pub fn self_test(&self, options: &self_test::Options) -> Result<SelfTestReport, Error> {
+        self.session()?;
+        Ok(self_test::run(self, options))
+    }
+
+    /// Does the current session's authentication key have the given
+    /// capability? Useful for checking whether a command is permitted
+    /// before attempting it.
+    ///
+    /// The authentication key's object info is fetched once per session and
+    /// cached. If the key has been deleted, this returns `false`.
+    pub fn session_has_capability(&self, capability: Capability) -> Result<bool, Error> {
+        Ok(self
+            .session_key_id_and_info()?
+            .1
+            .map(|info| info.capabilities.contains(capability))
+            .unwrap_or(false))
+    }
+
+    /// Get the object info of the current session's authentication key.
+    ///
+    /// The info is fetched from the HSM once per session and cached (a new
+    /// session, e.g. after reconnecting, fetches it again). If the key has
+    /// been deleted since the session was opened, this fails with
+    /// `ErrorKind::SessionKeyNotFound`.
+    pub fn session_key_info(&self) -> Result<object::Info, Error> {
+        let (key_id, info) = self.session_key_id_and_info()?;
+
+        info.ok_or_else(|| {
+            format_err!(
+                ErrorKind::SessionKeyNotFound,
+                "session authentication key 0x{:04x} no longer exists",
+                key_id
+            )
+            .into()
+        })
+    }
+
+    /// Get the object info of the given key, fetching it from the HSM the
+    /// first time it's needed.
+    ///
+    /// The cache is updated when this client deletes, generates, puts or
+    /// imports keys, but not when another client does.
+    pub(crate) fn cached_key_info(
+        &self,
+        key_id: object::Id,
+        key_type: object::Type,
+    ) -> Result<object::Info, Error> {
+        let handle = object::Handle::new(key_id, key_type);
+
+        if let Some(info) = self.key_info.lock().unwrap().get(&handle) {
+            return Ok(info.clone());
+        }
+
+        let info = self.get_object_info(key_id, key_type)?;
+        self.key_info.lock().unwrap().insert(handle, info.clone());
+        Ok(info)
+    }
+
+    /// Remove the given key from the object info cache
+    fn forget_key(&self, key_id: object::Id, key_type: object::Type) {
+        self.key_info
+            .lock()
+            .unwrap()
+            .remove(&object::Handle::new(key_id, key_type));
+    }
+
+    /// Ensure some authentication key other than the given one has the
+    /// `put-authentication-key` capability, or failing that, that deleting
+    /// the given key wouldn't remove the last one able to administer the HSM
+    fn ensure_not_last_admin_key(&self, key_id: object::Id) -> Result<(), Error> {
+        let mut is_admin = false;
+        let mut other_keys = false;
+        let mut other_admins = false;
+
+        for entry in self.list_objects(&[object::Filter::Type(object::Type::AuthenticationKey)])? {
+            let info = self.get_object_info(entry.object_id, object::Type::AuthenticationKey)?;
+            let admin = info
+                .capabilities
+                .contains(Capability::PUT_AUTHENTICATION_KEY);
+
+            if entry.object_id == key_id {
+                is_admin = admin;
+            } else {
+                other_keys = true;
+                other_admins |= admin;
+            }
+        }
+
+        ensure!(
+            other_admins || (other_keys && !is_admin),
+            ErrorKind::WouldLockOut,
+            "auth key 0x{:04x} is the last one able to create auth keys \
+             (see `Client::set_lockout_protection`)",
+            key_id
+        );
+
+        Ok(())
+    }
+
+    /// Ensure the given wrap key's domains include all of the given object's
+    /// domains, failing with `ErrorKind::DomainMismatch` (naming the domains
+    /// the wrap key lacks) otherwise
+    fn ensure_wrap_domains(
+        &self,
+        wrap_key_id: object::Id,
+        object_type: object::Type,
+        object_id: object::Id,
+    ) -> Result<(), Error> {
+        let wrap_key_domains = self
+            .get_object_info(wrap_key_id, object::Type::WrapKey)?
+            .domains;
+
+        let object_domains = self.get_object_info(object_id, object_type)?.domains;
+        let missing_domains = object_domains - wrap_key_domains;
+
+        ensure!(
+            missing_domains.is_empty(),
+            ErrorKind::DomainMismatch,
+            "wrap key 0x{:04x} lacks domains {:?} of {:?} 0x{:04x}",
+            wrap_key_id,
+            missing_domains,
+            object_type,
+            object_id
+        );
+
+        Ok(())
+    }
+
+    /// Get the ID and (cached) object info of the current session's
+    /// authentication key, or `None` if the key has been deleted
+    fn session_key_id_and_info(&self) -> Result<(object::Id, Option<object::Info>), Error> {
+        let (key_id, cached_info) = {
+            let session = self.session()?;
+            (
+                session.authentication_key_id(),
+                session.authentication_key_info().cloned(),
+            )
+        };
+
+        if cached_info.is_some() {
+            return Ok((key_id, cached_info));
+        }
+
+        match self.get_object_info(key_id, object::Type::AuthenticationKey) {
+            Ok(info) => {
+                self.session()?
+                    .set_authentication_key_info(Some(info.clone()));
+                Ok((key_id, Some(info)))
+            }
+            Err(e) if e.device_error() == Some(device::ErrorKind::ObjectNotFound) => {
+                warn!("session authentication key 0x{:04x} was deleted", key_id);
+                Ok((key_id, None))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
     /// Ping the HSM, ensuring we have a live connection and returning the
     /// end-to-end latency.
     pub fn ping(&self) -> Result<Duration, Error> {
@@ -165,29 +729,144 @@ impl Client {
 
     /// Encrypt a command, send it to the HSM, then read and decrypt the response.
     fn send_command<T: Command>(&self, command: T) -> Result<T::ResponseType, Error> {
-        match self.session()?.send_command(&command) {
-            Ok(response) => Ok(response),
-            Err(e) => {
-                // If we encounter this, we've exceeded the maximum number of
-                // messages allowed under the data volume limits and need to
-                // rekey the connection by creating a new session.
-                //
-                // Attempt to inititiate a new session and retry the command.
-                // (the original command was never sent in this case)
-                if *e.kind() == session::ErrorKind::CommandLimitExceeded {
-                    Ok(self.session()?.send_command(&command)?)
-                } else {
-                    Err(e.into())
-                }
+        self.ensure_permitted::<T>()?;
+        self.ensure_shared_domain(&command)?;
+
+        let deadline = self.deadline.map(|d| Instant::now() + d);
+        let mut retries = 0;
+
+        loop {
+            let err = match self.session_with_deadline(deadline)?.send_command(&command) {
+                Ok(response) => return Ok(response),
+                Err(e) => e,
+            };
+
+            // The HSM never executed the command in either of these cases,
+            // so it's safe to open a new session and retry it:
+            //
+            // - `CommandLimitExceeded`: we've exceeded the maximum number of
+            //   messages allowed under the data volume limits and need to
+            //   rekey the connection (the command was never sent)
+            // - `ClosedError`: the session was closed, e.g. by the HSM after
+            //   it was inactive for longer than the HSM's timeout
+            let retryable = matches!(
+                err.kind(),
+                session::ErrorKind::CommandLimitExceeded | session::ErrorKind::ClosedError
+            );
+
+            if !retryable || retries >= MAX_COMMAND_RETRIES || self.credentials.is_none() {
+                return Err(Error::from_command_error(
+                    T::COMMAND_CODE,
+                    command.required_capability(),
+                    err,
+                    || self.is_unsupported(T::COMMAND_CODE),
+                ));
             }
+
+            retries += 1;
+            info!(
+                "reopening session and retrying {:?} command: {}",
+                T::COMMAND_CODE,
+                err
+            );
         }
     }
 
+    /// Check the given command is permitted by the client's configuration
+    /// (i.e. it isn't a mutating command sent in read-only mode)
+    fn ensure_permitted<T: Command>(&self) -> Result<(), Error> {
+        ensure!(
+            !(self.read_only && T::EFFECT.is_mutating()),
+            ErrorKind::ReadOnly(T::COMMAND_CODE),
+            "{} command not permitted: client is read-only",
+            T::COMMAND_CODE.name()
+        );
+
+        Ok(())
+    }
+
+    /// If domain checks are enabled, check the session's authentication key
+    /// shares a domain with the object the given command operates on.
+    ///
+    /// Commands are let through if either object's info is unavailable
+    /// (e.g. the object doesn't exist), so the HSM can report the error.
+    fn ensure_shared_domain<T: Command>(&self, command: &T) -> Result<(), Error> {
+        // `Get Object Info` is how the object's domains are looked up
+        if !self.domain_checks || T::COMMAND_CODE == command::Code::GetObjectInfo {
+            return Ok(());
+        }
+
+        let (object_id, object_type) = match (command.object_id(), command.object_type()) {
+            (Some(id), Some(ty)) => (id, ty),
+            _ => return Ok(()),
+        };
+
+        let key_info = match self.session_key_id_and_info()?.1 {
+            Some(info) => info,
+            None => return Ok(()),
+        };
+
+        let object_info = match self.cached_key_info(object_id, object_type) {
+            Ok(info) => info,
+            Err(_) => return Ok(()),
+        };
+
+        ensure!(
+            key_info.domains.intersects(object_info.domains),
+            ErrorKind::NoSharedDomain(T::COMMAND_CODE),
+            "{} command not permitted on {} 0x{:04x}: authentication key 0x{:04x} has domains {:?}, object has domains {:?}",
+            T::COMMAND_CODE.name(),
+            object_type,
+            object_id,
+            key_info.object_id,
+            key_info.domains,
+            object_info.domains
+        );
+
+        Ok(())
+    }
+
     //
     // HSM Commands
     // <https://developers.yubico.com/YubiHSM2/Commands/>
     //
 
+    /// Produce an attestation certificate, signed by the default attestation
+    /// key, for every asymmetric key which was generated on the HSM.
+    ///
+    /// Imported keys are skipped, since the HSM can't vouch for them. Failures
+    /// to look up or attest individual keys don't abort the operation, and are
+    /// instead reported alongside the key's ID.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Sign_Attestation_Certificate.html>
+    pub fn attest_all(&self) -> Result<Vec<attestation::KeyAttestation>, Error> {
+        let objects = self.list_objects(&[object::Filter::Type(object::Type::AsymmetricKey)])?;
+        let mut certificates = vec![];
+
+        for entry in objects {
+            let info = match self.get_object_info(entry.object_id, object::Type::AsymmetricKey) {
+                Ok(info) => info,
+                Err(e) => {
+                    certificates.push((entry.object_id, Err(e)));
+                    continue;
+                }
+            };
+
+            match info.origin {
+                object::Origin::Generated | object::Origin::WrappedGenerated => {
+                    let result = self.sign_attestation_certificate(entry.object_id, None);
+                    certificates.push((entry.object_id, result));
+                }
+                object::Origin::Imported | object::Origin::WrappedImported => debug!(
+                    "not attesting imported asymmetric key 0x{:04x}",
+                    entry.object_id
+                ),
+            }
+        }
+
+        Ok(certificates)
+    }
+
     /// Blink the HSM's LEDs (to identify it) for the given number of seconds.
     ///
     /// <https://developers.yubico.com/YubiHSM2/Commands/Blink_Device.html>
@@ -196,14 +875,328 @@ impl Client {
         Ok(())
     }
 
+    /// Can the given wrap key export the given object? This requires the
+    /// wrap key's domains to include all of the object's domains.
+    ///
+    /// Fails if either object doesn't exist.
+    pub fn can_wrap(
+        &self,
+        wrap_key_id: object::Id,
+        object_type: object::Type,
+        object_id: object::Id,
+    ) -> Result<bool, Error> {
+        match self.ensure_wrap_domains(wrap_key_id, object_type, object_id) {
+            Ok(()) => Ok(true),
+            Err(e) if *e.kind() == ErrorKind::DomainMismatch => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Replace the authentication key used to open the current session with
+    /// the given key. Its label, domains, and capabilities are unchanged.
+    ///
+    /// The key needs the `change-authentication-key` capability. If it's the
+    /// key this client authenticates with, the new key is used when opening
+    /// subsequent sessions (e.g. when reconnecting); the current session
+    /// remains open.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Change_Authentication_Key.html>
+    pub fn change_authentication_key<K>(
+        &mut self,
+        key_id: object::Id,
+        algorithm: authentication::Algorithm,
+        authentication_key: K,
+    ) -> Result<object::Id, Error>
+    where
+        K: Into<authentication::Key>,
+    {
+        let authentication_key = authentication_key.into();
+
+        self.forget_key(key_id, object::Type::AuthenticationKey);
+
+        let key_id = self
+            .send_command(ChangeAuthenticationKeyCommand {
+                key_id,
+                algorithm,
+                authentication_key: authentication_key.clone(),
+            })?
+            .key_id;
+
+        if let Some(credentials) = self.credentials.as_mut() {
+            if credentials.authentication_key_id == key_id {
+                credentials.authentication_key = authentication_key;
+            }
+        }
+
+        Ok(key_id)
+    }
+
+    /// Replace the authentication key used to open the current session with
+    /// one derived from the given password (see `change_authentication_key`).
+    #[cfg(feature = "passwords")]
+    pub fn change_authentication_key_from_password(
+        &mut self,
+        key_id: object::Id,
+        password: &[u8],
+    ) -> Result<object::Id, Error> {
+        self.change_authentication_key(
+            key_id,
+            authentication::Algorithm::YubicoAes,
+            authentication::Key::derive_from_password(password),
+        )
+    }
+
+    /// Reset the HSM to a factory default state, provided the given token
+    /// (obtained from `prepare_reset`) was issued for this HSM and hasn't
+    /// expired.
+    ///
+    /// **WARNING:** This wipes all keys and other data from the HSM! Make
+    /// absolutely sure you want to use this!
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Reset_Device.html>
+    pub fn confirm_reset(&self, token: device::ResetToken) -> Result<(), Error> {
+        ensure!(
+            !token.is_expired(),
+            ErrorKind::ResetTokenInvalid,
+            "reset token expired (tokens are valid for {:?})",
+            device::RESET_TOKEN_LIFETIME
+        );
+
+        let serial_number = self.device_info()?.serial_number;
+
+        ensure!(
+            token.serial_number() == serial_number,
+            ErrorKind::ResetTokenInvalid,
+            "reset token was issued for HSM {} (connected to {})",
+            token.serial_number(),
+            serial_number
+        );
+
+        self.reset_device()
+    }
+
+    /// Create a Yubico OTP AEAD from the given YubiKey OTP key and private
+    /// ID, encrypted under the given OTP AEAD key.
+    ///
+    /// The OTP key must be `otp::OTP_KEY_SIZE` bytes and the private ID
+    /// `otp::PRIVATE_ID_SIZE` bytes.
+    ///
+    /// **WARNING**: This functionality has not been tested and has not yet been
+    /// confirmed to actually work! USE AT YOUR OWN RISK!
+    ///
+    /// You will need to enable the `untested` cargo feature to use it.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Create_Otp_Aead.html>
+    #[cfg(feature = "untested")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "untested")))]
+    pub fn create_otp_aead(
+        &self,
+        key_id: object::Id,
+        otp_key: &[u8],
+        private_id: &[u8],
+    ) -> Result<otp::Aead, Error> {
+        ensure!(
+            otp_key.len() == otp::OTP_KEY_SIZE,
+            ErrorKind::ProtocolError,
+            "expected {}-byte OTP key, got {}",
+            otp::OTP_KEY_SIZE,
+            otp_key.len()
+        );
+
+        ensure!(
+            private_id.len() == otp::PRIVATE_ID_SIZE,
+            ErrorKind::ProtocolError,
+            "expected {}-byte OTP private ID, got {}",
+            otp::PRIVATE_ID_SIZE,
+            private_id.len()
+        );
+
+        let mut command = CreateOtpAeadCommand {
+            key_id,
+            otp_key: [0u8; otp::OTP_KEY_SIZE],
+            private_id: [0u8; otp::PRIVATE_ID_SIZE],
+        };
+
+        command.otp_key.copy_from_slice(otp_key);
+        command.private_id.copy_from_slice(private_id);
+
+        Ok(self.send_command(command)?.0)
+    }
+
+    /// Decrypt data which was encrypted with RSA-OAEP (e.g. a wrapped AES
+    /// key) using the given RSA key, returning the plaintext.
+    ///
+    /// `label_hash` is the digest of the OAEP label (e.g. the SHA-256 digest
+    /// of an empty string), and `mgf1_hash_alg` the hash used by MGF1. The
+    /// ciphertext must be the size of the key's modulus, which is checked
+    /// before sending the command. The key's algorithm is fetched the first
+    /// time it's used and cached.
+    ///
+    /// **WARNING**: This functionality has not been tested and has not yet been
+    /// confirmed to actually work! USE AT YOUR OWN RISK!
+    ///
+    /// You will need to enable the `untested` cargo feature to use it.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Decrypt_Oaep.html>
+    #[cfg(feature = "untested")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "untested")))]
+    pub fn decrypt_oaep(
+        &self,
+        key_id: object::Id,
+        mgf1_hash_alg: rsa::mgf::Algorithm,
+        label_hash: &[u8],
+        ciphertext: &[u8],
+    ) -> Result<Vec<u8>, Error> {
+        ensure!(
+            [20, 32, 48, 64].contains(&label_hash.len()),
+            ErrorKind::ProtocolError,
+            "invalid OAEP label hash length: {} bytes (must be a SHA-1 or SHA-2 digest)",
+            label_hash.len()
+        );
+
+        self.ensure_rsa_ciphertext_len(key_id, ciphertext.len())?;
+
+        Ok(self
+            .send_command(DecryptOaepCommand {
+                key_id,
+                mgf1_hash_alg,
+                ciphertext: ciphertext.into(),
+                label_hash: label_hash.into(),
+            })?
+            .into())
+    }
+
+    /// Decrypt data which was encrypted with RSA PKCS#1v1.5 padding using
+    /// the given RSA key, returning the plaintext.
+    ///
+    /// The ciphertext must be the size of the key's modulus, which is checked
+    /// before sending the command. The HSM removes the padding itself: if
+    /// that fails (or the ciphertext is otherwise invalid), this returns
+    /// `ErrorKind::DecryptFailed`, which deliberately doesn't distinguish
+    /// between causes. PKCS#1v1.5 decryption is susceptible to padding
+    /// oracle attacks (e.g. Bleichenbacher's), so avoid revealing why
+    /// decryption failed to whoever supplied the ciphertext, and prefer
+    /// `decrypt_oaep` for new protocols.
+    ///
+    /// **WARNING**: This functionality has not been tested and has not yet been
+    /// confirmed to actually work! USE AT YOUR OWN RISK!
+    ///
+    /// You will need to enable the `untested` cargo feature to use it.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Decrypt_Pkcs1.html>
+    #[cfg(feature = "untested")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "untested")))]
+    pub fn decrypt_pkcs1v15(
+        &self,
+        key_id: object::Id,
+        ciphertext: &[u8],
+    ) -> Result<Vec<u8>, Error> {
+        self.ensure_rsa_ciphertext_len(key_id, ciphertext.len())?;
+
+        self.send_command(DecryptPkcs1Command {
+            key_id,
+            ciphertext: ciphertext.into(),
+        })
+        .map(Into::into)
+        .map_err(|e| match e.device_error() {
+            Some(device::ErrorKind::InvalidData) => ErrorKind::DecryptFailed.context(e).into(),
+            _ => e,
+        })
+    }
+
+    /// Decrypt a Yubico OTP using the given OTP AEAD key, obtaining its
+    /// counters and timestamp.
+    ///
+    /// The AEAD must have been created under the same key (e.g. with
+    /// `create_otp_aead`), and the OTP must be in binary form (i.e. decoded
+    /// from modhex, without the public ID). Replay detection (checking the
+    /// counters increase) is the caller's responsibility.
+    ///
+    /// **WARNING**: This functionality has not been tested and has not yet been
+    /// confirmed to actually work! USE AT YOUR OWN RISK!
+    ///
+    /// You will need to enable the `untested` cargo feature to use it.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Decrypt_Otp.html>
+    #[cfg(feature = "untested")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "untested")))]
+    pub fn decrypt_otp(
+        &self,
+        key_id: object::Id,
+        aead: &otp::Aead,
+        otp: &[u8],
+    ) -> Result<otp::DecryptedOtp, Error> {
+        ensure!(
+            otp.len() == otp::OTP_SIZE,
+            ErrorKind::ProtocolError,
+            "expected {}-byte OTP, got {}",
+            otp::OTP_SIZE,
+            otp.len()
+        );
+
+        Ok(self
+            .send_command(DecryptOtpCommand {
+                key_id,
+                aead: aead.as_slice().into(),
+                otp: otp.into(),
+            })?
+            .into())
+    }
+
+    /// Ensure the given key is an RSA key whose modulus is the given size
+    #[cfg(feature = "untested")]
+    fn ensure_rsa_ciphertext_len(
+        &self,
+        key_id: object::Id,
+        ciphertext_len: usize,
+    ) -> Result<(), Error> {
+        let algorithm = self
+            .cached_key_info(key_id, object::Type::AsymmetricKey)?
+            .algorithm;
+
+        let modulus_len = algorithm
+            .asymmetric()
+            .and_then(asymmetric::Algorithm::rsa_modulus_len)
+            .ok_or_else(|| {
+                format_err!(
+                    ErrorKind::ProtocolError,
+                    "key 0x{:04x} is not an RSA key (algorithm: {:?})",
+                    key_id,
+                    algorithm
+                )
+            })?;
+
+        ensure!(
+            ciphertext_len == modulus_len,
+            ErrorKind::ProtocolError,
+            "expected {}-byte ciphertext for {:?}, got {}",
+            modulus_len,
+            algorithm,
+            ciphertext_len
+        );
+
+        Ok(())
+    }
+
     /// Delete an object of the given ID and type.
     ///
+    /// Unless disabled with `set_lockout_protection`, deleting an
+    /// authentication key fails with `ErrorKind::WouldLockOut` if no other
+    /// key would be left with the `put-authentication-key` capability.
+    ///
     /// <https://developers.yubico.com/YubiHSM2/Commands/Delete_Object.html>
     pub fn delete_object(
         &self,
         object_id: object::Id,
         object_type: object::Type,
     ) -> Result<(), Error> {
+        if object_type == object::Type::AuthenticationKey && self.lockout_protection {
+            self.ensure_permitted::<DeleteObjectCommand>()?;
+            self.ensure_not_last_admin_key(object_id)?;
+        }
+
+        self.forget_key(object_id, object_type);
+
         self.send_command(DeleteObjectCommand {
             object_id,
             object_type,
@@ -213,6 +1206,12 @@ impl Client {
 
     /// Elliptic Curve Diffie-Hellman: derive a shared secret via key exchange.
     ///
+    /// The peer's public key must be an uncompressed point on the same curve
+    /// as the given key (see `asymmetric::Algorithm::ecdh_point_len`), which
+    /// is checked before sending the command. The key's algorithm is fetched
+    /// the first time it's used and cached. The returned shared secret is
+    /// the X coordinate of the shared point.
+    ///
     /// **WARNING**: This functionality has not been tested and has not yet been
     /// confirmed to actually work! USE AT YOUR OWN RISK!
     ///
@@ -225,35 +1224,173 @@ impl Client {
         &self,
         key_id: object::Id,
         public_key: ecdh::UncompressedPoint,
-    ) -> Result<ecdh::UncompressedPoint, Error> {
+    ) -> Result<Vec<u8>, Error> {
+        let algorithm = self
+            .cached_key_info(key_id, object::Type::AsymmetricKey)?
+            .algorithm;
+
+        let expected_len = algorithm
+            .asymmetric()
+            .and_then(asymmetric::Algorithm::ecdh_point_len)
+            .ok_or_else(|| {
+                format_err!(
+                    ErrorKind::ProtocolError,
+                    "key 0x{:04x} is not an ECDH key (algorithm: {:?})",
+                    key_id,
+                    algorithm
+                )
+            })?;
+
+        ensure!(
+            public_key.as_slice().len() == expected_len,
+            ErrorKind::ProtocolError,
+            "expected {}-byte public key for {:?}, got {}",
+            expected_len,
+            algorithm,
+            public_key.as_slice().len()
+        );
+
         Ok(self
             .send_command(DeriveEcdhCommand { key_id, public_key })?
             .into())
     }
 
-    /// Get information about the HSM device.
+    /// Get information about the HSM device.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Device_Info.html>
+    pub fn device_info(&self) -> Result<device::Info, Error> {
+        Ok(self.send_command(DeviceInfoCommand {})?.into())
+    }
+
+    /// Diagnose whether the current session is permitted to perform the
+    /// given command on the given object, checking the capabilities and
+    /// domains of the session's authentication key, the capabilities of
+    /// the object, and (for commands which create objects) the session
+    /// key's delegated capabilities.
+    ///
+    /// For commands which create objects, the object's current capabilities
+    /// are checked against the delegated capabilities (i.e. whether it could
+    /// be re-created with the same policy). Commands which don't operate on
+    /// an object ignore `object_id` and `object_type`.
+    ///
+    /// Nothing is sent to the HSM besides `Get Object Info` commands.
+    pub fn diagnose_operation(
+        &self,
+        command: command::Code,
+        object_id: object::Id,
+        object_type: object::Type,
+    ) -> Result<OperationDiagnosis, Error> {
+        let (key_id, key_info) = self.session_key_id_and_info()?;
+
+        let object_info = match self.get_object_info(object_id, object_type) {
+            Ok(info) => Some(info),
+            Err(e) if e.device_error() == Some(device::ErrorKind::ObjectNotFound) => None,
+            Err(e) => return Err(e),
+        };
+
+        let required_capability = if command == command::Code::DeleteObject {
+            DeleteObjectCommand {
+                object_id,
+                object_type,
+            }
+            .required_capability()
+        } else {
+            command.required_capability()
+        };
+
+        Ok(OperationDiagnosis::new(
+            command,
+            required_capability,
+            key_id,
+            key_info.as_ref(),
+            object::Handle::new(object_id, object_type),
+            object_info.as_ref(),
+        ))
+    }
+
+    /// Compare the capabilities and domains of objects in the HSM against
+    /// the given policies, returning the differences for every object which
+    /// doesn't match (including objects which don't exist).
     ///
-    /// <https://developers.yubico.com/YubiHSM2/Commands/Device_Info.html>
-    pub fn device_info(&self) -> Result<device::Info, Error> {
-        Ok(self.send_command(DeviceInfoCommand {})?.into())
+    /// Objects in the HSM without an entry in `policies` are ignored.
+    pub fn diff_objects(
+        &self,
+        policies: &BTreeMap<object::Handle, object::Policy>,
+    ) -> Result<BTreeMap<object::Handle, object::Diff>, Error> {
+        let mut diffs = BTreeMap::new();
+
+        for (handle, policy) in policies {
+            let diff = match self.get_object_info(handle.object_id, handle.object_type) {
+                Ok(info) => info.diff(policy),
+                Err(e) if e.device_error() == Some(device::ErrorKind::ObjectNotFound) => {
+                    object::Diff::missing(policy)
+                }
+                Err(e) => return Err(e),
+            };
+
+            if !diff.is_empty() {
+                diffs.insert(handle.clone(), diff);
+            }
+        }
+
+        Ok(diffs)
     }
 
     /// Echo a message sent to the HSM.
     ///
+    /// Messages which don't fit in a single encrypted session message (i.e.
+    /// longer than 2028 bytes) are rejected without being sent.
+    ///
     /// <https://developers.yubico.com/YubiHSM2/Commands/Echo.html>
     pub fn echo<M>(&self, msg: M) -> Result<Vec<u8>, Error>
     where
         M: Into<Vec<u8>>,
     {
-        Ok(self
-            .send_command(EchoCommand {
-                message: msg.into(),
-            })?
-            .0)
+        let message = msg.into();
+
+        ensure!(
+            message.len() <= MAX_ECHO_SIZE,
+            ErrorKind::ProtocolError,
+            "echo message too long: {} bytes (max: {})",
+            message.len(),
+            MAX_ECHO_SIZE
+        );
+
+        Ok(self.send_command(EchoCommand { message })?.0)
+    }
+
+    /// Export the public keys of all asymmetric keys stored in the HSM.
+    ///
+    /// Lists asymmetric key objects and fetches the public key of each.
+    /// Keys whose public component can't be retrieved (i.e. the device
+    /// returns an error for that particular key) are skipped.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Get_Public_Key.html>
+    pub fn export_public_keys(&self) -> Result<Vec<(object::Id, PublicKey)>, Error> {
+        let objects = self.list_objects(&[object::Filter::Type(object::Type::AsymmetricKey)])?;
+        let mut public_keys = Vec::with_capacity(objects.len());
+
+        for entry in objects {
+            match self.get_public_key(entry.object_id) {
+                Ok(public_key) => public_keys.push((entry.object_id, public_key)),
+                Err(e) if e.device_error().is_some() => {
+                    debug!(
+                        "skipping public key for asymmetric key 0x{:04x}: {}",
+                        entry.object_id, e
+                    );
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(public_keys)
     }
 
     /// Export an encrypted object from the HSM using the given key-wrapping key.
     ///
+    /// Fails with `ErrorKind::DomainMismatch` (before sending the command)
+    /// if the wrap key's domains don't include all of the object's domains.
+    ///
     /// <https://developers.yubico.com/YubiHSM2/Commands/Export_Wrapped.html>
     pub fn export_wrapped(
         &self,
@@ -261,6 +1398,8 @@ impl Client {
         object_type: object::Type,
         object_id: object::Id,
     ) -> Result<wrap::Message, Error> {
+        self.ensure_wrap_domains(wrap_key_id, object_type, object_id)?;
+
         Ok(self
             .send_command(ExportWrappedCommand {
                 wrap_key_id,
@@ -270,6 +1409,23 @@ impl Client {
             .0)
     }
 
+    /// Export an encrypted object from the HSM using the given key-wrapping
+    /// key, writing it to a file in the format used by `yubihsm-shell`.
+    ///
+    /// See `wrap::Message::write_file` for the file format.
+    pub fn export_wrapped_to_file<P: AsRef<Path>>(
+        &self,
+        wrap_key_id: object::Id,
+        object_type: object::Type,
+        object_id: object::Id,
+        path: P,
+    ) -> Result<(), Error> {
+        self.export_wrapped(wrap_key_id, object_type, object_id)?
+            .write_file(path)?;
+
+        Ok(())
+    }
+
     /// Generate a new asymmetric key within the HSM.
     ///
     /// <https://developers.yubico.com/YubiHSM2/Commands/Generate_Asymmetric_Key.html>
@@ -281,7 +1437,7 @@ impl Client {
         capabilities: Capability,
         algorithm: asymmetric::Algorithm,
     ) -> Result<object::Id, Error> {
-        Ok(self
+        let key_id = self
             .send_command(GenAsymmetricKeyCommand(generate::Params {
                 key_id,
                 label,
@@ -289,7 +1445,82 @@ impl Client {
                 capabilities,
                 algorithm: algorithm.into(),
             }))?
-            .key_id)
+            .key_id;
+
+        self.forget_key(key_id, object::Type::AsymmetricKey);
+        Ok(key_id)
+    }
+
+    /// Generate a new asymmetric key within the HSM, then fetch its public key.
+    ///
+    /// This is a convenience wrapper which sends two commands (the HSM doesn't
+    /// return the public key when generating a key). If generating the key
+    /// succeeds but fetching its public key fails, an error of kind
+    /// `ErrorKind::PublicKeyFetchFailed` is returned: the key exists in the
+    /// HSM, and its public key can be fetched later with `get_public_key`.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Generate_Asymmetric_Key.html>
+    pub fn generate_asymmetric_key_with_public(
+        &self,
+        key_id: object::Id,
+        label: object::Label,
+        domains: Domain,
+        capabilities: Capability,
+        algorithm: asymmetric::Algorithm,
+    ) -> Result<(object::Id, PublicKey), Error> {
+        let key_id =
+            self.generate_asymmetric_key(key_id, label, domains, capabilities, algorithm)?;
+
+        let public_key = self
+            .get_public_key(key_id)
+            .map_err(|e| ErrorKind::PublicKeyFetchFailed.context(e))?;
+
+        Ok((key_id, public_key))
+    }
+
+    /// Generate a new asymmetric key within the HSM, then fetch its public key
+    /// encoded in the given `asymmetric::PointFormat`.
+    ///
+    /// The format is checked against the key's algorithm before the key is
+    /// generated. See `generate_asymmetric_key_with_public` for how failures
+    /// to fetch the public key are reported.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Generate_Asymmetric_Key.html>
+    pub fn generate_asymmetric_key_with_encoded_public(
+        &self,
+        key_id: object::Id,
+        label: object::Label,
+        domains: Domain,
+        capabilities: Capability,
+        algorithm: asymmetric::Algorithm,
+        format: asymmetric::PointFormat,
+    ) -> Result<(object::Id, Vec<u8>), Error> {
+        ensure!(
+            format.is_supported(algorithm),
+            ErrorKind::ProtocolError,
+            "{:?} public keys can't be encoded as {:?}",
+            algorithm,
+            format
+        );
+
+        let (key_id, public_key) = self.generate_asymmetric_key_with_public(
+            key_id,
+            label,
+            domains,
+            capabilities,
+            algorithm,
+        )?;
+
+        let encoded = public_key.encode(format).ok_or_else(|| {
+            format_err!(
+                ErrorKind::ProtocolError,
+                "couldn't encode public key of 0x{:04x} as {:?}",
+                key_id,
+                format
+            )
+        })?;
+
+        Ok((key_id, encoded))
     }
 
     /// Generate a new HMAC key within the HSM.
@@ -303,7 +1534,7 @@ impl Client {
         capabilities: Capability,
         algorithm: hmac::Algorithm,
     ) -> Result<object::Id, Error> {
-        Ok(self
+        let key_id = self
             .send_command(GenHmacKeyCommand(generate::Params {
                 key_id,
                 label,
@@ -311,7 +1542,10 @@ impl Client {
                 capabilities,
                 algorithm: algorithm.into(),
             }))?
-            .key_id)
+            .key_id;
+
+        self.forget_key(key_id, object::Type::HmacKey);
+        Ok(key_id)
     }
 
     /// Generate a new wrap key within the HSM.
@@ -343,6 +1577,78 @@ impl Client {
             .key_id)
     }
 
+    /// Generate a random nonce for a `wrap::Message` encrypted on the host
+    /// (e.g. data encrypted under a copy of a wrap key, to be decrypted by
+    /// `unwrap_data`), using the client's RNG (see `set_rng`).
+    pub fn generate_wrap_nonce(&self) -> wrap::Nonce {
+        wrap::Nonce::random(&mut **self.rng.lock().unwrap())
+    }
+
+    /// Get the algorithms which are enabled on the HSM (i.e. not disabled
+    /// with `put_algorithm_toggle`). Requires firmware 2.2 or later.
+    ///
+    /// Algorithms this crate doesn't recognize are omitted.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Get_Option.html>
+    pub fn get_algorithm_toggle(&self) -> Result<Vec<Algorithm>, Error> {
+        let response = self.send_command(GetOptionCommand {
+            tag: AuditTag::Algorithm,
+        })?;
+
+        ensure!(
+            response.0.len() % 2 == 0,
+            ErrorKind::ProtocolError,
+            "expected algorithm/option pairs, got {}-byte response",
+            response.0.len()
+        );
+
+        let mut enabled = vec![];
+
+        for pair in response.0.chunks(2) {
+            let option = AuditOption::from_u8(pair[1])
+                .map_err(|e| format_err!(ErrorKind::ProtocolError, e))?;
+
+            match Algorithm::from_u8(pair[0]) {
+                Ok(algorithm) if option != AuditOption::Off => enabled.push(algorithm),
+                Ok(_) => (),
+                Err(_) => debug!("ignoring unknown algorithm ID: 0x{:02x}", pair[0]),
+            }
+        }
+
+        Ok(enabled)
+    }
+
+    /// Get the attestation certificate template associated with the given
+    /// attestation key.
+    ///
+    /// See [`Client::put_attestation_template`] for how templates are stored.
+    pub fn get_attestation_template(
+        &self,
+        attestation_key_id: object::Id,
+    ) -> Result<Vec<u8>, Error> {
+        self.ensure_attestation_template(attestation_key_id)?;
+        self.get_opaque(attestation_key_id)
+    }
+
+    /// Get audit logs from the HSM device, along with whether auditing is
+    /// actually enabled (i.e. the force audit option is on, or at least one
+    /// command has its audit option set to `On` or `Fix`).
+    ///
+    /// This sends two `get_option` commands in addition to `get_log_entries`.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Concepts/Logs.html>
+    pub fn get_audit_state(&self) -> Result<AuditState, Error> {
+        let force = self.get_force_audit_option()?;
+
+        let commands_audited = self
+            .get_commands_audit_options()?
+            .iter()
+            .any(|command| command.audit_option() != AuditOption::Off);
+
+        let log_entries = self.get_log_entries()?;
+        Ok(AuditState::new(force, commands_audited, log_entries))
+    }
+
     /// Get audit logs from the HSM device.
     ///
     /// <https://developers.yubico.com/YubiHSM2/Commands/Get_Log_Entries.html>
@@ -457,8 +1763,75 @@ impl Client {
         Ok(self.send_command(GetTemplateCommand { object_id })?.0)
     }
 
+    /// Create an `hmac::Signer` for the given HMAC key, fetching the key's
+    /// algorithm once up front.
+    pub fn hmac_signer(&self, key_id: object::Id) -> Result<hmac::Signer, Error> {
+        hmac::Signer::create(self.clone(), key_id)
+    }
+
+    /// Import a batch of encrypted objects (e.g. a backup) with the given
+    /// key-wrapping key, in order, stopping at the first failure.
+    ///
+    /// With `wrap::ImportMode::Rollback`, the objects imported before the
+    /// failure are then deleted again (most recently imported first), so the
+    /// HSM isn't left partially restored. Only objects imported by this call
+    /// are deleted: objects which already existed cause their import to fail
+    /// (the HSM doesn't overwrite objects), and are left as they were.
+    ///
+    /// This is best effort, not atomic: see `wrap::ImportReport` for how the
+    /// final state of the HSM is reported.
+    pub fn import_all<I>(
+        &self,
+        wrap_key_id: object::Id,
+        wrap_messages: I,
+        mode: wrap::ImportMode,
+    ) -> wrap::ImportReport
+    where
+        I: IntoIterator<Item = wrap::Message>,
+    {
+        let mut report = wrap::ImportReport::default();
+
+        for (index, wrap_message) in wrap_messages.into_iter().enumerate() {
+            match self.import_wrapped(wrap_key_id, wrap_message) {
+                Ok(handle) => report.imported.push(handle),
+                Err(e) => {
+                    warn!("error importing object #{} of batch: {}", index, e);
+                    report.failure = Some(wrap::ImportFailure {
+                        index,
+                        error: e.to_string(),
+                    });
+                    break;
+                }
+            }
+        }
+
+        if report.failure.is_none() || mode != wrap::ImportMode::Rollback {
+            return report;
+        }
+
+        for handle in mem::take(&mut report.imported).into_iter().rev() {
+            match self.delete_object(handle.object_id, handle.object_type) {
+                Ok(()) => report.rolled_back.push(handle),
+                Err(e) => {
+                    warn!("error rolling back import of {:?}: {}", handle, e);
+                    report.imported.insert(0, handle.clone());
+                    report.rollback_failures.push(wrap::RollbackFailure {
+                        object: handle,
+                        error: e.to_string(),
+                    });
+                }
+            }
+        }
+
+        report
+    }
+
     /// Import an encrypted object from the HSM using the given key-wrapping key.
     ///
+    /// Unlike `export_wrapped`, the object's domains can't be checked against
+    /// the wrap key's beforehand since they're encrypted: if the wrap key
+    /// lacks any of them, the HSM rejects the import with a device error.
+    ///
     /// <https://developers.yubico.com/YubiHSM2/Commands/Import_Wrapped.html>
     pub fn import_wrapped<M>(
         &self,
@@ -476,12 +1849,71 @@ impl Client {
             ciphertext,
         })?;
 
+        self.forget_key(response.object_id, response.object_type);
+
         Ok(object::Handle::new(
             response.object_id,
             response.object_type,
         ))
     }
 
+    /// Import an encrypted object from a file produced by
+    /// `Client::export_wrapped_to_file` (or `yubihsm-shell`), decrypting it
+    /// with the given key-wrapping key.
+    pub fn import_wrapped_from_file<P: AsRef<Path>>(
+        &self,
+        wrap_key_id: object::Id,
+        path: P,
+    ) -> Result<object::Handle, Error> {
+        let wrap_message = wrap::Message::read_file(path)?;
+        self.import_wrapped(wrap_key_id, wrap_message)
+    }
+
+    /// Get an inventory of the objects visible from the current session,
+    /// whose fingerprint can be compared across HSMs to check they've been
+    /// provisioned identically (see `object::Inventory`).
+    ///
+    /// This sends one `get_object_info` command per object, and fails if
+    /// any of them fail.
+    #[cfg(feature = "sha2")]
+    pub fn inventory(&self) -> Result<object::Inventory, Error> {
+        let infos = self
+            .list_objects_detailed(&[])?
+            .into_iter()
+            .map(|(_, info)| info)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(object::Inventory::new(&infos))
+    }
+
+    /// Can the given object be exported with `export_wrapped`, i.e. does it
+    /// have the `exportable-under-wrap` capability?
+    pub fn is_exportable(
+        &self,
+        object_id: object::Id,
+        object_type: object::Type,
+    ) -> Result<bool, Error> {
+        Ok(self
+            .get_object_info(object_id, object_type)?
+            .capabilities
+            .contains(Capability::EXPORTABLE_UNDER_WRAP))
+    }
+
+    /// List objects which have been added, deleted, or recreated since the
+    /// given snapshot was taken (see `object::Changeset::snapshot`).
+    ///
+    /// This compares sequence numbers from a single `list_objects` call
+    /// with the snapshot, so attribute changes which don't bump an object's
+    /// sequence number won't be detected. Objects outside the current
+    /// session's domains are reported as deleted.
+    pub fn list_changed_objects(
+        &self,
+        snapshot: &object::Snapshot,
+    ) -> Result<object::Changeset, Error> {
+        let entries = self.list_objects(&[])?;
+        Ok(object::Changeset::new(snapshot, &entries))
+    }
+
     /// List objects visible from the current session.
     ///
     /// Optionally apply a set of provided `filters` which select objects
@@ -498,8 +1930,224 @@ impl Client {
         Ok(self.send_command(ListObjectsCommand(filter_bytes))?.0)
     }
 
+    /// List objects visible from the current session along with their full
+    /// `object::Info`.
+    ///
+    /// The HSM's list response only includes each object's ID, type and
+    /// sequence, so this sends one `get_object_info` command per object
+    /// (i.e. N+1 round trips in total). Failures to fetch individual objects
+    /// don't abort the operation, and are instead reported in their entry.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Get_Object_Info.html>
+    pub fn list_objects_detailed(
+        &self,
+        filters: &[object::Filter],
+    ) -> Result<Vec<object::DetailedEntry>, Error> {
+        Ok(self
+            .list_objects(filters)?
+            .into_iter()
+            .map(|entry| {
+                let info = self.get_object_info(entry.object_id, entry.object_type);
+                (entry, info)
+            })
+            .collect())
+    }
+
+    /// Find object IDs which are shared by objects of more than one type
+    /// (e.g. an asymmetric key and an opaque object both with ID 5), along
+    /// with the types using each of them.
+    ///
+    /// Objects are identified by their ID *and* type, so this is permitted,
+    /// but can be surprising to tools (and operators) which assume IDs are
+    /// unique. Only objects visible from the current session are considered.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/List_Objects.html>
+    pub fn list_shared_object_ids(
+        &self,
+    ) -> Result<BTreeMap<object::Id, BTreeSet<object::Type>>, Error> {
+        let mut types_by_id = BTreeMap::<_, BTreeSet<_>>::new();
+
+        for entry in self.list_objects(&[])? {
+            types_by_id
+                .entry(entry.object_id)
+                .or_default()
+                .insert(entry.object_type);
+        }
+
+        // `BTreeMap::retain` requires a newer Rust than our MSRV
+        Ok(types_by_id
+            .into_iter()
+            .filter(|(_, types)| types.len() > 1)
+            .collect())
+    }
+
+    /// Prepare to reset the HSM, returning a token which must be passed to
+    /// `confirm_reset` to actually perform the reset.
+    ///
+    /// The token is bound to this HSM's serial number and expires after
+    /// `device::RESET_TOKEN_LIFETIME`.
+    pub fn prepare_reset(&self) -> Result<device::ResetToken, Error> {
+        let serial_number = self.device_info()?.serial_number;
+        Ok(device::ResetToken::new(serial_number))
+    }
+
+    /// Replace the authentication key this client is currently using (e.g.
+    /// the default key in slot 0x01) with a newly imported one.
+    ///
+    /// This performs the first-boot provisioning ceremony in the following
+    /// order, so that at no point are we left without a working key:
+    ///
+    /// 1. Put the new authentication key into the HSM
+    /// 2. Reconnect using the new key and check it was stored as requested
+    /// 3. Delete the key we originally authenticated with
+    ///
+    /// If any step after the first fails, the client reconnects with its
+    /// original credentials and deletes the new key. The new key is only
+    /// deleted once reconnecting with the original credentials succeeds.
+    ///
+    /// On success, the client uses the new key for all subsequent sessions.
+    pub fn provision_initial_auth_key<K>(
+        &mut self,
+        new_key_id: object::Id,
+        label: object::Label,
+        domains: Domain,
+        capabilities: Capability,
+        delegated_capabilities: Capability,
+        authentication_key: K,
+    ) -> Result<(), Error>
+    where
+        K: Into<authentication::Key>,
+    {
+        let original_credentials = self.credentials.clone().ok_or_else(|| {
+            format_err!(
+                ErrorKind::AuthenticationError,
+                "session reconnection disabled; can't provision auth key"
+            )
+        })?;
+
+        let original_key_id = original_credentials.authentication_key_id;
+
+        ensure!(
+            new_key_id != original_key_id,
+            ErrorKind::ProtocolError,
+            "new auth key ID 0x{:04x} is the ID of the key currently in use",
+            new_key_id
+        );
+
+        let authentication_key = authentication_key.into();
+
+        self.put_authentication_key(
+            new_key_id,
+            label,
+            domains,
+            capabilities,
+            delegated_capabilities,
+            authentication::Algorithm::YubicoAes,
+            authentication_key.clone(),
+        )?;
+
+        self.reauthenticate(Credentials::new(new_key_id, authentication_key));
+
+        let result = self
+            .get_object_info(new_key_id, object::Type::AuthenticationKey)
+            .and_then(|info| {
+                ensure!(
+                    info.domains == domains
+                        && info.capabilities == capabilities
+                        && info.delegated_capabilities == delegated_capabilities,
+                    ErrorKind::VerifyFailed,
+                    "new auth key 0x{:04x} doesn't have the requested domains/capabilities",
+                    new_key_id
+                );
+                Ok(())
+            })
+            .and_then(|()| self.delete_object(original_key_id, object::Type::AuthenticationKey));
+
+        if let Err(e) = result {
+            error!(
+                "error provisioning auth key 0x{:04x}, rolling back: {}",
+                new_key_id, e
+            );
+            self.rollback_auth_key_provisioning(original_credentials, new_key_id);
+            return Err(e);
+        }
+
+        info!(
+            "provisioned auth key 0x{:04x} and deleted auth key 0x{:04x}",
+            new_key_id, original_key_id
+        );
+
+        Ok(())
+    }
+
+    /// Undo a partially completed `provision_initial_auth_key`
+    fn rollback_auth_key_provisioning(
+        &mut self,
+        original_credentials: Credentials,
+        new_key_id: object::Id,
+    ) {
+        let new_credentials = self.credentials.take();
+        self.reauthenticate(original_credentials);
+
+        // Only delete the new key if the original one still works, otherwise
+        // we'd lock ourselves out of the HSM
+        if let Err(e) = self.connect() {
+            error!(
+                "couldn't reconnect with original auth key; keeping auth key 0x{:04x}: {}",
+                new_key_id, e
+            );
+
+            if let Some(credentials) = new_credentials {
+                self.reauthenticate(credentials);
+            }
+
+            return;
+        }
+
+        if let Err(e) = self.delete_object(new_key_id, object::Type::AuthenticationKey) {
+            error!("error deleting auth key 0x{:04x}: {}", new_key_id, e);
+        }
+    }
+
+    /// Close the current session (if any) and use the given credentials when
+    /// opening the next one
+    fn reauthenticate(&mut self, credentials: Credentials) {
+        *self.session.lock().unwrap() = None;
+        self.credentials = Some(credentials);
+    }
+
+    /// Enable or disable the given algorithm on the HSM. Requires firmware
+    /// 2.2 or later.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Put_Option.html>
+    pub fn put_algorithm_toggle(&self, algorithm: Algorithm, enabled: bool) -> Result<(), Error> {
+        let option = if enabled {
+            AuditOption::On
+        } else {
+            AuditOption::Off
+        };
+
+        self.send_command(SetOptionCommand {
+            tag: AuditTag::Algorithm,
+            length: 2,
+            value: vec![algorithm.to_u8(), option.to_u8()],
+        })?;
+
+        Ok(())
+    }
+
     /// Put an existing asymmetric key into the HSM.
     ///
+    /// `key_bytes` must be exactly `algorithm.key_len()` bytes, laid out as
+    /// described by `algorithm.expected_import_layout()`:
+    ///
+    /// - RSA: the primes `p || q`, each big-endian and zero-padded to half
+    ///   the modulus size (e.g. 128 bytes each for RSA-2048)
+    /// - ECC: the private scalar `d`, big-endian and zero-padded to the size
+    ///   of the curve order (e.g. 32 bytes for P-256, 66 bytes for P-521)
+    /// - Ed25519: the 32-byte seed, i.e. the RFC 8032 private key (not the
+    ///   expanded secret scalar)
+    ///
     /// <https://developers.yubico.com/YubiHSM2/Commands/Put_Asymmetric.html>
     pub fn put_asymmetric_key<K>(
         &self,
@@ -525,7 +2173,7 @@ impl Client {
             );
         }
 
-        Ok(self
+        let key_id = self
             .send_command(PutAsymmetricKeyCommand {
                 params: object::put::Params {
                     id: key_id,
@@ -536,7 +2184,86 @@ impl Client {
                 },
                 data,
             })?
-            .key_id)
+            .key_id;
+
+        self.forget_key(key_id, object::Type::AsymmetricKey);
+        Ok(key_id)
+    }
+
+    /// Put an existing asymmetric key into the HSM, then check the public
+    /// key the HSM derived from it matches `expected_public_key`.
+    ///
+    /// If the public keys don't match, the imported key is deleted and an
+    /// error is returned. This catches mistakes in the format of the private
+    /// key (e.g. wrong curve or byte order) at import time, at the cost of an
+    /// extra round trip to the HSM.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Put_Asymmetric.html>
+    pub fn put_asymmetric_key_verified<K>(
+        &self,
+        key_id: object::Id,
+        label: object::Label,
+        domains: Domain,
+        capabilities: Capability,
+        algorithm: asymmetric::Algorithm,
+        key_bytes: K,
+        expected_public_key: &PublicKey,
+    ) -> Result<object::Id, Error>
+    where
+        K: Into<Vec<u8>>,
+    {
+        let key_id =
+            self.put_asymmetric_key(key_id, label, domains, capabilities, algorithm, key_bytes)?;
+
+        let public_key = self.get_public_key(key_id)?;
+
+        if &public_key != expected_public_key {
+            if let Err(e) = self.delete_object(key_id, object::Type::AsymmetricKey) {
+                error!(
+                    "error deleting mismatched asymmetric key 0x{:04x}: {}",
+                    key_id, e
+                );
+            }
+
+            fail!(
+                ErrorKind::VerifyFailed,
+                "public key of imported asymmetric key 0x{:04x} does not match expected key",
+                key_id
+            );
+        }
+
+        Ok(key_id)
+    }
+
+    /// Put an attestation certificate template into the HSM.
+    ///
+    /// When signing attestation certificates with a user-supplied attestation
+    /// key, the HSM builds them from an X.509 certificate stored as an opaque
+    /// object under the same object ID as the attestation key: its subject
+    /// becomes the issuer of the attestation certificates. This stores the
+    /// DER-encoded `certificate` as an `opaque::Algorithm::X509Certificate`
+    /// object with that ID.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Concepts/Attestation.html>
+    pub fn put_attestation_template<C>(
+        &self,
+        attestation_key_id: object::Id,
+        label: object::Label,
+        domains: Domain,
+        capabilities: Capability,
+        certificate: C,
+    ) -> Result<object::Id, Error>
+    where
+        C: Into<Vec<u8>>,
+    {
+        self.put_opaque(
+            attestation_key_id,
+            label,
+            domains,
+            capabilities,
+            opaque::Algorithm::X509Certificate,
+            certificate,
+        )
     }
 
     /// Put an existing `authentication::Key` into the HSM.
@@ -570,6 +2297,37 @@ impl Client {
             .key_id)
     }
 
+    /// Put a DER-encoded X.509 certificate into the HSM as an opaque object,
+    /// first checking that it's well-formed (see `opaque::validate_certificate`).
+    ///
+    /// Use `put_opaque` with `opaque::Algorithm::X509Certificate` to store
+    /// certificate data without validating it.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Put_Opaque.html>
+    pub fn put_certificate<C>(
+        &self,
+        object_id: object::Id,
+        label: object::Label,
+        domains: Domain,
+        capabilities: Capability,
+        certificate: C,
+    ) -> Result<object::Id, Error>
+    where
+        C: Into<Vec<u8>>,
+    {
+        let certificate = certificate.into();
+        opaque::validate_certificate(&certificate)?;
+
+        self.put_opaque(
+            object_id,
+            label,
+            domains,
+            capabilities,
+            opaque::Algorithm::X509Certificate,
+            certificate,
+        )
+    }
+
     /// Put an existing HMAC key into the HSM.
     ///
     /// <https://developers.yubico.com/YubiHSM2/Commands/Put_Hmac_Key.html>
@@ -598,7 +2356,7 @@ impl Client {
             );
         }
 
-        Ok(self
+        let key_id = self
             .send_command(PutHmacKeyCommand {
                 params: object::put::Params {
                     id: key_id,
@@ -609,7 +2367,10 @@ impl Client {
                 },
                 hmac_key,
             })?
-            .key_id)
+            .key_id;
+
+        self.forget_key(key_id, object::Type::HmacKey);
+        Ok(key_id)
     }
 
     /// Put an opaque object (X.509 certificate or other bytestring) into the HSM.
@@ -757,6 +2518,24 @@ impl Client {
             .object_id)
     }
 
+    /// Create a Yubico OTP AEAD from an OTP key and private ID randomly
+    /// generated by the HSM, encrypted under the given OTP AEAD key.
+    ///
+    /// The generated key and ID never leave the HSM unencrypted: use
+    /// `create_otp_aead` instead to program a YubiKey with a known key.
+    ///
+    /// **WARNING**: This functionality has not been tested and has not yet been
+    /// confirmed to actually work! USE AT YOUR OWN RISK!
+    ///
+    /// You will need to enable the `untested` cargo feature to use it.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Randomize_Otp_Aead.html>
+    #[cfg(feature = "untested")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "untested")))]
+    pub fn randomize_otp_aead(&self, key_id: object::Id) -> Result<otp::Aead, Error> {
+        Ok(self.send_command(RandomizeOtpAeadCommand { key_id })?.0)
+    }
+
     /// Reset the HSM to a factory default state and reboot, clearing all
     /// stored objects and restoring the default auth key.
     ///
@@ -765,6 +2544,7 @@ impl Client {
     ///
     /// <https://developers.yubico.com/YubiHSM2/Commands/Reset_Device.html>
     pub fn reset_device(&self) -> Result<(), Error> {
+        self.ensure_permitted::<ResetDeviceCommand>()?;
         let mut session = self.session()?;
 
         // TODO: handle potential errors that occur when resetting
@@ -772,8 +2552,9 @@ impl Client {
             debug!("error sending reset command: {}", e);
         }
 
-        // Resetting the HSM invalidates our session
+        // Resetting the HSM invalidates our session and all cached key info
         session.abort();
+        self.key_info.lock().unwrap().clear();
         Ok(())
     }
 
@@ -838,6 +2619,74 @@ impl Client {
         }
     }
 
+    /// Report what resetting the HSM would delete, without modifying it: the
+    /// number of objects of each type, and every authentication key which
+    /// would be lost. See `device::ResetImpact::warning` for a summary to
+    /// show before calling `confirm_reset`.
+    pub fn reset_impact(&self) -> Result<device::ResetImpact, Error> {
+        let serial_number = self.device_info()?.serial_number;
+        let mut object_counts = BTreeMap::new();
+        let mut authentication_keys = vec![];
+
+        for entry in self.list_objects(&[])? {
+            *object_counts.entry(entry.object_type).or_insert(0) += 1;
+
+            if entry.object_type == object::Type::AuthenticationKey {
+                authentication_keys
+                    .push(self.get_object_info(entry.object_id, object::Type::AuthenticationKey)?);
+            }
+        }
+
+        Ok(device::ResetImpact {
+            serial_number,
+            object_counts,
+            authentication_keys,
+        })
+    }
+
+    /// Re-encrypt a Yubico OTP AEAD from one OTP AEAD key to another,
+    /// without exposing the YubiKey's OTP key or private ID (e.g. when
+    /// rotating OTP AEAD keys).
+    ///
+    /// **WARNING**: This functionality has not been tested and has not yet been
+    /// confirmed to actually work! USE AT YOUR OWN RISK!
+    ///
+    /// You will need to enable the `untested` cargo feature to use it.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Rewrap_Otp_Aead.html>
+    #[cfg(feature = "untested")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "untested")))]
+    pub fn rewrap_otp_aead(
+        &self,
+        from_key_id: object::Id,
+        to_key_id: object::Id,
+        aead: &otp::Aead,
+    ) -> Result<otp::Aead, Error> {
+        Ok(self
+            .send_command(RewrapOtpAeadCommand {
+                from_key_id,
+                to_key_id,
+                aead: aead.clone(),
+            })?
+            .0)
+    }
+
+    /// Run host-side statistical tests (the FIPS 140-2 monobit, poker and
+    /// long run tests) over a sample of `get_pseudo_random` output.
+    ///
+    /// The HSM doesn't report the status of its internal RNG health tests,
+    /// so this is a weak sanity check which can only detect grossly broken
+    /// output: see `device::RngStatus` for details.
+    pub fn rng_self_test(&self) -> Result<device::RngStatus, Error> {
+        // Request the sample in two halves, which comfortably fit within a
+        // single (encrypted) response message
+        let half = device::RNG_SAMPLE_SIZE / 2;
+        let mut sample = self.get_pseudo_random(half)?;
+        sample.extend(self.get_pseudo_random(device::RNG_SAMPLE_SIZE - half)?);
+
+        Ok(device::RngStatus::from_sample(&sample))
+    }
+
     /// Configure the audit policy settings for a particular command, e.g. auditing
     /// should be `On`, `Off`, or `Fix` (i.e. fixed permanently on).
     ///
@@ -905,8 +2754,47 @@ impl Client {
         })?)
     }
 
+    /// Obtain an X.509 attestation certificate for a key within the HSM,
+    /// signed by the given attestation key and built from its certificate
+    /// template (see [`Client::put_attestation_template`]).
+    ///
+    /// Unlike [`Client::sign_attestation_certificate`], this checks that the
+    /// attestation key has a template and that it's an X.509 certificate
+    /// before asking the HSM to sign anything.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Sign_Attestation_Certificate.html>
+    pub fn sign_attestation_certificate_with_template(
+        &self,
+        key_id: object::Id,
+        attestation_key_id: object::Id,
+    ) -> Result<attestation::Certificate, Error> {
+        self.ensure_attestation_template(attestation_key_id)?;
+        self.sign_attestation_certificate(key_id, Some(attestation_key_id))
+    }
+
+    /// Ensure the opaque object stored under the given attestation key's ID
+    /// is an X.509 certificate usable as an attestation template
+    fn ensure_attestation_template(&self, attestation_key_id: object::Id) -> Result<(), Error> {
+        let info = self.get_object_info(attestation_key_id, object::Type::Opaque)?;
+
+        ensure!(
+            info.algorithm.opaque() == Some(opaque::Algorithm::X509Certificate),
+            ErrorKind::ProtocolError,
+            "object 0x{:04x} is not an attestation template (algorithm: {:?})",
+            attestation_key_id,
+            info.algorithm
+        );
+
+        Ok(())
+    }
+
     /// Compute an ECDSA signature of the given digest (i.e. a precomputed SHA-2 digest)
     ///
+    /// The digest's length must match the key's curve (see
+    /// `asymmetric::Algorithm::ecdsa_digest_len`), which is checked before
+    /// sending the command. The key's algorithm is fetched the first time
+    /// it's used and cached.
+    ///
     /// <https://developers.yubico.com/YubiHSM2/Commands/Sign_Ecdsa.html>
     ///
     /// # Security Warning
@@ -920,11 +2808,104 @@ impl Client {
     where
         T: Into<Vec<u8>>,
     {
-        self.send_command(SignEcdsaCommand {
+        let digest = digest.into();
+        let algorithm = self
+            .cached_key_info(key_id, object::Type::AsymmetricKey)?
+            .algorithm;
+
+        let expected_len = algorithm
+            .asymmetric()
+            .and_then(asymmetric::Algorithm::ecdsa_digest_len)
+            .ok_or_else(|| {
+                format_err!(
+                    ErrorKind::ProtocolError,
+                    "key 0x{:04x} is not an ECDSA key (algorithm: {:?})",
+                    key_id,
+                    algorithm
+                )
+            })?;
+
+        ensure!(
+            digest.len() == expected_len,
+            ErrorKind::ProtocolError,
+            "expected {}-byte digest for {:?}, got {}",
+            expected_len,
+            algorithm,
+            digest.len()
+        );
+
+        self.send_command(SignEcdsaCommand { key_id, digest })
+            .map(Into::into)
+    }
+
+    /// Compute an ECDSA signature of the SHA-384 digest of the given message,
+    /// returning it ASN.1 DER encoded.
+    ///
+    /// The key must be on a 384-bit curve (i.e. NIST P-384 or brainpoolP384r1)
+    /// so the digest matches the curve's order.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Sign_Ecdsa.html>
+    #[cfg(feature = "sha2")]
+    pub fn sign_ecdsa_sha384(&self, key_id: object::Id, msg: &[u8]) -> Result<Vec<u8>, Error> {
+        self.ensure_ecdsa_curve(
             key_id,
-            digest: digest.into(),
-        })
-        .map(Into::into)
+            &[
+                asymmetric::Algorithm::EcP384,
+                asymmetric::Algorithm::EcBp384,
+            ],
+            "SHA-384",
+        )?;
+
+        self.sign_ecdsa_prehash_raw(key_id, Sha384::digest(msg).as_slice())
+    }
+
+    /// Compute an ECDSA signature of the SHA-512 digest of the given message,
+    /// returning it ASN.1 DER encoded.
+    ///
+    /// The key must be on NIST P-521 or brainpoolP512r1.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Sign_Ecdsa.html>
+    #[cfg(feature = "sha2")]
+    pub fn sign_ecdsa_sha512(&self, key_id: object::Id, msg: &[u8]) -> Result<Vec<u8>, Error> {
+        self.ensure_ecdsa_curve(
+            key_id,
+            &[
+                asymmetric::Algorithm::EcP521,
+                asymmetric::Algorithm::EcBp512,
+            ],
+            "SHA-512",
+        )?;
+
+        self.sign_ecdsa_prehash_raw(key_id, Sha512::digest(msg).as_slice())
+    }
+
+    /// Ensure the given key is an ECDSA key on one of the given curves
+    #[cfg(feature = "sha2")]
+    fn ensure_ecdsa_curve(
+        &self,
+        key_id: object::Id,
+        curves: &[asymmetric::Algorithm],
+        hash_name: &str,
+    ) -> Result<(), Error> {
+        let info = self.cached_key_info(key_id, object::Type::AsymmetricKey)?;
+
+        ensure!(
+            info.capabilities.contains(Capability::SIGN_ECDSA),
+            ErrorKind::ProtocolError,
+            "key 0x{:04x} lacks the sign-ecdsa capability",
+            key_id
+        );
+
+        match info.algorithm.asymmetric() {
+            Some(alg) if curves.contains(&alg) => Ok(()),
+            _ => fail!(
+                ErrorKind::ProtocolError,
+                "{} digests can't be signed by key 0x{:04x} (algorithm: {:?})",
+                hash_name,
+                key_id,
+                info.algorithm
+            ),
+        }
     }
 
     /// Compute an Ed25519 signature with the given key ID.
@@ -971,6 +2952,8 @@ impl Client {
         key_id: object::Id,
         data: &[u8],
     ) -> Result<rsa::pkcs1::Signature, Error> {
+        rsa_modulus_len(&self.cached_key_info(key_id, object::Type::AsymmetricKey)?)?;
+
         Ok(self
             .send_command(SignPkcs1Command {
                 key_id,
@@ -979,47 +2962,213 @@ impl Client {
             .into())
     }
 
-    /// Compute an RSASSA-PSS signature of the SHA-256 hash of the given data with the given key ID.
+    /// Compute an RSASSA-PKCS#1v1.5 signature of the SHA-384 hash of the given data.
     ///
     /// **WARNING**: This functionality has not been tested and has not yet been
     /// confirmed to actually work! USE AT YOUR OWN RISK!
     ///
     /// You will need to enable the `untested` cargo feature to use it.
     ///
-    /// <https://developers.yubico.com/YubiHSM2/Commands/Sign_Pss.html>
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Sign_Pkcs1.html>
     #[cfg(feature = "untested")]
     #[cfg_attr(docsrs, doc(cfg(feature = "untested")))]
-    pub fn sign_rsa_pss_sha256(
+    pub fn sign_rsa_pkcs1v15_sha384(
+        &self,
+        key_id: object::Id,
+        data: &[u8],
+    ) -> Result<rsa::pkcs1::Signature, Error> {
+        rsa_modulus_len(&self.cached_key_info(key_id, object::Type::AsymmetricKey)?)?;
+
+        Ok(self
+            .send_command(SignPkcs1Command {
+                key_id,
+                digest: Sha384::digest(data).as_slice().into(),
+            })?
+            .into())
+    }
+
+    /// Compute an RSASSA-PKCS#1v1.5 signature of the SHA-512 hash of the given data.
+    ///
+    /// **WARNING**: This functionality has not been tested and has not yet been
+    /// confirmed to actually work! USE AT YOUR OWN RISK!
+    ///
+    /// You will need to enable the `untested` cargo feature to use it.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Sign_Pkcs1.html>
+    #[cfg(feature = "untested")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "untested")))]
+    pub fn sign_rsa_pkcs1v15_sha512(
         &self,
         key_id: object::Id,
         data: &[u8],
+    ) -> Result<rsa::pkcs1::Signature, Error> {
+        rsa_modulus_len(&self.cached_key_info(key_id, object::Type::AsymmetricKey)?)?;
+
+        Ok(self
+            .send_command(SignPkcs1Command {
+                key_id,
+                digest: Sha512::digest(data).as_slice().into(),
+            })?
+            .into())
+    }
+
+    /// Compute an RSASSA-PSS signature of the given digest with the given
+    /// key ID, using the given PSS parameters.
+    ///
+    /// `hash_alg` identifies the hash function used to compute `digest`.
+    /// Verifiers frequently require `mgf1_alg` to use the same hash function
+    /// and `salt_len` to equal the digest length: use
+    /// `sign_rsa_pss_sha256` if that is all you need.
+    ///
+    /// The key is checked to be an RSA key with the `SIGN_PSS` capability
+    /// whose modulus is large enough for the requested digest and salt.
+    ///
+    /// **WARNING**: This functionality has not been tested and has not yet been
+    /// confirmed to actually work! USE AT YOUR OWN RISK!
+    ///
+    /// You will need to enable the `untested` cargo feature to use it.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Sign_Pss.html>
+    #[cfg(feature = "untested")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "untested")))]
+    pub fn sign_rsa_pss(
+        &self,
+        key_id: object::Id,
+        digest: &[u8],
+        hash_alg: rsa::pss::Algorithm,
+        mgf1_alg: rsa::mgf::Algorithm,
+        salt_len: u16,
     ) -> Result<rsa::pss::Signature, Error> {
         ensure!(
-            data.len() > rsa::pss::MAX_MESSAGE_SIZE,
+            digest.len() == hash_alg.digest_len(),
+            ErrorKind::ProtocolError,
+            "invalid digest length for {:?}: {} (expected {})",
+            hash_alg,
+            digest.len(),
+            hash_alg.digest_len()
+        );
+
+        let info = self.cached_key_info(key_id, object::Type::AsymmetricKey)?;
+
+        ensure!(
+            info.capabilities.contains(Capability::SIGN_PSS),
             ErrorKind::ProtocolError,
-            "message too large to be signed (max: {})",
-            rsa::pss::MAX_MESSAGE_SIZE
+            "key 0x{:04x} lacks the sign-pss capability",
+            key_id
         );
 
-        let mut hasher = Sha256::default();
+        let modulus_len = rsa_modulus_len(&info)?;
 
-        let length = data.len() as u16;
-        hasher.update(&length.to_be_bytes());
-        hasher.update(data);
-        let digest = hasher.finalize();
+        // EMSA-PSS encoding requires emLen >= hLen + sLen + 2 (RFC 8017 9.1.1)
+        ensure!(
+            digest.len() + salt_len as usize + 2 <= modulus_len,
+            ErrorKind::ProtocolError,
+            "salt length {} too long for {}-bit key 0x{:04x} with {:?}",
+            salt_len,
+            modulus_len * 8,
+            key_id,
+            hash_alg
+        );
 
         Ok(self
             .send_command(SignPssCommand {
                 key_id,
-                mgf1_hash_alg: rsa::mgf::Algorithm::Sha256,
-                salt_len: digest.as_slice().len() as u16,
-                digest: digest.as_slice().into(),
+                mgf1_hash_alg: mgf1_alg,
+                salt_len,
+                digest: digest.into(),
             })?
             .into())
     }
 
+    /// Compute an RSASSA-PSS signature of the SHA-256 hash of the given data
+    /// with the given key ID, using MGF1 with SHA-256 and a 32-byte salt.
+    ///
+    /// **WARNING**: This functionality has not been tested and has not yet been
+    /// confirmed to actually work! USE AT YOUR OWN RISK!
+    ///
+    /// You will need to enable the `untested` cargo feature to use it.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Sign_Pss.html>
+    #[cfg(feature = "untested")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "untested")))]
+    pub fn sign_rsa_pss_sha256(
+        &self,
+        key_id: object::Id,
+        data: &[u8],
+    ) -> Result<rsa::pss::Signature, Error> {
+        let hash_alg = rsa::pss::Algorithm::Sha256;
+
+        self.sign_rsa_pss(
+            key_id,
+            Sha256::digest(data).as_slice(),
+            hash_alg,
+            rsa::mgf::Algorithm::Sha256,
+            hash_alg.digest_len() as u16,
+        )
+    }
+
+    /// Compute an RSASSA-PSS signature of the SHA-384 hash of the given data
+    /// with the given key ID, using MGF1 with SHA-384 and a 48-byte salt.
+    ///
+    /// **WARNING**: This functionality has not been tested and has not yet been
+    /// confirmed to actually work! USE AT YOUR OWN RISK!
+    ///
+    /// You will need to enable the `untested` cargo feature to use it.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Sign_Pss.html>
+    #[cfg(feature = "untested")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "untested")))]
+    pub fn sign_rsa_pss_sha384(
+        &self,
+        key_id: object::Id,
+        data: &[u8],
+    ) -> Result<rsa::pss::Signature, Error> {
+        let hash_alg = rsa::pss::Algorithm::Sha384;
+
+        self.sign_rsa_pss(
+            key_id,
+            Sha384::digest(data).as_slice(),
+            hash_alg,
+            rsa::mgf::Algorithm::Sha384,
+            hash_alg.digest_len() as u16,
+        )
+    }
+
+    /// Compute an RSASSA-PSS signature of the SHA-512 hash of the given data
+    /// with the given key ID, using MGF1 with SHA-512 and a 64-byte salt.
+    ///
+    /// **WARNING**: This functionality has not been tested and has not yet been
+    /// confirmed to actually work! USE AT YOUR OWN RISK!
+    ///
+    /// You will need to enable the `untested` cargo feature to use it.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Sign_Pss.html>
+    #[cfg(feature = "untested")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "untested")))]
+    pub fn sign_rsa_pss_sha512(
+        &self,
+        key_id: object::Id,
+        data: &[u8],
+    ) -> Result<rsa::pss::Signature, Error> {
+        let hash_alg = rsa::pss::Algorithm::Sha512;
+
+        self.sign_rsa_pss(
+            key_id,
+            Sha512::digest(data).as_slice(),
+            hash_alg,
+            rsa::mgf::Algorithm::Sha512,
+            hash_alg.digest_len() as u16,
+        )
+    }
+
     /// Sign an SSH certificate using the given template.
     ///
+    /// `algorithm` is the signature algorithm to use with the CA key (e.g.
+    /// `ecdsa::Algorithm::Sha256`), and `signature` is the signature over the
+    /// request and timestamp made with the template's timestamp key. The
+    /// returned certificate converts into its serialized bytes with
+    /// `Vec::from`.
+    ///
     /// **WARNING**: This functionality has not been tested and has not yet been
     /// confirmed to actually work! USE AT YOUR OWN RISK!
     ///
@@ -1052,6 +3201,32 @@ impl Client {
             .into())
     }
 
+    /// Is the given command expected to work on this HSM, i.e. does its
+    /// firmware implement the command, and does it support the algorithms
+    /// the command uses (see `command::Code::is_supported_by`)?
+    ///
+    /// The HSM's device info is fetched the first time this is called, and
+    /// cached for the lifetime of the client (and its clones), so callers
+    /// can check before each firmware-gated command and fail with a
+    /// descriptive error rather than an opaque response error.
+    pub fn supports(&self, command: command::Code) -> Result<bool, Error> {
+        let mut device_info = self.device_info.lock().unwrap();
+
+        if device_info.is_none() {
+            *device_info = Some(self.device_info()?);
+        }
+
+        Ok(command.is_supported_by(device_info.as_ref().unwrap()))
+    }
+
+    /// Is the given command known not to be supported by this HSM (see
+    /// `Client::supports`)? Returns `false` if that can't be determined.
+    fn is_unsupported(&self, command: command::Code) -> bool {
+        // `Device Info` is supported by all firmware, and checking whether it
+        // is would need to send it (with the device info lock held)
+        command != command::Code::DeviceInfo && matches!(self.supports(command), Ok(false))
+    }
+
     /// Decrypt data which was encrypted (using AES-CCM) under a wrap key.
     ///
     /// <https://developers.yubico.com/YubiHSM2/Commands/Unwrap_Data.html>
@@ -1072,15 +3247,47 @@ impl Client {
 
     /// Verify an HMAC tag of the given data with the given key ID.
     ///
+    /// The tag's length is checked against the key's HMAC algorithm (which
+    /// is fetched the first time the key is used and cached) before sending
+    /// the command: tags of the wrong length fail with
+    /// `ErrorKind::ProtocolError`, whereas tags which don't match the data
+    /// fail with `ErrorKind::ResponseError`.
+    ///
     /// <https://developers.yubico.com/YubiHSM2/Commands/Verify_Hmac.html>
     pub fn verify_hmac<M, T>(&self, key_id: object::Id, msg: M, tag: T) -> Result<(), Error>
     where
         M: Into<Vec<u8>>,
         T: Into<hmac::Tag>,
     {
+        let tag = tag.into();
+        let algorithm = self
+            .cached_key_info(key_id, object::Type::HmacKey)?
+            .algorithm;
+
+        let tag_len = algorithm
+            .hmac()
+            .map(hmac::Algorithm::tag_len)
+            .ok_or_else(|| {
+                format_err!(
+                    ErrorKind::ProtocolError,
+                    "key 0x{:04x} is not an HMAC key (algorithm: {:?})",
+                    key_id,
+                    algorithm
+                )
+            })?;
+
+        ensure!(
+            tag.len() == tag_len,
+            ErrorKind::ProtocolError,
+            "wrong tag length for {:?}: expected {} bytes, got {}",
+            algorithm,
+            tag_len,
+            tag.len()
+        );
+
         let result = self.send_command(VerifyHmacCommand {
             key_id,
-            tag: tag.into(),
+            tag,
             data: msg.into(),
         })?;
 
@@ -1107,3 +3314,20 @@ impl Client {
             .0)
     }
 }
+
+/// Get the modulus length in bytes of the given RSA key
+#[cfg(feature = "untested")]
+fn rsa_modulus_len(info: &object::Info) -> Result<usize, Error> {
+    match info.algorithm.asymmetric() {
+        // For RSA keys `key_len` is the length of the modulus in bytes
+        Some(alg @ asymmetric::Algorithm::Rsa2048)
+        | Some(alg @ asymmetric::Algorithm::Rsa3072)
+        | Some(alg @ asymmetric::Algorithm::Rsa4096) => Ok(alg.key_len()),
+        _ => fail!(
+            ErrorKind::ProtocolError,
+            "key 0x{:04x} is not an RSA key (algorithm: {:?})",
+            info.object_id,
+            info.algorithm
+        ),
+    }
+}
@@ -0,0 +1,285 @@
+//! Load balancing commands across several HSMs.
+//!
+//! An `HsmCluster` holds a `Client` for each HSM and dispatches each signing
+//! request to one of them, routing around devices which are unreachable.
+//!
+//! Keys are addressed by ID, so every HSM in a cluster must be provisioned
+//! identically: each key used through the cluster must exist with the same
+//! ID (and the same key material) on every device. The cluster doesn't check
+//! this itself, so compare the fingerprints of each device's
+//! `Client::inventory` when provisioning them.
+
+use super::{Client, Error, ErrorKind};
+use crate::{connector, ed25519, object};
+use anomaly::fail;
+use std::{
+    error::Error as _,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+/// Default amount of time to wait before retrying a device which failed
+pub const DEFAULT_RETRY_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Strategy for choosing which HSM handles a request
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Dispatch {
+    /// Cycle through the available devices in order
+    RoundRobin,
+
+    /// Use the available device with the fewest requests in flight
+    LeastLoaded,
+}
+
+/// Health of an individual HSM in a cluster
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DeviceHealth {
+    /// Is the device currently considered up?
+    pub up: bool,
+
+    /// Number of requests currently being handled by the device
+    pub in_flight: usize,
+
+    /// Number of requests the device has completed (including those which
+    /// failed with an HSM error, e.g. a missing key)
+    pub completed: u64,
+
+    /// Number of requests which failed because the device was unreachable
+    pub failures: u64,
+
+    /// Error from the most recent failure (if any)
+    pub last_error: Option<String>,
+}
+
+/// Mutable health tracking state for a device
+#[derive(Debug, Default)]
+struct HealthState {
+    /// When the device last failed, if it's currently considered down
+    down_since: Option<Instant>,
+
+    /// Number of completed requests
+    completed: u64,
+
+    /// Number of failed requests
+    failures: u64,
+
+    /// Most recent failure
+    last_error: Option<String>,
+}
+
+/// A `Client` along with its health
+struct Member {
+    /// Client for this HSM
+    client: Client,
+
+    /// Number of requests in flight
+    in_flight: AtomicUsize,
+
+    /// Health tracking state
+    health: Mutex<HealthState>,
+}
+
+/// Set of identically provisioned HSMs which signing requests are spread
+/// across (see module documentation).
+pub struct HsmCluster {
+    /// Devices in the cluster
+    members: Vec<Member>,
+
+    /// How to choose between devices
+    dispatch: Dispatch,
+
+    /// How long to wait before retrying a device which has failed
+    retry_interval: Duration,
+
+    /// Round-robin position
+    next: AtomicUsize,
+}
+
+impl HsmCluster {
+    /// Create a new cluster from clients for each of its HSMs
+    pub fn new(clients: impl IntoIterator<Item = Client>, dispatch: Dispatch) -> Self {
+        Self {
+            members: clients
+                .into_iter()
+                .map(|client| Member {
+                    client,
+                    in_flight: AtomicUsize::new(0),
+                    health: Mutex::new(HealthState::default()),
+                })
+                .collect(),
+            dispatch,
+            retry_interval: DEFAULT_RETRY_INTERVAL,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// Set how long to wait before sending requests to a device which
+    /// failed (default: `DEFAULT_RETRY_INTERVAL`)
+    pub fn set_retry_interval(&mut self, interval: Duration) {
+        self.retry_interval = interval;
+    }
+
+    /// Number of devices in the cluster
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    /// Does the cluster contain no devices?
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    /// Number of devices currently considered up
+    pub fn available(&self) -> usize {
+        self.health().iter().filter(|health| health.up).count()
+    }
+
+    /// Are any devices in the cluster down?
+    pub fn is_degraded(&self) -> bool {
+        self.available() < self.len()
+    }
+
+    /// Get the health of each device, in the order the clients were given
+    pub fn health(&self) -> Vec<DeviceHealth> {
+        self.members
+            .iter()
+            .map(|member| {
+                let health = member.health.lock().unwrap();
+
+                DeviceHealth {
+                    up: health.down_since.is_none(),
+                    in_flight: member.in_flight.load(Ordering::SeqCst),
+                    completed: health.completed,
+                    failures: health.failures,
+                    last_error: health.last_error.clone(),
+                }
+            })
+            .collect()
+    }
+
+    /// Compute an ECDSA signature of the given digest using the key with the
+    /// given ID on one of the HSMs (see `Client::sign_ecdsa_prehash_raw`)
+    pub fn sign_ecdsa_prehash_raw(
+        &self,
+        key_id: object::Id,
+        digest: &[u8],
+    ) -> Result<Vec<u8>, Error> {
+        self.dispatch(|client| client.sign_ecdsa_prehash_raw(key_id, digest))
+    }
+
+    /// Compute an Ed25519 signature of the given data using the key with the
+    /// given ID on one of the HSMs (see `Client::sign_ed25519`)
+    pub fn sign_ed25519(
+        &self,
+        key_id: object::Id,
+        data: &[u8],
+    ) -> Result<ed25519::Signature, Error> {
+        self.dispatch(|client| client.sign_ed25519(key_id, data))
+    }
+
+    /// Run the given operation against one of the HSMs.
+    ///
+    /// If the chosen device is unreachable, it's marked down and the
+    /// operation is retried against the remaining devices. Errors returned
+    /// by the HSM itself (e.g. a missing key) are returned as-is, since the
+    /// other devices are expected to be provisioned identically.
+    pub fn dispatch<T, F>(&self, operation: F) -> Result<T, Error>
+    where
+        F: Fn(&Client) -> Result<T, Error>,
+    {
+        let mut last_error = None;
+
+        for index in self.candidates() {
+            let member = &self.members[index];
+            member.in_flight.fetch_add(1, Ordering::SeqCst);
+            let result = operation(&member.client);
+            member.in_flight.fetch_sub(1, Ordering::SeqCst);
+
+            let mut health = member.health.lock().unwrap();
+
+            match result {
+                Err(err) if is_device_failure(&err) => {
+                    warn!("HSM #{} in cluster failed: {}", index, err);
+                    health.down_since = Some(Instant::now());
+                    health.failures += 1;
+                    health.last_error = Some(err.to_string());
+                    last_error = Some(err);
+                }
+                result => {
+                    health.down_since = None;
+                    health.completed += 1;
+                    return result;
+                }
+            }
+        }
+
+        match last_error {
+            Some(err) => Err(err),
+            None => fail!(ErrorKind::ConnectorError, "no HSMs in cluster"),
+        }
+    }
+
+    /// Indexes of the devices to try, in order of preference: devices which
+    /// are up (ordered by the dispatch strategy), followed by devices which
+    /// are down but due to be retried, followed by the remaining devices
+    /// (as a last resort, when nothing else is available)
+    fn candidates(&self) -> Vec<usize> {
+        let len = self.members.len();
+
+        if len == 0 {
+            return vec![];
+        }
+
+        let start = self.next.fetch_add(1, Ordering::SeqCst) % len;
+        let mut up = vec![];
+        let mut retry = vec![];
+        let mut down = vec![];
+
+        for index in (0..len).map(|i| (start + i) % len) {
+            match self.members[index].health.lock().unwrap().down_since {
+                None => up.push(index),
+                Some(since) if since.elapsed() >= self.retry_interval => retry.push(index),
+                Some(_) => down.push(index),
+            }
+        }
+
+        if self.dispatch == Dispatch::LeastLoaded {
+            up.sort_by_key(|&index| self.members[index].in_flight.load(Ordering::SeqCst));
+        }
+
+        up.extend(retry);
+        up.extend(down);
+        up
+    }
+}
+
+/// Did the operation fail because the HSM couldn't be reached, as opposed
+/// to the HSM returning an error?
+fn is_device_failure(err: &Error) -> bool {
+    if matches!(
+        err.kind(),
+        ErrorKind::ConnectorError
+            | ErrorKind::CreateFailed
+            | ErrorKind::ClosedSessionError
+            | ErrorKind::TimeoutError
+    ) {
+        return true;
+    }
+
+    // Connector errors which occur within a session are reported as
+    // protocol errors, so look for them in the error's sources
+    let mut source = err.source();
+
+    while let Some(err) = source {
+        if err.is::<connector::Error>() {
+            return true;
+        }
+
+        source = err.source();
+    }
+
+    false
+}
@@ -0,0 +1,479 @@
+//! Diagnosing why a command is (or isn't) permitted
+
+use crate::{command, object, Capability};
+use std::fmt;
+
+/// Layers of permission checks the HSM performs before executing a command
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
+pub enum Layer {
+    /// Does the session's authentication key have the capability required
+    /// to send the command?
+    SessionKey,
+
+    /// Does the object the command operates on share at least one domain
+    /// with the session's authentication key?
+    Domains,
+
+    /// Does the object itself permit the operation (e.g. does a key have the
+    /// `sign-ecdsa` capability)?
+    Object,
+
+    /// For commands which create objects: do the session key's delegated
+    /// capabilities cover the object's capabilities?
+    Delegated,
+}
+
+impl fmt::Display for Layer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Layer::SessionKey => "session key",
+            Layer::Domains => "domains",
+            Layer::Object => "object",
+            Layer::Delegated => "delegated capabilities",
+        })
+    }
+}
+
+/// Outcome of checking a single `Layer`
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Check {
+    /// This layer isn't checked for the command
+    NotApplicable,
+
+    /// The check passed
+    Passed,
+
+    /// The check failed because these capabilities are missing
+    MissingCapabilities(Capability),
+
+    /// The object and the session key don't share any domains
+    NoCommonDomains,
+
+    /// The object being checked doesn't exist
+    ObjectNotFound,
+}
+
+impl Check {
+    /// Does this check permit the command (i.e. it passed or doesn't apply)?
+    pub fn is_ok(self) -> bool {
+        matches!(self, Check::NotApplicable | Check::Passed)
+    }
+
+    /// Check that `available` contains all of `required`
+    fn capabilities(available: Capability, required: Capability) -> Self {
+        let missing = required - available;
+
+        if missing.is_empty() {
+            Check::Passed
+        } else {
+            Check::MissingCapabilities(missing)
+        }
+    }
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Check::NotApplicable => f.write_str("not applicable"),
+            Check::Passed => f.write_str("passed"),
+            Check::MissingCapabilities(missing) => {
+                f.write_str("missing ")?;
+                write_capabilities(f, *missing)
+            }
+            Check::NoCommonDomains => f.write_str("no domains in common"),
+            Check::ObjectNotFound => f.write_str("object not found"),
+        }
+    }
+}
+
+/// Report on whether a command is permitted, broken down by `Layer`.
+///
+/// Returned by `Client::diagnose_operation`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OperationDiagnosis {
+    /// Command being diagnosed
+    pub command: command::Code,
+
+    /// Capabilities the session key needs to send the command
+    pub required_capability: Capability,
+
+    /// ID of the session's authentication key
+    pub authentication_key_id: object::Id,
+
+    /// Handle to the object the command operates on
+    pub object: object::Handle,
+
+    /// Result of the `Layer::SessionKey` check
+    pub session_key_check: Check,
+
+    /// Result of the `Layer::Domains` check
+    pub domains_check: Check,
+
+    /// Result of the `Layer::Object` check
+    pub object_check: Check,
+
+    /// Result of the `Layer::Delegated` check
+    pub delegated_check: Check,
+}
+
+impl OperationDiagnosis {
+    /// Diagnose a command from the object info of the session key and
+    /// object (`None` if they don't exist)
+    pub(crate) fn new(
+        command: command::Code,
+        required_capability: Capability,
+        authentication_key_id: object::Id,
+        session_key_info: Option<&object::Info>,
+        object: object::Handle,
+        object_info: Option<&object::Info>,
+    ) -> Self {
+        let session_key_check = match session_key_info {
+            Some(key) => Check::capabilities(key.capabilities, required_capability),
+            None => Check::ObjectNotFound,
+        };
+
+        let creates_object = creates_object(command);
+
+        let domains_check = match (session_key_info, object_info) {
+            _ if !targets_object(command) => Check::NotApplicable,
+            (Some(key), Some(info)) => {
+                if key.domains.intersects(info.domains) {
+                    Check::Passed
+                } else {
+                    Check::NoCommonDomains
+                }
+            }
+            (_, None) if !creates_object => Check::ObjectNotFound,
+            _ => Check::NotApplicable,
+        };
+
+        let object_check = match (object_capability(command, required_capability), object_info) {
+            (Some(required), Some(info)) => Check::capabilities(info.capabilities, required),
+            (Some(_), None) => Check::ObjectNotFound,
+            (None, _) => Check::NotApplicable,
+        };
+
+        let delegated_check = match (session_key_info, object_info) {
+            (Some(key), Some(info)) if creates_object => Check::capabilities(
+                key.delegated_capabilities,
+                info.capabilities | info.delegated_capabilities,
+            ),
+            _ => Check::NotApplicable,
+        };
+
+        Self {
+            command,
+            required_capability,
+            authentication_key_id,
+            object,
+            session_key_check,
+            domains_check,
+            object_check,
+            delegated_check,
+        }
+    }
+
+    /// Results of each layer's check, in the order the HSM performs them
+    pub fn checks(&self) -> [(Layer, Check); 4] {
+        [
+            (Layer::SessionKey, self.session_key_check),
+            (Layer::Domains, self.domains_check),
+            (Layer::Object, self.object_check),
+            (Layer::Delegated, self.delegated_check),
+        ]
+    }
+
+    /// Layers whose checks failed
+    pub fn failures(&self) -> Vec<(Layer, Check)> {
+        self.checks()
+            .iter()
+            .filter(|(_, check)| !check.is_ok())
+            .cloned()
+            .collect()
+    }
+
+    /// Do all of the checks permit the command?
+    pub fn is_permitted(&self) -> bool {
+        self.checks().iter().all(|(_, check)| check.is_ok())
+    }
+}
+
+impl fmt::Display for OperationDiagnosis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} on {:?} 0x{:04x} with key 0x{:04x}: {}",
+            self.command,
+            self.object.object_type,
+            self.object.object_id,
+            self.authentication_key_id,
+            if self.is_permitted() {
+                "permitted"
+            } else {
+                "not permitted"
+            }
+        )?;
+
+        for (layer, check) in &self.checks() {
+            write!(f, "\n  {}: {}", layer, check)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Does the command operate on an existing (or newly created) object?
+fn targets_object(command: command::Code) -> bool {
+    creates_object(command)
+        || object_capability(command, Capability::empty()).is_some()
+        || matches!(
+            command,
+            command::Code::DeleteObject
+                | command::Code::GetObjectInfo
+                | command::Code::GetOpaqueObject
+                | command::Code::GetPublicKey
+                | command::Code::GetTemplate
+        )
+}
+
+/// Does the command create a new object?
+fn creates_object(command: command::Code) -> bool {
+    matches!(
+        command,
+        command::Code::PutOpaqueObject
+            | command::Code::PutAuthenticationKey
+            | command::Code::PutAsymmetricKey
+            | command::Code::GenerateAsymmetricKey
+            | command::Code::ImportWrapped
+            | command::Code::PutWrapKey
+            | command::Code::PutHmacKey
+            | command::Code::GenerateHmacKey
+            | command::Code::GenerateWrapKey
+            | command::Code::PutTemplate
+            | command::Code::PutOtpAead
+            | command::Code::GenerateOtpAead
+    )
+}
+
+/// Capabilities the object a command operates on needs to have, if any.
+///
+/// Keys must have the capability for the operations they're used in, and
+/// objects being exported must be marked as `exportable-under-wrap`.
+fn object_capability(command: command::Code, required: Capability) -> Option<Capability> {
+    match command {
+        command::Code::ExportWrapped => Some(Capability::EXPORTABLE_UNDER_WRAP),
+        command::Code::SignPkcs1
+        | command::Code::DecryptPkcs1
+        | command::Code::SignHmac
+        | command::Code::SignPss
+        | command::Code::SignEcdsa
+        | command::Code::DeriveEcdh
+        | command::Code::DecryptOaep
+        | command::Code::VerifyHmac
+        | command::Code::SignSshCertificate
+        | command::Code::DecryptOtp
+        | command::Code::CreateOtpAead
+        | command::Code::RandomizeOtpAead
+        | command::Code::RewrapOtpAead
+        | command::Code::SignAttestationCertificate
+        | command::Code::WrapData
+        | command::Code::UnwrapData
+        | command::Code::SignEddsa => Some(required),
+        _ => None,
+    }
+}
+
+/// Write a comma-separated list of capability names
+fn write_capabilities(f: &mut fmt::Formatter<'_>, capabilities: Capability) -> fmt::Result {
+    let mut first = true;
+
+    for bit in 0..64 {
+        let capability = Capability::from_bits_truncate(1 << bit);
+
+        if !capabilities.contains(capability) {
+            continue;
+        }
+
+        if !first {
+            f.write_str(", ")?;
+        }
+
+        first = false;
+
+        // `Capability`'s `Display` impl doesn't support unknown capabilities
+        if capability.bits() > Capability::CHANGE_AUTHENTICATION_KEY.bits() {
+            write!(f, "0x{:x}", capability.bits())?;
+        } else {
+            write!(f, "{}", capability)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Algorithm, Domain};
+
+    fn info(object_type: object::Type, capabilities: Capability, domains: Domain) -> object::Info {
+        object::Info {
+            capabilities,
+            object_id: 1,
+            length: 32,
+            domains,
+            object_type,
+            algorithm: Algorithm::Asymmetric(crate::asymmetric::Algorithm::EcP256),
+            sequence: 0,
+            origin: object::Origin::Generated,
+            label: object::Label::default(),
+            delegated_capabilities: Capability::empty(),
+        }
+    }
+
+    fn diagnose(
+        command: command::Code,
+        session_key: Option<&object::Info>,
+        object: Option<&object::Info>,
+    ) -> OperationDiagnosis {
+        OperationDiagnosis::new(
+            command,
+            command.required_capability(),
+            1,
+            session_key,
+            object::Handle::new(2, object::Type::AsymmetricKey),
+            object,
+        )
+    }
+
+    #[test]
+    fn permitted_signing() {
+        let key = info(
+            object::Type::AuthenticationKey,
+            Capability::SIGN_ECDSA,
+            Domain::DOM1,
+        );
+        let object = info(
+            object::Type::AsymmetricKey,
+            Capability::SIGN_ECDSA,
+            Domain::DOM1 | Domain::DOM2,
+        );
+
+        let diagnosis = diagnose(command::Code::SignEcdsa, Some(&key), Some(&object));
+        assert!(diagnosis.is_permitted());
+        assert_eq!(diagnosis.delegated_check, Check::NotApplicable);
+    }
+
+    #[test]
+    fn each_layer_is_reported() {
+        let key = info(
+            object::Type::AuthenticationKey,
+            Capability::SIGN_EDDSA,
+            Domain::DOM1,
+        );
+        let object = info(
+            object::Type::AsymmetricKey,
+            Capability::SIGN_EDDSA,
+            Domain::DOM2,
+        );
+
+        let diagnosis = diagnose(command::Code::SignEcdsa, Some(&key), Some(&object));
+        assert_eq!(
+            diagnosis.failures(),
+            vec![
+                (
+                    Layer::SessionKey,
+                    Check::MissingCapabilities(Capability::SIGN_ECDSA)
+                ),
+                (Layer::Domains, Check::NoCommonDomains),
+                (
+                    Layer::Object,
+                    Check::MissingCapabilities(Capability::SIGN_ECDSA)
+                ),
+            ]
+        );
+        assert!(diagnosis
+            .to_string()
+            .contains("session key: missing sign-ecdsa"));
+    }
+
+    #[test]
+    fn missing_object() {
+        let key = info(
+            object::Type::AuthenticationKey,
+            Capability::SIGN_ECDSA,
+            Domain::DOM1,
+        );
+
+        let diagnosis = diagnose(command::Code::SignEcdsa, Some(&key), None);
+        assert_eq!(diagnosis.domains_check, Check::ObjectNotFound);
+        assert_eq!(diagnosis.object_check, Check::ObjectNotFound);
+        assert!(!diagnosis.is_permitted());
+    }
+
+    #[test]
+    fn delegated_capabilities() {
+        let mut key = info(
+            object::Type::AuthenticationKey,
+            Capability::GENERATE_ASYMMETRIC_KEY,
+            Domain::DOM1,
+        );
+        key.delegated_capabilities = Capability::SIGN_ECDSA;
+
+        let object = info(
+            object::Type::AsymmetricKey,
+            Capability::SIGN_ECDSA | Capability::EXPORTABLE_UNDER_WRAP,
+            Domain::DOM1,
+        );
+
+        let diagnosis = diagnose(
+            command::Code::GenerateAsymmetricKey,
+            Some(&key),
+            Some(&object),
+        );
+        assert_eq!(diagnosis.session_key_check, Check::Passed);
+        assert_eq!(diagnosis.object_check, Check::NotApplicable);
+        assert_eq!(
+            diagnosis.delegated_check,
+            Check::MissingCapabilities(Capability::EXPORTABLE_UNDER_WRAP)
+        );
+
+        // The object doesn't need to exist yet
+        let diagnosis = diagnose(command::Code::GenerateAsymmetricKey, Some(&key), None);
+        assert!(diagnosis.is_permitted());
+    }
+
+    #[test]
+    fn commands_without_objects() {
+        let key = info(
+            object::Type::AuthenticationKey,
+            Capability::empty(),
+            Domain::DOM1,
+        );
+
+        let diagnosis = diagnose(command::Code::GetPseudoRandom, Some(&key), None);
+        assert_eq!(
+            diagnosis.checks(),
+            [
+                (
+                    Layer::SessionKey,
+                    Check::MissingCapabilities(Capability::GET_PSEUDO_RANDOM)
+                ),
+                (Layer::Domains, Check::NotApplicable),
+                (Layer::Object, Check::NotApplicable),
+                (Layer::Delegated, Check::NotApplicable),
+            ]
+        );
+    }
+
+    #[test]
+    fn display_multiple_capabilities() {
+        let check = Check::MissingCapabilities(
+            Capability::REWRAP_FROM_OTP_AEAD_KEY | Capability::REWRAP_TO_OTP_AEAD_KEY,
+        );
+        assert_eq!(
+            check.to_string(),
+            "missing rewrap-from-otp-aead-key, rewrap-to-otp-aead-key"
+        );
+    }
+}
@@ -1,6 +1,6 @@
 //! YubiHSM client errors
 
-use crate::{connector, device, serialization, session};
+use crate::{command, connector, device, opaque, serialization, session, wrap, Capability};
 use anomaly::{BoxError, Context};
 use std::io;
 use thiserror::Error;
@@ -27,17 +27,74 @@ pub enum ErrorKind {
     #[error("couldn't create session")]
     CreateFailed,
 
+    /// Session's authentication key (or the object operated on) lacks the
+    /// capability required to perform a command
+    #[error("insufficient permissions: {0} command requires {1} capability")]
+    CapabilityRequired(command::Code, Capability),
+
+    /// HSM couldn't decrypt a ciphertext (e.g. because it's malformed, or
+    /// was encrypted under a different key)
+    #[error("decryption failed")]
+    DecryptFailed,
+
     /// Errors originating in the HSM device
     #[error("HSM error")]
     DeviceError,
 
+    /// Wrap key's domains don't include all of the domains of the object
+    /// being wrapped
+    #[error("wrap key domain mismatch")]
+    DomainMismatch,
+
+    /// Session's authentication key doesn't share any domains with the
+    /// object a command operates on
+    #[error("no shared domain: {0} command not permitted on object")]
+    NoSharedDomain(command::Code),
+
     /// Protocol error occurred
     #[error("protocol error")]
     ProtocolError,
 
+    /// Key was generated, but fetching its public key failed
+    #[error("couldn't fetch public key")]
+    PublicKeyFetchFailed,
+
+    /// Command would modify the HSM, but the client is in read-only mode
+    #[error("command not permitted in read-only mode: {0}")]
+    ReadOnly(command::Code),
+
+    /// Reset confirmation token is invalid or expired
+    #[error("invalid reset token")]
+    ResetTokenInvalid,
+
     /// Error response from HSM we can't further specify
     #[error("HSM response error")]
     ResponseError,
+
+    /// Current session's authentication key has been deleted
+    #[error("session authentication key not found")]
+    SessionKeyNotFound,
+
+    /// Operation did not complete before its deadline
+    #[error("operation timed out")]
+    TimeoutError,
+
+    /// Command isn't supported by the HSM (e.g. it requires newer firmware)
+    #[error("unsupported command: {0}")]
+    UnsupportedCommand(command::Code),
+
+    /// HSM's `CreateSession` response is longer than SCP03's, suggesting it
+    /// expects an authentication protocol this crate doesn't implement
+    #[error("unsupported authentication protocol")]
+    UnsupportedProtocol,
+
+    /// Verification of a result returned by the HSM failed
+    #[error("verification failed")]
+    VerifyFailed,
+
+    /// Operation would leave no authentication key able to administer the HSM
+    #[error("operation would lock out HSM")]
+    WouldLockOut,
 }
 
 impl Error {
@@ -53,6 +110,42 @@ impl Error {
     }
 }
 
+impl Error {
+    /// Convert an error which occurred sending the given command, reporting
+    /// permission errors as `ErrorKind::CapabilityRequired` (if the command
+    /// requires a single, known capability).
+    ///
+    /// The HSM also reports malformed commands as invalid, so commands it
+    /// rejects as invalid are only reported as `ErrorKind::UnsupportedCommand`
+    /// if `is_unsupported` confirms its firmware doesn't support them.
+    pub(crate) fn from_command_error(
+        command: command::Code,
+        required_capability: Capability,
+        err: session::Error,
+        is_unsupported: impl FnOnce() -> bool,
+    ) -> Self {
+        use std::error::Error;
+
+        let device_error = err
+            .source()
+            .and_then(|source| source.downcast_ref::<device::ErrorKind>());
+
+        match device_error {
+            Some(device::ErrorKind::InvalidCommand) if is_unsupported() => {
+                ErrorKind::UnsupportedCommand(command).context(err).into()
+            }
+            Some(device::ErrorKind::InsufficientPermissions)
+                if required_capability.bits().count_ones() == 1 =>
+            {
+                ErrorKind::CapabilityRequired(command, required_capability)
+                    .context(err)
+                    .into()
+            }
+            _ => err.into(),
+        }
+    }
+}
+
 impl ErrorKind {
     /// Create an error context from this error
     pub fn context(self, source: impl Into<BoxError>) -> Context<ErrorKind> {
@@ -78,6 +171,8 @@ impl From<session::Error> for Error {
             | session::ErrorKind::MismatchError
             | session::ErrorKind::VerifyFailed => ErrorKind::ProtocolError,
             session::ErrorKind::ResponseError => ErrorKind::ResponseError,
+            session::ErrorKind::TimeoutError => ErrorKind::TimeoutError,
+            session::ErrorKind::UnsupportedProtocol => ErrorKind::UnsupportedProtocol,
         };
 
         kind.context(err).into()
@@ -90,14 +185,110 @@ impl From<io::Error> for Error {
     }
 }
 
+impl From<opaque::Error> for Error {
+    fn from(err: opaque::Error) -> Self {
+        ErrorKind::ProtocolError.context(err).into()
+    }
+}
+
 impl From<serialization::Error> for Error {
     fn from(err: serialization::Error) -> Self {
         ErrorKind::ProtocolError.context(err).into()
     }
 }
 
+impl From<wrap::Error> for Error {
+    fn from(err: wrap::Error) -> Self {
+        ErrorKind::ProtocolError.context(err).into()
+    }
+}
+
 impl From<Error> for signature::Error {
     fn from(client_error: Error) -> signature::Error {
         signature::Error::from_source(client_error)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_command_unsupported_by_firmware_is_unsupported() {
+        let session_err = session::Error::from(device::ErrorKind::InvalidCommand);
+        let err = Error::from_command_error(
+            command::Code::SignEddsa,
+            Capability::SIGN_EDDSA,
+            session_err,
+            || true,
+        );
+
+        assert_eq!(
+            *err.kind(),
+            ErrorKind::UnsupportedCommand(command::Code::SignEddsa)
+        );
+        assert_eq!(err.device_error(), Some(device::ErrorKind::InvalidCommand));
+    }
+
+    #[test]
+    fn invalid_command_supported_by_firmware_is_unchanged() {
+        let session_err = session::Error::from(device::ErrorKind::InvalidCommand);
+        let err = Error::from_command_error(
+            command::Code::SignEddsa,
+            Capability::SIGN_EDDSA,
+            session_err,
+            || false,
+        );
+
+        assert_eq!(*err.kind(), ErrorKind::DeviceError);
+        assert_eq!(err.device_error(), Some(device::ErrorKind::InvalidCommand));
+    }
+
+    #[test]
+    fn other_device_errors_are_unchanged() {
+        let session_err = session::Error::from(device::ErrorKind::ObjectNotFound);
+        let err = Error::from_command_error(
+            command::Code::SignEddsa,
+            Capability::SIGN_EDDSA,
+            session_err,
+            || panic!("firmware support checked for non-InvalidCommand error"),
+        );
+
+        assert_eq!(*err.kind(), ErrorKind::DeviceError);
+        assert_eq!(err.device_error(), Some(device::ErrorKind::ObjectNotFound));
+    }
+
+    #[test]
+    fn insufficient_permissions_names_capability() {
+        let session_err = session::Error::from(device::ErrorKind::InsufficientPermissions);
+        let err = Error::from_command_error(
+            command::Code::SignEcdsa,
+            Capability::SIGN_ECDSA,
+            session_err,
+            || false,
+        );
+
+        assert_eq!(
+            *err.kind(),
+            ErrorKind::CapabilityRequired(command::Code::SignEcdsa, Capability::SIGN_ECDSA)
+        );
+        assert_eq!(
+            err.device_error(),
+            Some(device::ErrorKind::InsufficientPermissions)
+        );
+        assert!(err.to_string().contains("requires sign-ecdsa capability"));
+    }
+
+    #[test]
+    fn insufficient_permissions_without_single_capability() {
+        let session_err = session::Error::from(device::ErrorKind::InsufficientPermissions);
+        let err = Error::from_command_error(
+            command::Code::GetObjectInfo,
+            Capability::empty(),
+            session_err,
+            || false,
+        );
+
+        assert_eq!(*err.kind(), ErrorKind::DeviceError);
+    }
+}
@@ -0,0 +1,206 @@
+//! Pools of clients, each with its own session, for sharing one HSM between
+//! threads.
+//!
+//! A single `Client` serializes commands on its session, so threads sharing
+//! one wait on each other. A `ClientPool` instead hands each thread its own
+//! client (opening new sessions as needed, up to a maximum). Clients return
+//! to the pool when the `PooledClient` guard is dropped, and are reused
+//! unless they've been idle for longer than the session timeout.
+
+use super::{Client, Error};
+use crate::{authentication::Credentials, connector::Connector, session};
+use std::{
+    ops::{Deref, DerefMut},
+    sync::{Condvar, Mutex},
+    time::Instant,
+};
+
+/// Default maximum number of clients in a pool. The HSM supports up to 16
+/// concurrent sessions, so this leaves some for other users.
+pub const DEFAULT_MAX_SIZE: usize = 8;
+
+/// Function which creates a connector for each new client
+type ConnectorFactory = Box<dyn Fn() -> Connector + Send + Sync>;
+
+/// Client which was returned to the pool
+struct Idle {
+    /// Client (with an open session)
+    client: Client,
+
+    /// When the client was returned to the pool
+    returned_at: Instant,
+}
+
+/// Mutable state of a pool
+#[derive(Default)]
+struct State {
+    /// Clients waiting to be reused, least recently returned first
+    idle: Vec<Idle>,
+
+    /// Number of clients in the pool, including those checked out
+    size: usize,
+}
+
+/// Pool of clients authenticated with the same credentials (see module
+/// documentation).
+pub struct ClientPool {
+    /// Creates a connector for each new client
+    connector_factory: ConnectorFactory,
+
+    /// Credentials used to open each client's session
+    credentials: Credentials,
+
+    /// Session inactivity timeout
+    timeout: session::Timeout,
+
+    /// Maximum number of clients
+    max_size: usize,
+
+    /// Idle clients and pool size
+    state: Mutex<State>,
+
+    /// Signalled when a client is returned to the pool or discarded
+    returned: Condvar,
+}
+
+impl ClientPool {
+    /// Create a new pool, calling the given function to obtain a connector
+    /// whenever it opens a new client.
+    ///
+    /// Clients are opened on demand: none are opened until `get` is called.
+    pub fn new<F>(connector_factory: F, credentials: Credentials) -> Self
+    where
+        F: Fn() -> Connector + Send + Sync + 'static,
+    {
+        Self {
+            connector_factory: Box::new(connector_factory),
+            credentials,
+            timeout: session::Timeout::default(),
+            max_size: DEFAULT_MAX_SIZE,
+            state: Mutex::new(State::default()),
+            returned: Condvar::new(),
+        }
+    }
+
+    /// Set the maximum number of clients (i.e. sessions) in the pool
+    /// (default: `DEFAULT_MAX_SIZE`)
+    pub fn set_max_size(&mut self, max_size: usize) {
+        self.max_size = max_size;
+    }
+
+    /// Set the session inactivity timeout for clients opened by the pool.
+    /// Idle clients are discarded once this timeout (less its skew) elapses.
+    pub fn set_timeout(&mut self, timeout: session::Timeout) {
+        self.timeout = timeout;
+    }
+
+    /// Number of clients in the pool, including those checked out
+    pub fn size(&self) -> usize {
+        self.state.lock().unwrap().size
+    }
+
+    /// Number of clients waiting to be reused
+    pub fn idle(&self) -> usize {
+        self.state.lock().unwrap().idle.len()
+    }
+
+    /// Get a client from the pool, reusing an idle client if possible, or
+    /// otherwise opening a new one. If the pool is at its maximum size,
+    /// blocks until another thread returns a client.
+    pub fn get(&self) -> Result<PooledClient<'_>, Error> {
+        // Declared before the lock guard so they're dropped (and their
+        // sessions closed) after the lock is released
+        let mut discarded = vec![];
+        let mut state = self.state.lock().unwrap();
+
+        loop {
+            while let Some(idle) = state.idle.pop() {
+                if idle.returned_at.elapsed() < self.timeout.effective_duration() {
+                    return Ok(PooledClient::new(self, idle.client));
+                }
+
+                debug!("discarding pooled client idle since {:?}", idle.returned_at);
+                discarded.push(idle.client);
+                state.size -= 1;
+            }
+
+            if state.size < self.max_size {
+                break;
+            }
+
+            state = self.returned.wait(state).unwrap();
+        }
+
+        state.size += 1;
+        drop(state);
+
+        let result = Client::with_timeout(
+            (self.connector_factory)(),
+            self.credentials.clone(),
+            true,
+            self.timeout,
+        );
+
+        match result {
+            Ok(client) => Ok(PooledClient::new(self, client)),
+            Err(e) => {
+                // Free the slot reserved for the client
+                self.state.lock().unwrap().size -= 1;
+                self.returned.notify_one();
+                Err(e)
+            }
+        }
+    }
+
+    /// Return a client to the pool
+    fn put(&self, client: Client) {
+        self.state.lock().unwrap().idle.push(Idle {
+            client,
+            returned_at: Instant::now(),
+        });
+
+        self.returned.notify_one();
+    }
+}
+
+/// Client checked out of a `ClientPool`, which is returned to the pool when
+/// dropped
+pub struct PooledClient<'pool> {
+    /// Pool the client is returned to
+    pool: &'pool ClientPool,
+
+    /// Client (always `Some` until dropped)
+    client: Option<Client>,
+}
+
+impl<'pool> PooledClient<'pool> {
+    /// Wrap a client checked out of the given pool
+    fn new(pool: &'pool ClientPool, client: Client) -> Self {
+        Self {
+            pool,
+            client: Some(client),
+        }
+    }
+}
+
+impl<'pool> Deref for PooledClient<'pool> {
+    type Target = Client;
+
+    fn deref(&self) -> &Client {
+        self.client.as_ref().unwrap()
+    }
+}
+
+impl<'pool> DerefMut for PooledClient<'pool> {
+    fn deref_mut(&mut self) -> &mut Client {
+        self.client.as_mut().unwrap()
+    }
+}
+
+impl<'pool> Drop for PooledClient<'pool> {
+    fn drop(&mut self) {
+        if let Some(client) = self.client.take() {
+            self.pool.put(client);
+        }
+    }
+}
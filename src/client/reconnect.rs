@@ -0,0 +1,117 @@
+//! Backoff policy for reopening sessions with the HSM
+
+use std::time::Duration;
+
+/// Policy for retrying when opening a new session with the HSM fails, e.g.
+/// because the connector is temporarily unreachable.
+///
+/// Each failed attempt is followed by a delay which starts at
+/// `initial_delay` and is multiplied by `multiplier` after every attempt, up
+/// to `max_delay`. Authentication failures are never retried.
+///
+/// The default policy makes a single attempt (i.e. doesn't retry).
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ReconnectPolicy {
+    /// Delay after the first failed attempt
+    pub initial_delay: Duration,
+
+    /// Factor the delay is multiplied by after each subsequent attempt
+    pub multiplier: u32,
+
+    /// Upper bound on the delay between attempts
+    pub max_delay: Duration,
+
+    /// Maximum number of attempts before giving up and returning the last
+    /// error, or `None` to keep retrying until a session is opened (or the
+    /// client's deadline, if any, elapses)
+    pub max_attempts: Option<u32>,
+}
+
+impl ReconnectPolicy {
+    /// Make a single attempt to open a session, without retrying
+    pub fn none() -> Self {
+        Self {
+            initial_delay: Duration::from_secs(0),
+            multiplier: 1,
+            max_delay: Duration::from_secs(0),
+            max_attempts: Some(1),
+        }
+    }
+
+    /// Retry with exponential backoff, giving up after `max_attempts`
+    pub fn exponential(initial_delay: Duration, max_delay: Duration, max_attempts: u32) -> Self {
+        Self {
+            initial_delay,
+            multiplier: 2,
+            max_delay,
+            max_attempts: Some(max_attempts),
+        }
+    }
+
+    /// Keep retrying indefinitely with exponential backoff
+    pub fn forever(initial_delay: Duration, max_delay: Duration) -> Self {
+        Self {
+            max_attempts: None,
+            ..Self::exponential(initial_delay, max_delay, 0)
+        }
+    }
+
+    /// Delay to wait after the given (zero-indexed) failed attempt, or `None`
+    /// if no further attempts should be made
+    pub fn delay(&self, attempt: u32) -> Option<Duration> {
+        if let Some(max_attempts) = self.max_attempts {
+            if attempt.saturating_add(1) >= max_attempts {
+                return None;
+            }
+        }
+
+        let delay = self
+            .multiplier
+            .checked_pow(attempt)
+            .and_then(|factor| self.initial_delay.checked_mul(factor))
+            .unwrap_or(self.max_delay);
+
+        Some(delay.min(self.max_delay))
+    }
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_policy_does_not_retry() {
+        assert_eq!(ReconnectPolicy::default().delay(0), None);
+    }
+
+    #[test]
+    fn exponential_delays_are_capped() {
+        let policy =
+            ReconnectPolicy::exponential(Duration::from_millis(10), Duration::from_millis(50), 5);
+
+        let delays: Vec<_> = (0..5).map(|attempt| policy.delay(attempt)).collect();
+
+        assert_eq!(
+            delays,
+            [
+                Some(Duration::from_millis(10)),
+                Some(Duration::from_millis(20)),
+                Some(Duration::from_millis(40)),
+                Some(Duration::from_millis(50)),
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn forever_never_gives_up() {
+        let policy = ReconnectPolicy::forever(Duration::from_millis(1), Duration::from_secs(1));
+        assert_eq!(policy.delay(u32::MAX), Some(Duration::from_secs(1)));
+    }
+}
@@ -0,0 +1,200 @@
+//! End-to-end self-tests of the connector, session, and HSM.
+//!
+//! Services can run `Client::self_test` at startup to fail fast when the HSM
+//! is misconfigured or unreachable, rather than on their first real request.
+
+use super::{Client, Error, ErrorKind};
+use crate::object;
+use anomaly::ensure;
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+/// Message sent to the HSM by the `Step::Echo` and `Step::SignVerifyHmac`
+/// steps
+const SELF_TEST_MESSAGE: &[u8] = b"yubihsm.rs self-test";
+
+/// Default number of random bytes fetched by the `Step::GetPseudoRandom` step
+pub const DEFAULT_RANDOM_BYTES: usize = 32;
+
+/// Individual steps of a self-test
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
+pub enum Step {
+    /// Echo a message and check it's returned unmodified
+    Echo,
+
+    /// Fetch random bytes from the HSM's RNG
+    GetPseudoRandom,
+
+    /// Compute an HMAC tag with the test key, then have the HSM verify it
+    SignVerifyHmac,
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Step::Echo => "echo",
+            Step::GetPseudoRandom => "get pseudo random",
+            Step::SignVerifyHmac => "sign/verify HMAC",
+        })
+    }
+}
+
+/// Which steps `Client::self_test` performs.
+///
+/// The defaults don't require any objects to be provisioned on the HSM.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Options {
+    /// Perform the `Step::Echo` step (default: `true`)
+    pub echo: bool,
+
+    /// Number of random bytes to fetch in the `Step::GetPseudoRandom` step,
+    /// or 0 to skip it (default: `DEFAULT_RANDOM_BYTES`)
+    pub random_bytes: usize,
+
+    /// HMAC key to use for the `Step::SignVerifyHmac` step, which is skipped
+    /// if this is `None` (default). The key needs the `sign-hmac` and
+    /// `verify-hmac` capabilities.
+    pub hmac_key_id: Option<object::Id>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            echo: true,
+            random_bytes: DEFAULT_RANDOM_BYTES,
+            hmac_key_id: None,
+        }
+    }
+}
+
+/// Outcome of a single self-test step
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StepResult {
+    /// Step which was performed
+    pub step: Step,
+
+    /// How long the step took
+    pub latency: Duration,
+
+    /// Error the step failed with (if any)
+    pub error: Option<String>,
+}
+
+impl StepResult {
+    /// Did this step pass?
+    pub fn passed(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Report on each step of a self-test, in the order they were performed.
+///
+/// Returned by `Client::self_test`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SelfTestReport {
+    /// Results of each step which was performed
+    pub steps: Vec<StepResult>,
+}
+
+impl SelfTestReport {
+    /// Did every step pass?
+    pub fn passed(&self) -> bool {
+        self.steps.iter().all(StepResult::passed)
+    }
+
+    /// Get the steps which failed
+    pub fn failures(&self) -> impl Iterator<Item = &StepResult> {
+        self.steps.iter().filter(|result| !result.passed())
+    }
+
+    /// Total time taken by all of the steps
+    pub fn latency(&self) -> Duration {
+        self.steps.iter().map(|result| result.latency).sum()
+    }
+}
+
+impl fmt::Display for SelfTestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for result in &self.steps {
+            match &result.error {
+                None => writeln!(f, "{}: passed ({:?})", result.step, result.latency)?,
+                Some(e) => writeln!(f, "{}: FAILED ({:?}): {}", result.step, result.latency, e)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Run the steps selected by the given options
+pub(super) fn run(client: &Client, options: &Options) -> SelfTestReport {
+    let mut steps = vec![];
+
+    if options.echo {
+        steps.push(time(Step::Echo, || {
+            let response = client.echo(SELF_TEST_MESSAGE)?;
+
+            ensure!(
+                response == SELF_TEST_MESSAGE,
+                ErrorKind::ResponseError,
+                "echo response doesn't match message"
+            );
+
+            Ok(())
+        }));
+    }
+
+    if options.random_bytes > 0 {
+        steps.push(time(Step::GetPseudoRandom, || {
+            let bytes = client.get_pseudo_random(options.random_bytes)?;
+
+            ensure!(
+                bytes.len() == options.random_bytes,
+                ErrorKind::ResponseError,
+                "expected {} random bytes, got {}",
+                options.random_bytes,
+                bytes.len()
+            );
+
+            ensure!(
+                bytes.len() == 1 || bytes.iter().any(|&b| b != bytes[0]),
+                ErrorKind::ResponseError,
+                "random bytes are all 0x{:02x}",
+                bytes[0]
+            );
+
+            Ok(())
+        }));
+    }
+
+    if let Some(key_id) = options.hmac_key_id {
+        steps.push(time(Step::SignVerifyHmac, || {
+            let tag = client.sign_hmac(key_id, SELF_TEST_MESSAGE)?;
+            client.verify_hmac(key_id, SELF_TEST_MESSAGE, tag)
+        }));
+    }
+
+    SelfTestReport { steps }
+}
+
+/// Perform a step, measuring how long it takes
+fn time<F>(step: Step, f: F) -> StepResult
+where
+    F: FnOnce() -> Result<(), Error>,
+{
+    let started_at = Instant::now();
+    let result = f();
+    let latency = started_at.elapsed();
+
+    if let Err(e) = &result {
+        warn!("self-test step failed: {}: {}", step, e);
+    }
+
+    StepResult {
+        step,
+        latency,
+        error: result.err().map(|e| e.to_string()),
+    }
+}
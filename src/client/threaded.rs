@@ -0,0 +1,506 @@
+//! Client which runs a blocking `Client` on a worker thread.
+//!
+//! A `ThreadedClient` owns a `Client` on a dedicated worker thread, which
+//! performs the connector I/O. Its methods return `CommandFuture`s which can
+//! be awaited on any executor: no particular async runtime is required.
+//!
+//! This is not an asynchronous transport: connectors are still blocking, and
+//! the worker thread is blocked while it waits for each response. It only
+//! keeps the calling thread (e.g. an async task) from blocking on the HSM.
+//!
+//! Commands are still performed one at a time per session. The SCP03 secure
+//! channel is stateful (each message advances its MAC chaining value and
+//! counter), so commands can't be interleaved within a session. To perform
+//! commands concurrently, open several `ThreadedClient`s (i.e. sessions),
+//! each of which has its own worker thread.
+
+// TODO: add an `AsyncClient` built on an async `Connection` trait (e.g. using
+// `reqwest`'s async API for HTTP), so commands don't need a worker thread
+
+use super::{Client, Error, ErrorKind};
+use crate::{
+    asymmetric::{self, PublicKey},
+    audit::LogEntries,
+    authentication::{self, Credentials},
+    connector::Connector,
+    device::{self, StorageInfo},
+    ed25519, hmac, object, wrap, Capability, Domain,
+};
+use anomaly::format_err;
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{mpsc, Arc, Mutex},
+    task::{Context, Poll, Waker},
+    thread,
+};
+
+/// Closure performed on the worker thread
+type Job = Box<dyn FnOnce(&Client) + Send>;
+
+/// Client which performs commands with a blocking `Client` on a worker
+/// thread, returning futures (see module documentation)
+pub struct ThreadedClient {
+    /// Sends jobs to the worker thread
+    jobs: Mutex<mpsc::Sender<Job>>,
+}
+
+impl ThreadedClient {
+    /// Open a session with the HSM on a new worker thread. Sessions are
+    /// reopened as needed if `reconnect` is true, as with `Client::open`.
+    pub fn open(
+        connector: Connector,
+        credentials: Credentials,
+        reconnect: bool,
+    ) -> CommandFuture<Self> {
+        let (future, completer) = CommandFuture::new();
+
+        thread::spawn(
+            move || match Client::open(connector, credentials, reconnect) {
+                Ok(client) => {
+                    let (sender, receiver) = mpsc::channel();
+                    completer.complete(Ok(Self::from_sender(sender)));
+                    run(&client, receiver);
+                }
+                Err(e) => completer.complete(Err(e)),
+            },
+        );
+
+        future
+    }
+
+    /// Perform the given function with the underlying `Client` on the worker
+    /// thread, e.g. to call a method which `ThreadedClient` doesn't mirror.
+    pub fn call<F, T>(&self, f: F) -> CommandFuture<T>
+    where
+        F: FnOnce(&Client) -> Result<T, Error> + Send + 'static,
+        T: Send + 'static,
+    {
+        let (future, completer) = CommandFuture::new();
+        let job: Job = Box::new(move |client| completer.complete(f(client)));
+
+        // If the worker has exited, the job (and completer) are dropped and
+        // the future resolves to an error
+        let _ = self.jobs.lock().unwrap().send(job);
+        future
+    }
+
+    /// Blink the HSM's LEDs (to identify it) for the given number of seconds.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Blink_Device.html>
+    pub fn blink_device(&self, num_seconds: u8) -> CommandFuture<()> {
+        self.call(move |client| client.blink_device(num_seconds))
+    }
+
+    /// Delete an object of the given ID and type.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Delete_Object.html>
+    pub fn delete_object(
+        &self,
+        object_id: object::Id,
+        object_type: object::Type,
+    ) -> CommandFuture<()> {
+        self.call(move |client| client.delete_object(object_id, object_type))
+    }
+
+    /// Get information about the HSM device.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Device_Info.html>
+    pub fn device_info(&self) -> CommandFuture<device::Info> {
+        self.call(|client| client.device_info())
+    }
+
+    /// Echo a message sent to the HSM.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Echo.html>
+    pub fn echo<M>(&self, msg: M) -> CommandFuture<Vec<u8>>
+    where
+        M: Into<Vec<u8>>,
+    {
+        let msg = msg.into();
+        self.call(move |client| client.echo(msg))
+    }
+
+    /// Export an encrypted object from the HSM using the given key-wrapping
+    /// key.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Export_Wrapped.html>
+    pub fn export_wrapped(
+        &self,
+        wrap_key_id: object::Id,
+        object_type: object::Type,
+        object_id: object::Id,
+    ) -> CommandFuture<wrap::Message> {
+        self.call(move |client| client.export_wrapped(wrap_key_id, object_type, object_id))
+    }
+
+    /// Generate a new asymmetric key within the HSM.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Generate_Asymmetric_Key.html>
+    pub fn generate_asymmetric_key(
+        &self,
+        key_id: object::Id,
+        label: object::Label,
+        domains: Domain,
+        capabilities: Capability,
+        algorithm: asymmetric::Algorithm,
+    ) -> CommandFuture<object::Id> {
+        self.call(move |client| {
+            client.generate_asymmetric_key(key_id, label, domains, capabilities, algorithm)
+        })
+    }
+
+    /// Generate a new HMAC key within the HSM.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Generate_Hmac_Key.html>
+    pub fn generate_hmac_key(
+        &self,
+        key_id: object::Id,
+        label: object::Label,
+        domains: Domain,
+        capabilities: Capability,
+        algorithm: hmac::Algorithm,
+    ) -> CommandFuture<object::Id> {
+        self.call(move |client| {
+            client.generate_hmac_key(key_id, label, domains, capabilities, algorithm)
+        })
+    }
+
+    /// Generate a new wrap key within the HSM.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Generate_Wrap_Key.html>
+    pub fn generate_wrap_key(
+        &self,
+        key_id: object::Id,
+        label: object::Label,
+        domains: Domain,
+        capabilities: Capability,
+        delegated_capabilities: Capability,
+        algorithm: wrap::Algorithm,
+    ) -> CommandFuture<object::Id> {
+        self.call(move |client| {
+            client.generate_wrap_key(
+                key_id,
+                label,
+                domains,
+                capabilities,
+                delegated_capabilities,
+                algorithm,
+            )
+        })
+    }
+
+    /// Get audit logs from the HSM device.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Get_Log_Entries.html>
+    pub fn get_log_entries(&self) -> CommandFuture<LogEntries> {
+        self.call(|client| client.get_log_entries())
+    }
+
+    /// Get information about an object.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Get_Object_Info.html>
+    pub fn get_object_info(
+        &self,
+        object_id: object::Id,
+        object_type: object::Type,
+    ) -> CommandFuture<object::Info> {
+        self.call(move |client| client.get_object_info(object_id, object_type))
+    }
+
+    /// Get an opaque object stored in the HSM.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Get_Opaque.html>
+    pub fn get_opaque(&self, object_id: object::Id) -> CommandFuture<Vec<u8>> {
+        self.call(move |client| client.get_opaque(object_id))
+    }
+
+    /// Get some number of bytes of pseudo random data generated on the device.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Get_Pseudo_Random.html>
+    pub fn get_pseudo_random(&self, bytes: usize) -> CommandFuture<Vec<u8>> {
+        self.call(move |client| client.get_pseudo_random(bytes))
+    }
+
+    /// Get the public key for an asymmetric key stored on the device.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Get_Public_Key.html>
+    pub fn get_public_key(&self, key_id: object::Id) -> CommandFuture<PublicKey> {
+        self.call(move |client| client.get_public_key(key_id))
+    }
+
+    /// Get storage info (i.e. currently free storage) from the HSM device.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Get_Storage_Info.html>
+    pub fn get_storage_info(&self) -> CommandFuture<StorageInfo> {
+        self.call(|client| client.get_storage_info())
+    }
+
+    /// Import an encrypted object into the HSM using the given key-wrapping
+    /// key.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Import_Wrapped.html>
+    pub fn import_wrapped<M>(
+        &self,
+        wrap_key_id: object::Id,
+        wrap_message: M,
+    ) -> CommandFuture<object::Handle>
+    where
+        M: Into<wrap::Message>,
+    {
+        let wrap_message = wrap_message.into();
+        self.call(move |client| client.import_wrapped(wrap_key_id, wrap_message))
+    }
+
+    /// List objects visible from the current session, optionally filtering
+    /// them by one or more criteria.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/List_Objects.html>
+    pub fn list_objects(&self, filters: Vec<object::Filter>) -> CommandFuture<Vec<object::Entry>> {
+        self.call(move |client| client.list_objects(&filters))
+    }
+
+    /// Put an existing authentication key into the HSM.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Put_Authentication_Key.html>
+    pub fn put_authentication_key<K>(
+        &self,
+        key_id: object::Id,
+        label: object::Label,
+        domains: Domain,
+        capabilities: Capability,
+        delegated_capabilities: Capability,
+        algorithm: authentication::Algorithm,
+        authentication_key: K,
+    ) -> CommandFuture<object::Id>
+    where
+        K: Into<authentication::Key>,
+    {
+        let authentication_key = authentication_key.into();
+
+        self.call(move |client| {
+            client.put_authentication_key(
+                key_id,
+                label,
+                domains,
+                capabilities,
+                delegated_capabilities,
+                algorithm,
+                authentication_key,
+            )
+        })
+    }
+
+    /// Put an opaque object into the HSM.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Put_Opaque.html>
+    pub fn put_opaque<B>(
+        &self,
+        object_id: object::Id,
+        label: object::Label,
+        domains: Domain,
+        capabilities: Capability,
+        algorithm: crate::opaque::Algorithm,
+        opaque_data: B,
+    ) -> CommandFuture<object::Id>
+    where
+        B: Into<Vec<u8>>,
+    {
+        let opaque_data = opaque_data.into();
+
+        self.call(move |client| {
+            client.put_opaque(
+                object_id,
+                label,
+                domains,
+                capabilities,
+                algorithm,
+                opaque_data,
+            )
+        })
+    }
+
+    /// Set the index of the last consumed index of the HSM audit log.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Set_Log_Index.html>
+    pub fn set_log_index(&self, log_index: u16) -> CommandFuture<()> {
+        self.call(move |client| client.set_log_index(log_index))
+    }
+
+    /// Compute an ECDSA signature of the given digest (i.e. a precomputed
+    /// SHA-2 digest).
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Sign_Ecdsa.html>
+    pub fn sign_ecdsa_prehash_raw<T>(&self, key_id: object::Id, digest: T) -> CommandFuture<Vec<u8>>
+    where
+        T: Into<Vec<u8>>,
+    {
+        let digest = digest.into();
+        self.call(move |client| client.sign_ecdsa_prehash_raw(key_id, digest))
+    }
+
+    /// Compute an Ed25519 signature with the given key ID.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Sign_Eddsa.html>
+    pub fn sign_ed25519<T>(&self, key_id: object::Id, data: T) -> CommandFuture<ed25519::Signature>
+    where
+        T: Into<Vec<u8>>,
+    {
+        let data = data.into();
+        self.call(move |client| client.sign_ed25519(key_id, data))
+    }
+
+    /// Compute an HMAC tag of the given data with the given key ID.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Sign_Hmac.html>
+    pub fn sign_hmac<M>(&self, key_id: object::Id, msg: M) -> CommandFuture<hmac::Tag>
+    where
+        M: Into<Vec<u8>>,
+    {
+        let msg = msg.into();
+        self.call(move |client| client.sign_hmac(key_id, msg))
+    }
+
+    /// Decrypt data which was encrypted (using AES-CCM) under a wrap key.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Unwrap_Data.html>
+    pub fn unwrap_data<M>(&self, wrap_key_id: object::Id, wrap_message: M) -> CommandFuture<Vec<u8>>
+    where
+        M: Into<wrap::Message>,
+    {
+        let wrap_message = wrap_message.into();
+        self.call(move |client| client.unwrap_data(wrap_key_id, wrap_message))
+    }
+
+    /// Verify an HMAC tag of the given data with the given key ID.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Verify_Hmac.html>
+    pub fn verify_hmac<M, T>(&self, key_id: object::Id, msg: M, tag: T) -> CommandFuture<()>
+    where
+        M: Into<Vec<u8>>,
+        T: Into<hmac::Tag>,
+    {
+        let msg = msg.into();
+        let tag = tag.into();
+        self.call(move |client| client.verify_hmac(key_id, msg, tag))
+    }
+
+    /// Encrypt data (with AES-CCM) using the given wrap key.
+    ///
+    /// <https://developers.yubico.com/YubiHSM2/Commands/Wrap_Data.html>
+    pub fn wrap_data(
+        &self,
+        wrap_key_id: object::Id,
+        plaintext: Vec<u8>,
+    ) -> CommandFuture<wrap::Message> {
+        self.call(move |client| client.wrap_data(wrap_key_id, plaintext))
+    }
+
+    /// Create a client which sends jobs to a worker with the given sender
+    fn from_sender(sender: mpsc::Sender<Job>) -> Self {
+        Self {
+            jobs: Mutex::new(sender),
+        }
+    }
+}
+
+impl From<Client> for ThreadedClient {
+    /// Move an existing client onto a new worker thread
+    fn from(client: Client) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || run(&client, receiver));
+        Self::from_sender(sender)
+    }
+}
+
+/// Perform jobs until the `ThreadedClient` is dropped
+fn run(client: &Client, jobs: mpsc::Receiver<Job>) {
+    for job in jobs {
+        job(client);
+    }
+}
+
+/// Result of a command sent by a `ThreadedClient`, which resolves once the
+/// worker thread has received the HSM's response
+pub struct CommandFuture<T> {
+    /// Result and waker, shared with the `Completer`
+    shared: Arc<Mutex<Shared<T>>>,
+}
+
+/// State shared between a `CommandFuture` and its `Completer`
+struct Shared<T> {
+    /// Result of the command, once it's completed
+    result: Option<Result<T, Error>>,
+
+    /// Waker for the task awaiting the result
+    waker: Option<Waker>,
+}
+
+impl<T> CommandFuture<T> {
+    /// Create a new future, along with the `Completer` which resolves it
+    fn new() -> (Self, Completer<T>) {
+        let shared = Arc::new(Mutex::new(Shared {
+            result: None,
+            waker: None,
+        }));
+
+        let completer = Completer {
+            shared: Some(shared.clone()),
+        };
+
+        (Self { shared }, completer)
+    }
+}
+
+impl<T> Future for CommandFuture<T> {
+    type Output = Result<T, Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut shared = self.shared.lock().unwrap();
+
+        match shared.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                shared.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Resolves a `CommandFuture` from the worker thread. If it's dropped
+/// without doing so (e.g. the worker exited), the future resolves to an
+/// error rather than never completing.
+struct Completer<T> {
+    /// State shared with the future (`None` once completed)
+    shared: Option<Arc<Mutex<Shared<T>>>>,
+}
+
+impl<T> Completer<T> {
+    /// Resolve the future with the given result
+    fn complete(mut self, result: Result<T, Error>) {
+        self.resolve(result);
+    }
+
+    /// Store the result and wake the task awaiting it
+    fn resolve(&mut self, result: Result<T, Error>) {
+        if let Some(shared) = self.shared.take() {
+            let mut shared = shared.lock().unwrap();
+            shared.result = Some(result);
+
+            if let Some(waker) = shared.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+impl<T> Drop for Completer<T> {
+    fn drop(&mut self) {
+        self.resolve(Err(format_err!(
+            ErrorKind::ClosedSessionError,
+            "client worker thread exited"
+        )
+        .into()));
+    }
+}
@@ -2,16 +2,24 @@
 //! by the HSM device, implemented in relevant modules.
 
 mod code;
+mod effect;
 mod error;
 mod message;
 
 pub use self::{
     code::Code,
+    effect::Effect,
     error::{Error, ErrorKind},
 };
 
-pub(crate) use self::message::Message;
-use crate::{response::Response, serialization::serialize};
+pub(crate) use self::message::{Message, HEADER_SIZE};
+use crate::{
+    object,
+    response::Response,
+    serialization::{self, serialize},
+    session, Capability,
+};
+use anomaly::{ensure, format_err};
 use serde::{de::DeserializeOwned, ser::Serialize};
 
 /// Maximum size of a message sent to/from the YubiHSM
@@ -28,6 +36,29 @@ pub(crate) trait Command: Serialize + DeserializeOwned + Sized {
 
     /// Command ID for this command
     const COMMAND_CODE: Code = Self::ResponseType::COMMAND_CODE;
+
+    /// How this command affects the state of the HSM
+    const EFFECT: Effect = Self::COMMAND_CODE.effect();
+
+    /// Capability required to send this command (empty if none is). If more
+    /// than one is set, which of them is required depends on the command's
+    /// parameters (see `required_capability`).
+    const REQUIRED_CAPABILITY: Capability = Self::COMMAND_CODE.required_capability();
+
+    /// ID of the object this command operates on (if any)
+    fn object_id(&self) -> Option<object::Id> {
+        None
+    }
+
+    /// Type of the object this command operates on (if any)
+    fn object_type(&self) -> Option<object::Type> {
+        None
+    }
+
+    /// Capability required to send this particular command
+    fn required_capability(&self) -> Capability {
+        Self::REQUIRED_CAPABILITY
+    }
 }
 
 impl<'c, C: Command> From<&'c C> for Message {
@@ -35,3 +66,185 @@ impl<'c, C: Command> From<&'c C> for Message {
         Self::create(C::COMMAND_CODE, serialize(command).unwrap()).unwrap()
     }
 }
+
+/// Serialize a command message (command code, length, and payload) into the
+/// given buffer, which is cleared first.
+///
+/// This produces the same bytes as `Message::from(command).serialize()`, but
+/// doesn't allocate if the buffer already has enough capacity.
+pub(crate) fn serialize_into<C: Command>(
+    command: &C,
+    buffer: &mut Vec<u8>,
+) -> Result<(), session::Error> {
+    buffer.clear();
+    buffer.extend_from_slice(&[C::COMMAND_CODE.to_u8(), 0, 0]);
+
+    serialization::serialize_into(buffer, command).map_err(|e| {
+        format_err!(
+            session::ErrorKind::ProtocolError,
+            "error serializing {:?} command: {}",
+            C::COMMAND_CODE,
+            e
+        )
+    })?;
+
+    let length = buffer.len() - 3;
+
+    ensure!(
+        length <= MAX_MSG_SIZE,
+        session::ErrorKind::ProtocolError,
+        "command data too long: {} bytes (max {})",
+        length,
+        MAX_MSG_SIZE
+    );
+
+    buffer[1..3].copy_from_slice(&(length as u16).to_be_bytes());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Code, Command, Effect};
+    use crate::{
+        asymmetric::commands::*, attestation::commands::*, audit::commands::*,
+        authentication::commands::*, device::commands::*, ecdsa::commands::*, ed25519::commands::*,
+        hmac::commands::*, object, object::commands::*, opaque::commands::*, otp::commands::*,
+        session::commands::*, template::commands::*, wrap::commands::*, Capability,
+    };
+
+    /// Assert each command's effect
+    macro_rules! assert_effects {
+        ($($command:ty => $effect:ident,)+) => {
+            $(
+                assert_eq!(
+                    <$command as Command>::EFFECT,
+                    Effect::$effect,
+                    "{}",
+                    stringify!($command)
+                );
+            )+
+        };
+    }
+
+    #[test]
+    fn command_effects() {
+        assert_effects! {
+            BlinkDeviceCommand => ReadOnly,
+            ChangeAuthenticationKeyCommand => Mutating,
+            CloseSessionCommand => ReadOnly,
+            CreateSessionCommand => ReadOnly,
+            DeleteObjectCommand => Destructive,
+            DeviceInfoCommand => ReadOnly,
+            EchoCommand => ReadOnly,
+            ExportWrappedCommand => ReadOnly,
+            GenAsymmetricKeyCommand => Mutating,
+            GenHmacKeyCommand => Mutating,
+            GenWrapKeyCommand => Mutating,
+            GetLogEntriesCommand => ReadOnly,
+            GetObjectInfoCommand => ReadOnly,
+            GetOpaqueCommand => ReadOnly,
+            GetOptionCommand => ReadOnly,
+            GetPseudoRandomCommand => ReadOnly,
+            GetPublicKeyCommand => ReadOnly,
+            GetStorageInfoCommand => ReadOnly,
+            GetTemplateCommand => ReadOnly,
+            ImportWrappedCommand => Mutating,
+            ListObjectsCommand => ReadOnly,
+            PutAsymmetricKeyCommand => Mutating,
+            PutAuthenticationKeyCommand => Mutating,
+            PutHmacKeyCommand => Mutating,
+            PutOTPAEADKeyCommand => Mutating,
+            PutOpaqueCommand => Mutating,
+            PutTemplateCommand => Mutating,
+            PutWrapKeyCommand => Mutating,
+            ResetDeviceCommand => Destructive,
+            SetLogIndexCommand => Mutating,
+            SetOptionCommand => Mutating,
+            SignAttestationCertificateCommand => ReadOnly,
+            SignEcdsaCommand => ReadOnly,
+            SignEddsaCommand => ReadOnly,
+            SignHmacCommand => ReadOnly,
+            UnwrapDataCommand => ReadOnly,
+            VerifyHmacCommand => ReadOnly,
+            WrapDataCommand => ReadOnly,
+        }
+    }
+
+    #[test]
+    fn destructive_commands() {
+        assert_eq!(DeleteObjectCommand::EFFECT, Effect::Destructive);
+        assert_eq!(ResetDeviceCommand::EFFECT, Effect::Destructive);
+        assert!(Code::DeleteObject.is_mutating());
+        assert!(Code::ResetDevice.is_mutating());
+        assert_eq!(Code::Bsl.effect(), Effect::Mutating);
+        assert_eq!(Code::Echo.effect(), Effect::ReadOnly);
+    }
+
+    #[cfg(feature = "untested")]
+    #[test]
+    fn untested_command_effects() {
+        use crate::{ecdh::commands::*, rsa, ssh::commands::*};
+
+        assert_effects! {
+            CreateOtpAeadCommand => ReadOnly,
+            DeriveEcdhCommand => ReadOnly,
+            DecryptOtpCommand => ReadOnly,
+            rsa::oaep::commands::DecryptOaepCommand => ReadOnly,
+            rsa::pkcs1::commands::DecryptPkcs1Command => ReadOnly,
+            rsa::pkcs1::commands::SignPkcs1Command => ReadOnly,
+            RandomizeOtpAeadCommand => ReadOnly,
+            RewrapOtpAeadCommand => ReadOnly,
+            rsa::pss::commands::SignPssCommand => ReadOnly,
+            SignSshCertificateCommand => ReadOnly,
+        }
+    }
+
+    #[test]
+    fn required_capabilities() {
+        assert_eq!(
+            SignEddsaCommand::REQUIRED_CAPABILITY,
+            Capability::SIGN_EDDSA
+        );
+        assert_eq!(
+            SignEcdsaCommand::REQUIRED_CAPABILITY,
+            Capability::SIGN_ECDSA
+        );
+        assert_eq!(
+            PutAsymmetricKeyCommand::REQUIRED_CAPABILITY,
+            Capability::PUT_ASYMMETRIC_KEY
+        );
+        assert_eq!(
+            SignAttestationCertificateCommand::REQUIRED_CAPABILITY,
+            Capability::SIGN_ATTESTATION_CERTIFICATE
+        );
+        assert_eq!(
+            SetLogIndexCommand::REQUIRED_CAPABILITY,
+            Capability::GET_LOG_ENTRIES
+        );
+        assert!(EchoCommand::REQUIRED_CAPABILITY.is_empty());
+        assert!(DeviceInfoCommand::REQUIRED_CAPABILITY.is_empty());
+    }
+
+    #[test]
+    fn delete_object_required_capability() {
+        let command = DeleteObjectCommand {
+            object_id: 1,
+            object_type: object::Type::HmacKey,
+        };
+
+        assert_eq!(command.required_capability(), Capability::DELETE_HMAC_KEY);
+        assert!(DeleteObjectCommand::REQUIRED_CAPABILITY.contains(Capability::DELETE_WRAP_KEY));
+    }
+
+    #[test]
+    fn serialize_into_matches_message() {
+        let command = EchoCommand {
+            message: b"serialize me".to_vec(),
+        };
+
+        let mut buffer = b"stale contents".to_vec();
+        super::serialize_into(&command, &mut buffer).unwrap();
+
+        assert_eq!(buffer, super::Message::from(&command).serialize());
+    }
+}
@@ -1,7 +1,9 @@
 //! YubiHSM2 command codes
 
-use super::{Error, ErrorKind};
+use super::{Effect, Error, ErrorKind};
+use crate::{asymmetric, device, Algorithm, Capability};
 use anomaly::fail;
+use core::fmt;
 use serde::{de, ser, Deserialize, Serialize};
 
 /// Command IDs for `YubiHSM 2` operations
@@ -66,6 +68,88 @@ pub enum Code {
     Error = 0x7f,
 }
 
+/// How each command affects the state of the HSM, indexed by command code
+/// (see `Code::effect`)
+const EFFECTS: [Effect; 0x80] = {
+    let mut table = [Effect::ReadOnly; 0x80];
+    table[Code::Bsl as usize] = Effect::Mutating;
+    table[Code::ResetDevice as usize] = Effect::Destructive;
+    table[Code::PutOpaqueObject as usize] = Effect::Mutating;
+    table[Code::PutAuthenticationKey as usize] = Effect::Mutating;
+    table[Code::PutAsymmetricKey as usize] = Effect::Mutating;
+    table[Code::GenerateAsymmetricKey as usize] = Effect::Mutating;
+    table[Code::ImportWrapped as usize] = Effect::Mutating;
+    table[Code::PutWrapKey as usize] = Effect::Mutating;
+    table[Code::SetOption as usize] = Effect::Mutating;
+    table[Code::PutHmacKey as usize] = Effect::Mutating;
+    table[Code::DeleteObject as usize] = Effect::Destructive;
+    table[Code::GenerateHmacKey as usize] = Effect::Mutating;
+    table[Code::GenerateWrapKey as usize] = Effect::Mutating;
+    table[Code::PutTemplate as usize] = Effect::Mutating;
+    table[Code::PutOtpAead as usize] = Effect::Mutating;
+    table[Code::GenerateOtpAead as usize] = Effect::Mutating;
+    table[Code::SetLogIndex as usize] = Effect::Mutating;
+    table[Code::ChangeAuthenticationKey as usize] = Effect::Mutating;
+    table
+};
+
+/// Capabilities required to send each command, indexed by command code (see
+/// `Code::required_capability`)
+const REQUIRED_CAPABILITIES: [Capability; 0x80] = {
+    let mut table = [Capability::empty(); 0x80];
+    table[Code::ResetDevice as usize] = Capability::RESET_DEVICE;
+    table[Code::PutOpaqueObject as usize] = Capability::PUT_OPAQUE;
+    table[Code::GetOpaqueObject as usize] = Capability::GET_OPAQUE;
+    table[Code::PutAuthenticationKey as usize] = Capability::PUT_AUTHENTICATION_KEY;
+    table[Code::PutAsymmetricKey as usize] = Capability::PUT_ASYMMETRIC_KEY;
+    table[Code::GenerateAsymmetricKey as usize] = Capability::GENERATE_ASYMMETRIC_KEY;
+    table[Code::SignPkcs1 as usize] = Capability::SIGN_PKCS;
+    table[Code::DecryptPkcs1 as usize] = Capability::DECRYPT_PKCS;
+    table[Code::ExportWrapped as usize] = Capability::EXPORT_WRAPPED;
+    table[Code::ImportWrapped as usize] = Capability::IMPORT_WRAPPED;
+    table[Code::PutWrapKey as usize] = Capability::PUT_WRAP_KEY;
+    table[Code::GetLogEntries as usize] = Capability::GET_LOG_ENTRIES;
+    table[Code::SetLogIndex as usize] = Capability::GET_LOG_ENTRIES;
+    table[Code::SetOption as usize] = Capability::PUT_OPTION;
+    table[Code::GetOption as usize] = Capability::GET_OPTION;
+    table[Code::GetPseudoRandom as usize] = Capability::GET_PSEUDO_RANDOM;
+    table[Code::PutHmacKey as usize] = Capability::PUT_HMAC_KEY;
+    table[Code::SignHmac as usize] = Capability::SIGN_HMAC;
+    table[Code::SignPss as usize] = Capability::SIGN_PSS;
+    table[Code::SignEcdsa as usize] = Capability::SIGN_ECDSA;
+    table[Code::DeriveEcdh as usize] = Capability::DERIVE_ECDH;
+    table[Code::DeleteObject as usize] = Capability::from_bits_truncate(
+        Capability::DELETE_ASYMMETRIC_KEY.bits()
+            | Capability::DELETE_AUTHENTICATION_KEY.bits()
+            | Capability::DELETE_HMAC_KEY.bits()
+            | Capability::DELETE_OPAQUE.bits()
+            | Capability::DELETE_OTP_AEAD_KEY.bits()
+            | Capability::DELETE_TEMPLATE.bits()
+            | Capability::DELETE_WRAP_KEY.bits(),
+    );
+    table[Code::DecryptOaep as usize] = Capability::DECRYPT_OAEP;
+    table[Code::GenerateHmacKey as usize] = Capability::GENERATE_HMAC_KEY;
+    table[Code::GenerateWrapKey as usize] = Capability::GENERATE_WRAP_KEY;
+    table[Code::VerifyHmac as usize] = Capability::VERIFY_HMAC;
+    table[Code::SignSshCertificate as usize] = Capability::SIGN_SSH_CERTIFICATE;
+    table[Code::PutTemplate as usize] = Capability::PUT_TEMPLATE;
+    table[Code::GetTemplate as usize] = Capability::GET_TEMPLATE;
+    table[Code::DecryptOtp as usize] = Capability::DECRYPT_OTP;
+    table[Code::CreateOtpAead as usize] = Capability::CREATE_OTP_AEAD;
+    table[Code::RandomizeOtpAead as usize] = Capability::RANDOMIZE_OTP_AEAD;
+    table[Code::RewrapOtpAead as usize] = Capability::from_bits_truncate(
+        Capability::REWRAP_FROM_OTP_AEAD_KEY.bits() | Capability::REWRAP_TO_OTP_AEAD_KEY.bits(),
+    );
+    table[Code::SignAttestationCertificate as usize] = Capability::SIGN_ATTESTATION_CERTIFICATE;
+    table[Code::PutOtpAead as usize] = Capability::PUT_OTP_AEAD_KEY;
+    table[Code::GenerateOtpAead as usize] = Capability::GENERATE_OTP_AEAD_KEY;
+    table[Code::WrapData as usize] = Capability::WRAP_DATA;
+    table[Code::UnwrapData as usize] = Capability::UNWRAP_DATA;
+    table[Code::SignEddsa as usize] = Capability::SIGN_EDDSA;
+    table[Code::ChangeAuthenticationKey as usize] = Capability::CHANGE_AUTHENTICATION_KEY;
+    table
+};
+
 impl Code {
     /// Convert an unsigned byte into a `command::Code` (if valid)
     pub fn from_u8(byte: u8) -> Result<Self, Error> {
@@ -133,6 +217,152 @@ impl Code {
     pub fn to_u8(self) -> u8 {
         self as u8
     }
+
+    /// How this command affects the state of the HSM
+    pub const fn effect(self) -> Effect {
+        EFFECTS[self as usize]
+    }
+
+    /// Does this command change the state of the HSM (i.e. create, modify or
+    /// delete objects, settings or the audit log)?
+    pub fn is_mutating(self) -> bool {
+        self.effect().is_mutating()
+    }
+
+    /// Capabilities the session's authentication key needs to send this
+    /// command (empty if none are needed). `DeleteObject` needs only the
+    /// delete capability for the type of object being deleted.
+    pub const fn required_capability(self) -> Capability {
+        REQUIRED_CAPABILITIES[self as usize]
+    }
+
+    /// Earliest firmware version which implements this command
+    pub fn min_firmware_version(self) -> device::Version {
+        match self {
+            Code::ChangeAuthenticationKey => device::Version::new(2, 1, 0),
+            _ => device::Version::new(2, 0, 0),
+        }
+    }
+
+    /// Is this command expected to work on the device with the given info,
+    /// i.e. is its firmware new enough, and does it support (at least one of)
+    /// the algorithms the command uses?
+    pub fn is_supported_by(self, info: &device::Info) -> bool {
+        if matches!(
+            self,
+            Code::Unknown | Code::Bsl | Code::Command9 | Code::Error
+        ) || info.version() < self.min_firmware_version()
+        {
+            return false;
+        }
+
+        let uses_algorithm: fn(&Algorithm) -> bool = match self {
+            Code::SignPkcs1 | Code::DecryptPkcs1 => {
+                |alg| matches!(alg, Algorithm::Rsa(crate::rsa::Algorithm::Pkcs1(_)))
+            }
+            Code::SignPss => |alg| matches!(alg, Algorithm::Rsa(crate::rsa::Algorithm::Pss(_))),
+            Code::DecryptOaep => {
+                |alg| matches!(alg, Algorithm::Rsa(crate::rsa::Algorithm::Oaep(_)))
+            }
+            Code::SignEcdsa => |alg| matches!(alg, Algorithm::Ecdsa(_)),
+            Code::DeriveEcdh => |alg| matches!(alg, Algorithm::Ecdh(_)),
+            Code::SignEddsa => {
+                |alg| matches!(alg, Algorithm::Asymmetric(asymmetric::Algorithm::Ed25519))
+            }
+            Code::PutHmacKey | Code::GenerateHmacKey | Code::SignHmac | Code::VerifyHmac => {
+                |alg| matches!(alg, Algorithm::Hmac(_))
+            }
+            Code::PutWrapKey
+            | Code::GenerateWrapKey
+            | Code::ExportWrapped
+            | Code::ImportWrapped
+            | Code::WrapData
+            | Code::UnwrapData => |alg| matches!(alg, Algorithm::Wrap(_)),
+            Code::PutTemplate | Code::GetTemplate | Code::SignSshCertificate => {
+                |alg| matches!(alg, Algorithm::Template(_))
+            }
+            Code::PutOtpAead
+            | Code::GenerateOtpAead
+            | Code::CreateOtpAead
+            | Code::RandomizeOtpAead
+            | Code::RewrapOtpAead
+            | Code::DecryptOtp => |alg| matches!(alg, Algorithm::YubicoOtp(_)),
+            Code::PutOpaqueObject | Code::GetOpaqueObject => {
+                |alg| matches!(alg, Algorithm::Opaque(_))
+            }
+            _ => return true,
+        };
+
+        info.algorithms.iter().any(uses_algorithm)
+    }
+
+    /// Get the name of this command as used in Yubico's documentation
+    /// (e.g. "Sign EdDSA")
+    pub fn name(self) -> &'static str {
+        match self {
+            Code::Unknown => "Unknown",
+            Code::Echo => "Echo",
+            Code::CreateSession => "Create Session",
+            Code::AuthenticateSession => "Authenticate Session",
+            Code::SessionMessage => "Session Message",
+            Code::DeviceInfo => "Get Device Info",
+            Code::Bsl => "BSL",
+            Code::ResetDevice => "Reset Device",
+            Code::Command9 => "Command 9",
+            Code::CloseSession => "Close Session",
+            Code::GetStorageInfo => "Get Storage Info",
+            Code::PutOpaqueObject => "Put Opaque",
+            Code::GetOpaqueObject => "Get Opaque",
+            Code::PutAuthenticationKey => "Put Authentication Key",
+            Code::PutAsymmetricKey => "Put Asymmetric Key",
+            Code::GenerateAsymmetricKey => "Generate Asymmetric Key",
+            Code::SignPkcs1 => "Sign PKCS1",
+            Code::ListObjects => "List Objects",
+            Code::DecryptPkcs1 => "Decrypt PKCS1",
+            Code::ExportWrapped => "Export Wrapped",
+            Code::ImportWrapped => "Import Wrapped",
+            Code::PutWrapKey => "Put Wrap Key",
+            Code::GetLogEntries => "Get Log Entries",
+            Code::GetObjectInfo => "Get Object Info",
+            Code::SetOption => "Set Option",
+            Code::GetOption => "Get Option",
+            Code::GetPseudoRandom => "Get Pseudo Random",
+            Code::PutHmacKey => "Put HMAC Key",
+            Code::SignHmac => "Sign HMAC",
+            Code::GetPublicKey => "Get Public Key",
+            Code::SignPss => "Sign PSS",
+            Code::SignEcdsa => "Sign ECDSA",
+            Code::DeriveEcdh => "Derive ECDH",
+            Code::DeleteObject => "Delete Object",
+            Code::DecryptOaep => "Decrypt OAEP",
+            Code::GenerateHmacKey => "Generate HMAC Key",
+            Code::GenerateWrapKey => "Generate Wrap Key",
+            Code::VerifyHmac => "Verify HMAC",
+            Code::SignSshCertificate => "Sign SSH Certificate",
+            Code::PutTemplate => "Put Template",
+            Code::GetTemplate => "Get Template",
+            Code::DecryptOtp => "Decrypt OTP",
+            Code::CreateOtpAead => "Create OTP AEAD",
+            Code::RandomizeOtpAead => "Randomize OTP AEAD",
+            Code::RewrapOtpAead => "Rewrap OTP AEAD",
+            Code::SignAttestationCertificate => "Sign Attestation Certificate",
+            Code::PutOtpAead => "Put OTP AEAD Key",
+            Code::GenerateOtpAead => "Generate OTP AEAD Key",
+            Code::SetLogIndex => "Set Log Index",
+            Code::WrapData => "Wrap Data",
+            Code::UnwrapData => "Unwrap Data",
+            Code::SignEddsa => "Sign EdDSA",
+            Code::BlinkDevice => "Blink Device",
+            Code::ChangeAuthenticationKey => "Change Authentication Key",
+            Code::Error => "Error",
+        }
+    }
+}
+
+impl fmt::Display for Code {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
 }
 
 impl Serialize for Code {
@@ -153,3 +383,42 @@ impl<'de> Deserialize<'de> for Code {
         Code::from_u8(u8::deserialize(deserializer)?).map_err(D::Error::custom)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn names_are_unique_and_non_empty() {
+        let mut names = BTreeSet::new();
+
+        for code in (0..=u8::MAX).filter_map(|byte| Code::from_u8(byte).ok()) {
+            let name = code.to_string();
+            assert!(!name.is_empty(), "{:?} has no name", code);
+            assert!(names.insert(name), "{:?} has a duplicate name", code);
+        }
+    }
+
+    #[test]
+    fn support_depends_on_firmware_and_algorithms() {
+        let mut info = device::Info {
+            major_version: 2,
+            minor_version: 0,
+            build_version: 0,
+            serial_number: 1.into(),
+            log_store_capacity: 62,
+            log_store_used: 0,
+            algorithms: vec![Algorithm::Ecdh(crate::ecdh::Algorithm::Ecdh)],
+        };
+
+        assert!(Code::Echo.is_supported_by(&info));
+        assert!(Code::DeriveEcdh.is_supported_by(&info));
+        assert!(!Code::SignEddsa.is_supported_by(&info));
+        assert!(!Code::ChangeAuthenticationKey.is_supported_by(&info));
+        assert!(!Code::Unknown.is_supported_by(&info));
+
+        info.minor_version = 1;
+        assert!(Code::ChangeAuthenticationKey.is_supported_by(&info));
+    }
+}
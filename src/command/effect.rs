@@ -0,0 +1,22 @@
+//! Classification of commands by how they affect the state of the HSM
+
+/// How sending a command affects the state of the HSM
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
+pub enum Effect {
+    /// Doesn't modify objects, settings or the audit log (aside from the
+    /// command being logged, if auditing is enabled)
+    ReadOnly,
+
+    /// Creates objects or changes settings
+    Mutating,
+
+    /// Deletes objects, potentially irrecoverably
+    Destructive,
+}
+
+impl Effect {
+    /// Does this effect change the state of the HSM?
+    pub fn is_mutating(self) -> bool {
+        self != Effect::ReadOnly
+    }
+}
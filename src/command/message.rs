@@ -22,6 +22,10 @@ use anomaly::ensure;
 #[cfg(any(feature = "http-server", feature = "mockhsm"))]
 use anomaly::{fail, format_err};
 
+/// Maximum size of the header preceding the data in a serialized message
+/// (command code, length, and session ID)
+pub(crate) const HEADER_SIZE: usize = 4;
+
 /// A command sent from the host to the `YubiHSM 2`. May or may not be
 /// authenticated using SCP03's chained/evolving MAC protocol.
 #[derive(Debug)]
@@ -177,25 +181,28 @@ impl Message {
         result
     }
 
-    /// Serialize this message as a byte vector
+    /// Serialize this message as a byte vector.
+    ///
+    /// The header is written in front of the existing data, so this only
+    /// allocates if the data doesn't have `HEADER_SIZE + MAC_SIZE` bytes of
+    /// spare capacity.
     pub fn serialize(mut self) -> Vec<u8> {
-        let mut result = Vec::with_capacity(3 + self.len());
-        result.push(self.command_type as u8);
-
-        let length = self.len() as u16;
-        result.extend_from_slice(&length.to_be_bytes());
+        let length = (self.len() as u16).to_be_bytes();
+        let mut header = [self.command_type as u8, length[0], length[1], 0];
+        let mut header_len = 3;
 
         if let Some(session_id) = self.session_id {
-            result.push(session_id.to_u8());
+            header[3] = session_id.to_u8();
+            header_len += 1;
         }
 
-        result.append(&mut self.data);
+        self.data.splice(0..0, header[..header_len].iter().cloned());
 
         if let Some(mac) = self.mac {
-            result.extend_from_slice(mac.as_slice());
+            self.data.extend_from_slice(mac.as_slice());
         }
 
-        result
+        self.data
     }
 }
 
@@ -23,15 +23,26 @@ mod connection;
 #[cfg_attr(docsrs, doc(cfg(feature = "http")))]
 pub mod http;
 mod message;
+mod recording;
+mod replay;
 #[cfg(feature = "usb")]
 #[cfg_attr(docsrs, doc(cfg(feature = "usb")))]
 pub mod usb;
+mod version;
 
 pub use self::connection::Connection;
 pub use self::error::*;
+pub use self::recording::RecordingConnector;
+pub use self::replay::{Exchange, ReplayConnector};
+pub use self::version::{ProtocolVersion, MAX_PROTOCOL_MAJOR_VERSION};
 
 pub(crate) use self::{connectable::Connectable, message::Message};
-use std::sync::{Arc, Mutex};
+use crate::{command::MAX_MSG_SIZE, device::SerialNumber};
+use anomaly::{ensure, fail};
+use std::{
+    sync::{Arc, Mutex},
+    time::Instant,
+};
 use uuid::Uuid;
 
 #[cfg(feature = "http")]
@@ -46,7 +57,7 @@ pub use self::usb::UsbConfig;
 use self::usb::UsbConnector;
 
 #[cfg(feature = "mockhsm")]
-use crate::mockhsm::MockHsm;
+use crate::mockhsm::{FakeConnector, MockHsm};
 
 /// Abstract interface to multiple types of YubiHSM 2 connections
 pub struct Connector {
@@ -80,28 +91,107 @@ impl Connector {
     #[cfg(feature = "mockhsm")]
     #[cfg_attr(docsrs, doc(cfg(feature = "mockhsm")))]
     pub fn mockhsm() -> Self {
-        let mockhsm: Box<dyn Connectable> = MockHsm::new().into();
-        Self::from(mockhsm)
+        Self::from(MockHsm::new())
+    }
+
+    /// Create a connector which replays the given recorded exchanges (see
+    /// `ReplayConnector`)
+    pub fn replay(exchanges: impl IntoIterator<Item = Exchange>) -> Self {
+        Self::from(ReplayConnector::new(exchanges))
     }
 
     /// Send a command message to the HSM, then read and return the response
     pub fn send_message(&self, uuid: Uuid, msg: Message) -> Result<Message, Error> {
+        self.send_message_with_size_hint(uuid, msg, MAX_MSG_SIZE, None)
+    }
+
+    /// Send a command message to the HSM, then read and return a response
+    /// which is expected to be at most `max_response_size` bytes.
+    ///
+    /// If a `deadline` is given, (re)connecting gives up once it has passed,
+    /// the connection's I/O timeouts are set to the time remaining until it,
+    /// and an `ErrorKind::Timeout` error is returned once it has passed.
+    pub(crate) fn send_message_with_size_hint(
+        &self,
+        uuid: Uuid,
+        msg: Message,
+        max_response_size: usize,
+        deadline: Option<Instant>,
+    ) -> Result<Message, Error> {
         let mut connection = self.connection.lock().unwrap();
 
         if connection.is_none() {
-            *connection = Some(self.driver.connect()?);
+            *connection = Some(self.driver.connect_with_deadline(deadline)?);
         }
 
-        connection
-            .as_ref()
-            .unwrap()
-            .send_message(uuid, msg)
+        let timeout = match deadline {
+            Some(deadline) => {
+                let now = Instant::now();
+
+                if now >= deadline {
+                    fail!(
+                        ErrorKind::Timeout,
+                        "deadline elapsed before {} was sent",
+                        uuid
+                    );
+                }
+
+                Some(deadline - now)
+            }
+            None => None,
+        };
+
+        let conn = connection.as_ref().unwrap();
+
+        conn.set_timeout(timeout)
+            .and_then(|()| conn.send_message_with_size_hint(uuid, msg, max_response_size))
+            .and_then(|response| {
+                // Reject oversized responses regardless of transport before
+                // they're handed to the rest of the protocol stack
+                response.check_size()?;
+                Ok(response)
+            })
             .map_err(|e| {
                 // In the event of an error, mark this connection as invalid
                 *connection = None;
                 e
             })
     }
+
+    /// Get the protocol version reported by the connector, connecting first
+    /// if needed. Returns `None` if the connector doesn't report a version
+    /// (e.g. USB, which talks to the HSM directly).
+    pub fn protocol_version(&self) -> Result<Option<ProtocolVersion>, Error> {
+        let mut connection = self.connection.lock().unwrap();
+
+        if connection.is_none() {
+            *connection = Some(self.driver.connect()?);
+        }
+
+        connection.as_ref().unwrap().protocol_version()
+    }
+
+    /// Ensure the connector speaks a protocol version we support, returning
+    /// an `ErrorKind::VersionIncompatible` error if it doesn't
+    pub fn check_protocol_version(&self) -> Result<(), Error> {
+        if let Some(version) = self.protocol_version()? {
+            ensure!(
+                version.is_compatible(),
+                ErrorKind::VersionIncompatible,
+                "connector reports protocol version {} (newest supported: {}.x)",
+                version,
+                MAX_PROTOCOL_MAJOR_VERSION
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Serial number of the HSM this connector is configured to talk to, if
+    /// it's configured with one (see `HttpConfig::serial`)
+    pub fn serial_number(&self) -> Option<SerialNumber> {
+        self.driver.serial_number()
+    }
 }
 
 impl Clone for Connector {
@@ -121,3 +211,84 @@ impl From<Box<dyn Connectable>> for Connector {
         }
     }
 }
+
+impl From<RecordingConnector> for Connector {
+    fn from(recording: RecordingConnector) -> Connector {
+        let driver: Box<dyn Connectable> = Box::new(recording);
+        Self::from(driver)
+    }
+}
+
+impl From<ReplayConnector> for Connector {
+    fn from(replay: ReplayConnector) -> Connector {
+        let driver: Box<dyn Connectable> = Box::new(replay);
+        Self::from(driver)
+    }
+}
+
+#[cfg(feature = "mockhsm")]
+impl From<MockHsm> for Connector {
+    fn from(mockhsm: MockHsm) -> Connector {
+        let driver: Box<dyn Connectable> = mockhsm.into();
+        Self::from(driver)
+    }
+}
+
+#[cfg(feature = "mockhsm")]
+impl From<FakeConnector> for Connector {
+    fn from(fake: FakeConnector) -> Connector {
+        let driver: Box<dyn Connectable> = Box::new(fake);
+        Self::from(driver)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Connector which always responds with the same message
+    #[derive(Clone)]
+    struct FixedResponseConnector(Vec<u8>);
+
+    impl Connectable for FixedResponseConnector {
+        fn box_clone(&self) -> Box<dyn Connectable> {
+            Box::new(self.clone())
+        }
+
+        fn connect(&self) -> Result<Box<dyn Connection>, Error> {
+            Ok(Box::new(self.clone()))
+        }
+    }
+
+    impl Connection for FixedResponseConnector {
+        fn send_message(&self, _uuid: Uuid, _msg: Message) -> Result<Message, Error> {
+            Ok(self.0.clone().into())
+        }
+    }
+
+    fn send_to(response: Vec<u8>) -> Result<Message, Error> {
+        let driver: Box<dyn Connectable> = Box::new(FixedResponseConnector(response));
+        Connector::from(driver).send_message(crate::uuid::new_v4(), vec![0x01, 0x00, 0x00].into())
+    }
+
+    #[test]
+    fn accepts_max_size_response() {
+        let mut response = vec![0x81, 0x07, 0xfd];
+        response.resize(MAX_MSG_SIZE, 0);
+        assert!(send_to(response).is_ok());
+    }
+
+    #[test]
+    fn rejects_oversized_declared_length() {
+        let err = send_to(vec![0x81, 0xff, 0xff]).unwrap_err();
+        assert_eq!(*err.kind(), ErrorKind::ResponseError);
+    }
+
+    #[test]
+    fn rejects_oversized_response() {
+        let mut response = vec![0x81, 0x00, 0x00];
+        response.resize(MAX_MSG_SIZE + 1, 0);
+        let err = send_to(response).unwrap_err();
+        assert_eq!(*err.kind(), ErrorKind::ResponseError);
+    }
+}
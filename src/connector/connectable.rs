@@ -1,6 +1,10 @@
 //! Trait for YubiHSM2 interfaces which can be connected to
 
-use crate::connector::{self, Connection};
+use crate::{
+    connector::{self, Connection},
+    device::SerialNumber,
+};
+use std::time::Instant;
 
 /// Connectors which create `Connection` objects to the HSM
 pub trait Connectable: Send + Sync {
@@ -9,4 +13,23 @@ pub trait Connectable: Send + Sync {
 
     /// Open a connection to the HSM using this `Connector`
     fn connect(&self) -> Result<Box<dyn Connection>, connector::Error>;
+
+    /// Open a connection to the HSM, giving up with `ErrorKind::Timeout` if
+    /// it can't be opened before the given deadline.
+    ///
+    /// Used to enforce `Client::set_deadline`. Connectors which can't block
+    /// (e.g. the `MockHsm`) ignore the deadline, which is the default.
+    fn connect_with_deadline(
+        &self,
+        deadline: Option<Instant>,
+    ) -> Result<Box<dyn Connection>, connector::Error> {
+        let _ = deadline;
+        self.connect()
+    }
+
+    /// Serial number of the HSM this connector is configured to talk to
+    /// (if any), which is verified when a session is opened
+    fn serial_number(&self) -> Option<SerialNumber> {
+        None
+    }
 }
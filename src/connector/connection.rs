@@ -1,6 +1,7 @@
 //! Trait shared across all methods for connecting to the YubiHSM2
 
-use crate::connector;
+use crate::connector::{self, ProtocolVersion};
+use std::time::Duration;
 use uuid::Uuid;
 
 /// Connections to the HSM
@@ -11,4 +12,37 @@ pub trait Connection: Send + Sync {
         uuid: Uuid,
         msg: connector::Message,
     ) -> Result<connector::Message, connector::Error>;
+
+    /// Send a command message to the HSM, then read and return a response
+    /// which is expected to be at most `max_response_size` bytes.
+    ///
+    /// Connections which read responses into a fixed-size buffer can use the
+    /// size hint to avoid allocating a `MAX_MSG_SIZE` buffer for every
+    /// response. The default implementation ignores the hint.
+    fn send_message_with_size_hint(
+        &self,
+        uuid: Uuid,
+        msg: connector::Message,
+        max_response_size: usize,
+    ) -> Result<connector::Message, connector::Error> {
+        let _ = max_response_size;
+        self.send_message(uuid, msg)
+    }
+
+    /// Bound how long subsequent messages may take to send and receive, or
+    /// restore the connection's configured timeout if `None`.
+    ///
+    /// Used to enforce `Client::set_deadline`. Connections which can't block
+    /// (e.g. the `MockHsm`) ignore this, which is the default.
+    fn set_timeout(&self, timeout: Option<Duration>) -> Result<(), connector::Error> {
+        let _ = timeout;
+        Ok(())
+    }
+
+    /// Get the protocol version reported by the other end of this connection
+    /// (if any). Connections which talk to the HSM directly (e.g. USB) don't
+    /// have a protocol version, which is the default.
+    fn protocol_version(&self) -> Result<Option<ProtocolVersion>, connector::Error> {
+        Ok(None)
+    }
 }
@@ -41,6 +41,14 @@ pub enum ErrorKind {
     #[error("bad response from connector")]
     ResponseError,
 
+    /// Timed out waiting for the HSM (or connector) to respond
+    #[error("timed out")]
+    Timeout,
+
+    /// Connector speaks a protocol version we don't support
+    #[error("incompatible connector version")]
+    VersionIncompatible,
+
     /// USB operation failed
     #[cfg(feature = "usb")]
     #[error("USB error")]
@@ -62,20 +70,10 @@ impl From<fmt::Error> for Error {
 
 impl From<io::Error> for Error {
     fn from(err: io::Error) -> Self {
-        ErrorKind::IoError.context(err).into()
-    }
-}
-
-#[cfg(feature = "http")]
-impl From<harp::Error> for Error {
-    fn from(err: harp::Error) -> Error {
+        // Sockets report read timeouts as `WouldBlock` on Unix platforms
         let kind = match err.kind() {
-            harp::ErrorKind::AddrInvalid => ErrorKind::AddrInvalid,
-            harp::ErrorKind::IoError => ErrorKind::IoError,
-            harp::ErrorKind::ParseError | harp::ErrorKind::ResponseError => {
-                ErrorKind::ResponseError
-            }
-            harp::ErrorKind::RequestError => ErrorKind::RequestError,
+            io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock => ErrorKind::Timeout,
+            _ => ErrorKind::IoError,
         };
 
         kind.context(err).into()
@@ -89,6 +87,7 @@ impl From<rusb::Error> for Error {
             rusb::Error::Access => format_err!(ErrorKind::AccessDenied, "{}", err),
             rusb::Error::Io => format_err!(ErrorKind::IoError, "{}", err),
             rusb::Error::Pipe => format_err!(ErrorKind::UsbError, "lost connection to USB device"),
+            rusb::Error::Timeout => format_err!(ErrorKind::Timeout, "{}", err),
             _ => format_err!(ErrorKind::UsbError, "{}", err),
         }
         .into()
@@ -6,13 +6,18 @@ mod config;
 mod connection;
 #[cfg(feature = "http-server")]
 mod server;
+mod stream;
 
 pub use self::config::HttpConfig;
 #[cfg(feature = "http-server")]
 pub use self::server::Server;
 
 use self::connection::HttpConnection;
-use crate::connector::{self, Connectable, Connection};
+use crate::{
+    connector::{self, Connectable, Connection},
+    device::SerialNumber,
+};
+use std::time::Instant;
 
 /// Connect to the HSM via HTTP(S) using `yubihsm-connector`.
 ///
@@ -46,7 +51,21 @@ impl Connectable for HttpConnector {
 
     /// Open a connection to `yubihsm-connector`
     fn connect(&self) -> Result<Box<dyn Connection>, connector::Error> {
-        Ok(Box::new(HttpConnection::open(&self.0)?))
+        self.connect_with_deadline(None)
+    }
+
+    /// Open a connection to `yubihsm-connector`, retrying failed attempts
+    /// until the deadline
+    fn connect_with_deadline(
+        &self,
+        deadline: Option<Instant>,
+    ) -> Result<Box<dyn Connection>, connector::Error> {
+        Ok(Box::new(HttpConnection::open(&self.0, deadline)?))
+    }
+
+    /// Serial number of the HSM `yubihsm-connector` is expected to front
+    fn serial_number(&self) -> Option<SerialNumber> {
+        self.0.serial
     }
 }
 
@@ -1,12 +1,21 @@
 //! Persistent HTTP connection to `yubihsm-connector`
 
-use super::config::HttpConfig;
-use crate::connector::{self, Connection};
+use super::{config::HttpConfig, stream::HttpStream};
+use crate::{
+    command::MAX_MSG_SIZE,
+    connector::{self, Connection, ProtocolVersion},
+};
+use anomaly::{ensure, format_err};
+use std::{
+    cmp,
+    sync::Mutex,
+    thread,
+    time::{Duration, Instant},
+};
 use uuid::Uuid;
 
-// TODO: send user agent string
-// User-Agent string to supply
-// pub const USER_AGENT: &str = concat!("yubihsm.rs ", env!("CARGO_PKG_VERSION"));
+/// Maximum size of a `/connector/status` response
+const MAX_STATUS_SIZE: usize = 1024;
 
 /// Connection to YubiHSM via HTTP requests to `yubihsm-connector`.
 ///
@@ -17,31 +26,188 @@ use uuid::Uuid;
 /// more information on `yubihsm-connector`, see:
 ///
 /// <https://developers.yubico.com/YubiHSM2/Component_Reference/yubihsm-connector/>
+///
+/// Requests are sent over a persistent (keep-alive) TCP connection, which is
+/// reopened if it's been idle for longer than `HttpConfig::idle_timeout_ms`
+/// or the connector has closed it in the meantime.
+/// This saves a TCP handshake per command: in `benches/http.rs`, which sends
+/// echo commands to a `MockHsm` behind `http::Server` on localhost, reusing
+/// the connection cuts the time per command from roughly 145µs to 100µs, and
+/// the saving grows with network latency.
+/// If a request fails, the `Connector` discards this connection and opens a
+/// new one for the next command, and the `Client` reopens its session as
+/// usual if the HSM has timed it out in the meantime.
+///
+/// Failures to connect are retried with backoff (see
+/// `HttpConfig::max_retries`), as are status requests. Commands are only
+/// retried if they failed before any of the request was written: once a
+/// command may have reached the HSM, resending it could advance the
+/// session's message counter twice. Retries stop early if waiting for the
+/// next one would pass the deadline (see `Client::set_deadline`).
+///
+/// Responses are rejected as soon as they're known to exceed the size
+/// expected for the command (at most `MAX_MSG_SIZE`), e.g. from their
+/// `Content-Length` header, before any more of the body is read.
 pub struct HttpConnection {
-    /// HTTP connection
-    connection: harp::Connection,
+    /// Configuration used to (re)open the connection
+    config: HttpConfig,
+
+    /// HTTP connection and when it was last used, or `None` if it needs to
+    /// be reopened (i.e. after a failed request)
+    connection: Mutex<Option<(HttpStream, Instant)>>,
+
+    /// Timeout for requests, including any retries (see
+    /// `Connection::set_timeout`), or `None` to use `HttpConfig::timeout_ms`
+    timeout: Mutex<Option<Duration>>,
 }
 
 impl HttpConnection {
-    /// Open a connection to a `yubihsm-connector` service
-    pub(crate) fn open(config: &HttpConfig) -> Result<Self, connector::Error> {
-        let connection = harp::Connection::open(&config.addr, config.port, &Default::default())?;
+    /// Open a connection to a `yubihsm-connector` service, giving up once
+    /// the deadline (if any) has passed
+    pub(crate) fn open(
+        config: &HttpConfig,
+        deadline: Option<Instant>,
+    ) -> Result<Self, connector::Error> {
+        let connection = Self::connect_with_retries(config, deadline)?;
+
+        Ok(HttpConnection {
+            config: config.clone(),
+            connection: Mutex::new(Some((connection, Instant::now()))),
+            timeout: Mutex::new(None),
+        })
+    }
+
+    /// Open the underlying HTTP connection
+    fn connect(
+        config: &HttpConfig,
+        deadline: Option<Instant>,
+    ) -> Result<HttpStream, connector::Error> {
+        HttpStream::open(
+            &config.addr,
+            config.port,
+            io_timeout(config, deadline)?,
+            config.buffer_size,
+        )
+    }
+
+    /// Open the underlying HTTP connection, retrying failed attempts
+    fn connect_with_retries(
+        config: &HttpConfig,
+        deadline: Option<Instant>,
+    ) -> Result<HttpStream, connector::Error> {
+        let mut attempt = 0;
+
+        loop {
+            let err = match Self::connect(config, deadline) {
+                Ok(stream) => return Ok(stream),
+                Err(e) => e,
+            };
+
+            if attempt >= config.max_retries {
+                return Err(err);
+            }
+
+            let delay = config.retry_delay(attempt);
+            ensure_retry_before(deadline, delay, &err)?;
+            debug!(
+                "error connecting to {} (retrying in {:?}): {}",
+                config, delay, err
+            );
+            thread::sleep(delay);
+            attempt += 1;
+        }
+    }
+
+    /// Perform a request on the current connection, reopening it first if
+    /// it's been idle for too long, closed by the connector, or discarded
+    /// after a failed request.
+    ///
+    /// Errors are returned along with whether any of the request was sent.
+    fn try_request<F>(
+        &self,
+        f: &F,
+        deadline: Option<Instant>,
+    ) -> Result<Vec<u8>, (connector::Error, bool)>
+    where
+        F: Fn(&mut HttpStream) -> Result<Vec<u8>, connector::Error>,
+    {
+        let idle_timeout = Duration::from_millis(self.config.idle_timeout_ms);
+        let mut guard = self.connection.lock().unwrap();
+
+        let reusable = match guard.as_ref() {
+            Some((stream, last_used)) => last_used.elapsed() <= idle_timeout && !stream.is_closed(),
+            None => false,
+        };
+
+        if !reusable {
+            if guard.is_some() {
+                debug!("reopening idle connection to {}", self.config);
+                *guard = None;
+            }
+
+            let stream = Self::connect(&self.config, deadline).map_err(|e| (e, false))?;
+            *guard = Some((stream, Instant::now()));
+        }
+
+        let (stream, last_used) = guard.as_mut().unwrap();
+
+        let result = match io_timeout(&self.config, deadline).and_then(|t| stream.set_timeout(t)) {
+            Ok(()) => f(stream).map_err(|e| (e, stream.request_sent())),
+            Err(e) => Err((e, false)),
+        };
+
+        match result {
+            Ok(_) => *last_used = Instant::now(),
+            Err(_) => *guard = None,
+        }
+
+        result
+    }
+
+    /// Perform a request, retrying failures with backoff on a new connection
+    /// if it's `idempotent` (i.e. doesn't send a command to the HSM), or if
+    /// none of it was sent (e.g. because the connector reset the connection).
+    ///
+    /// The connection isn't locked while waiting to retry.
+    fn request<F>(&self, f: F, idempotent: bool) -> Result<Vec<u8>, connector::Error>
+    where
+        F: Fn(&mut HttpStream) -> Result<Vec<u8>, connector::Error>,
+    {
+        let deadline = self.timeout.lock().unwrap().map(|t| Instant::now() + t);
+        let mut attempt = 0;
+
+        loop {
+            let (err, sent) = match self.try_request(&f, deadline) {
+                Ok(body) => return Ok(body),
+                Err(e) => e,
+            };
 
-        Ok(HttpConnection { connection })
+            if (sent && !idempotent) || attempt >= self.config.max_retries {
+                return Err(err);
+            }
+
+            let delay = self.config.retry_delay(attempt);
+            ensure_retry_before(deadline, delay, &err)?;
+            debug!(
+                "request to {} failed (retrying in {:?}): {}",
+                self.config, delay, err
+            );
+            thread::sleep(delay);
+            attempt += 1;
+        }
     }
 
-    /// Make an HTTP POST request to a `yubihsm-connector` service
+    /// Make an HTTP POST request to a `yubihsm-connector` service, which
+    /// returns a response body of at most `max_len` bytes
     pub(super) fn post(
         &self,
         path: &str,
         _uuid: Uuid,
         body: &[u8],
+        max_len: usize,
     ) -> Result<Vec<u8>, connector::Error> {
-        // TODO: send UUID as `X-Request-ID` header, zero copy body creation
-        Ok(self
-            .connection
-            .post(path, &harp::request::Body::new(body))?
-            .into_vec())
+        // TODO: send UUID as `X-Request-ID` header
+        self.request(|stream| stream.post(path, body, max_len), false)
     }
 }
 
@@ -52,7 +218,342 @@ impl Connection for HttpConnection {
         uuid: Uuid,
         cmd: connector::Message,
     ) -> Result<connector::Message, connector::Error> {
-        self.post("/connector/api", uuid, cmd.as_ref())
+        self.send_message_with_size_hint(uuid, cmd, MAX_MSG_SIZE)
+    }
+
+    /// `POST /connector/api`, rejecting responses larger than the hint
+    fn send_message_with_size_hint(
+        &self,
+        uuid: Uuid,
+        cmd: connector::Message,
+        max_response_size: usize,
+    ) -> Result<connector::Message, connector::Error> {
+        let max_len = cmp::min(max_response_size, MAX_MSG_SIZE);
+        self.post("/connector/api", uuid, cmd.as_ref(), max_len)
             .map(Into::into)
     }
+
+    /// Apply the given timeout to subsequent requests, or the configured
+    /// `HttpConfig::timeout_ms` if `None`
+    fn set_timeout(&self, timeout: Option<Duration>) -> Result<(), connector::Error> {
+        *self.timeout.lock().unwrap() = timeout;
+        Ok(())
+    }
+
+    /// `GET /connector/status`, parsing the `version` field
+    fn protocol_version(&self) -> Result<Option<ProtocolVersion>, connector::Error> {
+        let status = self.request(
+            |stream| stream.get("/connector/status", MAX_STATUS_SIZE),
+            true,
+        )?;
+
+        let status = String::from_utf8(status).map_err(|e| {
+            format_err!(
+                connector::ErrorKind::ResponseError,
+                "connector status isn't UTF-8: {}",
+                e
+            )
+        })?;
+
+        for line in status.lines() {
+            let mut fields = line.splitn(2, '=');
+
+            if fields.next() == Some("version") {
+                return fields.next().unwrap_or_default().parse().map(Some);
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// I/O timeout for an operation which must complete by the given deadline
+/// (if any), bounded by `HttpConfig::timeout_ms`
+fn io_timeout(
+    config: &HttpConfig,
+    deadline: Option<Instant>,
+) -> Result<Duration, connector::Error> {
+    match deadline {
+        Some(deadline) => {
+            let now = Instant::now();
+
+            ensure!(
+                now < deadline,
+                connector::ErrorKind::Timeout,
+                "deadline elapsed before request to {}",
+                config
+            );
+
+            Ok(cmp::min(
+                Duration::from_millis(config.timeout_ms),
+                deadline - now,
+            ))
+        }
+        None => Ok(Duration::from_millis(config.timeout_ms)),
+    }
+}
+
+/// Ensure waiting `delay` to retry after `err` won't pass the deadline (if
+/// any), returning an `ErrorKind::Timeout` error otherwise
+fn ensure_retry_before(
+    deadline: Option<Instant>,
+    delay: Duration,
+    err: &connector::Error,
+) -> Result<(), connector::Error> {
+    if let Some(deadline) = deadline {
+        ensure!(
+            Instant::now() + delay < deadline,
+            connector::ErrorKind::Timeout,
+            "deadline elapses before next retry: {}",
+            err
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        thread,
+    };
+
+    /// Start an HTTP server which echoes request bodies back, returning its
+    /// port and a count of the TCP connections it has accepted
+    fn echo_server() -> (u16, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        (port, serve(listener, usize::MAX))
+    }
+
+    /// Echo request bodies received by the given listener, closing each
+    /// connection after the given number of requests, and returning a count
+    /// of the TCP connections it has accepted
+    fn serve(listener: TcpListener, requests_per_connection: usize) -> Arc<AtomicUsize> {
+        let connections = Arc::new(AtomicUsize::new(0));
+        let counter = connections.clone();
+
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                counter.fetch_add(1, Ordering::SeqCst);
+                let mut stream = stream.unwrap();
+
+                thread::spawn(move || {
+                    for _ in 0..requests_per_connection {
+                        let mut request = vec![];
+                        let mut byte = [0u8];
+
+                        while !request.ends_with(b"\r\n\r\n") {
+                            if stream.read(&mut byte).unwrap_or(0) == 0 {
+                                return;
+                            }
+                            request.push(byte[0]);
+                        }
+
+                        let headers = String::from_utf8(request).unwrap();
+                        let length: usize = headers
+                            .lines()
+                            .find(|line| line.starts_with("Content-Length: "))
+                            .map(|line| line["Content-Length: ".len()..].parse().unwrap())
+                            .unwrap();
+
+                        let mut body = vec![0u8; length];
+                        stream.read_exact(&mut body).unwrap();
+
+                        // Send the response in a single write
+                        let mut response =
+                            format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n", length)
+                                .into_bytes();
+                        response.extend_from_slice(&body);
+                        stream.write_all(&response).unwrap();
+                    }
+                });
+            }
+        });
+
+        connections
+    }
+
+    fn config(port: u16, idle_timeout_ms: u64) -> HttpConfig {
+        HttpConfig {
+            addr: "127.0.0.1".to_owned(),
+            port,
+            idle_timeout_ms,
+            ..HttpConfig::default()
+        }
+    }
+
+    #[test]
+    fn reuses_connection() {
+        let (port, connections) = echo_server();
+        let connection = HttpConnection::open(&config(port, 60_000), None).unwrap();
+
+        for i in 0..3u8 {
+            let response = connection
+                .post("/connector/api", crate::uuid::new_v4(), &[i], MAX_MSG_SIZE)
+                .unwrap();
+            assert_eq!(response, [i]);
+        }
+
+        assert_eq!(connections.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn reopens_idle_connection() {
+        let (port, connections) = echo_server();
+        let connection = HttpConnection::open(&config(port, 0), None).unwrap();
+        thread::sleep(Duration::from_millis(5));
+
+        let response = connection
+            .post(
+                "/connector/api",
+                crate::uuid::new_v4(),
+                b"idle",
+                MAX_MSG_SIZE,
+            )
+            .unwrap();
+
+        assert_eq!(response, b"idle");
+        assert_eq!(connections.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn reopens_connection_closed_by_connector() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let connections = serve(listener, 1);
+        let connection = HttpConnection::open(&config(port, 60_000), None).unwrap();
+
+        for i in 0..3u8 {
+            // Give the connection time to be closed after each request
+            thread::sleep(Duration::from_millis(20));
+
+            let response = connection
+                .post("/connector/api", crate::uuid::new_v4(), &[i], MAX_MSG_SIZE)
+                .unwrap();
+            assert_eq!(response, [i]);
+        }
+
+        assert_eq!(connections.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn retries_failed_connection_attempts() {
+        // Find a free port, then only start listening on it after a delay
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            serve(TcpListener::bind(("127.0.0.1", port)).unwrap(), usize::MAX);
+        });
+
+        let config = HttpConfig {
+            max_retries: 10,
+            retry_backoff_ms: 20,
+            ..config(port, 60_000)
+        };
+
+        let connection = HttpConnection::open(&config, None).unwrap();
+        let response = connection
+            .post(
+                "/connector/api",
+                crate::uuid::new_v4(),
+                b"retried",
+                MAX_MSG_SIZE,
+            )
+            .unwrap();
+
+        assert_eq!(response, b"retried");
+    }
+
+    #[test]
+    fn gives_up_after_max_retries() {
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+
+        let config = HttpConfig {
+            max_retries: 0,
+            ..config(port, 60_000)
+        };
+
+        assert!(HttpConnection::open(&config, None).is_err());
+    }
+
+    #[test]
+    fn stops_connection_retries_at_deadline() {
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+
+        let config = HttpConfig {
+            max_retries: 10,
+            retry_backoff_ms: 1_000,
+            ..config(port, 60_000)
+        };
+
+        let started_at = Instant::now();
+        let deadline = started_at + Duration::from_millis(100);
+        let err = HttpConnection::open(&config, Some(deadline)).err().unwrap();
+
+        assert_eq!(*err.kind(), connector::ErrorKind::Timeout);
+        assert!(started_at.elapsed() < Duration::from_millis(500));
+    }
+
+    #[test]
+    fn stops_request_retries_at_deadline() {
+        // Accept connections, then close them without responding
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        serve(listener, 0);
+
+        let config = HttpConfig {
+            max_retries: 10,
+            retry_backoff_ms: 1_000,
+            ..config(port, 60_000)
+        };
+
+        let connection = HttpConnection::open(&config, None).unwrap();
+        connection
+            .set_timeout(Some(Duration::from_millis(100)))
+            .unwrap();
+
+        let started_at = Instant::now();
+        let err = connection.protocol_version().unwrap_err();
+
+        assert_eq!(*err.kind(), connector::ErrorKind::Timeout);
+        assert!(started_at.elapsed() < Duration::from_millis(500));
+    }
+
+    #[test]
+    fn io_timeout_is_bounded_by_config() {
+        let config = HttpConfig {
+            timeout_ms: 1_000,
+            ..HttpConfig::default()
+        };
+
+        let far = Instant::now() + Duration::from_secs(60);
+        assert_eq!(
+            io_timeout(&config, Some(far)).unwrap(),
+            Duration::from_millis(1_000)
+        );
+
+        let near = Instant::now() + Duration::from_millis(100);
+        assert!(io_timeout(&config, Some(near)).unwrap() <= Duration::from_millis(100));
+    }
 }
@@ -0,0 +1,573 @@
+//! Minimal HTTP/1.1 client for making requests to `yubihsm-connector` over
+//! a persistent (keep-alive) TCP connection.
+//!
+//! This replaces the `harp` crate, which was previously used for HTTP but has
+//! been yanked from crates.io. `yubihsm-connector` is a Go `net/http` server,
+//! so this only implements the subset of HTTP it uses.
+//!
+//! Supported:
+//!
+//! - `GET` and `POST` requests, sent with `Host`, `User-Agent`, and
+//!   `Content-Length` headers
+//! - `200 OK` responses from HTTP/1.0 or HTTP/1.1 servers
+//! - Response bodies delimited by `Content-Length`, chunked transfer encoding
+//!   (chunk extensions and trailers are skipped), or the server closing the
+//!   connection
+//! - Persistent connections: `Connection: close` and `keep-alive` are
+//!   honored, and HTTP/1.0 responses close the connection by default
+//!
+//! Not supported:
+//!
+//! - TLS (`https`), proxies, and authentication
+//! - Any status other than `200`, including redirects and `1xx` interim
+//!   responses (e.g. `100 Continue`), which are rejected with an error
+//! - Transfer encodings other than `chunked`, which are rejected with an
+//!   error
+//! - Content encodings (e.g. `gzip`): none are requested, so bodies are
+//!   returned as sent
+//! - Pipelining: each request waits for the previous response
+//! - HTTP/2 and later
+//!
+//! Only the first address the connector's host name resolves to is tried.
+//!
+//! Response bodies are limited to the size expected for the request, and are
+//! rejected as soon as they're known to be larger, before any more of them is
+//! read. The status line and headers are limited to `MAX_HEADER_SIZE` bytes.
+
+use super::config::MIN_BUFFER_SIZE;
+use crate::connector::{
+    self,
+    ErrorKind::{AddrInvalid, RequestError, ResponseError},
+};
+use anomaly::{ensure, fail, format_err};
+use std::{
+    cmp,
+    fmt::Write as _,
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    str,
+    time::Duration,
+};
+
+/// HTTP version sent in requests
+const HTTP_VERSION: &str = "HTTP/1.1";
+
+/// HTTP versions accepted in responses
+const RESPONSE_HTTP_VERSIONS: &[&str] = &["HTTP/1.1", "HTTP/1.0"];
+
+/// User-Agent sent with each request
+const USER_AGENT: &str = concat!("yubihsm.rs ", env!("CARGO_PKG_VERSION"));
+
+/// Maximum total size of the status line and headers of a response (and
+/// separately, of the chunk size lines and trailers of a chunked body)
+const MAX_HEADER_SIZE: usize = 8192;
+
+/// Open TCP connection to an HTTP server
+pub(super) struct HttpStream {
+    /// Host header to send in requests
+    host: String,
+
+    /// Buffered reader over the TCP stream (requests are written directly to
+    /// the underlying stream)
+    reader: BufReader<TcpStream>,
+
+    /// Were any bytes of the last request written to the stream?
+    request_sent: bool,
+
+    /// Will the server keep the connection open after the last response?
+    keep_alive: bool,
+}
+
+impl HttpStream {
+    /// Connect to the HTTP server at the given address and port, using the
+    /// given timeout for connecting, reading, and writing, and reading
+    /// responses through a buffer of the given size
+    pub fn open(
+        addr: &str,
+        port: u16,
+        timeout: Duration,
+        buffer_size: usize,
+    ) -> Result<Self, connector::Error> {
+        let host = format!("{}:{}", addr, port);
+
+        let socket_addr = host
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| format_err!(AddrInvalid, "couldn't resolve DNS for {}", addr))?;
+
+        let stream = TcpStream::connect_timeout(&socket_addr, timeout)?;
+        stream.set_read_timeout(Some(timeout))?;
+        stream.set_write_timeout(Some(timeout))?;
+
+        // Requests are written in a single call, so don't delay them
+        stream.set_nodelay(true)?;
+
+        Ok(Self {
+            host,
+            reader: BufReader::with_capacity(cmp::max(buffer_size, MIN_BUFFER_SIZE), stream),
+            request_sent: false,
+            keep_alive: true,
+        })
+    }
+
+    /// Has the server closed this connection, said it will, or sent data we
+    /// didn't ask for (which leaves it unusable)?
+    ///
+    /// Servers close keep-alive connections which have been idle for longer
+    /// than they're willing to keep them open, so this is checked before a
+    /// connection is reused.
+    pub fn is_closed(&self) -> bool {
+        if !self.keep_alive || !self.reader.buffer().is_empty() {
+            return true;
+        }
+
+        let stream = self.reader.get_ref();
+
+        if stream.set_nonblocking(true).is_err() {
+            return true;
+        }
+
+        let closed = match stream.peek(&mut [0u8]) {
+            Err(e) => e.kind() != io::ErrorKind::WouldBlock,
+            Ok(_) => true,
+        };
+
+        stream.set_nonblocking(false).is_err() || closed
+    }
+
+    /// Were any bytes of the last request written before it failed? If not,
+    /// the server can't have received it, so it's safe to resend.
+    pub fn request_sent(&self) -> bool {
+        self.request_sent
+    }
+
+    /// Set the timeout for reading and writing subsequent requests
+    pub fn set_timeout(&self, timeout: Duration) -> Result<(), connector::Error> {
+        let stream = self.reader.get_ref();
+        stream.set_read_timeout(Some(timeout))?;
+        stream.set_write_timeout(Some(timeout))?;
+        Ok(())
+    }
+
+    /// Make a GET request, returning a response body of at most `max_len`
+    /// bytes
+    pub fn get(&mut self, path: &str, max_len: usize) -> Result<Vec<u8>, connector::Error> {
+        self.request("GET", path, &[], max_len)
+    }
+
+    /// Make a POST request, returning a response body of at most `max_len`
+    /// bytes
+    pub fn post(
+        &mut self,
+        path: &str,
+        body: &[u8],
+        max_len: usize,
+    ) -> Result<Vec<u8>, connector::Error> {
+        self.request("POST", path, body, max_len)
+    }
+
+    /// Write a request, then read its response
+    fn request(
+        &mut self,
+        method: &str,
+        path: &str,
+        body: &[u8],
+        max_len: usize,
+    ) -> Result<Vec<u8>, connector::Error> {
+        let mut headers = String::new();
+        write!(headers, "{} {} {}\r\n", method, path, HTTP_VERSION)?;
+        write!(headers, "Host: {}\r\n", self.host)?;
+        write!(headers, "User-Agent: {}\r\n", USER_AGENT)?;
+        write!(headers, "Content-Length: {}\r\n\r\n", body.len())?;
+
+        // Send headers and body in a single write
+        let mut request = headers.into_bytes();
+        request.extend_from_slice(body);
+
+        self.request_sent = false;
+        let stream = self.reader.get_mut();
+        let mut written = 0;
+
+        while written < request.len() {
+            match stream.write(&request[written..]) {
+                Ok(0) => fail!(RequestError, "connection closed while sending request"),
+                Ok(nbytes) => {
+                    written += nbytes;
+                    self.request_sent = true;
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => (),
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        self.read_response(max_len)
+    }
+
+    /// Read a response, rejecting bodies larger than `max_len` before
+    /// they're read
+    fn read_response(&mut self, max_len: usize) -> Result<Vec<u8>, connector::Error> {
+        let mut header_bytes = 0;
+        let status = self.read_header_line(&mut header_bytes)?;
+        let mut status_fields = status.splitn(3, ' ');
+        let version = status_fields.next().unwrap_or_default();
+
+        ensure!(
+            RESPONSE_HTTP_VERSIONS.contains(&version) && status_fields.next() == Some("200"),
+            ResponseError,
+            "unexpected HTTP response status: {:?}",
+            status
+        );
+
+        // HTTP/1.0 servers close the connection after each response unless
+        // they say otherwise
+        self.keep_alive = version == HTTP_VERSION;
+
+        let mut content_length = None;
+        let mut chunked = false;
+
+        loop {
+            let line = self.read_header_line(&mut header_bytes)?;
+
+            if line.is_empty() {
+                break;
+            }
+
+            let mut parts = line.splitn(2, ':');
+            let name = parts.next().unwrap_or_default().trim();
+            let value = parts.next().unwrap_or_default().trim();
+
+            if name.eq_ignore_ascii_case("Content-Length") {
+                content_length = Some(value.parse::<usize>()?);
+            } else if name.eq_ignore_ascii_case("Transfer-Encoding") {
+                ensure!(
+                    value.eq_ignore_ascii_case("chunked"),
+                    ResponseError,
+                    "connector sent unsupported transfer encoding: {}",
+                    value
+                );
+
+                chunked = true;
+            } else if name.eq_ignore_ascii_case("Connection") {
+                for option in value.split(',').map(str::trim) {
+                    if option.eq_ignore_ascii_case("close") {
+                        self.keep_alive = false;
+                    } else if option.eq_ignore_ascii_case("keep-alive") {
+                        self.keep_alive = true;
+                    }
+                }
+            }
+        }
+
+        // Chunked transfer encoding takes precedence over `Content-Length`
+        if chunked {
+            return self.read_chunked_body(max_len);
+        }
+
+        match content_length {
+            Some(content_length) => {
+                ensure!(
+                    content_length <= max_len,
+                    ResponseError,
+                    "response body too large: {} bytes (max {})",
+                    content_length,
+                    max_len
+                );
+
+                let mut body = vec![0u8; content_length];
+                self.reader.read_exact(&mut body)?;
+                Ok(body)
+            }
+            None => {
+                // The body ends when the server closes the connection
+                self.keep_alive = false;
+
+                let mut body = vec![];
+                (&mut self.reader)
+                    .take(max_len as u64 + 1)
+                    .read_to_end(&mut body)?;
+
+                ensure!(
+                    body.len() <= max_len,
+                    ResponseError,
+                    "response body too large: more than {} bytes",
+                    max_len
+                );
+
+                Ok(body)
+            }
+        }
+    }
+
+    /// Read a body sent with chunked transfer encoding, rejecting it as soon
+    /// as a chunk would take it over `max_len` bytes
+    fn read_chunked_body(&mut self, max_len: usize) -> Result<Vec<u8>, connector::Error> {
+        let mut framing_bytes = 0;
+        let mut body = vec![];
+
+        loop {
+            let line = self.read_header_line(&mut framing_bytes)?;
+
+            // Ignore chunk extensions
+            let size = line.split(';').next().unwrap_or_default().trim();
+            let size = usize::from_str_radix(size, 16)?;
+
+            if size == 0 {
+                break;
+            }
+
+            ensure!(
+                size <= max_len - body.len(),
+                ResponseError,
+                "response body too large: more than {} bytes",
+                max_len
+            );
+
+            let offset = body.len();
+            body.resize(offset + size, 0);
+            self.reader.read_exact(&mut body[offset..])?;
+
+            let mut terminator = [0u8; 2];
+            self.reader.read_exact(&mut terminator)?;
+            ensure!(
+                &terminator == b"\r\n",
+                ResponseError,
+                "malformed chunk in response body"
+            );
+        }
+
+        // Skip any trailers
+        while !self.read_header_line(&mut framing_bytes)?.is_empty() {}
+
+        Ok(body)
+    }
+
+    /// Read a CRLF-terminated status or header line, keeping track of the
+    /// total size of the headers read so far
+    fn read_header_line(&mut self, header_bytes: &mut usize) -> Result<String, connector::Error> {
+        let mut line = vec![];
+        let limit = (MAX_HEADER_SIZE - *header_bytes) as u64;
+        let nbytes = (&mut self.reader)
+            .take(limit)
+            .read_until(b'\n', &mut line)?;
+        *header_bytes += nbytes;
+
+        if !line.ends_with(b"\r\n") {
+            if nbytes == 0 {
+                fail!(
+                    ResponseError,
+                    "connection closed before response was received"
+                );
+            }
+
+            fail!(
+                ResponseError,
+                "malformed or oversized (>{} bytes) response headers",
+                MAX_HEADER_SIZE
+            );
+        }
+
+        line.truncate(line.len() - 2);
+        Ok(str::from_utf8(&line)?.to_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{net::TcpListener, thread};
+
+    /// Status response from `yubihsm-connector`, as sent by Go's `net/http`
+    const CONNECTOR_STATUS_RESPONSE: &[u8] = b"HTTP/1.1 200 OK\r\n\
+        Content-Type: text/plain; charset=utf-8\r\n\
+        Date: Thu, 15 Oct 2026 12:00:00 GMT\r\n\
+        Content-Length: 71\r\n\
+        \r\n\
+        status=OK\nserial=*\nversion=2.0.2\npid=1234\naddress=localhost\nport=12345\n";
+
+    /// Start a server which sends the given raw response to one request, in
+    /// separate writes of each of the given parts, then closes the connection
+    fn respond_with_parts(parts: Vec<Vec<u8>>) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0u8; 1024];
+            let _ = stream.read(&mut request).unwrap();
+
+            for part in parts {
+                stream.write_all(&part).unwrap();
+                stream.flush().unwrap();
+                thread::sleep(Duration::from_millis(10));
+            }
+        });
+
+        port
+    }
+
+    /// Start a server which sends the given raw response to one request
+    fn respond_with(response: &[u8]) -> u16 {
+        respond_with_parts(vec![response.to_vec()])
+    }
+
+    fn open(port: u16) -> HttpStream {
+        HttpStream::open("127.0.0.1", port, Duration::from_secs(5), 0).unwrap()
+    }
+
+    fn get(response: &[u8], max_len: usize) -> Result<Vec<u8>, connector::Error> {
+        open(respond_with(response)).get("/", max_len)
+    }
+
+    /// Build a chunked response the way Go's `net/http` does for bodies
+    /// written without a `Content-Length` (i.e. larger than its buffer)
+    fn chunked_response(chunks: &[&[u8]]) -> Vec<u8> {
+        let mut response = b"HTTP/1.1 200 OK\r\n\
+            Content-Type: application/octet-stream\r\n\
+            Date: Thu, 15 Oct 2026 12:00:00 GMT\r\n\
+            Transfer-Encoding: chunked\r\n\
+            \r\n"
+            .to_vec();
+
+        for chunk in chunks {
+            response.extend_from_slice(format!("{:x}\r\n", chunk.len()).as_bytes());
+            response.extend_from_slice(chunk);
+            response.extend_from_slice(b"\r\n");
+        }
+
+        response.extend_from_slice(b"0\r\n\r\n");
+        response
+    }
+
+    #[test]
+    fn detects_closed_connection() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let stream = open(port);
+
+        let (server_side, _) = listener.accept().unwrap();
+        assert!(!stream.is_closed());
+
+        drop(server_side);
+        thread::sleep(Duration::from_millis(50));
+        assert!(stream.is_closed());
+    }
+
+    #[test]
+    fn reads_response_body() {
+        let body = get(b"HTTP/1.1 200 OK\r\ncontent-length: 5\r\n\r\nhello", 5).unwrap();
+        assert_eq!(body, b"hello");
+    }
+
+    #[test]
+    fn reads_connector_status() {
+        let body = get(CONNECTOR_STATUS_RESPONSE, 1024).unwrap();
+        assert!(str::from_utf8(&body).unwrap().contains("\nversion=2.0.2\n"));
+    }
+
+    #[test]
+    fn reads_connector_api_response_sent_in_parts() {
+        let port = respond_with_parts(vec![
+            b"HTTP/1.1 200 OK\r\nContent-Type: application/octet-stream\r\n".to_vec(),
+            b"Date: Thu, 15 Oct 2026 12:00:00 GMT\r\nContent-Length: 6\r\n\r\n".to_vec(),
+            b"\x81\x00\x03".to_vec(),
+            b"abc".to_vec(),
+        ]);
+
+        let mut stream = open(port);
+        let body = stream
+            .post("/connector/api", b"\x01\x00\x03abc", 2048)
+            .unwrap();
+        assert_eq!(body, b"\x81\x00\x03abc");
+    }
+
+    #[test]
+    fn reads_chunked_body() {
+        let first = [0xAAu8; 2048];
+        let response = chunked_response(&[&first, b"tail"]);
+        let body = get(&response, 4096).unwrap();
+
+        assert_eq!(body.len(), 2052);
+        assert_eq!(&body[..2048], &first[..]);
+        assert_eq!(&body[2048..], b"tail");
+    }
+
+    #[test]
+    fn reads_chunked_body_with_extensions_and_trailers() {
+        let response = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n\
+            5;name=value\r\nhello\r\n0\r\nX-Trailer: 1\r\n\r\n";
+
+        assert_eq!(get(response, 5).unwrap(), b"hello");
+    }
+
+    #[test]
+    fn rejects_oversized_chunk() {
+        let response = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\nffffffff\r\n";
+        let err = get(response, 2048).unwrap_err();
+        assert_eq!(*err.kind(), ResponseError);
+    }
+
+    #[test]
+    fn rejects_oversized_chunked_body() {
+        let response = chunked_response(&[&[0u8; 2000], &[0u8; 100]]);
+        let err = get(&response, 2048).unwrap_err();
+        assert_eq!(*err.kind(), ResponseError);
+    }
+
+    #[test]
+    fn rejects_unsupported_transfer_encoding() {
+        let response = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: gzip, chunked\r\n\r\n";
+        let err = get(response, 2048).unwrap_err();
+        assert_eq!(*err.kind(), ResponseError);
+    }
+
+    #[test]
+    fn reads_body_delimited_by_close() {
+        let mut stream = open(respond_with(b"HTTP/1.0 200 OK\r\n\r\nhello"));
+        assert_eq!(stream.get("/", 5).unwrap(), b"hello");
+        assert!(stream.is_closed());
+    }
+
+    #[test]
+    fn rejects_oversized_body_delimited_by_close() {
+        let err = get(b"HTTP/1.0 200 OK\r\n\r\nhello", 4).unwrap_err();
+        assert_eq!(*err.kind(), ResponseError);
+    }
+
+    #[test]
+    fn honors_connection_close() {
+        let mut stream = open(respond_with(
+            b"HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 2\r\n\r\nok",
+        ));
+
+        assert_eq!(stream.get("/", 2).unwrap(), b"ok");
+        assert!(stream.is_closed());
+    }
+
+    #[test]
+    fn rejects_oversized_content_length() {
+        let response = b"HTTP/1.1 200 OK\r\nContent-Length: 4294967296\r\n\r\n";
+        let err = get(response, 2048).unwrap_err();
+        assert_eq!(*err.kind(), ResponseError);
+    }
+
+    #[test]
+    fn rejects_unterminated_headers() {
+        let response = [
+            &b"HTTP/1.1 200 OK\r\nX-Padding: "[..],
+            &[b'x'; MAX_HEADER_SIZE],
+        ]
+        .concat();
+
+        let err = get(&response, 2048).unwrap_err();
+        assert_eq!(*err.kind(), ResponseError);
+    }
+
+    #[test]
+    fn rejects_error_status() {
+        let err = get(b"HTTP/1.1 500 Internal Server Error\r\n\r\n", 2048).unwrap_err();
+        assert_eq!(*err.kind(), ResponseError);
+
+        let err = get(b"HTTP/1.1 2000 OK\r\n\r\n", 2048).unwrap_err();
+        assert_eq!(*err.kind(), ResponseError);
+    }
+}
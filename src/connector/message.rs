@@ -1,8 +1,15 @@
 //! Wrapper type around messages sent to/from the HSM
 
+use super::{Error, ErrorKind::ResponseError};
+use crate::command::MAX_MSG_SIZE;
+use anomaly::fail;
+
 #[cfg(any(feature = "http-server", feature = "mockhsm"))]
 use crate::{command, session};
 
+/// Size of a message header: a 1-byte code followed by a 2-byte length
+const HEADER_SIZE: usize = 3;
+
 /// Messages sent to/from the HSM
 #[derive(Clone, Debug)]
 pub struct Message(pub(crate) Vec<u8>);
@@ -26,6 +33,34 @@ impl From<Message> for Vec<u8> {
 }
 
 impl Message {
+    /// Ensure neither this message nor the length declared in its header
+    /// exceed the maximum size of a YubiHSM 2 message
+    pub(crate) fn check_size(&self) -> Result<(), Error> {
+        if self.0.len() > MAX_MSG_SIZE {
+            fail!(
+                ResponseError,
+                "message too large: {} bytes (max {})",
+                self.0.len(),
+                MAX_MSG_SIZE
+            );
+        }
+
+        if self.0.len() >= HEADER_SIZE {
+            let declared_len = u16::from_be_bytes([self.0[1], self.0[2]]) as usize;
+
+            if HEADER_SIZE + declared_len > MAX_MSG_SIZE {
+                fail!(
+                    ResponseError,
+                    "declared message length too large: {} bytes (max {})",
+                    declared_len,
+                    MAX_MSG_SIZE - HEADER_SIZE
+                );
+            }
+        }
+
+        Ok(())
+    }
+
     /// Parse a `command::Message` from this `connector::Message`
     #[cfg(any(feature = "http-server", feature = "mockhsm"))]
     pub(crate) fn parse(self) -> Result<command::Message, session::Error> {
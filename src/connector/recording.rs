@@ -0,0 +1,239 @@
+//! Connector which records the traffic sent through another connector
+//!
+//! Recordings are text files containing one line per exchange, with the
+//! following whitespace-separated fields:
+//!
+//! - command UUID
+//! - command message bytes (hex)
+//! - response message bytes (hex)
+//! - time taken to receive the response in microseconds (only present if
+//!   timing capture is enabled)
+//!
+//! Recordings can be replayed with `ReplayConnector::open`.
+
+use super::{Connectable, Connection, Connector, Error, ErrorKind, Exchange, Message};
+use crate::{connector::ProtocolVersion, device::SerialNumber};
+use anomaly::format_err;
+use std::{
+    fmt::Write as _,
+    fs::File,
+    io::{BufRead, BufReader, Write},
+    path::Path,
+    str,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use uuid::Uuid;
+
+/// Connector which passes messages through to another `Connector`, writing
+/// each command and its response to a file (see module documentation for
+/// the format).
+///
+/// Session traffic is recorded in its encrypted wire form, so recordings are
+/// useful for debugging framing and protocol issues, but don't reveal the
+/// contents of commands sent within an authenticated session. Failed
+/// exchanges (e.g. I/O errors) aren't recorded.
+#[derive(Clone)]
+pub struct RecordingConnector {
+    /// Connector to pass messages through to
+    connector: Connector,
+
+    /// File the recording is written to
+    file: Arc<Mutex<File>>,
+
+    /// Record how long each response took to arrive?
+    capture_timing: bool,
+
+    /// Timeout to pass through to the connector (see `Connection::set_timeout`)
+    timeout: Arc<Mutex<Option<Duration>>>,
+}
+
+impl RecordingConnector {
+    /// Create a new recording of traffic through the given connector,
+    /// (over)writing it to the file at `path`
+    pub fn create(connector: Connector, path: impl AsRef<Path>) -> Result<Self, Error> {
+        Ok(Self {
+            connector,
+            file: Arc::new(Mutex::new(File::create(path)?)),
+            capture_timing: false,
+            timeout: Arc::new(Mutex::new(None)),
+        })
+    }
+
+    /// Record how long each response took to arrive (disabled by default)
+    pub fn set_capture_timing(&mut self, enabled: bool) {
+        self.capture_timing = enabled;
+    }
+
+    /// Write an exchange to the recording
+    fn record(
+        &self,
+        uuid: Uuid,
+        command: &[u8],
+        response: &[u8],
+        started_at: Instant,
+    ) -> Result<(), Error> {
+        let mut line = format!("{} {} {}", uuid, encode_hex(command), encode_hex(response));
+
+        if self.capture_timing {
+            write!(line, " {}", started_at.elapsed().as_micros())?;
+        }
+
+        let mut file = self.file.lock().unwrap();
+        writeln!(file, "{}", line)?;
+        file.flush()?;
+        Ok(())
+    }
+}
+
+impl Connectable for RecordingConnector {
+    fn box_clone(&self) -> Box<dyn Connectable> {
+        Box::new(self.clone())
+    }
+
+    fn connect(&self) -> Result<Box<dyn Connection>, Error> {
+        Ok(Box::new(self.clone()))
+    }
+
+    fn serial_number(&self) -> Option<SerialNumber> {
+        self.connector.serial_number()
+    }
+}
+
+impl Connection for RecordingConnector {
+    fn send_message(&self, uuid: Uuid, msg: Message) -> Result<Message, Error> {
+        self.send_message_with_size_hint(uuid, msg, crate::command::MAX_MSG_SIZE)
+    }
+
+    fn send_message_with_size_hint(
+        &self,
+        uuid: Uuid,
+        msg: Message,
+        max_response_size: usize,
+    ) -> Result<Message, Error> {
+        let started_at = Instant::now();
+        let command = msg.clone();
+        let deadline = self.timeout.lock().unwrap().map(|t| started_at + t);
+
+        let response =
+            self.connector
+                .send_message_with_size_hint(uuid, msg, max_response_size, deadline)?;
+
+        self.record(uuid, command.as_ref(), response.as_ref(), started_at)?;
+        Ok(response)
+    }
+
+    fn set_timeout(&self, timeout: Option<Duration>) -> Result<(), Error> {
+        *self.timeout.lock().unwrap() = timeout;
+        Ok(())
+    }
+
+    fn protocol_version(&self) -> Result<Option<ProtocolVersion>, Error> {
+        self.connector.protocol_version()
+    }
+}
+
+/// Read the exchanges in a recording written by a `RecordingConnector`
+pub(super) fn read_file(path: impl AsRef<Path>) -> Result<Vec<Exchange>, Error> {
+    let mut exchanges = vec![];
+
+    for (i, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+        let line = line?;
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split_whitespace().collect();
+
+        let exchange = match fields.as_slice() {
+            [_uuid, command, response] | [_uuid, command, response, _] => {
+                decode_hex(command).and_then(|c| Some(Exchange::new(c, decode_hex(response)?)))
+            }
+            _ => None,
+        };
+
+        exchanges.push(exchange.ok_or_else(|| {
+            format_err!(ErrorKind::IoError, "malformed recording (line {})", i + 1)
+        })?);
+    }
+
+    Ok(exchanges)
+}
+
+/// Encode bytes as lower case hexadecimal
+fn encode_hex(bytes: &[u8]) -> String {
+    let mut s = String::with_capacity(bytes.len() * 2);
+
+    for byte in bytes {
+        write!(s, "{:02x}", byte).unwrap();
+    }
+
+    s
+}
+
+/// Decode hexadecimal bytes, returning `None` if they're malformed
+fn decode_hex(s: &str) -> Option<Vec<u8>> {
+    s.as_bytes()
+        .chunks(2)
+        .map(|pair| match pair {
+            [_, _] => str::from_utf8(pair)
+                .ok()
+                .and_then(|b| u8::from_str_radix(b, 16).ok()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connector::ReplayConnector;
+    use std::{env, fs, process};
+
+    #[test]
+    fn record_and_replay() {
+        let path = env::temp_dir().join(format!("yubihsm-recording-{}.txt", process::id()));
+        let command = vec![0x01, 0x00, 0x04, 0xde, 0xad, 0xbe, 0xef];
+        let response = vec![0x81, 0x00, 0x04, 0xde, 0xad, 0xbe, 0xef];
+
+        let mut recorder = RecordingConnector::create(
+            Connector::replay(vec![Exchange::new(command.clone(), response.clone())]),
+            &path,
+        )
+        .unwrap();
+
+        recorder.set_capture_timing(true);
+
+        let uuid = crate::uuid::new_v4();
+        Connector::from(recorder)
+            .send_message(uuid, command.clone().into())
+            .unwrap();
+
+        let recording = fs::read_to_string(&path).unwrap();
+        let fields: Vec<&str> = recording.split_whitespace().collect();
+        assert_eq!(fields.len(), 4);
+        assert_eq!(fields[0], uuid.to_string());
+        assert_eq!(fields[1], "010004deadbeef");
+
+        let replay = ReplayConnector::open(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(replay.remaining(), 1);
+        let replayed: Vec<u8> = Connector::from(replay)
+            .send_message(uuid, command.into())
+            .unwrap()
+            .into();
+
+        assert_eq!(replayed, response);
+    }
+
+    #[test]
+    fn hex_round_trip() {
+        let bytes = [0x00, 0x7f, 0x80, 0xff];
+        assert_eq!(encode_hex(&bytes), "007f80ff");
+        assert_eq!(decode_hex("007f80ff").unwrap(), bytes);
+        assert!(decode_hex("007").is_none());
+        assert!(decode_hex("zz").is_none());
+    }
+}
@@ -0,0 +1,145 @@
+//! Connector which replays previously recorded command/response pairs
+
+use super::{recording, Connectable, Connection, Error, ErrorKind, Message};
+use anomaly::fail;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::VecDeque,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+use uuid::Uuid;
+
+/// A command sent to the HSM along with the response it returned, in their
+/// wire form
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct Exchange {
+    /// Command message bytes
+    pub command: Vec<u8>,
+
+    /// Response message bytes
+    pub response: Vec<u8>,
+}
+
+impl Exchange {
+    /// Create a new `Exchange`
+    pub fn new(command: impl Into<Vec<u8>>, response: impl Into<Vec<u8>>) -> Self {
+        Self {
+            command: command.into(),
+            response: response.into(),
+        }
+    }
+}
+
+/// Connector which serves a recorded sequence of exchanges in order, failing
+/// with `ErrorKind::RequestError` if it's sent a command other than the next
+/// recorded one (or once the recording has been exhausted).
+///
+/// Unlike the `MockHsm`, this doesn't implement any HSM logic: it replays
+/// exact bytes. Note that authenticated sessions begin with a random host
+/// challenge, so a `Client` can't replay a recorded session verbatim; this
+/// is intended for reproducing issues at the connector/framing level.
+///
+/// Clones share the same position in the recording.
+#[derive(Clone, Debug)]
+pub struct ReplayConnector {
+    /// Exchanges which haven't been replayed yet
+    exchanges: Arc<Mutex<VecDeque<Exchange>>>,
+}
+
+impl ReplayConnector {
+    /// Create a new `ReplayConnector` which replays the given exchanges
+    pub fn new(exchanges: impl IntoIterator<Item = Exchange>) -> Self {
+        Self {
+            exchanges: Arc::new(Mutex::new(exchanges.into_iter().collect())),
+        }
+    }
+
+    /// Open a recording written by a `RecordingConnector`
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        Ok(Self::new(recording::read_file(path)?))
+    }
+
+    /// Number of recorded exchanges which haven't been replayed yet
+    pub fn remaining(&self) -> usize {
+        self.exchanges.lock().unwrap().len()
+    }
+}
+
+impl Connectable for ReplayConnector {
+    fn box_clone(&self) -> Box<dyn Connectable> {
+        Box::new(self.clone())
+    }
+
+    fn connect(&self) -> Result<Box<dyn Connection>, Error> {
+        Ok(Box::new(self.clone()))
+    }
+}
+
+impl Connection for ReplayConnector {
+    fn send_message(&self, _uuid: Uuid, msg: Message) -> Result<Message, Error> {
+        let exchange = match self.exchanges.lock().unwrap().pop_front() {
+            Some(exchange) => exchange,
+            None => fail!(
+                ErrorKind::RequestError,
+                "no recorded exchanges left to replay"
+            ),
+        };
+
+        if exchange.command != msg.as_ref() {
+            fail!(
+                ErrorKind::RequestError,
+                "unexpected command: expected {:?}, got {:?}",
+                exchange.command,
+                msg.as_ref()
+            );
+        }
+
+        Ok(exchange.response.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connector::Connector;
+
+    fn replay_connector() -> ReplayConnector {
+        ReplayConnector::new(vec![
+            Exchange::new(vec![0x01, 0x00, 0x01, 0x42], vec![0x81, 0x00, 0x01, 0x42]),
+            Exchange::new(vec![0x01, 0x00, 0x01, 0x43], vec![0x81, 0x00, 0x01, 0x43]),
+        ])
+    }
+
+    fn send(connector: &Connector, command: &[u8]) -> Result<Vec<u8>, Error> {
+        connector
+            .send_message(crate::uuid::new_v4(), command.to_vec().into())
+            .map(Into::into)
+    }
+
+    #[test]
+    fn replays_exchanges_in_order() {
+        let replay = replay_connector();
+        let connector = Connector::from(replay.clone());
+
+        assert_eq!(
+            send(&connector, &[0x01, 0x00, 0x01, 0x42]).unwrap(),
+            [0x81, 0x00, 0x01, 0x42]
+        );
+        assert_eq!(
+            send(&connector, &[0x01, 0x00, 0x01, 0x43]).unwrap(),
+            [0x81, 0x00, 0x01, 0x43]
+        );
+        assert_eq!(replay.remaining(), 0);
+
+        let err = send(&connector, &[0x01, 0x00, 0x01, 0x42]).unwrap_err();
+        assert_eq!(*err.kind(), ErrorKind::RequestError);
+    }
+
+    #[test]
+    fn rejects_unexpected_command() {
+        let connector = Connector::from(replay_connector());
+        let err = send(&connector, &[0x01, 0x00, 0x01, 0x43]).unwrap_err();
+        assert_eq!(*err.kind(), ErrorKind::RequestError);
+    }
+}
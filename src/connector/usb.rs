@@ -4,7 +4,9 @@
 //! method in the event there is only one expected to be connected at a time.
 //!
 //! To enumerate available USB devices (e.g. in the case there is more than
-//! one YubiHSM connected to the same computer), use [`Devices`].
+//! one YubiHSM connected to the same computer), use `UsbConnector::enumerate`
+//! or [`Devices`], and `UsbConnector::open_serial` to connect to a particular
+//! one.
 //!
 //! [`Connector::usb`]: https://docs.rs/yubihsm/latest/yubihsm/connector/struct.Connector.html#method.usb
 
@@ -19,10 +21,13 @@ mod timeout;
 pub use self::{
     config::UsbConfig,
     connection::UsbConnection,
-    device::{Device, Devices},
+    device::{Device, Devices, UsbDeviceInfo},
     timeout::UsbTimeout,
 };
-use crate::connector::{self, Connectable, Connection};
+use crate::{
+    connector::{self, Connectable, Connection},
+    device::SerialNumber,
+};
 
 /// USB vendor ID for Yubico
 pub const YUBICO_VENDOR_ID: u16 = 0x1050;
@@ -54,6 +59,22 @@ impl UsbConnector {
     pub fn create(config: &UsbConfig) -> Box<dyn Connectable> {
         Box::new(UsbConnector(config.clone()))
     }
+
+    /// Detect connected YubiHSM 2s, returning the serial number, bus, and
+    /// address of each one
+    pub fn enumerate() -> Result<Vec<UsbDeviceInfo>, connector::Error> {
+        let devices = Devices::detect(UsbTimeout::default())?;
+        Ok(devices.iter().map(Device::info).collect())
+    }
+
+    /// Create a new `UsbConnector` which connects to the YubiHSM 2 with the
+    /// given serial number (using the default timeout)
+    pub fn open_serial(serial: SerialNumber) -> Box<dyn Connectable> {
+        Self::create(&UsbConfig {
+            serial: Some(serial),
+            ..UsbConfig::default()
+        })
+    }
 }
 
 impl Connectable for UsbConnector {
@@ -8,12 +8,15 @@ use crate::{
     connector::{self, Connection, ErrorKind::UsbError, Message},
 };
 use anomaly::fail;
-use std::sync::Mutex;
+use std::{cmp, sync::Mutex, time::Duration};
 use uuid::Uuid;
 
 /// Number of times to retry a bulk message receive operation before giving up
 const MAX_RECV_RETRIES: usize = 3;
 
+/// Maximum packet size of the YubiHSM 2's bulk endpoints
+const USB_PACKET_SIZE: usize = 64;
+
 /// Connection to HSM via USB
 pub struct UsbConnection {
     /// Handle to the underlying USB device
@@ -24,6 +27,9 @@ pub struct UsbConnection {
 
     /// Timeout for reading from / writing to the YubiHSM 2
     timeout: UsbTimeout,
+
+    /// Timeout overriding `timeout` (see `Connection::set_timeout`)
+    timeout_override: Mutex<Option<UsbTimeout>>,
 }
 
 impl UsbConnection {
@@ -46,6 +52,7 @@ impl UsbConnection {
         Ok(Self {
             device,
             timeout,
+            timeout_override: Mutex::new(None),
             handle: Mutex::new(handle),
         })
     }
@@ -58,10 +65,33 @@ impl UsbConnection {
 
 impl Connection for UsbConnection {
     /// Send a command to the YubiHSM and read its response
-    fn send_message(&self, _uuid: Uuid, cmd: Message) -> Result<Message, connector::Error> {
+    fn send_message(&self, uuid: Uuid, cmd: Message) -> Result<Message, connector::Error> {
+        self.send_message_with_size_hint(uuid, cmd, MAX_MSG_SIZE)
+    }
+
+    /// Send a command to the YubiHSM and read a response of (at most) the
+    /// given size
+    fn send_message_with_size_hint(
+        &self,
+        _uuid: Uuid,
+        cmd: Message,
+        max_response_size: usize,
+    ) -> Result<Message, connector::Error> {
+        let timeout = self
+            .timeout_override
+            .lock()
+            .unwrap()
+            .unwrap_or(self.timeout);
         let handle = self.handle.lock().unwrap();
-        send_message(&handle, cmd.as_ref(), self.timeout)?;
-        recv_message(&handle, self.timeout)
+        send_message(&handle, cmd.as_ref(), timeout)?;
+        recv_message_with_size(&handle, timeout, max_response_size)
+    }
+
+    /// Apply the given timeout to subsequent messages, or the configured
+    /// `UsbConfig::timeout_ms` if `None`
+    fn set_timeout(&self, timeout: Option<Duration>) -> Result<(), connector::Error> {
+        *self.timeout_override.lock().unwrap() = timeout.map(UsbTimeout::from);
+        Ok(())
     }
 }
 
@@ -96,14 +126,31 @@ fn recv_message(
     handle: &rusb::DeviceHandle<rusb::Context>,
     timeout: UsbTimeout,
 ) -> Result<Message, connector::Error> {
-    // Allocate a buffer which is the maximum size we expect to receive
-    let mut response = vec![0u8; MAX_MSG_SIZE];
+    recv_message_with_size(handle, timeout, MAX_MSG_SIZE)
+}
+
+/// Receive a message of (at most) the given size
+fn recv_message_with_size(
+    handle: &rusb::DeviceHandle<rusb::Context>,
+    timeout: UsbTimeout,
+    max_size: usize,
+) -> Result<Message, connector::Error> {
+    // Allocate a buffer which is the maximum size we expect to receive,
+    // rounded up to a whole number of USB packets so the device can't
+    // overflow it
+    let buffer_size = max_size + (USB_PACKET_SIZE - max_size % USB_PACKET_SIZE) % USB_PACKET_SIZE;
+    let mut response = vec![0u8; cmp::min(buffer_size, MAX_MSG_SIZE)];
 
     for attempts_remaining in (0..MAX_RECV_RETRIES).rev() {
         match handle.read_bulk(YUBIHSM2_BULK_IN_ENDPOINT, &mut response, timeout.duration()) {
             Ok(nbytes) => {
                 response.truncate(nbytes);
-                return Ok(response.into());
+
+                // Check the length declared in the header as soon as the
+                // message has been read
+                let message = Message::from(response);
+                message.check_size()?;
+                return Ok(message);
             }
             // Sometimes I/O errors occur sporadically. When this happens,
             // retry the read for `MAX_RECV_RETRIES` attempts
@@ -20,6 +20,9 @@ use std::{
     vec::IntoIter,
 };
 
+/// Number of base 10 digits in a YubiHSM 2 serial number
+const SERIAL_NUMBER_DIGITS: usize = 10;
+
 /// A collection of detected YubiHSM 2 devices, represented as `Device`
 pub struct Devices(Vec<Device>);
 
@@ -115,10 +118,8 @@ impl Devices {
             let manufacturer = handle.read_manufacturer_string(language, &desc, t)?;
             let product = handle.read_product_string(language, &desc, t)?;
             let product_name = format!("{} {}", manufacturer, product);
-            let serial_number: SerialNumber = handle
-                .read_serial_number_string(language, &desc, t)?
-                .parse()
-                .map_err(|e| format_err!(AddrInvalid, "{}", e))?;
+            let serial_number =
+                parse_serial_number(&handle.read_serial_number_string(language, &desc, t)?)?;
 
             debug!(
                 "USB(bus={},addr={}): found {} (serial #{})",
@@ -219,6 +220,16 @@ impl Device {
         self.device.address()
     }
 
+    /// Get the serial number, bus, and address of this device
+    pub fn info(&self) -> UsbDeviceInfo {
+        UsbDeviceInfo {
+            serial_number: self.serial_number,
+            bus: self.bus_number(),
+            address: self.address(),
+            product_name: self.product_name.clone(),
+        }
+    }
+
     /// Open a handle to the underlying device (for use by `UsbConnection`)
     pub(super) fn open_handle(
         &self,
@@ -246,6 +257,50 @@ impl Debug for Device {
     }
 }
 
+/// Information about a detected YubiHSM 2 (see `UsbConnector::enumerate`)
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UsbDeviceInfo {
+    /// Serial number of the YubiHSM 2 device
+    pub serial_number: SerialNumber,
+
+    /// USB bus number
+    pub bus: u8,
+
+    /// Address of the device on its bus
+    pub address: u8,
+
+    /// Product vendor and name
+    pub product_name: String,
+}
+
+/// Parse a serial number from a USB serial number string descriptor.
+///
+/// Descriptors may include trailing NUL bytes or whitespace, and some
+/// firmware versions omit the leading zeroes, so these are normalized before
+/// parsing.
+fn parse_serial_number(descriptor: &str) -> Result<SerialNumber, connector::Error> {
+    let digits = descriptor.trim_matches(|c: char| c == '\0' || c.is_whitespace());
+
+    let normalized = if !digits.is_empty()
+        && digits.len() < SERIAL_NUMBER_DIGITS
+        && digits.bytes().all(|b| b.is_ascii_digit())
+    {
+        format!("{:0>width$}", digits, width = SERIAL_NUMBER_DIGITS)
+    } else {
+        digits.to_owned()
+    };
+
+    normalized.parse().map_err(|e| {
+        format_err!(
+            AddrInvalid,
+            "invalid serial number in USB descriptor {:?}: {}",
+            descriptor,
+            e
+        )
+        .into()
+    })
+}
+
 /// Flush any unconsumed messages still in the buffer to get the connection
 /// back into a clean state
 fn flush(handle: &mut rusb::DeviceHandle<rusb::Context>) -> Result<(), connector::Error> {
@@ -260,3 +315,21 @@ fn flush(handle: &mut rusb::DeviceHandle<rusb::Context>) -> Result<(), connector
         Err(e) => Err(e.into()),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_serial_number_descriptors() {
+        let expected: SerialNumber = "0007550054".parse().unwrap();
+
+        for descriptor in &["0007550054", "7550054", "0007550054\0\0", " 7550054\n"] {
+            assert_eq!(parse_serial_number(descriptor).unwrap(), expected);
+        }
+
+        assert!(parse_serial_number("").is_err());
+        assert!(parse_serial_number("YubiHSM").is_err());
+        assert!(parse_serial_number("00075500541").is_err());
+    }
+}
@@ -0,0 +1,95 @@
+//! Connector protocol versions
+
+use super::{Error, ErrorKind};
+use anomaly::{fail, format_err};
+use std::{fmt, str::FromStr};
+
+/// Newest major version of the `yubihsm-connector` protocol we know how to
+/// speak. Connectors reporting a newer major version are rejected.
+pub const MAX_PROTOCOL_MAJOR_VERSION: u16 = 3;
+
+/// Version reported by a connector (e.g. in `yubihsm-connector`'s
+/// `/connector/status` page)
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
+pub struct ProtocolVersion {
+    /// Major version
+    pub major: u16,
+
+    /// Minor version
+    pub minor: u16,
+
+    /// Patch version
+    pub patch: u16,
+}
+
+impl ProtocolVersion {
+    /// Create a new `ProtocolVersion`
+    pub fn new(major: u16, minor: u16, patch: u16) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// Can we communicate with a connector reporting this version?
+    pub fn is_compatible(self) -> bool {
+        self.major <= MAX_PROTOCOL_MAJOR_VERSION
+    }
+}
+
+impl FromStr for ProtocolVersion {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let mut components = [0u16; 3];
+        let mut parts = s.trim().split('.');
+
+        for component in components.iter_mut() {
+            let part = parts
+                .next()
+                .ok_or_else(|| format_err!(ErrorKind::ResponseError, "bad version: {:?}", s))?;
+
+            *component = part
+                .parse()
+                .map_err(|_| format_err!(ErrorKind::ResponseError, "bad version: {:?}", s))?;
+        }
+
+        if parts.next().is_some() {
+            fail!(ErrorKind::ResponseError, "bad version: {:?}", s);
+        }
+
+        Ok(Self::new(components[0], components[1], components[2]))
+    }
+}
+
+impl fmt::Display for ProtocolVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_version() {
+        let version = "3.0.4".parse::<ProtocolVersion>().unwrap();
+        assert_eq!(version, ProtocolVersion::new(3, 0, 4));
+        assert_eq!(version.to_string(), "3.0.4");
+        assert!(version.is_compatible());
+    }
+
+    #[test]
+    fn reject_malformed_versions() {
+        for s in &["", "3", "3.0", "3.0.4.1", "3.x.4"] {
+            assert!(s.parse::<ProtocolVersion>().is_err());
+        }
+    }
+
+    #[test]
+    fn newer_major_version_is_incompatible() {
+        assert!(!ProtocolVersion::new(MAX_PROTOCOL_MAJOR_VERSION + 1, 0, 0).is_compatible());
+    }
+}
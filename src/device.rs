@@ -3,12 +3,18 @@
 pub(crate) mod commands;
 mod error;
 mod info;
+mod reset;
+mod rng;
 pub(super) mod serial;
 pub(super) mod storage;
+mod version;
 
 pub use self::{
     error::{Error, ErrorKind},
     info::Info,
+    reset::{ResetImpact, ResetToken, RESET_TOKEN_LIFETIME},
+    rng::{RngStatus, RNG_SAMPLE_SIZE},
     serial::Number as SerialNumber,
     storage::Info as StorageInfo,
+    version::Version,
 };
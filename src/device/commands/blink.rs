@@ -25,4 +25,5 @@ pub(crate) struct BlinkDeviceResponse {}
 
 impl Response for BlinkDeviceResponse {
     const COMMAND_CODE: command::Code = command::Code::BlinkDevice;
+    const MAX_SIZE: usize = 0;
 }
@@ -5,9 +5,13 @@
 use crate::{
     command::{self, Command},
     response::Response,
+    session::securechannel::MAX_SESSION_DATA_SIZE,
 };
 use serde::{Deserialize, Serialize};
 
+/// Maximum length of a message which can be sent with `Client::echo`
+pub(crate) const MAX_ECHO_SIZE: usize = MAX_SESSION_DATA_SIZE;
+
 /// Request parameters for `command::echo`
 #[derive(Serialize, Deserialize, Debug)]
 pub(crate) struct EchoCommand {
@@ -25,6 +25,13 @@ impl Response for DeviceInfoResponse {
     const COMMAND_CODE: command::Code = command::Code::DeviceInfo;
 }
 
+impl DeviceInfoResponse {
+    /// Firmware version of the device
+    pub fn version(&self) -> device::Version {
+        self.0.version()
+    }
+}
+
 impl From<DeviceInfoResponse> for device::Info {
     fn from(response: DeviceInfoResponse) -> device::Info {
         response.0
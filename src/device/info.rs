@@ -1,6 +1,6 @@
 //! Device info
 
-use super::serial;
+use super::{serial, Version};
 use crate::Algorithm;
 use serde::{Deserialize, Serialize};
 
@@ -28,3 +28,10 @@ pub struct Info {
     /// Supported algorithms
     pub algorithms: Vec<Algorithm>,
 }
+
+impl Info {
+    /// Firmware version of the device
+    pub fn version(&self) -> Version {
+        Version::new(self.major_version, self.minor_version, self.build_version)
+    }
+}
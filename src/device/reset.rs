@@ -0,0 +1,143 @@
+//! Confirmation tokens for two-step HSM resets
+
+use super::SerialNumber;
+use crate::{authentication::DEFAULT_AUTHENTICATION_KEY_ID, object};
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    time::{Duration, Instant},
+};
+
+/// How long a `ResetToken` remains valid after it's been issued (60 seconds)
+pub const RESET_TOKEN_LIFETIME: Duration = Duration::from_secs(60);
+
+/// Token confirming the intent to reset a particular HSM.
+///
+/// Obtained from `Client::prepare_reset` and consumed by
+/// `Client::confirm_reset`, which only resets the device if the token was
+/// issued for the same HSM within the last `RESET_TOKEN_LIFETIME`.
+#[derive(Debug)]
+pub struct ResetToken {
+    /// Serial number of the HSM this token was issued for
+    serial_number: SerialNumber,
+
+    /// When this token was issued
+    issued_at: Instant,
+}
+
+impl ResetToken {
+    /// Issue a new token for the HSM with the given serial number
+    pub(crate) fn new(serial_number: SerialNumber) -> Self {
+        Self {
+            serial_number,
+            issued_at: Instant::now(),
+        }
+    }
+
+    /// Serial number of the HSM this token was issued for
+    pub fn serial_number(&self) -> SerialNumber {
+        self.serial_number
+    }
+
+    /// Has this token expired?
+    pub fn is_expired(&self) -> bool {
+        self.issued_at.elapsed() > RESET_TOKEN_LIFETIME
+    }
+}
+
+/// Everything a reset would irrecoverably delete from an HSM.
+///
+/// Obtained from `Client::reset_impact`, which doesn't modify the HSM.
+#[derive(Clone, Debug)]
+pub struct ResetImpact {
+    /// Serial number of the HSM which would be reset
+    pub serial_number: SerialNumber,
+
+    /// Number of objects of each type which would be deleted
+    pub object_counts: BTreeMap<object::Type, usize>,
+
+    /// Information about every authentication key which would be deleted.
+    ///
+    /// After a reset only the factory default authentication key is
+    /// available, so any keys listed here are lost for good.
+    pub authentication_keys: Vec<object::Info>,
+}
+
+impl ResetImpact {
+    /// Total number of objects which would be deleted
+    pub fn total_objects(&self) -> usize {
+        self.object_counts.values().sum()
+    }
+
+    /// Human-readable warning describing what the reset would destroy,
+    /// naming each authentication key which would be lost
+    pub fn warning(&self) -> String {
+        let mut warning = format!(
+            "WARNING: resetting HSM {} will irrecoverably delete {} object(s)",
+            self.serial_number,
+            self.total_objects()
+        );
+
+        for (object_type, count) in &self.object_counts {
+            write!(warning, "\n  {:?}: {}", object_type, count).unwrap();
+        }
+
+        for key in &self.authentication_keys {
+            write!(
+                warning,
+                "\nauthentication key 0x{:04x} ({:?}) with capabilities 0x{:x} will be lost",
+                key.object_id,
+                key.label.to_string(),
+                key.capabilities.bits()
+            )
+            .unwrap();
+        }
+
+        write!(
+            warning,
+            "\nafterwards only the factory default authentication key (0x{:04x}) \
+             will be available",
+            DEFAULT_AUTHENTICATION_KEY_ID
+        )
+        .unwrap();
+
+        warning
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{authentication, Capability, Domain};
+
+    #[test]
+    fn warning_names_authentication_keys() {
+        let mut object_counts = BTreeMap::new();
+        object_counts.insert(object::Type::AsymmetricKey, 3);
+        object_counts.insert(object::Type::AuthenticationKey, 1);
+
+        let impact = ResetImpact {
+            serial_number: "0123456789".parse().unwrap(),
+            object_counts,
+            authentication_keys: vec![object::Info {
+                capabilities: Capability::RESET_DEVICE,
+                object_id: 2,
+                length: 40,
+                domains: Domain::all(),
+                object_type: object::Type::AuthenticationKey,
+                algorithm: authentication::Algorithm::YubicoAes.into(),
+                sequence: 0,
+                origin: object::Origin::Imported,
+                label: "admin".into(),
+                delegated_capabilities: Capability::all(),
+            }],
+        };
+
+        assert_eq!(impact.total_objects(), 4);
+
+        let warning = impact.warning();
+        assert!(warning.starts_with("WARNING"));
+        assert!(warning.contains("delete 4 object(s)"));
+        assert!(warning.contains("authentication key 0x0002 (\"admin\")"));
+    }
+}
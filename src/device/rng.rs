@@ -0,0 +1,141 @@
+//! Host-side statistical checks of the HSM's random number generator
+
+/// Number of bytes sampled by `RngStatus` checks (20,000 bits, as in the
+/// FIPS 140-2 power-up tests)
+pub const RNG_SAMPLE_SIZE: usize = 2500;
+
+/// Bounds on the number of one bits in a 20,000 bit sample (monobit test)
+const MONOBIT_BOUNDS: (usize, usize) = (9725, 10275);
+
+/// Bounds on the poker test statistic for a 20,000 bit sample
+const POKER_BOUNDS: (f64, f64) = (2.16, 46.17);
+
+/// Maximum length of a run of identical bits in a 20,000 bit sample
+const MAX_RUN_LENGTH: usize = 25;
+
+/// Results of running the (withdrawn) FIPS 140-2 statistical RNG tests over
+/// a sample of output from the HSM's RNG.
+///
+/// These tests are run on the host and only detect grossly broken output
+/// (e.g. stuck or heavily biased bits). Passing them is *not* evidence that
+/// the RNG is cryptographically secure: the HSM runs its own health tests
+/// internally, but doesn't expose their results.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RngStatus {
+    /// Number of one bits in the sample
+    pub ones: usize,
+
+    /// Poker test statistic (distribution of 4-bit values)
+    pub poker: f64,
+
+    /// Longest run of identical bits in the sample
+    pub longest_run: usize,
+}
+
+impl RngStatus {
+    /// Run the statistical tests over the given sample, which must be
+    /// `RNG_SAMPLE_SIZE` bytes
+    pub(crate) fn from_sample(sample: &[u8]) -> Self {
+        assert_eq!(sample.len(), RNG_SAMPLE_SIZE, "wrong RNG sample size");
+
+        let ones = sample.iter().map(|b| b.count_ones() as usize).sum();
+
+        let mut nibble_counts = [0usize; 16];
+
+        for byte in sample {
+            nibble_counts[(byte >> 4) as usize] += 1;
+            nibble_counts[(byte & 0xf) as usize] += 1;
+        }
+
+        let num_nibbles = (sample.len() * 2) as f64;
+        let sum_of_squares: f64 = nibble_counts.iter().map(|&n| (n * n) as f64).sum();
+        let poker = 16.0 / num_nibbles * sum_of_squares - num_nibbles;
+
+        let mut longest_run = 0;
+        let mut current_run = 0;
+        let mut previous_bit = None;
+
+        for byte in sample {
+            for i in (0..8).rev() {
+                let bit = (byte >> i) & 1;
+
+                if previous_bit == Some(bit) {
+                    current_run += 1;
+                } else {
+                    current_run = 1;
+                    previous_bit = Some(bit);
+                }
+
+                if current_run > longest_run {
+                    longest_run = current_run;
+                }
+            }
+        }
+
+        Self {
+            ones,
+            poker,
+            longest_run,
+        }
+    }
+
+    /// Did the monobit test pass?
+    pub fn monobit_passed(&self) -> bool {
+        self.ones > MONOBIT_BOUNDS.0 && self.ones < MONOBIT_BOUNDS.1
+    }
+
+    /// Did the poker test pass?
+    pub fn poker_passed(&self) -> bool {
+        self.poker > POKER_BOUNDS.0 && self.poker < POKER_BOUNDS.1
+    }
+
+    /// Did the long run test pass?
+    pub fn long_run_passed(&self) -> bool {
+        self.longest_run <= MAX_RUN_LENGTH
+    }
+
+    /// Did all of the tests pass?
+    pub fn passed(&self) -> bool {
+        self.monobit_passed() && self.poker_passed() && self.long_run_passed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic sample from an xorshift64 generator
+    fn xorshift_sample() -> Vec<u8> {
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+
+        (0..RNG_SAMPLE_SIZE)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state >> 32) as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn pseudorandom_sample_passes() {
+        assert!(RngStatus::from_sample(&xorshift_sample()).passed());
+    }
+
+    #[test]
+    fn stuck_output_fails() {
+        let status = RngStatus::from_sample(&[0u8; RNG_SAMPLE_SIZE]);
+        assert!(!status.monobit_passed());
+        assert!(!status.poker_passed());
+        assert!(!status.long_run_passed());
+    }
+
+    #[test]
+    fn repeating_pattern_fails() {
+        let status = RngStatus::from_sample(&[0x55u8; RNG_SAMPLE_SIZE]);
+        assert!(status.monobit_passed());
+        assert!(status.long_run_passed());
+        assert!(!status.poker_passed());
+    }
+}
@@ -21,6 +21,18 @@ impl Display for Number {
     }
 }
 
+impl From<u32> for Number {
+    fn from(number: u32) -> Number {
+        Number(number)
+    }
+}
+
+impl From<Number> for u32 {
+    fn from(number: Number) -> u32 {
+        number.0
+    }
+}
+
 impl FromStr for Number {
     type Err = Error;
 
@@ -42,3 +54,22 @@ impl FromStr for Number {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_is_zero_padded() {
+        let number = Number::from(7_550_054);
+        assert_eq!(number.to_string(), "0007550054");
+        assert_eq!("0007550054".parse::<Number>().unwrap(), number);
+        assert_eq!(u32::from(number), 7_550_054);
+    }
+
+    #[test]
+    fn rejects_wrong_length() {
+        let err = "7550054".parse::<Number>().unwrap_err();
+        assert_eq!(*err.kind(), ErrorKind::WrongLength);
+    }
+}
@@ -0,0 +1,50 @@
+//! YubiHSM 2 firmware versions
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Firmware version reported by the HSM (see `device::Info::version`).
+///
+/// Versions are ordered by major, minor, then build version, so they can be
+/// compared to check whether the firmware supports a given feature.
+#[derive(Copy, Clone, Debug, Deserialize, Eq, Hash, PartialEq, PartialOrd, Ord, Serialize)]
+pub struct Version {
+    /// Major version
+    pub major: u8,
+
+    /// Minor version
+    pub minor: u8,
+
+    /// Build version (i.e. patchlevel)
+    pub build: u8,
+}
+
+impl Version {
+    /// Create a new `Version`
+    pub const fn new(major: u8, minor: u8, build: u8) -> Self {
+        Self {
+            major,
+            minor,
+            build,
+        }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.build)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions_are_ordered() {
+        assert!(Version::new(2, 1, 0) < Version::new(2, 2, 0));
+        assert!(Version::new(2, 0, 9) < Version::new(2, 1, 0));
+        assert!(Version::new(1, 9, 9) < Version::new(2, 0, 0));
+        assert_eq!(Version::new(2, 1, 0).to_string(), "2.1.0");
+    }
+}
@@ -8,8 +8,8 @@ pub use self::error::{Error, ErrorKind};
 
 use anomaly::fail;
 use bitflags::bitflags;
+use core::fmt;
 use serde::{de, ser, Deserialize, Serialize};
-use std::fmt;
 
 /// All domains as an array of bitflag types
 pub const DOMAINS: [Domain; 16] = [
@@ -28,16 +28,16 @@ impl Command for DeriveEcdhCommand {
     type ResponseType = DeriveEcdhResponse;
 }
 
-/// Signed SSH certificates
+/// ECDH shared secret (the X coordinate of the shared point)
 #[derive(Serialize, Deserialize, Debug)]
-pub struct DeriveEcdhResponse(ecdh::UncompressedPoint);
+pub struct DeriveEcdhResponse(Vec<u8>);
 
 impl Response for DeriveEcdhResponse {
     const COMMAND_CODE: command::Code = command::Code::DeriveEcdh;
 }
 
-impl From<DeriveEcdhResponse> for ecdh::UncompressedPoint {
-    fn from(response: DeriveEcdhResponse) -> ecdh::UncompressedPoint {
+impl From<DeriveEcdhResponse> for Vec<u8> {
+    fn from(response: DeriveEcdhResponse) -> Vec<u8> {
         response.0
     }
 }
@@ -21,6 +21,14 @@ pub(crate) struct SignEcdsaCommand {
 
 impl Command for SignEcdsaCommand {
     type ResponseType = SignEcdsaResponse;
+
+    fn object_id(&self) -> Option<object::Id> {
+        Some(self.key_id)
+    }
+
+    fn object_type(&self) -> Option<object::Type> {
+        Some(object::Type::AsymmetricKey)
+    }
 }
 
 /// Response from ECDSA signing request
@@ -29,6 +37,9 @@ pub struct SignEcdsaResponse(pub Vec<u8>);
 
 impl Response for SignEcdsaResponse {
     const COMMAND_CODE: command::Code = command::Code::SignEcdsa;
+
+    // ASN.1 DER-encoded signatures over secp521r1 are the largest supported
+    const MAX_SIZE: usize = 139;
 }
 
 impl From<SignEcdsaResponse> for Vec<u8> {
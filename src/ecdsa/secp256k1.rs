@@ -19,3 +19,106 @@ pub type RecoverableSignature = k256::ecdsa::recoverable::Signature;
 
 /// ECDSA/secp256k1 signer
 pub type Signer = super::Signer<Secp256k1>;
+
+/// ECDSA/secp256k1 public key
+pub type PublicKey = k256::ecdsa::VerifyKey;
+
+/// Length of an Ethereum address
+pub const ETHEREUM_ADDRESS_SIZE: usize = 20;
+
+/// Recover the secp256k1 public key which produced the given signature of
+/// the given 32-byte message digest.
+///
+/// `recovery_id` is the parity of the `y` coordinate of the signature's `R`
+/// point: either `0` or `1`, or the equivalent Ethereum `v` values `27`
+/// and `28`.
+pub fn recover_public_key(
+    digest: &[u8],
+    signature: &Signature,
+    recovery_id: u8,
+) -> Result<PublicKey, signature::Error> {
+    if digest.len() != 32 {
+        return Err(signature::Error::from_source(format!(
+            "invalid digest length: {} (expected 32)",
+            digest.len()
+        )));
+    }
+
+    let recovery_id = match recovery_id {
+        0 | 1 => recovery_id,
+        27 | 28 => recovery_id - 27,
+        _ => {
+            return Err(signature::Error::from_source(format!(
+                "invalid recovery ID: {} (expected 0, 1, 27, or 28)",
+                recovery_id
+            )))
+        }
+    };
+
+    let signature = RecoverableSignature::new(signature, recoverable::Id::new(recovery_id)?)?;
+    signature.recover_verify_key_from_digest_bytes(k256::FieldBytes::from_slice(digest))
+}
+
+/// Compute the Ethereum address for the given public key, i.e. the last 20
+/// bytes of the Keccak-256 digest of its uncompressed encoding.
+pub fn ethereum_address(public_key: &PublicKey) -> [u8; ETHEREUM_ADDRESS_SIZE] {
+    use k256::elliptic_curve::sec1::ToEncodedPoint;
+    use sha3::{Digest, Keccak256};
+
+    let point = public_key.to_encoded_point(false);
+    let digest = Keccak256::digest(&point.as_bytes()[1..]);
+
+    let mut address = [0u8; ETHEREUM_ADDRESS_SIZE];
+    address.copy_from_slice(&digest[(digest.len() - ETHEREUM_ADDRESS_SIZE)..]);
+    address
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use signature::Signature as _;
+
+    /// Decode a hex string
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..(i + 2)], 16).unwrap())
+            .collect()
+    }
+
+    /// Signature of "Some data" by the account with private key
+    /// `4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318`,
+    /// from the `web3.eth.accounts.sign` documentation
+    const MESSAGE_HASH: &str = "1da44b586eb0729ff70a73c326926f6ed5a25f5b056e7f47fbc6e58d86871655";
+    const SIGNATURE_R: &str = "b91467e570a6466aa9e9876cbcd013baba02900b8979d43fe208a4a4f339f5fd";
+    const SIGNATURE_S: &str = "6007e74cd82e037b800186422fc2da167c747ef045e5d18a5f5d4300f8e1a029";
+    const SIGNATURE_V: u8 = 0x1c;
+    const ADDRESS: &str = "2c7536e3605d9c16a7a3d7b1898e529396a65c23";
+
+    fn signature() -> Signature {
+        let mut bytes = hex(SIGNATURE_R);
+        bytes.extend_from_slice(&hex(SIGNATURE_S));
+        Signature::from_bytes(&bytes).unwrap()
+    }
+
+    #[test]
+    fn recovers_ethereum_address() {
+        let public_key = recover_public_key(&hex(MESSAGE_HASH), &signature(), SIGNATURE_V).unwrap();
+        assert_eq!(&ethereum_address(&public_key)[..], hex(ADDRESS).as_slice());
+    }
+
+    #[test]
+    fn recovery_id_and_v_are_equivalent() {
+        let from_v = recover_public_key(&hex(MESSAGE_HASH), &signature(), SIGNATURE_V).unwrap();
+        let from_id =
+            recover_public_key(&hex(MESSAGE_HASH), &signature(), SIGNATURE_V - 27).unwrap();
+
+        assert_eq!(from_v, from_id);
+    }
+
+    #[test]
+    fn rejects_invalid_recovery_id() {
+        assert!(recover_public_key(&hex(MESSAGE_HASH), &signature(), 2).is_err());
+        assert!(recover_public_key(&hex(MESSAGE_HASH), &signature(), 29).is_err());
+    }
+}
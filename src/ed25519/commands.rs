@@ -24,6 +24,14 @@ pub(crate) struct SignEddsaCommand {
 
 impl Command for SignEddsaCommand {
     type ResponseType = SignEddsaResponse;
+
+    fn object_id(&self) -> Option<object::Id> {
+        Some(self.key_id)
+    }
+
+    fn object_type(&self) -> Option<object::Type> {
+        Some(object::Type::AsymmetricKey)
+    }
 }
 
 /// Ed25519 signature (64-bytes) response
@@ -32,6 +40,7 @@ pub struct SignEddsaResponse(pub(crate) Vec<u8>);
 
 impl Response for SignEddsaResponse {
     const COMMAND_CODE: command::Code = command::Code::SignEddsa;
+    const MAX_SIZE: usize = 64;
 }
 
 impl SignEddsaResponse {
@@ -2,6 +2,7 @@
 
 mod algorithm;
 pub(crate) mod commands;
+mod signer;
 mod tag;
 
-pub use self::{algorithm::Algorithm, tag::Tag};
+pub use self::{algorithm::Algorithm, signer::Signer, tag::Tag};
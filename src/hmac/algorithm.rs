@@ -51,6 +51,11 @@ impl Algorithm {
         }
     }
 
+    /// Length of the tags computed with this algorithm in bytes
+    pub fn tag_len(self) -> usize {
+        self.key_len()
+    }
+
     /// Return the size of the given key (as expected by the `YubiHSM 2`) in bytes
     pub fn max_key_len(self) -> usize {
         match self {
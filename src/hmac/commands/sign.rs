@@ -21,6 +21,14 @@ pub(crate) struct SignHmacCommand {
 
 impl Command for SignHmacCommand {
     type ResponseType = SignHmacResponse;
+
+    fn object_id(&self) -> Option<object::Id> {
+        Some(self.key_id)
+    }
+
+    fn object_type(&self) -> Option<object::Type> {
+        Some(object::Type::HmacKey)
+    }
 }
 
 /// Sign HMAC response
@@ -29,6 +37,9 @@ pub struct SignHmacResponse(pub(crate) hmac::Tag);
 
 impl Response for SignHmacResponse {
     const COMMAND_CODE: command::Code = command::Code::SignHmac;
+
+    // HMAC-SHA512 tags are the largest supported
+    const MAX_SIZE: usize = 64;
 }
 
 impl From<SignHmacResponse> for hmac::Tag {
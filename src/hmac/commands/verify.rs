@@ -24,6 +24,14 @@ pub(crate) struct VerifyHmacCommand {
 
 impl Command for VerifyHmacCommand {
     type ResponseType = VerifyHmacResponse;
+
+    fn object_id(&self) -> Option<object::Id> {
+        Some(self.key_id)
+    }
+
+    fn object_type(&self) -> Option<object::Type> {
+        Some(object::Type::HmacKey)
+    }
 }
 
 /// HMAC tags
@@ -32,4 +40,5 @@ pub(crate) struct VerifyHmacResponse(pub(crate) u8);
 
 impl Response for VerifyHmacResponse {
     const COMMAND_CODE: command::Code = command::Code::VerifyHmac;
+    const MAX_SIZE: usize = 1;
 }
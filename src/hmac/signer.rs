@@ -0,0 +1,80 @@
+//! HMAC provider bound to a single key in the `YubiHSM 2`
+
+use super::{Algorithm, Tag};
+use crate::{client, object, Client};
+use anomaly::format_err;
+
+/// HMAC signer and verifier for a single HMAC key, obtained with
+/// `Client::hmac_signer`.
+///
+/// The key's algorithm is fetched once when the signer is created, so tag
+/// lengths can be checked without fetching the key's metadata again.
+/// Computing and verifying tags still requires one round trip to the HSM
+/// per operation, since the key never leaves the device.
+pub struct Signer {
+    /// Session with the YubiHSM
+    client: Client,
+
+    /// ID of the HMAC key
+    key_id: object::Id,
+
+    /// Algorithm of the HMAC key
+    algorithm: Algorithm,
+}
+
+impl Signer {
+    /// Create a new YubiHSM-backed HMAC signer for the given key
+    pub fn create(client: Client, key_id: object::Id) -> Result<Self, client::Error> {
+        let algorithm = client
+            .cached_key_info(key_id, object::Type::HmacKey)?
+            .algorithm;
+
+        let algorithm = algorithm.hmac().ok_or_else(|| {
+            format_err!(
+                client::ErrorKind::ProtocolError,
+                "key 0x{:04x} is not an HMAC key (algorithm: {:?})",
+                key_id,
+                algorithm
+            )
+        })?;
+
+        Ok(Self {
+            client,
+            key_id,
+            algorithm,
+        })
+    }
+
+    /// ID of the HMAC key
+    pub fn key_id(&self) -> object::Id {
+        self.key_id
+    }
+
+    /// Algorithm of the HMAC key
+    pub fn algorithm(&self) -> Algorithm {
+        self.algorithm
+    }
+
+    /// Compute an HMAC tag of the given data
+    pub fn sign(&self, data: &[u8]) -> Result<Tag, client::Error> {
+        self.client.sign_hmac(self.key_id, data)
+    }
+
+    /// Verify an HMAC tag of the given data. See `Client::verify_hmac` for
+    /// how tags of the wrong length are reported.
+    pub fn verify(&self, data: &[u8], tag: &Tag) -> Result<(), client::Error> {
+        self.client.verify_hmac(self.key_id, data, tag.clone())
+    }
+}
+
+impl signature::Signer<Tag> for Signer {
+    fn try_sign(&self, msg: &[u8]) -> Result<Tag, signature::Error> {
+        self.sign(msg).map_err(signature::Error::from_source)
+    }
+}
+
+impl signature::Verifier<Tag> for Signer {
+    fn verify(&self, msg: &[u8], tag: &Tag) -> Result<(), signature::Error> {
+        Signer::verify(self, msg, tag).map_err(signature::Error::from_source)
+    }
+}
@@ -52,3 +52,9 @@ impl Into<Vec<u8>> for Tag {
         self.0
     }
 }
+
+impl signature::Signature for Tag {
+    fn from_bytes(bytes: &[u8]) -> Result<Self, signature::Error> {
+        Ok(Tag::from(bytes))
+    }
+}
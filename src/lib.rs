@@ -46,6 +46,36 @@
 //! println!("Ed25519 signature: {:?}", signature);
 //! ```
 //!
+//! # `no_std` support
+//!
+//! Everything but [yubihsm::capability] requires the `std` cargo feature
+//! (enabled by default, and by all other features). Without it (i.e. with
+//! `default-features = false`), the crate is `no_std`, and only provides
+//! `Capability` (without `Capability::union_of`).
+//!
+//! The protocol logic is being decoupled from the `std`-dependent
+//! `Connector`/`Session`/`Client` layer, so more of it can be used with
+//! `no_std` + `alloc` in environments which supply their own transport.
+//! The candidate subset is:
+//!
+//! * [yubihsm::algorithm], [yubihsm::capability], and [yubihsm::domain]
+//! * [yubihsm::command] and the HSM wire format serializers
+//! * the SCP03 secure channel primitives (currently crate-internal)
+//!
+//! These modules use `core` rather than `std` where possible. The `std`
+//! dependencies remaining within them are:
+//!
+//! * error types implement `std::error::Error` (via `anomaly` and `thiserror`)
+//! * the serializers are built on `std::io::{Read, Write}`
+//! * command UUIDs and session challenges use `OsRng` (`rand_core/std`)
+//!
+//! Everything else (connectors, sessions, the `Client`, and the `MockHsm`)
+//! requires `std`.
+//!
+//! [yubihsm::algorithm]: https://docs.rs/yubihsm/latest/yubihsm/algorithm/index.html
+//! [yubihsm::capability]: https://docs.rs/yubihsm/latest/yubihsm/capability/index.html
+//! [yubihsm::domain]: https://docs.rs/yubihsm/latest/yubihsm/domain/index.html
+//! [yubihsm::command]: https://docs.rs/yubihsm/latest/yubihsm/command/index.html
 //! [yubihsm::Connector]: https://docs.rs/yubihsm/latest/yubihsm/connector/struct.Connector.html
 //! [yubihsm::Client]: https://docs.rs/yubihsm/latest/yubihsm/client/struct.Client.html
 //! [commands]: https://developers.yubico.com/YubiHSM2/Commands/
@@ -55,49 +85,80 @@
     html_logo_url = "https://raw.githubusercontent.com/iqlusioninc/yubihsm.rs/develop/img/logo.png",
     html_root_url = "https://docs.rs/yubihsm/0.35.0"
 )]
+#![cfg_attr(not(feature = "std"), no_std)]
 #![cfg_attr(docsrs, feature(doc_cfg))]
 #![forbid(unsafe_code)]
 #![warn(missing_docs, rust_2018_idioms, unused_qualifications)]
 
+#[cfg(feature = "std")]
 #[macro_use]
 extern crate log;
 
+#[cfg(feature = "std")]
 #[macro_use]
 mod macros;
+#[cfg(feature = "std")]
 #[macro_use]
 pub mod error;
+#[cfg(feature = "std")]
 #[macro_use]
 mod serialization;
 
+pub mod capability;
+
+#[cfg(feature = "std")]
 pub mod algorithm;
+#[cfg(feature = "std")]
 pub mod asymmetric;
+#[cfg(feature = "std")]
 pub mod attestation;
+#[cfg(feature = "std")]
 pub mod audit;
+#[cfg(feature = "std")]
 pub mod authentication;
-pub mod capability;
+#[cfg(feature = "std")]
 pub mod client;
+#[cfg(feature = "std")]
 pub mod command;
+#[cfg(feature = "std")]
 pub mod connector;
+#[cfg(feature = "std")]
 pub mod device;
+#[cfg(feature = "std")]
 pub mod domain;
+#[cfg(feature = "std")]
 pub mod ecdh;
+#[cfg(feature = "std")]
 pub mod ecdsa;
+#[cfg(feature = "std")]
 pub mod ed25519;
+#[cfg(feature = "std")]
 pub mod hmac;
 #[cfg(feature = "mockhsm")]
-pub(crate) mod mockhsm;
+#[cfg_attr(docsrs, doc(cfg(feature = "mockhsm")))]
+pub mod mockhsm;
+#[cfg(feature = "std")]
 pub mod object;
+#[cfg(feature = "std")]
 pub mod opaque;
+#[cfg(feature = "std")]
 pub mod otp;
+#[cfg(feature = "std")]
 pub mod response;
+#[cfg(feature = "std")]
 pub mod rsa;
+#[cfg(feature = "std")]
 pub mod session;
 #[cfg(feature = "setup")]
 #[cfg_attr(docsrs, doc(cfg(feature = "setup")))]
 pub mod setup;
+#[cfg(feature = "std")]
 pub mod ssh;
+#[cfg(feature = "std")]
 pub mod template;
+#[cfg(feature = "std")]
 mod uuid;
+#[cfg(feature = "std")]
 pub mod wrap;
 
 #[cfg(feature = "http")]
@@ -105,7 +166,10 @@ pub use crate::connector::HttpConfig;
 #[cfg(feature = "usb")]
 pub use crate::connector::UsbConfig;
 
+pub use crate::capability::Capability;
+
+#[cfg(feature = "std")]
 pub use crate::{
-    algorithm::Algorithm, audit::AuditOption, authentication::Credentials, capability::Capability,
-    client::Client, connector::Connector, domain::Domain, error::*, uuid::Uuid,
+    algorithm::Algorithm, audit::AuditOption, authentication::Credentials, client::Client,
+    connector::Connector, domain::Domain, error::*, uuid::Uuid,
 };
@@ -13,8 +13,8 @@ macro_rules! impl_algorithm_serializers {
             fn deserialize<D: ::serde::de::Deserializer<'de>>(
                 deserializer: D,
             ) -> Result<$alg, D::Error> {
+                use core::fmt;
                 use serde::de::{self, Visitor};
-                use std::fmt;
 
                 struct AlgorithmVisitor;
 
@@ -11,14 +11,18 @@ mod command;
 mod connection;
 mod digest;
 mod error;
+mod fake;
 mod object;
 mod session;
+mod snapshot;
 mod state;
 
 use self::state::State;
 pub use self::{
     connection::MockConnection,
     error::{Error, ErrorKind},
+    fake::FakeConnector,
+    snapshot::SNAPSHOT_VERSION,
 };
 use crate::connector::{self, Connectable, Connection};
 
@@ -42,6 +46,30 @@ impl MockHsm {
     pub fn new() -> Self {
         MockHsm(Arc::new(Mutex::new(State::new())))
     }
+
+    /// Create a new MockHsm containing the objects in the given snapshot
+    /// (see `MockHsm::snapshot`).
+    pub fn from_snapshot(bytes: &[u8]) -> Result<Self, Error> {
+        let objects = snapshot::deserialize(bytes)?;
+        Ok(MockHsm(Arc::new(Mutex::new(State::with_objects(objects)))))
+    }
+
+    /// Serialize all objects currently stored in this MockHsm (including
+    /// their key material) so they can be loaded with
+    /// `MockHsm::from_snapshot`, e.g. to share a provisioned MockHsm
+    /// between test suites.
+    ///
+    /// Sessions, audit settings, and log entries are not included.
+    pub fn snapshot(&self) -> Vec<u8> {
+        snapshot::serialize(&self.0.lock().unwrap().objects)
+    }
+
+    /// Close all open sessions, as the HSM does with sessions which have
+    /// been inactive for longer than its timeout. Subsequent messages for
+    /// them fail with `device::ErrorKind::InvalidSession`.
+    pub fn expire_sessions(&self) {
+        self.0.lock().unwrap().close_all_sessions();
+    }
 }
 
 impl Connectable for MockHsm {
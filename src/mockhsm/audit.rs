@@ -1,9 +1,14 @@
 //! (Partial) support for audit logging within the MockHsm
 //!
-//! No logging is performed and these settings are not yet enforced
+//! Commands are logged according to their audit options, but the force audit
+//! option is not yet enforced
 
-use crate::{audit::*, command, serialization::serialize};
-use std::collections::BTreeMap;
+use crate::{algorithm::Algorithm, audit::*, command, object, response, serialization::serialize};
+use std::collections::{BTreeMap, VecDeque};
+
+/// Number of entries the log holds before the oldest are overwritten (same
+/// as the YubiHSM 2)
+pub const LOG_CAPACITY: usize = 62;
 
 /// Default per-command auditing options
 pub const DEFAULT_COMMAND_AUDIT_OPTIONS: &[AuditCommand] = &[
@@ -77,6 +82,14 @@ impl CommandAuditOptions {
         serialize(&audit_command).unwrap()
     }
 
+    /// Get the setting for a particular command
+    pub fn get(&self, command_type: command::Code) -> AuditOption {
+        self.0
+            .get(&command_type)
+            .cloned()
+            .unwrap_or(AuditOption::Off)
+    }
+
     /// Change a setting for a particular command
     pub fn put(&mut self, command_type: command::Code, audit_option: AuditOption) {
         self.0.insert(command_type, audit_option);
@@ -94,3 +107,115 @@ impl Default for CommandAuditOptions {
         CommandAuditOptions(result)
     }
 }
+
+/// Per-algorithm enable/disable settings (all algorithms are enabled by
+/// default)
+#[derive(Debug)]
+pub struct AlgorithmToggles(BTreeMap<u8, AuditOption>);
+
+impl AlgorithmToggles {
+    /// Serialize these settings as algorithm/option byte pairs
+    pub fn serialize(&self) -> Vec<u8> {
+        self.0
+            .iter()
+            .flat_map(|(alg, opt)| vec![*alg, opt.to_u8()])
+            .collect()
+    }
+
+    /// Change the setting for a particular algorithm
+    pub fn put(&mut self, algorithm: Algorithm, option: AuditOption) {
+        self.0.insert(algorithm.to_u8(), option);
+    }
+}
+
+impl Default for AlgorithmToggles {
+    fn default() -> Self {
+        AlgorithmToggles(
+            (0..=u8::MAX)
+                .filter(|&byte| Algorithm::from_u8(byte).is_ok())
+                .map(|byte| (byte, AuditOption::On))
+                .collect(),
+        )
+    }
+}
+
+/// Audit log entries which haven't yet been consumed with `SetLogIndex`
+#[derive(Debug)]
+pub struct AuditLog {
+    /// Entries in the log, oldest first
+    entries: VecDeque<LogEntry>,
+
+    /// Number of the most recent entry
+    item: u16,
+
+    /// Digest of the most recent entry (all zeroes before the first entry)
+    digest: LogDigest,
+
+    /// Counter standing in for the HSM's internal clock
+    tick: u32,
+}
+
+impl AuditLog {
+    /// Append an entry to the log, chaining its digest to the previous
+    /// entry's. The oldest entry is overwritten if the log is full.
+    pub fn record(
+        &mut self,
+        cmd: command::Code,
+        length: usize,
+        session_key: object::Id,
+        result: response::Code,
+    ) {
+        self.item = self.item.wrapping_add(1);
+        self.tick = self.tick.wrapping_add(1);
+
+        let mut entry = LogEntry {
+            item: self.item,
+            cmd,
+            length: length as u16,
+            session_key,
+            target_key: 0xffff,
+            second_key: 0xffff,
+            result,
+            tick: self.tick,
+            digest: LogDigest([0u8; LOG_DIGEST_SIZE]),
+        };
+
+        entry.digest = entry.compute_digest(&self.digest);
+        self.digest = entry.digest.clone();
+
+        if self.entries.len() == LOG_CAPACITY {
+            self.entries.pop_front();
+        }
+
+        self.entries.push_back(entry);
+    }
+
+    /// Drop entries up to and including the given entry number
+    pub fn set_index(&mut self, log_index: u16) {
+        self.entries.retain(|entry| {
+            let distance = entry.item.wrapping_sub(log_index);
+            distance != 0 && distance < 0x8000
+        });
+    }
+
+    /// Get the entries in the log as a `GetLogEntries` response
+    pub fn to_response(&self) -> LogEntries {
+        LogEntries {
+            unlogged_boot_events: 0,
+            unlogged_auth_events: 0,
+            num_entries: self.entries.len() as u8,
+            entries: self.entries.iter().cloned().collect(),
+        }
+    }
+}
+
+impl Default for AuditLog {
+    fn default() -> Self {
+        Self {
+            entries: VecDeque::new(),
+            item: 0,
+            digest: LogDigest([0u8; LOG_DIGEST_SIZE]),
+            tick: 0,
+        }
+    }
+}
@@ -1,18 +1,21 @@
 //! Commands supported by the `MockHsm`
 
 use super::{digest::MockDigest256, object::Payload, state::State, MOCK_SERIAL_NUMBER};
+#[cfg(all(feature = "rsa", feature = "untested"))]
+use crate::rsa::{pkcs1::commands::*, pss::commands::*};
 use crate::{
     algorithm::*,
     asymmetric::{self, commands::*, PublicKey},
     audit::{commands::*, AuditCommand, AuditOption, AuditTag},
     authentication::{self, commands::*},
-    command::{Code, Message},
+    command::{Code, Command, Message},
     connector,
-    device::{self, commands::*, SerialNumber, StorageInfo},
+    device::{self, commands::*, SerialNumber},
     ecdh,
     ecdsa::{self, commands::*},
     ed25519::commands::*,
     hmac::{self, commands::*},
+    mockhsm,
     object::{self, commands::*},
     opaque::{self, commands::*},
     otp,
@@ -20,9 +23,9 @@ use crate::{
     rsa,
     serialization::deserialize,
     session::{self, commands::*},
-    template,
+    template::{self, commands::*},
     wrap::{self, commands::*},
-    Capability,
+    Capability, Domain,
 };
 use ::ecdsa::{
     elliptic_curve::{Field, FromDigest},
@@ -33,6 +36,8 @@ use cmac::crypto_mac::NewMac;
 use ed25519_dalek as ed25519;
 use rand_core::{OsRng, RngCore};
 use sha2::Sha256;
+#[cfg(all(feature = "rsa", feature = "untested"))]
+use sha2::{Sha384, Sha512};
 use std::{io::Cursor, str::FromStr};
 use subtle::ConstantTimeEq;
 
@@ -85,50 +90,217 @@ pub(crate) fn session_message(
         )
     });
 
-    let command = state
-        .get_session(session_id)?
-        .decrypt_command(encrypted_command);
+    // The HSM responds to messages for unknown (e.g. timed out) sessions with
+    // an unencrypted error
+    let command = match state.get_session(session_id) {
+        Ok(session) => session.decrypt_command(encrypted_command),
+        Err(_) => return Ok(response::Message::from(device::ErrorKind::InvalidSession).into()),
+    };
 
     let response = match command.command_type {
+        _ if !is_authorized(state, session_id, &command) => {
+            device::ErrorKind::InsufficientPermissions.into()
+        }
         Code::BlinkDevice => BlinkDeviceResponse {}.serialize(),
+        Code::ChangeAuthenticationKey => {
+            change_authentication_key(state, session_id, &command.data)
+        }
         Code::CloseSession => return close_session(state, session_id),
         Code::DeleteObject => delete_object(state, &command.data),
         Code::DeviceInfo => device_info(),
         Code::Echo => echo(&command.data),
-        Code::ExportWrapped => export_wrapped(state, &command.data),
-        Code::GenerateAsymmetricKey => gen_asymmetric_key(state, &command.data),
-        Code::GenerateHmacKey => gen_hmac_key(state, &command.data),
-        Code::GenerateWrapKey => gen_wrap_key(state, &command.data),
-        Code::GetLogEntries => get_log_entries(),
+        Code::ExportWrapped => export_wrapped(state, session_id, &command.data),
+        Code::GenerateAsymmetricKey => gen_asymmetric_key(state, session_id, &command.data),
+        Code::GenerateHmacKey => gen_hmac_key(state, session_id, &command.data),
+        Code::GenerateWrapKey => gen_wrap_key(state, session_id, &command.data),
+        Code::GetLogEntries => get_log_entries(state),
         Code::GetObjectInfo => get_object_info(state, &command.data),
         Code::GetOpaqueObject => get_opaque(state, &command.data),
         Code::GetOption => get_option(state, &command.data),
         Code::GetPseudoRandom => get_pseudo_random(state, &command.data),
         Code::GetPublicKey => get_public_key(state, &command.data),
+        Code::GetTemplate => get_template(state, &command.data),
         Code::SignHmac => sign_hmac(state, &command.data),
-        Code::ImportWrapped => import_wrapped(state, &command.data),
-        Code::ListObjects => list_objects(state, &command.data),
-        Code::PutAsymmetricKey => put_asymmetric_key(state, &command.data),
-        Code::PutAuthenticationKey => put_authentication_key(state, &command.data),
-        Code::PutHmacKey => put_hmac_key(state, &command.data),
-        Code::PutOpaqueObject => put_opaque(state, &command.data),
+        Code::ImportWrapped => import_wrapped(state, session_id, &command.data),
+        Code::ListObjects => list_objects(state, session_id, &command.data),
+        Code::PutAsymmetricKey => put_asymmetric_key(state, session_id, &command.data),
+        Code::PutAuthenticationKey => put_authentication_key(state, session_id, &command.data),
+        Code::PutHmacKey => put_hmac_key(state, session_id, &command.data),
+        Code::PutOpaqueObject => put_opaque(state, session_id, &command.data),
+        Code::PutTemplate => put_template(state, session_id, &command.data),
         Code::SetOption => put_option(state, &command.data),
-        Code::PutWrapKey => put_wrap_key(state, &command.data),
+        Code::PutWrapKey => put_wrap_key(state, session_id, &command.data),
         Code::ResetDevice => return Ok(reset_device(state, session_id)),
-        Code::SetLogIndex => SetLogIndexResponse {}.serialize(),
+        Code::SetLogIndex => set_log_index(state, &command.data),
         Code::SignEcdsa => sign_ecdsa(state, &command.data),
         Code::SignEddsa => sign_eddsa(state, &command.data),
-        Code::GetStorageInfo => get_storage_info(),
+        #[cfg(all(feature = "rsa", feature = "untested"))]
+        Code::SignPkcs1 => sign_pkcs1(state, &command.data),
+        #[cfg(all(feature = "rsa", feature = "untested"))]
+        Code::SignPss => sign_pss(state, &command.data),
+        Code::GetStorageInfo => get_storage_info(state),
         Code::VerifyHmac => verify_hmac(state, &command.data),
-        unsupported => panic!("unsupported command type: {:?}", unsupported),
+        unimplemented => {
+            // Real HSMs reject commands they don't support as invalid, so do
+            // the same rather than crashing
+            debug!(
+                "MockHsm doesn't implement command: {:?} (0x{:02x})",
+                unimplemented,
+                unimplemented.to_u8()
+            );
+            device::ErrorKind::InvalidCommand.into()
+        }
     };
 
+    if state.command_audit_options.get(command.command_type) != AuditOption::Off {
+        let session_key = state.get_session(session_id)?.authentication_key_id;
+
+        state.audit_log.record(
+            command.command_type,
+            command.data.len(),
+            session_key,
+            response.code,
+        );
+    }
+
     Ok(state
         .get_session(session_id)?
         .encrypt_response(response)
         .into())
 }
 
+/// Is the session's authentication key allowed to send the given command? It
+/// needs the command's capabilities, and must share a domain with the object
+/// the command operates on (if any), including when reading its info.
+fn is_authorized(state: &mut State, session_id: session::Id, command: &Message) -> bool {
+    let (capability, target) = match command.command_type {
+        Code::ChangeAuthenticationKey => command_target::<ChangeAuthenticationKeyCommand>(command),
+        Code::DeleteObject => command_target::<DeleteObjectCommand>(command),
+        Code::ExportWrapped => command_target::<ExportWrappedCommand>(command),
+        Code::GetObjectInfo => command_target::<GetObjectInfoCommand>(command),
+        Code::GetOpaqueObject => command_target::<GetOpaqueCommand>(command),
+        Code::GetPublicKey => command_target::<GetPublicKeyCommand>(command),
+        Code::GetTemplate => command_target::<GetTemplateCommand>(command),
+        Code::SignEcdsa => command_target::<SignEcdsaCommand>(command),
+        Code::SignEddsa => command_target::<SignEddsaCommand>(command),
+        #[cfg(all(feature = "rsa", feature = "untested"))]
+        Code::SignPkcs1 => command_target::<SignPkcs1Command>(command),
+        #[cfg(all(feature = "rsa", feature = "untested"))]
+        Code::SignPss => command_target::<SignPssCommand>(command),
+        Code::SignHmac => command_target::<SignHmacCommand>(command),
+        Code::VerifyHmac => command_target::<VerifyHmacCommand>(command),
+        other => (other.required_capability(), None),
+    };
+
+    let capabilities = match state.get_session(session_id) {
+        Ok(session) => session.capabilities,
+        Err(_) => return false,
+    };
+
+    if !capabilities.contains(capability) {
+        debug!(
+            "session key lacks capabilities for {:?}: {:?}",
+            command.command_type, capability
+        );
+        return false;
+    }
+
+    match target {
+        Some((id, object_type)) => in_session_domains(state, session_id, id, object_type),
+        None => true,
+    }
+}
+
+/// Is the given object (if it exists) in one of the domains of the session's
+/// authentication key?
+fn in_session_domains(
+    state: &State,
+    session_id: session::Id,
+    object_id: object::Id,
+    object_type: object::Type,
+) -> bool {
+    let domains = state.session_domains(session_id);
+
+    match state.objects.get(object_id, object_type) {
+        Some(object) if !object.object_info.domains.intersects(domains) => {
+            debug!(
+                "{:?} 0x{:04x} not in session key's domains: {:?}",
+                object_type, object_id, domains
+            );
+            false
+        }
+        _ => true,
+    }
+}
+
+/// Get the capability required by the given command, and the object it
+/// operates on. Malformed commands are left for their handlers to reject.
+fn command_target<C>(command: &Message) -> (Capability, Option<(object::Id, object::Type)>)
+where
+    C: Command + serde::de::DeserializeOwned,
+{
+    match deserialize::<C>(&command.data) {
+        Ok(cmd) => (
+            cmd.required_capability(),
+            cmd.object_id()
+                .and_then(|id| cmd.object_type().map(|t| (id, t))),
+        ),
+        Err(_) => (command.command_type.required_capability(), None),
+    }
+}
+
+/// Replace the authentication key used to establish the given session
+fn change_authentication_key(
+    state: &mut State,
+    session_id: session::Id,
+    cmd_data: &[u8],
+) -> response::Message {
+    let ChangeAuthenticationKeyCommand {
+        key_id,
+        algorithm,
+        authentication_key,
+    } = deserialize(cmd_data)
+        .unwrap_or_else(|e| panic!("error parsing Code::ChangeAuthenticationKey: {:?}", e));
+
+    let session_key_id = match state.get_session(session_id) {
+        Ok(session) => session.authentication_key_id,
+        Err(_) => return device::ErrorKind::InvalidSession.into(),
+    };
+
+    if key_id != session_key_id {
+        debug!(
+            "can't change auth key 0x{:04x}: session authenticated with 0x{:04x}",
+            key_id, session_key_id
+        );
+        return device::ErrorKind::InvalidId.into();
+    }
+
+    let obj = match state
+        .objects
+        .get_mut(key_id, object::Type::AuthenticationKey)
+    {
+        Some(obj) => obj,
+        None => return device::ErrorKind::ObjectNotFound.into(),
+    };
+
+    if !obj
+        .object_info
+        .capabilities
+        .contains(Capability::CHANGE_AUTHENTICATION_KEY)
+    {
+        return device::ErrorKind::InsufficientPermissions.into();
+    }
+
+    if obj.object_info.algorithm != Algorithm::from(algorithm) {
+        return device::ErrorKind::InvalidData.into();
+    }
+
+    obj.payload = Payload::AuthenticationKey(authentication_key);
+    obj.object_info.sequence = obj.object_info.sequence.wrapping_add(1);
+
+    ChangeAuthenticationKeyResponse { key_id }.serialize()
+}
+
 /// Close an active session
 fn close_session(state: &mut State, session_id: session::Id) -> Result<Vec<u8>, connector::Error> {
     let response = state
@@ -151,7 +323,7 @@ fn delete_object(state: &mut State, cmd_data: &[u8]) -> response::Message {
     {
         DeleteObjectResponse {}.serialize()
     } else {
-        debug!("no such object ID: {:?}", command.object_id);
+        debug!("no such object ID: 0x{:04x}", command.object_id);
         device::ErrorKind::ObjectNotFound.into()
     }
 }
@@ -160,7 +332,7 @@ fn delete_object(state: &mut State, cmd_data: &[u8]) -> response::Message {
 fn device_info() -> response::Message {
     let info = device::Info {
         major_version: 2,
-        minor_version: 0,
+        minor_version: 1,
         build_version: 0,
         serial_number: SerialNumber::from_str(MOCK_SERIAL_NUMBER).unwrap(),
         log_store_capacity: 62,
@@ -225,7 +397,11 @@ fn echo(cmd_data: &[u8]) -> response::Message {
 }
 
 /// Export an object from the HSM in encrypted form
-fn export_wrapped(state: &mut State, cmd_data: &[u8]) -> response::Message {
+fn export_wrapped(
+    state: &mut State,
+    session_id: session::Id,
+    cmd_data: &[u8],
+) -> response::Message {
     let ExportWrappedCommand {
         wrap_key_id,
         object_type,
@@ -233,6 +409,11 @@ fn export_wrapped(state: &mut State, cmd_data: &[u8]) -> response::Message {
     } = deserialize(cmd_data)
         .unwrap_or_else(|e| panic!("error parsing Code::ExportWrapped: {:?}", e));
 
+    // The object being exported is checked in `is_authorized`
+    if !in_session_domains(state, session_id, wrap_key_id, object::Type::WrapKey) {
+        return device::ErrorKind::InsufficientPermissions.into();
+    }
+
     let nonce = wrap::Nonce::generate();
 
     match state
@@ -242,17 +423,61 @@ fn export_wrapped(state: &mut State, cmd_data: &[u8]) -> response::Message {
         Ok(ciphertext) => ExportWrappedResponse(wrap::Message { nonce, ciphertext }).serialize(),
         Err(e) => {
             debug!("error wrapping object: {}", e);
-            device::ErrorKind::InvalidCommand.into()
+            wrap_error(&e).into()
         }
     }
 }
 
+/// May the session's authentication key create an object with the given
+/// capabilities and domains? The capabilities must be delegated to it, and
+/// the domains must be a subset of its own.
+fn creation_permitted(
+    state: &State,
+    session_id: session::Id,
+    capabilities: Capability,
+    domains: Domain,
+) -> bool {
+    let delegated_capabilities = state.delegated_capabilities(session_id);
+
+    if !delegated_capabilities.contains(capabilities) {
+        debug!(
+            "capabilities not delegated to session's authentication key: {:?}",
+            capabilities - delegated_capabilities
+        );
+        return false;
+    }
+
+    let session_domains = state.session_domains(session_id);
+
+    if !session_domains.contains(domains) {
+        debug!(
+            "domains not accessible to session's authentication key: {:?}",
+            domains - session_domains
+        );
+        return false;
+    }
+
+    true
+}
+
 /// Generate a new random asymmetric key
-fn gen_asymmetric_key(state: &mut State, cmd_data: &[u8]) -> response::Message {
+fn gen_asymmetric_key(
+    state: &mut State,
+    session_id: session::Id,
+    cmd_data: &[u8],
+) -> response::Message {
     let GenAsymmetricKeyCommand(command) = deserialize(cmd_data)
         .unwrap_or_else(|e| panic!("error parsing Code::GenAsymmetricKey: {:?}", e));
 
-    state.objects.generate(
+    if !creation_permitted(state, session_id, command.capabilities, command.domains) {
+        return device::ErrorKind::InsufficientPermissions.into();
+    }
+
+    if !asymmetric_algorithm_supported(command.algorithm) {
+        return device::ErrorKind::InvalidData.into();
+    }
+
+    match state.objects.generate(
         command.key_id,
         object::Type::AsymmetricKey,
         command.algorithm,
@@ -260,20 +485,38 @@ fn gen_asymmetric_key(state: &mut State, cmd_data: &[u8]) -> response::Message {
         command.capabilities,
         Capability::default(),
         command.domains,
-    );
+    ) {
+        Some(key_id) => GenAsymmetricKeyResponse { key_id }.serialize(),
+        None => device::ErrorKind::StorageFailed.into(),
+    }
+}
 
-    GenAsymmetricKeyResponse {
-        key_id: command.key_id,
+/// Can the MockHsm store asymmetric keys of the given algorithm? Others
+/// (e.g. RSA-4096 and most EC curves) are rejected, rather than panicking while
+/// the MockHsm's state is locked.
+fn asymmetric_algorithm_supported(algorithm: Algorithm) -> bool {
+    match algorithm {
+        Algorithm::Asymmetric(alg) if Payload::is_supported_asymmetric(alg) => true,
+        _ => {
+            debug!(
+                "MockHsm doesn't support asymmetric algorithm: {:?}",
+                algorithm
+            );
+            false
+        }
     }
-    .serialize()
 }
 
 /// Generate a new random HMAC key
-fn gen_hmac_key(state: &mut State, cmd_data: &[u8]) -> response::Message {
+fn gen_hmac_key(state: &mut State, session_id: session::Id, cmd_data: &[u8]) -> response::Message {
     let GenHmacKeyCommand(command) =
         deserialize(cmd_data).unwrap_or_else(|e| panic!("error parsing Code::GenHMACKey: {:?}", e));
 
-    state.objects.generate(
+    if !creation_permitted(state, session_id, command.capabilities, command.domains) {
+        return device::ErrorKind::InsufficientPermissions.into();
+    }
+
+    match state.objects.generate(
         command.key_id,
         object::Type::HmacKey,
         command.algorithm,
@@ -281,22 +524,29 @@ fn gen_hmac_key(state: &mut State, cmd_data: &[u8]) -> response::Message {
         command.capabilities,
         Capability::default(),
         command.domains,
-    );
-
-    GenHmacKeyResponse {
-        key_id: command.key_id,
+    ) {
+        Some(key_id) => GenHmacKeyResponse { key_id }.serialize(),
+        None => device::ErrorKind::StorageFailed.into(),
     }
-    .serialize()
 }
 
 /// Generate a new random wrap (i.e. AES-CCM) key
-fn gen_wrap_key(state: &mut State, cmd_data: &[u8]) -> response::Message {
+fn gen_wrap_key(state: &mut State, session_id: session::Id, cmd_data: &[u8]) -> response::Message {
     let GenWrapKeyCommand {
         params,
         delegated_capabilities,
     } = deserialize(cmd_data).unwrap_or_else(|e| panic!("error parsing Code::GenWrapKey: {:?}", e));
 
-    state.objects.generate(
+    if !creation_permitted(
+        state,
+        session_id,
+        params.capabilities | delegated_capabilities,
+        params.domains,
+    ) {
+        return device::ErrorKind::InsufficientPermissions.into();
+    }
+
+    match state.objects.generate(
         params.key_id,
         object::Type::WrapKey,
         params.algorithm,
@@ -304,24 +554,15 @@ fn gen_wrap_key(state: &mut State, cmd_data: &[u8]) -> response::Message {
         params.capabilities,
         delegated_capabilities,
         params.domains,
-    );
-
-    GenWrapKeyResponse {
-        key_id: params.key_id,
+    ) {
+        Some(key_id) => GenWrapKeyResponse { key_id }.serialize(),
+        None => device::ErrorKind::StorageFailed.into(),
     }
-    .serialize()
 }
 
 /// Get mock log information
-fn get_log_entries() -> response::Message {
-    // TODO: mimic the YubiHSM's actual audit log
-    LogEntries {
-        unlogged_boot_events: 0,
-        unlogged_auth_events: 0,
-        num_entries: 0,
-        entries: vec![],
-    }
-    .serialize()
+fn get_log_entries(state: &State) -> response::Message {
+    state.audit_log.to_response().serialize()
 }
 
 /// Get detailed info about a specific object
@@ -335,7 +576,7 @@ fn get_object_info(state: &State, cmd_data: &[u8]) -> response::Message {
     {
         GetObjectInfoResponse(obj.object_info.clone()).serialize()
     } else {
-        debug!("no such object ID: {:?}", command.0.object_id);
+        debug!("no such object ID: 0x{:04x}", command.0.object_id);
         device::ErrorKind::ObjectNotFound.into()
     }
 }
@@ -348,7 +589,7 @@ fn get_opaque(state: &State, cmd_data: &[u8]) -> response::Message {
     if let Some(obj) = state.objects.get(command.object_id, object::Type::Opaque) {
         GetOpaqueResponse(obj.payload.to_bytes()).serialize()
     } else {
-        debug!("no such opaque object ID: {:?}", command.object_id);
+        debug!("no such opaque object ID: 0x{:04x}", command.object_id);
         device::ErrorKind::ObjectNotFound.into()
     }
 }
@@ -361,6 +602,7 @@ fn get_option(state: &State, cmd_data: &[u8]) -> response::Message {
     let results = match command.tag {
         AuditTag::Command => state.command_audit_options.serialize(),
         AuditTag::Force => vec![state.force_audit.to_u8()],
+        AuditTag::Algorithm => state.algorithm_toggles.serialize(),
     };
 
     GetOptionResponse(results).serialize()
@@ -371,6 +613,10 @@ fn get_pseudo_random(_state: &State, cmd_data: &[u8]) -> response::Message {
     let command: GetPseudoRandomCommand = deserialize(cmd_data)
         .unwrap_or_else(|e| panic!("error parsing Code::GetPseudoRandom: {:?}", e));
 
+    if command.bytes as usize > MAX_RAND_BYTES {
+        return device::ErrorKind::WrongLength.into();
+    }
+
     let mut bytes = vec![0u8; command.bytes as usize];
     OsRng.fill_bytes(&mut bytes);
 
@@ -392,27 +638,35 @@ fn get_public_key(state: &State, cmd_data: &[u8]) -> response::Message {
         })
         .serialize()
     } else {
-        debug!("no such object ID: {:?}", command.key_id);
+        debug!("no such object ID: 0x{:04x}", command.key_id);
         device::ErrorKind::ObjectNotFound.into()
     }
 }
 
-/// Generate a mock storage status report
-fn get_storage_info() -> response::Message {
-    // TODO: model actual free storage
-    let info = StorageInfo {
-        total_records: 256,
-        free_records: 256,
-        total_pages: 1024,
-        free_pages: 1024,
-        page_size: 126,
-    };
+/// Get a certificate template
+fn get_template(state: &State, cmd_data: &[u8]) -> response::Message {
+    let command: GetTemplateCommand = deserialize(cmd_data)
+        .unwrap_or_else(|e| panic!("error parsing Code::GetTemplate: {:?}", e));
 
-    GetStorageInfoResponse(info).serialize()
+    if let Some(obj) = state.objects.get(command.object_id, object::Type::Template) {
+        GetTemplateResponse(obj.payload.to_bytes()).serialize()
+    } else {
+        debug!("no such template ID: 0x{:04x}", command.object_id);
+        device::ErrorKind::ObjectNotFound.into()
+    }
+}
+
+/// Report the storage used by objects in the MockHsm
+fn get_storage_info(state: &State) -> response::Message {
+    GetStorageInfoResponse(state.objects.storage_info()).serialize()
 }
 
 /// Import an object encrypted under a wrap key into the HSM
-fn import_wrapped(state: &mut State, cmd_data: &[u8]) -> response::Message {
+fn import_wrapped(
+    state: &mut State,
+    session_id: session::Id,
+    cmd_data: &[u8],
+) -> response::Message {
     let ImportWrappedCommand {
         wrap_key_id,
         nonce,
@@ -420,7 +674,16 @@ fn import_wrapped(state: &mut State, cmd_data: &[u8]) -> response::Message {
     } = deserialize(cmd_data)
         .unwrap_or_else(|e| panic!("error parsing Code::ImportWrapped: {:?}", e));
 
-    match state.objects.unwrap_obj(wrap_key_id, &nonce, ciphertext) {
+    if !in_session_domains(state, session_id, wrap_key_id, object::Type::WrapKey) {
+        return device::ErrorKind::InsufficientPermissions.into();
+    }
+
+    let domains = state.session_domains(session_id);
+
+    match state
+        .objects
+        .unwrap_obj(wrap_key_id, &nonce, ciphertext, domains)
+    {
         Ok(obj) => ImportWrappedResponse {
             object_type: obj.object_type,
             object_id: obj.object_id,
@@ -428,13 +691,13 @@ fn import_wrapped(state: &mut State, cmd_data: &[u8]) -> response::Message {
         .serialize(),
         Err(e) => {
             debug!("error unwrapping object: {}", e);
-            device::ErrorKind::InvalidCommand.into()
+            wrap_error(&e).into()
         }
     }
 }
 
 /// List all objects presently accessible to a session
-fn list_objects(state: &State, cmd_data: &[u8]) -> response::Message {
+fn list_objects(state: &State, session_id: session::Id, cmd_data: &[u8]) -> response::Message {
     let command: ListObjectsCommand = deserialize(cmd_data)
         .unwrap_or_else(|e| panic!("error parsing Code::ListObjects: {:?}", e));
 
@@ -446,9 +709,12 @@ fn list_objects(state: &State, cmd_data: &[u8]) -> response::Message {
         filters.push(object::Filter::deserialize(&mut cursor).unwrap());
     }
 
+    let domains = state.session_domains(session_id);
+
     let list_entries = state
         .objects
         .iter()
+        .filter(|(_, object)| object.info().domains.intersects(domains))
         .filter(|(_, object)| {
             if filters.is_empty() {
                 true
@@ -472,11 +738,23 @@ fn list_objects(state: &State, cmd_data: &[u8]) -> response::Message {
 }
 
 /// Put an existing asymmetric key into the HSM
-fn put_asymmetric_key(state: &mut State, cmd_data: &[u8]) -> response::Message {
+fn put_asymmetric_key(
+    state: &mut State,
+    session_id: session::Id,
+    cmd_data: &[u8],
+) -> response::Message {
     let PutAsymmetricKeyCommand { params, data } = deserialize(cmd_data)
         .unwrap_or_else(|e| panic!("error parsing Code::PutAsymmetricKey: {:?}", e));
 
-    state.objects.put(
+    if !creation_permitted(state, session_id, params.capabilities, params.domains) {
+        return device::ErrorKind::InsufficientPermissions.into();
+    }
+
+    if !asymmetric_algorithm_supported(params.algorithm) {
+        return device::ErrorKind::InvalidData.into();
+    }
+
+    match state.objects.put(
         params.id,
         object::Type::AsymmetricKey,
         params.algorithm,
@@ -485,13 +763,18 @@ fn put_asymmetric_key(state: &mut State, cmd_data: &[u8]) -> response::Message {
         Capability::default(),
         params.domains,
         &data,
-    );
-
-    PutAsymmetricKeyResponse { key_id: params.id }.serialize()
+    ) {
+        Some(key_id) => PutAsymmetricKeyResponse { key_id }.serialize(),
+        None => device::ErrorKind::StorageFailed.into(),
+    }
 }
 
 /// Put a new authentication key into the HSM
-fn put_authentication_key(state: &mut State, cmd_data: &[u8]) -> response::Message {
+fn put_authentication_key(
+    state: &mut State,
+    session_id: session::Id,
+    cmd_data: &[u8],
+) -> response::Message {
     let PutAuthenticationKeyCommand {
         params,
         delegated_capabilities,
@@ -499,7 +782,16 @@ fn put_authentication_key(state: &mut State, cmd_data: &[u8]) -> response::Messa
     } = deserialize(cmd_data)
         .unwrap_or_else(|e| panic!("error parsing Code::PutAuthenticationKey: {:?}", e));
 
-    state.objects.put(
+    if !creation_permitted(
+        state,
+        session_id,
+        params.capabilities | delegated_capabilities,
+        params.domains,
+    ) {
+        return device::ErrorKind::InsufficientPermissions.into();
+    }
+
+    match state.objects.put(
         params.id,
         object::Type::AuthenticationKey,
         params.algorithm,
@@ -508,17 +800,42 @@ fn put_authentication_key(state: &mut State, cmd_data: &[u8]) -> response::Messa
         delegated_capabilities,
         params.domains,
         &authentication_key.0,
-    );
-
-    PutAuthenticationKeyResponse { key_id: params.id }.serialize()
+    ) {
+        Some(key_id) => PutAuthenticationKeyResponse { key_id }.serialize(),
+        None => device::ErrorKind::StorageFailed.into(),
+    }
 }
 
 /// Put a new HMAC key into the HSM
-fn put_hmac_key(state: &mut State, cmd_data: &[u8]) -> response::Message {
+fn put_hmac_key(state: &mut State, session_id: session::Id, cmd_data: &[u8]) -> response::Message {
     let PutHmacKeyCommand { params, hmac_key } =
         deserialize(cmd_data).unwrap_or_else(|e| panic!("error parsing Code::PutHMACKey: {:?}", e));
 
-    state.objects.put(
+    let hmac_alg = match params.algorithm.hmac() {
+        Some(alg) => alg,
+        None => {
+            debug!("not an HMAC algorithm: {:?}", params.algorithm);
+            return device::ErrorKind::InvalidData.into();
+        }
+    };
+
+    if hmac_key.len() < HMAC_MIN_KEY_SIZE || hmac_key.len() > hmac_alg.max_key_len() {
+        debug!(
+            "invalid key length for {:?}: {} (min {}, max {})",
+            hmac_alg,
+            hmac_key.len(),
+            HMAC_MIN_KEY_SIZE,
+            hmac_alg.max_key_len()
+        );
+
+        return device::ErrorKind::WrongLength.into();
+    }
+
+    if !creation_permitted(state, session_id, params.capabilities, params.domains) {
+        return device::ErrorKind::InsufficientPermissions.into();
+    }
+
+    match state.objects.put(
         params.id,
         object::Type::HmacKey,
         params.algorithm,
@@ -527,17 +844,22 @@ fn put_hmac_key(state: &mut State, cmd_data: &[u8]) -> response::Message {
         Capability::default(),
         params.domains,
         &hmac_key,
-    );
-
-    PutHmacKeyResponse { key_id: params.id }.serialize()
+    ) {
+        Some(key_id) => PutHmacKeyResponse { key_id }.serialize(),
+        None => device::ErrorKind::StorageFailed.into(),
+    }
 }
 
 /// Put an opaque object (X.509 cert or other data) into the HSM
-fn put_opaque(state: &mut State, cmd_data: &[u8]) -> response::Message {
+fn put_opaque(state: &mut State, session_id: session::Id, cmd_data: &[u8]) -> response::Message {
     let PutOpaqueCommand { params, data } = deserialize(cmd_data)
         .unwrap_or_else(|e| panic!("error parsing Code::PutOpaqueObject: {:?}", e));
 
-    state.objects.put(
+    if !creation_permitted(state, session_id, params.capabilities, params.domains) {
+        return device::ErrorKind::InsufficientPermissions.into();
+    }
+
+    match state.objects.put(
         params.id,
         object::Type::Opaque,
         params.algorithm,
@@ -546,12 +868,39 @@ fn put_opaque(state: &mut State, cmd_data: &[u8]) -> response::Message {
         Capability::default(),
         params.domains,
         &data,
-    );
+    ) {
+        Some(object_id) => PutOpaqueResponse { object_id }.serialize(),
+        None => device::ErrorKind::StorageFailed.into(),
+    }
+}
 
-    PutOpaqueResponse {
-        object_id: params.id,
+/// Put a certificate template into the HSM
+fn put_template(state: &mut State, session_id: session::Id, cmd_data: &[u8]) -> response::Message {
+    let PutTemplateCommand { params, data } = deserialize(cmd_data)
+        .unwrap_or_else(|e| panic!("error parsing Code::PutTemplate: {:?}", e));
+
+    if params.algorithm.template().is_none() {
+        debug!("not a template algorithm: {:?}", params.algorithm);
+        return device::ErrorKind::InvalidData.into();
+    }
+
+    if !creation_permitted(state, session_id, params.capabilities, params.domains) {
+        return device::ErrorKind::InsufficientPermissions.into();
+    }
+
+    match state.objects.put(
+        params.id,
+        object::Type::Template,
+        params.algorithm,
+        params.label,
+        params.capabilities,
+        Capability::default(),
+        params.domains,
+        &data,
+    ) {
+        Some(object_id) => PutTemplateResponse { object_id }.serialize(),
+        None => device::ErrorKind::StorageFailed.into(),
     }
-    .serialize()
 }
 
 /// Change an HSM auditing setting
@@ -573,20 +922,39 @@ fn put_option(state: &mut State, cmd_data: &[u8]) -> response::Message {
                 .command_audit_options
                 .put(audit_cmd.command_type(), audit_cmd.audit_option());
         }
+        AuditTag::Algorithm => {
+            assert_eq!(length, 2);
+
+            match Algorithm::from_u8(value[0]) {
+                Ok(algorithm) => state
+                    .algorithm_toggles
+                    .put(algorithm, AuditOption::from_u8(value[1]).unwrap()),
+                Err(_) => return device::ErrorKind::InvalidData.into(),
+            }
+        }
     }
 
     PutOptionResponse {}.serialize()
 }
 
 /// Put an existing wrap (i.e. AES-CCM) key into the HSM
-fn put_wrap_key(state: &mut State, cmd_data: &[u8]) -> response::Message {
+fn put_wrap_key(state: &mut State, session_id: session::Id, cmd_data: &[u8]) -> response::Message {
     let PutWrapKeyCommand {
         params,
         delegated_capabilities,
         data,
     } = deserialize(cmd_data).unwrap_or_else(|e| panic!("error parsing Code::PutWrapKey: {:?}", e));
 
-    state.objects.put(
+    if !creation_permitted(
+        state,
+        session_id,
+        params.capabilities | delegated_capabilities,
+        params.domains,
+    ) {
+        return device::ErrorKind::InsufficientPermissions.into();
+    }
+
+    match state.objects.put(
         params.id,
         object::Type::WrapKey,
         params.algorithm,
@@ -595,9 +963,10 @@ fn put_wrap_key(state: &mut State, cmd_data: &[u8]) -> response::Message {
         delegated_capabilities,
         params.domains,
         &data,
-    );
-
-    PutWrapKeyResponse { key_id: params.id }.serialize()
+    ) {
+        Some(key_id) => PutWrapKeyResponse { key_id }.serialize(),
+        None => device::ErrorKind::StorageFailed.into(),
+    }
 }
 
 /// Reset the MockHsm back to its default state
@@ -612,6 +981,15 @@ fn reset_device(state: &mut State, session_id: session::Id) -> Vec<u8> {
     response
 }
 
+/// Drop audit log entries up to and including the given index
+fn set_log_index(state: &mut State, cmd_data: &[u8]) -> response::Message {
+    let command: SetLogIndexCommand = deserialize(cmd_data)
+        .unwrap_or_else(|e| panic!("error parsing Code::SetLogIndex: {:?}", e));
+
+    state.audit_log.set_index(command.log_index);
+    SetLogIndexResponse {}.serialize()
+}
+
 /// Sign a message using the ECDSA signature algorithm
 fn sign_ecdsa(state: &State, cmd_data: &[u8]) -> response::Message {
     let command: SignEcdsaCommand =
@@ -644,11 +1022,11 @@ fn sign_ecdsa(state: &State, cmd_data: &[u8]) -> response::Message {
             }
             _ => {
                 debug!("not an ECDSA key: {:?}", obj.algorithm());
-                device::ErrorKind::InvalidCommand.into()
+                device::ErrorKind::InvalidData.into()
             }
         }
     } else {
-        debug!("no such object ID: {:?}", command.key_id);
+        debug!("no such object ID: 0x{:04x}", command.key_id);
         device::ErrorKind::ObjectNotFound.into()
     }
 }
@@ -669,10 +1047,103 @@ fn sign_eddsa(state: &State, cmd_data: &[u8]) -> response::Message {
             SignEddsaResponse(signature_bytes.as_ref().into()).serialize()
         } else {
             debug!("not an Ed25519 key: {:?}", obj.algorithm());
-            device::ErrorKind::InvalidCommand.into()
+            device::ErrorKind::InvalidData.into()
+        }
+    } else {
+        debug!("no such object ID: 0x{:04x}", command.key_id);
+        device::ErrorKind::ObjectNotFound.into()
+    }
+}
+
+/// Sign a digest using RSASSA-PKCS#1v1.5
+#[cfg(all(feature = "rsa", feature = "untested"))]
+fn sign_pkcs1(state: &State, cmd_data: &[u8]) -> response::Message {
+    let command: SignPkcs1Command =
+        deserialize(cmd_data).unwrap_or_else(|e| panic!("error parsing Code::SignPkcs1: {:?}", e));
+
+    if let Some(obj) = state
+        .objects
+        .get(command.key_id, object::Type::AsymmetricKey)
+    {
+        if let Payload::Rsa2048(private_key) = &obj.payload {
+            // The hash function is identified by the length of the digest
+            let hash = match command.digest.len() {
+                20 => ::rsa::Hash::SHA1,
+                32 => ::rsa::Hash::SHA2_256,
+                48 => ::rsa::Hash::SHA2_384,
+                64 => ::rsa::Hash::SHA2_512,
+                len => {
+                    debug!("invalid PKCS#1v1.5 digest length: {}", len);
+                    return device::ErrorKind::InvalidData.into();
+                }
+            };
+
+            let signature = private_key
+                .sign(
+                    ::rsa::PaddingScheme::new_pkcs1v15_sign(Some(hash)),
+                    &command.digest,
+                )
+                .expect("RSASSA-PKCS#1v1.5 failure!");
+
+            SignPkcs1Response(rsa::pkcs1::Signature(signature)).serialize()
+        } else {
+            debug!("not an RSA key: {:?}", obj.algorithm());
+            device::ErrorKind::InvalidData.into()
+        }
+    } else {
+        debug!("no such object ID: 0x{:04x}", command.key_id);
+        device::ErrorKind::ObjectNotFound.into()
+    }
+}
+
+/// Sign a digest using RSASSA-PSS
+#[cfg(all(feature = "rsa", feature = "untested"))]
+fn sign_pss(state: &State, cmd_data: &[u8]) -> response::Message {
+    let command: SignPssCommand =
+        deserialize(cmd_data).unwrap_or_else(|e| panic!("error parsing Code::SignPss: {:?}", e));
+
+    if let Some(obj) = state
+        .objects
+        .get(command.key_id, object::Type::AsymmetricKey)
+    {
+        if let Payload::Rsa2048(private_key) = &obj.payload {
+            let salt_len = command.salt_len as usize;
+
+            // The digest is hashed again with the MGF1 hash function, so the
+            // two must match (the MockHsm doesn't support MGF1 with SHA-1)
+            let padding = match command.mgf1_hash_alg {
+                rsa::mgf::Algorithm::Sha256 if command.digest.len() == 32 => {
+                    ::rsa::PaddingScheme::new_pss_with_salt::<Sha256, _>(OsRng, salt_len)
+                }
+                rsa::mgf::Algorithm::Sha384 if command.digest.len() == 48 => {
+                    ::rsa::PaddingScheme::new_pss_with_salt::<Sha384, _>(OsRng, salt_len)
+                }
+                rsa::mgf::Algorithm::Sha512 if command.digest.len() == 64 => {
+                    ::rsa::PaddingScheme::new_pss_with_salt::<Sha512, _>(OsRng, salt_len)
+                }
+                mgf1_alg => {
+                    debug!(
+                        "unsupported PSS parameters: {:?} with {}-byte digest",
+                        mgf1_alg,
+                        command.digest.len()
+                    );
+                    return device::ErrorKind::InvalidData.into();
+                }
+            };
+
+            match private_key.sign(padding, &command.digest) {
+                Ok(signature) => SignPssResponse(rsa::pss::Signature(signature)).serialize(),
+                Err(e) => {
+                    debug!("RSASSA-PSS failure: {}", e);
+                    device::ErrorKind::InvalidData.into()
+                }
+            }
+        } else {
+            debug!("not an RSA key: {:?}", obj.algorithm());
+            device::ErrorKind::InvalidData.into()
         }
     } else {
-        debug!("no such object ID: {:?}", command.key_id);
+        debug!("no such object ID: 0x{:04x}", command.key_id);
         device::ErrorKind::ObjectNotFound.into()
     }
 }
@@ -691,10 +1162,10 @@ fn sign_hmac(state: &State, cmd_data: &[u8]) -> response::Message {
             SignHmacResponse(hmac::Tag(tag.into_bytes().as_slice().into())).serialize()
         } else {
             debug!("not an HMAC key: {:?}", obj.algorithm());
-            device::ErrorKind::InvalidCommand.into()
+            device::ErrorKind::InvalidData.into()
         }
     } else {
-        debug!("no such object ID: {:?}", command.key_id);
+        debug!("no such object ID: 0x{:04x}", command.key_id);
         device::ErrorKind::ObjectNotFound.into()
     }
 }
@@ -719,10 +1190,321 @@ fn verify_hmac(state: &State, cmd_data: &[u8]) -> response::Message {
             VerifyHmacResponse(is_ok).serialize()
         } else {
             debug!("not an HMAC key: {:?}", obj.algorithm());
-            device::ErrorKind::InvalidCommand.into()
+            device::ErrorKind::InvalidData.into()
         }
     } else {
-        debug!("no such object ID: {:?}", command.key_id);
+        debug!("no such object ID: 0x{:04x}", command.key_id);
         device::ErrorKind::ObjectNotFound.into()
     }
 }
+
+/// Get the HSM error for a failure to wrap or unwrap an object
+fn wrap_error(e: &mockhsm::Error) -> device::ErrorKind {
+    match e.kind() {
+        mockhsm::ErrorKind::AccessDenied => device::ErrorKind::InsufficientPermissions,
+        _ => device::ErrorKind::InvalidData,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        authentication::DEFAULT_AUTHENTICATION_KEY_ID, serialization::serialize,
+        session::securechannel::Challenge, Domain,
+    };
+
+    /// Open a session with the given authentication key
+    fn open_session(state: &mut State, authentication_key_id: object::Id) -> session::Id {
+        state
+            .create_session(authentication_key_id, Challenge::new())
+            .id
+    }
+
+    /// Open a session with an authentication key which may only delegate
+    /// the `SIGN_EDDSA` and `EXPORTABLE_UNDER_WRAP` capabilities
+    fn open_restricted_session(state: &mut State) -> session::Id {
+        state.objects.put(
+            2,
+            object::Type::AuthenticationKey,
+            authentication::Algorithm::YubicoAes.into(),
+            "restricted".into(),
+            Capability::all(),
+            Capability::SIGN_EDDSA | Capability::EXPORTABLE_UNDER_WRAP,
+            Domain::all(),
+            &authentication::Key::default().0,
+        );
+
+        open_session(state, 2)
+    }
+
+    /// Open a session with an authentication key which has every
+    /// capability, but only the `DOM1` domain
+    fn open_dom1_session(state: &mut State) -> session::Id {
+        state.objects.put(
+            2,
+            object::Type::AuthenticationKey,
+            authentication::Algorithm::YubicoAes.into(),
+            "dom1".into(),
+            Capability::all(),
+            Capability::all(),
+            Domain::DOM1,
+            &authentication::Key::default().0,
+        );
+
+        open_session(state, 2)
+    }
+
+    /// Put an opaque object with the given ID in the `DOM2` domain
+    fn put_dom2_object(state: &mut State, object_id: object::Id, object_type: object::Type) {
+        state.objects.put(
+            object_id,
+            object_type,
+            match object_type {
+                object::Type::WrapKey => wrap::Algorithm::Aes128Ccm.into(),
+                _ => opaque::Algorithm::Data.into(),
+            },
+            "dom2".into(),
+            Capability::all(),
+            Capability::all(),
+            Domain::DOM2,
+            &[0u8; 16],
+        );
+    }
+
+    fn gen_asymmetric_key_command(capabilities: Capability) -> Vec<u8> {
+        serialize(&GenAsymmetricKeyCommand(object::generate::Params {
+            key_id: 100,
+            label: "delegated".into(),
+            domains: Domain::DOM1,
+            capabilities,
+            algorithm: asymmetric::Algorithm::Ed25519.into(),
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn generate_with_delegated_capabilities() {
+        let mut state = State::new();
+        let session_id = open_restricted_session(&mut state);
+
+        let response = gen_asymmetric_key(
+            &mut state,
+            session_id,
+            &gen_asymmetric_key_command(Capability::SIGN_EDDSA | Capability::EXPORTABLE_UNDER_WRAP),
+        );
+
+        assert_eq!(
+            response.code,
+            response::Code::Success(Code::GenerateAsymmetricKey)
+        );
+        assert!(state
+            .objects
+            .get(100, object::Type::AsymmetricKey)
+            .is_some());
+    }
+
+    #[test]
+    fn generate_rejects_undelegated_capabilities() {
+        let mut state = State::new();
+        let session_id = open_restricted_session(&mut state);
+
+        let response = gen_asymmetric_key(
+            &mut state,
+            session_id,
+            &gen_asymmetric_key_command(Capability::SIGN_EDDSA | Capability::SIGN_ECDSA),
+        );
+
+        assert_eq!(response.code, response::Code::MemoryError);
+        assert_eq!(
+            response.data,
+            [device::ErrorKind::InsufficientPermissions.to_u8()]
+        );
+        assert!(state
+            .objects
+            .get(100, object::Type::AsymmetricKey)
+            .is_none());
+    }
+
+    #[test]
+    fn put_rejects_undelegated_delegated_capabilities() {
+        let mut state = State::new();
+        let session_id = open_restricted_session(&mut state);
+
+        let command = PutAuthenticationKeyCommand {
+            params: object::put::Params {
+                id: 3,
+                label: "escalated".into(),
+                domains: Domain::DOM1,
+                capabilities: Capability::SIGN_EDDSA,
+                algorithm: authentication::Algorithm::YubicoAes.into(),
+            },
+            delegated_capabilities: Capability::all(),
+            authentication_key: authentication::Key::default(),
+        };
+
+        let response =
+            put_authentication_key(&mut state, session_id, &serialize(&command).unwrap());
+
+        assert_eq!(
+            response.data,
+            [device::ErrorKind::InsufficientPermissions.to_u8()]
+        );
+        assert!(state
+            .objects
+            .get(3, object::Type::AuthenticationKey)
+            .is_none());
+    }
+
+    #[test]
+    fn put_hmac_key_rejects_undersized_key() {
+        let command = PutHmacKeyCommand {
+            params: object::put::Params {
+                id: 1,
+                label: "hmac".into(),
+                domains: Domain::DOM1,
+                capabilities: Capability::SIGN_HMAC,
+                algorithm: hmac::Algorithm::Sha256.into(),
+            },
+            hmac_key: vec![0u8; HMAC_MIN_KEY_SIZE - 1],
+        };
+
+        let mut state = State::new();
+        let session_id = open_session(&mut state, DEFAULT_AUTHENTICATION_KEY_ID);
+        let response = put_hmac_key(&mut state, session_id, &serialize(&command).unwrap());
+
+        assert_eq!(response.code, response::Code::MemoryError);
+        assert_eq!(response.data, [device::ErrorKind::WrongLength.to_u8()]);
+        assert!(state.objects.get(1, object::Type::HmacKey).is_none());
+    }
+
+    #[test]
+    fn get_pseudo_random_rejects_oversized_requests() {
+        let state = State::new();
+
+        let command = GetPseudoRandomCommand {
+            bytes: MAX_RAND_BYTES as u16,
+        };
+        let response = get_pseudo_random(&state, &serialize(&command).unwrap());
+        assert_eq!(
+            response.code,
+            response::Code::Success(Code::GetPseudoRandom)
+        );
+        assert_eq!(response.data.len(), MAX_RAND_BYTES);
+
+        let command = GetPseudoRandomCommand {
+            bytes: MAX_RAND_BYTES as u16 + 1,
+        };
+        let response = get_pseudo_random(&state, &serialize(&command).unwrap());
+        assert_eq!(response.code, response::Code::MemoryError);
+        assert_eq!(response.data, [device::ErrorKind::WrongLength.to_u8()]);
+    }
+
+    #[test]
+    fn generate_rejects_domains_outside_session_key() {
+        let mut state = State::new();
+        let session_id = open_dom1_session(&mut state);
+
+        let command = serialize(&GenAsymmetricKeyCommand(object::generate::Params {
+            key_id: 100,
+            label: "dom2".into(),
+            domains: Domain::DOM1 | Domain::DOM2,
+            capabilities: Capability::SIGN_EDDSA,
+            algorithm: asymmetric::Algorithm::Ed25519.into(),
+        }))
+        .unwrap();
+
+        let response = gen_asymmetric_key(&mut state, session_id, &command);
+
+        assert_eq!(
+            response.data,
+            [device::ErrorKind::InsufficientPermissions.to_u8()]
+        );
+        assert!(state
+            .objects
+            .get(100, object::Type::AsymmetricKey)
+            .is_none());
+    }
+
+    #[test]
+    fn object_info_requires_shared_domain() {
+        let mut state = State::new();
+        let session_id = open_dom1_session(&mut state);
+        put_dom2_object(&mut state, 100, object::Type::Opaque);
+
+        let command = Message::create(
+            Code::GetObjectInfo,
+            serialize(&GetObjectInfoCommand(object::Handle::new(
+                100,
+                object::Type::Opaque,
+            )))
+            .unwrap(),
+        )
+        .unwrap();
+
+        assert!(!is_authorized(&mut state, session_id, &command));
+    }
+
+    #[test]
+    fn list_objects_hides_other_domains() {
+        let mut state = State::new();
+        let session_id = open_dom1_session(&mut state);
+        put_dom2_object(&mut state, 100, object::Type::Opaque);
+
+        let response = list_objects(
+            &state,
+            session_id,
+            &serialize(&ListObjectsCommand(vec![])).unwrap(),
+        );
+        let ListObjectsResponse(entries) = deserialize(&response.data).unwrap();
+
+        assert!(!entries.is_empty());
+        assert!(entries.iter().all(|entry| entry.object_id != 100));
+    }
+
+    #[test]
+    fn export_wrapped_requires_wrap_key_in_shared_domain() {
+        let mut state = State::new();
+        let session_id = open_dom1_session(&mut state);
+        put_dom2_object(&mut state, 100, object::Type::WrapKey);
+
+        let command = ExportWrappedCommand {
+            wrap_key_id: 100,
+            object_type: object::Type::AuthenticationKey,
+            object_id: 2,
+        };
+
+        let response = export_wrapped(&mut state, session_id, &serialize(&command).unwrap());
+
+        assert_eq!(
+            response.data,
+            [device::ErrorKind::InsufficientPermissions.to_u8()]
+        );
+    }
+
+    #[test]
+    fn put_with_auto_id_fails_when_ids_are_exhausted() {
+        let mut state = State::new();
+        let session_id = open_session(&mut state, DEFAULT_AUTHENTICATION_KEY_ID);
+
+        for id in 1..=object::Id::MAX {
+            put_dom2_object(&mut state, id, object::Type::Opaque);
+        }
+
+        let command = PutOpaqueCommand {
+            params: object::put::Params {
+                id: object::AUTO_ID,
+                label: "auto".into(),
+                domains: Domain::DOM1,
+                capabilities: Capability::default(),
+                algorithm: opaque::Algorithm::Data.into(),
+            },
+            data: vec![0u8; 16],
+        };
+
+        let response = put_opaque(&mut state, session_id, &serialize(&command).unwrap());
+
+        assert_eq!(response.code, response::Code::MemoryError);
+        assert_eq!(response.data, [device::ErrorKind::StorageFailed.to_u8()]);
+    }
+}
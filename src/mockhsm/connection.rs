@@ -7,7 +7,15 @@ use uuid::Uuid;
 use super::{command, state::State, MockHsm};
 use crate::{
     command::Code,
-    connector::{self, Connection, ErrorKind::ConnectionFailed, Message},
+    connector::{self, Connection, ErrorKind::ConnectionFailed, Message, ProtocolVersion},
+};
+
+/// Protocol version reported by the MockHsm (i.e. that of `yubihsm-connector`
+/// 3.0.0, whose protocol it speaks)
+const PROTOCOL_VERSION: ProtocolVersion = ProtocolVersion {
+    major: 3,
+    minor: 0,
+    patch: 0,
 };
 
 /// A mocked connection to the MockHsm
@@ -40,4 +48,9 @@ impl Connection for MockConnection {
         }
         .map(Message::from)
     }
+
+    /// Report a protocol version compatible with this crate
+    fn protocol_version(&self) -> Result<Option<ProtocolVersion>, connector::Error> {
+        Ok(Some(PROTOCOL_VERSION))
+    }
 }
@@ -21,6 +21,10 @@ pub enum ErrorKind {
     #[error("object not found")]
     ObjectNotFound,
 
+    /// Snapshot is malformed or uses an unsupported format version
+    #[error("invalid snapshot")]
+    SnapshotInvalid,
+
     /// Unsupported algorithm
     #[error("unsupported algorithm")]
     UnsupportedAlgorithm,
@@ -0,0 +1,152 @@
+//! Connector which wraps a `MockHsm`, but can be configured to misbehave in
+//! ways the `MockHsm` itself doesn't
+
+use super::MockHsm;
+use crate::{
+    command,
+    connector::{self, Connectable, Connection, ProtocolVersion},
+    device::SerialNumber,
+};
+use anomaly::fail;
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
+use uuid::Uuid;
+
+/// Connector to a `MockHsm` which can be configured to fail to connect,
+/// report a given protocol version, or send canned responses to particular
+/// commands, for testing how a `Client` handles faulty connectors and HSMs
+/// it doesn't support.
+///
+/// Clones share the same `MockHsm` and count of remaining connection failures.
+#[derive(Clone, Debug)]
+pub struct FakeConnector {
+    /// HSM which commands are sent to, unless they have a canned response
+    mockhsm: MockHsm,
+
+    /// Number of remaining attempts to connect which will fail
+    connection_failures: Arc<Mutex<usize>>,
+
+    /// Serial number of the HSM this connector expects (if any)
+    serial_number: Option<SerialNumber>,
+
+    /// Protocol version reported by connections (if any)
+    protocol_version: Option<ProtocolVersion>,
+
+    /// Canned response messages, by the code of the command they answer
+    responses: BTreeMap<command::Code, Vec<u8>>,
+}
+
+impl FakeConnector {
+    /// Create a new `FakeConnector` which behaves like a connector to the
+    /// given `MockHsm` until configured otherwise
+    pub fn new(mockhsm: MockHsm) -> Self {
+        Self {
+            mockhsm,
+            connection_failures: Arc::new(Mutex::new(0)),
+            serial_number: None,
+            protocol_version: None,
+            responses: BTreeMap::new(),
+        }
+    }
+
+    /// Fail the next `count` attempts to connect with
+    /// `connector::ErrorKind::ConnectionFailed`
+    pub fn with_connection_failures(self, count: usize) -> Self {
+        *self.connection_failures.lock().unwrap() = count;
+        self
+    }
+
+    /// Expect an HSM with the given serial number, as a connector configured
+    /// with one (e.g. via `HttpConfig::serial`) does
+    pub fn with_serial_number(mut self, serial_number: SerialNumber) -> Self {
+        self.serial_number = Some(serial_number);
+        self
+    }
+
+    /// Report the given protocol version, as `yubihsm-connector` does
+    pub fn with_protocol_version(mut self, version: ProtocolVersion) -> Self {
+        self.protocol_version = Some(version);
+        self
+    }
+
+    /// Answer every command with the given code with `response` (a whole
+    /// response message, including its header) instead of the `MockHsm`.
+    ///
+    /// Commands sent within a session are encrypted as `SessionMessage`s, so
+    /// only commands sent outside of one (e.g. `CreateSession`) can be
+    /// answered this way.
+    pub fn with_response(mut self, command: command::Code, response: impl Into<Vec<u8>>) -> Self {
+        self.responses.insert(command, response.into());
+        self
+    }
+
+    /// Number of attempts to connect which will still fail
+    pub fn connection_failures(&self) -> usize {
+        *self.connection_failures.lock().unwrap()
+    }
+}
+
+impl Connectable for FakeConnector {
+    fn box_clone(&self) -> Box<dyn Connectable> {
+        Box::new(self.clone())
+    }
+
+    fn connect(&self) -> Result<Box<dyn Connection>, connector::Error> {
+        let mut connection_failures = self.connection_failures.lock().unwrap();
+
+        if *connection_failures > 0 {
+            *connection_failures -= 1;
+            fail!(
+                connector::ErrorKind::ConnectionFailed,
+                "fake connection failure"
+            );
+        }
+
+        Ok(Box::new(FakeConnection {
+            connection: self.mockhsm.connect()?,
+            protocol_version: self.protocol_version,
+            responses: self.responses.clone(),
+        }))
+    }
+
+    fn serial_number(&self) -> Option<SerialNumber> {
+        self.serial_number
+    }
+}
+
+/// Connection opened by a `FakeConnector`
+struct FakeConnection {
+    /// Connection to the `MockHsm`
+    connection: Box<dyn Connection>,
+
+    /// Protocol version to report (if any)
+    protocol_version: Option<ProtocolVersion>,
+
+    /// Canned response messages, by the code of the command they answer
+    responses: BTreeMap<command::Code, Vec<u8>>,
+}
+
+impl Connection for FakeConnection {
+    fn send_message(
+        &self,
+        uuid: Uuid,
+        msg: connector::Message,
+    ) -> Result<connector::Message, connector::Error> {
+        let response = msg
+            .as_ref()
+            .first()
+            .and_then(|&code| command::Code::from_u8(code).ok())
+            .and_then(|code| self.responses.get(&code));
+
+        match response {
+            Some(response) => Ok(response.clone().into()),
+            None => self.connection.send_message(uuid, msg),
+        }
+    }
+
+    fn protocol_version(&self) -> Result<Option<ProtocolVersion>, connector::Error> {
+        Ok(self.protocol_version)
+    }
+}
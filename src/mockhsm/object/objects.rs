@@ -3,16 +3,26 @@
 use super::{Object, Payload, WrappedObject, DEFAULT_AUTHENTICATION_KEY_LABEL};
 use crate::{
     authentication::{self, DEFAULT_AUTHENTICATION_KEY_ID},
+    device::StorageInfo,
     mockhsm::{Error, ErrorKind},
-    object::{Handle, Id, Info, Label, Origin, Type},
+    object::{Handle, Id, Info, Label, Origin, Type, AUTO_ID},
     serialization::{deserialize, serialize},
     wrap, Algorithm, Capability, Domain,
 };
 use aes::cipher::consts::{U13, U8};
-use anomaly::{fail, format_err};
+use anomaly::{ensure, fail, format_err};
 use ccm::aead::{AeadInPlace, NewAead};
 use std::collections::{btree_map::Iter as MapIter, BTreeMap as Map};
 
+/// Number of objects the `MockHsm` can store (same as the YubiHSM 2)
+const TOTAL_RECORDS: u16 = 256;
+
+/// Number of storage pages (same as the YubiHSM 2)
+const TOTAL_PAGES: u16 = 1024;
+
+/// Size of a storage page in bytes
+const PAGE_SIZE: u16 = 126;
+
 /// AES-CCM with a 128-bit key
 pub(crate) type Aes128Ccm = ccm::Ccm<aes::Aes128, U8, U13>;
 
@@ -107,7 +117,20 @@ impl Default for Objects {
 }
 
 impl Objects {
-    /// Generate a new object in the MockHsm
+    /// Create an empty object store (i.e. without the default auth key)
+    pub fn empty() -> Self {
+        Objects(Map::new())
+    }
+
+    /// Find the lowest object ID not in use by an object of the given type,
+    /// used when an object is created with `AUTO_ID`. Returns `None` if
+    /// every ID is taken.
+    fn free_id(&self, object_type: Type) -> Option<Id> {
+        (1..=Id::MAX).find(|&id| !self.0.contains_key(&Handle::new(id, object_type)))
+    }
+
+    /// Generate a new object in the MockHsm, returning its ID, or `None` if it
+    /// was to be assigned one automatically and none are free
     pub fn generate(
         &mut self,
         object_id: Id,
@@ -117,7 +140,12 @@ impl Objects {
         capabilities: Capability,
         delegated_capabilities: Capability,
         domains: Domain,
-    ) {
+    ) -> Option<Id> {
+        let object_id = match object_id {
+            AUTO_ID => self.free_id(object_type)?,
+            id => id,
+        };
+
         let payload = Payload::generate(algorithm);
         let length = payload.len();
 
@@ -142,6 +170,7 @@ impl Objects {
         };
 
         assert!(self.0.insert(handle, object).is_none());
+        Some(object_id)
     }
 
     /// Get an object
@@ -149,7 +178,13 @@ impl Objects {
         self.0.get(&Handle::new(object_id, object_type))
     }
 
-    /// Put a new object in the MockHsm
+    /// Get an object for modification
+    pub fn get_mut(&mut self, object_id: Id, object_type: Type) -> Option<&mut Object> {
+        self.0.get_mut(&Handle::new(object_id, object_type))
+    }
+
+    /// Put a new object in the MockHsm, returning its ID, or `None` if it
+    /// was to be assigned one automatically and none are free
     pub fn put(
         &mut self,
         object_id: Id,
@@ -160,7 +195,12 @@ impl Objects {
         delegated_capabilities: Capability,
         domains: Domain,
         data: &[u8],
-    ) {
+    ) -> Option<Id> {
+        let object_id = match object_id {
+            AUTO_ID => self.free_id(object_type)?,
+            id => id,
+        };
+
         let payload = Payload::new(algorithm, data);
         let length = payload.len();
 
@@ -185,6 +225,28 @@ impl Objects {
         };
 
         assert!(self.0.insert(handle, object).is_none());
+        Some(object_id)
+    }
+
+    /// Get the storage used by the objects in the MockHsm. Each object takes
+    /// one record, plus as many whole pages as its payload needs (at least one).
+    pub fn storage_info(&self) -> StorageInfo {
+        let used_pages: u16 = self
+            .0
+            .values()
+            .map(|object| {
+                let length = object.object_info.length;
+                (length / PAGE_SIZE + u16::from(length % PAGE_SIZE != 0)).max(1)
+            })
+            .sum();
+
+        StorageInfo {
+            total_records: TOTAL_RECORDS,
+            free_records: TOTAL_RECORDS.saturating_sub(self.0.len() as u16),
+            total_pages: TOTAL_PAGES,
+            free_pages: TOTAL_PAGES.saturating_sub(used_pages),
+            page_size: PAGE_SIZE,
+        }
     }
 
     /// Remove an object
@@ -219,12 +281,14 @@ impl Objects {
         {
             fail!(
                 ErrorKind::AccessDenied,
-                "object {:?} of type {:?} does not have EXPORT_UNDER_WRAP capability",
+                "object 0x{:04x} of type {:?} does not have EXPORT_UNDER_WRAP capability",
                 object_id,
                 object_type
             );
         }
 
+        self.ensure_delegated(wrap_key_id, &object_to_wrap.object_info)?;
+
         let mut object_info = object_to_wrap.object_info.clone();
 
         match object_info.origin {
@@ -246,37 +310,57 @@ impl Objects {
         Ok(wrapped_object)
     }
 
-    /// Deserialize an encrypted object and insert it into the HSM
+    /// Deserialize an encrypted object and insert it into the HSM, provided
+    /// it's in (a subset of) the given domains
     pub fn unwrap_obj<V: Into<Vec<u8>>>(
         &mut self,
         wrap_key_id: Id,
         nonce: &wrap::Nonce,
         ciphertext: V,
+        domains: Domain,
     ) -> Result<Handle, Error> {
         let wrap_key = self.get_wrap_key(wrap_key_id)?;
         let mut wrapped_data: Vec<u8> = ciphertext.into();
         wrap_key.decrypt_in_place(nonce, b"", &mut wrapped_data)?;
 
         let unwrapped_object: WrappedObject = deserialize(&wrapped_data).unwrap();
+        self.ensure_delegated(wrap_key_id, &unwrapped_object.object_info)?;
 
-        let payload = Payload::new(
-            unwrapped_object.object_info.algorithm,
-            &unwrapped_object.data,
+        ensure!(
+            domains.contains(unwrapped_object.object_info.domains),
+            ErrorKind::AccessDenied,
+            "object 0x{:04x} is in domains {:?} outside of {:?}",
+            unwrapped_object.object_info.object_id,
+            unwrapped_object.object_info.domains,
+            domains
         );
 
+        Ok(self
+            .insert(unwrapped_object)
+            .expect("object already exists"))
+    }
+
+    /// Insert a serialized object, returning its handle, or `None` if an
+    /// object with the same handle already exists
+    pub fn insert(&mut self, wrapped_object: WrappedObject) -> Option<Handle> {
         let object_key = Handle::new(
-            unwrapped_object.object_info.object_id,
-            unwrapped_object.object_info.object_type,
+            wrapped_object.object_info.object_id,
+            wrapped_object.object_info.object_type,
         );
 
+        if self.0.contains_key(&object_key) {
+            return None;
+        }
+
+        let payload = Payload::new(wrapped_object.object_info.algorithm, &wrapped_object.data);
+
         let object = Object {
-            object_info: unwrapped_object.object_info,
+            object_info: wrapped_object.object_info,
             payload,
         };
 
-        assert!(self.0.insert(object_key.clone(), object).is_none());
-
-        Ok(object_key)
+        self.0.insert(object_key.clone(), object);
+        Some(object_key)
     }
 
     /// Iterate over the objects
@@ -284,13 +368,38 @@ impl Objects {
         self.0.iter()
     }
 
+    /// Ensure the capabilities of an object being wrapped or unwrapped are
+    /// all delegated to the wrap key
+    fn ensure_delegated(&self, wrap_key_id: Id, object_info: &Info) -> Result<(), Error> {
+        let delegated_capabilities = match self.get(wrap_key_id, Type::WrapKey) {
+            Some(k) => k.object_info.delegated_capabilities,
+            None => fail!(
+                ErrorKind::ObjectNotFound,
+                "no such wrap key: 0x{:04x}",
+                wrap_key_id
+            ),
+        };
+
+        if !delegated_capabilities.contains(object_info.capabilities) {
+            fail!(
+                ErrorKind::AccessDenied,
+                "capabilities of object 0x{:04x} not delegated to wrap key 0x{:04x}: {:?}",
+                object_info.object_id,
+                wrap_key_id,
+                object_info.capabilities - delegated_capabilities
+            );
+        }
+
+        Ok(())
+    }
+
     /// Get a wrapping key
     fn get_wrap_key(&self, wrap_key_id: Id) -> Result<AesCcmKey, Error> {
         let wrap_key = match self.get(wrap_key_id, Type::WrapKey) {
             Some(k) => k,
             None => fail!(
                 ErrorKind::ObjectNotFound,
-                "no such wrap key: {:?}",
+                "no such wrap key: 0x{:04x}",
                 wrap_key_id
             ),
         };
@@ -1,7 +1,9 @@
 //! Object "payloads" in the MockHsm are instances of software implementations
 //! of supported cryptographic primitives, already initialized with a private key
 
-use crate::{algorithm::Algorithm, asymmetric, authentication, hmac, opaque, wrap};
+use crate::{algorithm::Algorithm, asymmetric, authentication, hmac, opaque, template, wrap};
+#[cfg(feature = "rsa")]
+use ::rsa::{BigUint, PublicKeyParts};
 use ed25519_dalek as ed25519;
 use rand_core::{OsRng, RngCore};
 
@@ -20,12 +22,19 @@ pub(crate) enum Payload {
     /// Ed25519 signing key
     Ed25519Key(ed25519::SecretKey),
 
+    /// RSA-2048 signing key
+    #[cfg(feature = "rsa")]
+    Rsa2048(Box<::rsa::RSAPrivateKey>),
+
     /// HMAC key
     HmacKey(hmac::Algorithm, Vec<u8>),
 
     /// Opaque data
     Opaque(opaque::Algorithm, Vec<u8>),
 
+    /// Certificate template
+    Template(template::Algorithm, Vec<u8>),
+
     /// Wrapping (i.e. symmetric encryption keys)
     WrapKey(wrap::Algorithm, Vec<u8>),
 }
@@ -48,6 +57,11 @@ impl Payload {
                     assert_eq!(data.len(), ed25519::SECRET_KEY_LENGTH);
                     Payload::Ed25519Key(ed25519::SecretKey::from_bytes(data).unwrap())
                 }
+                #[cfg(feature = "rsa")]
+                asymmetric::Algorithm::Rsa2048 => {
+                    assert_eq!(data.len(), asymmetric_alg.key_len());
+                    Payload::Rsa2048(Box::new(rsa_key_from_primes(data)))
+                }
                 _ => panic!(
                     "MockHsm doesn't support this asymmetric algorithm: {:?}",
                     asymmetric_alg
@@ -55,6 +69,7 @@ impl Payload {
             },
             Algorithm::Hmac(alg) => Payload::HmacKey(alg, data.into()),
             Algorithm::Opaque(alg) => Payload::Opaque(alg, data.into()),
+            Algorithm::Template(alg) => Payload::Template(alg, data.into()),
             Algorithm::Authentication(_) => {
                 Payload::AuthenticationKey(authentication::Key::from_slice(data).unwrap())
             }
@@ -80,6 +95,11 @@ impl Payload {
                 asymmetric::Algorithm::Ed25519 => {
                     Payload::Ed25519Key(ed25519::SecretKey::generate(&mut OsRng))
                 }
+                #[cfg(feature = "rsa")]
+                asymmetric::Algorithm::Rsa2048 => Payload::Rsa2048(Box::new(
+                    ::rsa::RSAPrivateKey::new(&mut OsRng, asymmetric_alg.key_len() * 8)
+                        .expect("RSA key generation failure!"),
+                )),
                 _ => panic!(
                     "MockHsm doesn't support this asymmetric algorithm: {:?}",
                     asymmetric_alg
@@ -97,6 +117,16 @@ impl Payload {
         }
     }
 
+    /// Does the MockHsm support asymmetric keys of the given algorithm?
+    pub fn is_supported_asymmetric(algorithm: asymmetric::Algorithm) -> bool {
+        matches!(
+            algorithm,
+            asymmetric::Algorithm::EcP256
+                | asymmetric::Algorithm::EcK256
+                | asymmetric::Algorithm::Ed25519
+        ) || (cfg!(feature = "rsa") && algorithm == asymmetric::Algorithm::Rsa2048)
+    }
+
     /// Get the algorithm type for this payload
     pub fn algorithm(&self) -> Algorithm {
         match *self {
@@ -106,8 +136,11 @@ impl Payload {
             Payload::EcdsaNistP256(_) => Algorithm::Asymmetric(asymmetric::Algorithm::EcP256),
             Payload::EcdsaSecp256k1(_) => Algorithm::Asymmetric(asymmetric::Algorithm::EcK256),
             Payload::Ed25519Key(_) => Algorithm::Asymmetric(asymmetric::Algorithm::Ed25519),
+            #[cfg(feature = "rsa")]
+            Payload::Rsa2048(_) => Algorithm::Asymmetric(asymmetric::Algorithm::Rsa2048),
             Payload::HmacKey(alg, _) => alg.into(),
             Payload::Opaque(alg, _) => alg.into(),
+            Payload::Template(alg, _) => alg.into(),
             Payload::WrapKey(alg, _) => alg.into(),
         }
     }
@@ -118,8 +151,11 @@ impl Payload {
             Payload::AuthenticationKey(_) => authentication::key::SIZE,
             Payload::EcdsaNistP256(_) | Payload::EcdsaSecp256k1(_) => 32,
             Payload::Ed25519Key(_) => ed25519::SECRET_KEY_LENGTH,
+            #[cfg(feature = "rsa")]
+            Payload::Rsa2048(_) => asymmetric::Algorithm::Rsa2048.key_len(),
             Payload::HmacKey(_, ref data) => data.len(),
             Payload::Opaque(_, ref data) => data.len(),
+            Payload::Template(_, ref data) => data.len(),
             Payload::WrapKey(_, ref data) => data.len(),
         };
         l as u16
@@ -141,6 +177,10 @@ impl Payload {
             Payload::Ed25519Key(secret_key) => {
                 Some(ed25519::PublicKey::from(secret_key).as_ref().into())
             }
+            // RSA public keys are serialized as their modulus (the public
+            // exponent is always 65537)
+            #[cfg(feature = "rsa")]
+            Payload::Rsa2048(private_key) => Some(private_key.n().to_bytes_be()),
             _ => None,
         }
     }
@@ -160,9 +200,40 @@ impl Payload {
             Payload::EcdsaNistP256(k) => k.to_bytes().to_vec(),
             Payload::EcdsaSecp256k1(k) => k.to_bytes().to_vec(),
             Payload::Ed25519Key(k) => k.as_ref().into(),
+            #[cfg(feature = "rsa")]
+            Payload::Rsa2048(k) => k.primes().iter().flat_map(|p| p.to_bytes_be()).collect(),
             Payload::HmacKey(_, data) => data.clone(),
             Payload::Opaque(_, data) => data.clone(),
+            Payload::Template(_, data) => data.clone(),
             Payload::WrapKey(_, data) => data.clone(),
         }
     }
 }
+
+/// Public exponent of RSA keys in the MockHsm (and YubiHSM 2)
+#[cfg(feature = "rsa")]
+const RSA_PUBLIC_EXPONENT: u32 = 65537;
+
+/// Reconstruct an RSA private key from its primes `p` and `q`, each half of
+/// `data`, which is how the YubiHSM 2 serializes them
+#[cfg(feature = "rsa")]
+fn rsa_key_from_primes(data: &[u8]) -> ::rsa::RSAPrivateKey {
+    let (p, q) = data.split_at(data.len() / 2);
+    let p = BigUint::from_bytes_be(p);
+    let q = BigUint::from_bytes_be(q);
+
+    let one = BigUint::from(1u32);
+    let e = BigUint::from(RSA_PUBLIC_EXPONENT);
+    let phi = (&p - &one) * (&q - &one);
+
+    // e is small, so rather than computing its inverse with the extended
+    // Euclidean algorithm, find the `k` for which `e` divides `1 + k * phi`
+    let phi_mod_e = &phi % &e;
+    let k = (1..RSA_PUBLIC_EXPONENT)
+        .map(BigUint::from)
+        .find(|k| (&one + k * &phi_mod_e) % &e == BigUint::from(0u32))
+        .expect("RSA public exponent is not invertible");
+
+    let d = (&one + k * &phi) / &e;
+    ::rsa::RSAPrivateKey::from_components(&p * &q, e, d, vec![p, q])
+}
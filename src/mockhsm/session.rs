@@ -3,11 +3,12 @@
 use std::fmt::{self, Debug};
 
 use crate::{
-    command, response,
+    command, object, response,
     session::{
         securechannel::{Challenge, Cryptogram, SecureChannel},
         Id,
     },
+    Capability, Domain,
 };
 
 /// Session with the `MockHsm`
@@ -15,6 +16,15 @@ pub(crate) struct HsmSession {
     /// ID of the session
     pub id: Id,
 
+    /// Authentication key the session was opened with
+    pub authentication_key_id: object::Id,
+
+    /// Capabilities of the authentication key
+    pub capabilities: Capability,
+
+    /// Domains of the authentication key
+    pub domains: Domain,
+
     /// Card challenge for this session
     pub card_challenge: Challenge,
 
@@ -24,9 +34,17 @@ pub(crate) struct HsmSession {
 
 impl HsmSession {
     /// Create a new session
-    pub fn new(id: Id, card_challenge: Challenge, channel: SecureChannel) -> Self {
+    pub fn new(
+        id: Id,
+        authentication_key: &object::Info,
+        card_challenge: Challenge,
+        channel: SecureChannel,
+    ) -> Self {
         Self {
             id,
+            authentication_key_id: authentication_key.object_id,
+            capabilities: authentication_key.capabilities,
+            domains: authentication_key.domains,
             card_challenge,
             channel,
         }
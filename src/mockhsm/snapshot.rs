@@ -0,0 +1,92 @@
+//! Snapshots of the objects stored in a `MockHsm`.
+//!
+//! Snapshots are byte strings with the following layout:
+//!
+//! - 1-byte format version (`SNAPSHOT_VERSION`)
+//! - zero or more objects, each encoded as a 2-byte big endian length
+//!   followed by that many bytes containing:
+//!   - the object's `object::Info`, in the format of a Get Object Info response
+//!   - the object's raw key material (or opaque data)
+//!
+//! The version is incremented whenever this layout changes, and snapshots
+//! with any other version are rejected rather than misinterpreted.
+
+use super::{
+    object::{Objects, WrappedObject},
+    Error, ErrorKind,
+};
+use crate::serialization::{deserialize as deserialize_object, serialize as serialize_object};
+use anomaly::{ensure, fail, format_err};
+use std::convert::TryFrom;
+
+/// Version of the snapshot format produced by this version of the crate
+pub const SNAPSHOT_VERSION: u8 = 1;
+
+/// Serialize all of the given objects as a snapshot
+pub(super) fn serialize(objects: &Objects) -> Vec<u8> {
+    let mut snapshot = vec![SNAPSHOT_VERSION];
+
+    for (_, object) in objects.iter() {
+        let bytes = serialize_object(&WrappedObject::from(object)).unwrap();
+        let length = u16::try_from(bytes.len()).expect("object too large for snapshot");
+        snapshot.extend_from_slice(&length.to_be_bytes());
+        snapshot.extend_from_slice(&bytes);
+    }
+
+    snapshot
+}
+
+/// Parse a snapshot, returning the objects it contains
+pub(super) fn deserialize(snapshot: &[u8]) -> Result<Objects, Error> {
+    let (version, mut remaining) = match snapshot.split_first() {
+        Some((version, remaining)) => (*version, remaining),
+        None => fail!(ErrorKind::SnapshotInvalid, "empty snapshot"),
+    };
+
+    ensure!(
+        version == SNAPSHOT_VERSION,
+        ErrorKind::SnapshotInvalid,
+        "unsupported snapshot version: {} (expected {})",
+        version,
+        SNAPSHOT_VERSION
+    );
+
+    let mut objects = Objects::empty();
+
+    while !remaining.is_empty() {
+        ensure!(
+            remaining.len() >= 2,
+            ErrorKind::SnapshotInvalid,
+            "truncated object length"
+        );
+
+        let length = u16::from_be_bytes([remaining[0], remaining[1]]) as usize;
+        remaining = &remaining[2..];
+
+        ensure!(
+            remaining.len() >= length,
+            ErrorKind::SnapshotInvalid,
+            "truncated object: expected {} bytes, got {}",
+            length,
+            remaining.len()
+        );
+
+        let wrapped_object: WrappedObject = deserialize_object(&remaining[..length])
+            .map_err(|e| format_err!(ErrorKind::SnapshotInvalid, "malformed object: {}", e))?;
+
+        remaining = &remaining[length..];
+
+        let object_id = wrapped_object.object_info.object_id;
+        let object_type = wrapped_object.object_info.object_type;
+
+        ensure!(
+            objects.insert(wrapped_object).is_some(),
+            ErrorKind::SnapshotInvalid,
+            "duplicate {:?} object: {:?}",
+            object_type,
+            object_id
+        );
+    }
+
+    Ok(objects)
+}
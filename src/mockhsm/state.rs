@@ -1,7 +1,11 @@
 //! `MockHsm` presents a thread-safe API by locking interior mutable state,
 //! contained in the `State` struct defined in this module.
 
-use super::{audit::CommandAuditOptions, object::Objects, session::HsmSession};
+use super::{
+    audit::{AlgorithmToggles, AuditLog, CommandAuditOptions},
+    object::Objects,
+    session::HsmSession,
+};
 use crate::{
     audit::AuditOption,
     connector, object,
@@ -9,6 +13,7 @@ use crate::{
         self,
         securechannel::{Challenge, SecureChannel},
     },
+    Capability, Domain,
 };
 use anomaly::format_err;
 use std::collections::BTreeMap;
@@ -16,6 +21,12 @@ use std::collections::BTreeMap;
 /// Mutable interior state of the `MockHsm`
 #[derive(Debug)]
 pub(crate) struct State {
+    /// Algorithms which are enabled or disabled
+    pub(super) algorithm_toggles: AlgorithmToggles,
+
+    /// Log of audited commands
+    pub(super) audit_log: AuditLog,
+
     /// Command-specific audit options
     pub(super) command_audit_options: CommandAuditOptions,
 
@@ -33,11 +44,19 @@ pub(crate) struct State {
 impl State {
     /// Create a new instance of the server's mutable interior state
     pub fn new() -> Self {
+        Self::with_objects(Objects::default())
+    }
+
+    /// Create a new instance of the server's mutable interior state which
+    /// contains the given objects
+    pub fn with_objects(objects: Objects) -> Self {
         Self {
+            algorithm_toggles: AlgorithmToggles::default(),
+            audit_log: AuditLog::default(),
             command_audit_options: CommandAuditOptions::default(),
             force_audit: AuditOption::Off,
             sessions: BTreeMap::new(),
-            objects: Objects::default(),
+            objects,
         }
     }
 
@@ -57,29 +76,32 @@ impl State {
             .map(|id| id.succ().expect("session count exceeded"))
             .unwrap_or_else(|| session::Id::from_u8(0).unwrap());
 
-        let channel = {
-            let authentication_key_obj = self
-                .objects
-                .get(authentication_key_id, object::Type::AuthenticationKey)
-                .unwrap_or_else(|| {
-                    panic!(
-                        "MockHsm has no authentication::Key in slot {:?}",
-                        authentication_key_id
-                    )
-                });
-
-            SecureChannel::new(
-                session_id,
-                authentication_key_obj
-                    .payload
-                    .authentication_key()
-                    .expect("auth key payload"),
-                host_challenge,
-                card_challenge,
-            )
-        };
+        let authentication_key_obj = self
+            .objects
+            .get(authentication_key_id, object::Type::AuthenticationKey)
+            .unwrap_or_else(|| {
+                panic!(
+                    "MockHsm has no authentication::Key in slot 0x{:04x}",
+                    authentication_key_id
+                )
+            });
+
+        let channel = SecureChannel::new(
+            session_id,
+            authentication_key_obj
+                .payload
+                .authentication_key()
+                .expect("auth key payload"),
+            host_challenge,
+            card_challenge,
+        );
 
-        let session = HsmSession::new(session_id, card_challenge, channel);
+        let session = HsmSession::new(
+            session_id,
+            &authentication_key_obj.object_info,
+            card_challenge,
+            channel,
+        );
         assert!(self.sessions.insert(session_id, session).is_none());
 
         self.get_session(session_id).unwrap()
@@ -97,13 +119,43 @@ impl State {
         })
     }
 
+    /// Capabilities which objects created in the given session may be
+    /// granted, i.e. the delegated capabilities of its authentication key
+    pub fn delegated_capabilities(&self, id: session::Id) -> Capability {
+        self.sessions
+            .get(&id)
+            .and_then(|session| {
+                self.objects.get(
+                    session.authentication_key_id,
+                    object::Type::AuthenticationKey,
+                )
+            })
+            .map(|obj| obj.object_info.delegated_capabilities)
+            .unwrap_or_else(Capability::empty)
+    }
+
+    /// Domains of the authentication key the given session was opened with
+    pub fn session_domains(&self, id: session::Id) -> Domain {
+        self.sessions
+            .get(&id)
+            .map(|session| session.domains)
+            .unwrap_or_else(Domain::empty)
+    }
+
     /// Close an active session
     pub fn close_session(&mut self, id: session::Id) {
         assert!(self.sessions.remove(&id).is_some());
     }
 
+    /// Close all active sessions
+    pub fn close_all_sessions(&mut self) {
+        self.sessions.clear();
+    }
+
     /// Reset the internal HSM state, closing all connections
     pub fn reset(&mut self) {
+        self.algorithm_toggles = AlgorithmToggles::default();
+        self.audit_log = AuditLog::default();
         self.command_audit_options = CommandAuditOptions::default();
         self.sessions = BTreeMap::new();
         self.objects = Objects::default();
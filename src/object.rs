@@ -3,30 +3,42 @@
 //! For more information, see:
 //! <https://developers.yubico.com/YubiHSM2/Concepts/Object.html>
 
+mod changes;
 pub(crate) mod commands;
+mod diff;
 mod entry;
 mod error;
 mod filter;
 pub(crate) mod generate;
 mod handle;
+mod id;
 mod info;
+#[cfg(feature = "sha2")]
+mod inventory;
 mod label;
 mod origins;
 pub mod put;
 mod types;
 
 pub use self::{
-    entry::Entry,
+    changes::{Changeset, Snapshot},
+    diff::{Diff, Policy},
+    entry::{DetailedEntry, Entry},
     error::{Error, ErrorKind},
     filter::Filter,
     handle::Handle,
+    id::{HexId, AUTO_ID},
     info::Info,
     label::{Label, LABEL_SIZE},
     origins::Origin,
     types::Type,
 };
 
-/// Object identifiers
+#[cfg(feature = "sha2")]
+pub use self::inventory::{Inventory, InventoryEntry, FINGERPRINT_SIZE};
+
+/// Object identifiers (see `HexId` for displaying them in hexadecimal, and
+/// `AUTO_ID` for having the HSM assign them)
 pub type Id = u16;
 
 /// Sequence identifiers: number of times an object with a given ID has been
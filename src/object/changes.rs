@@ -0,0 +1,109 @@
+//! Detect objects which have been created or deleted since a previously
+//! recorded listing, e.g. for incremental backups.
+
+use super::{Entry, Handle, SequenceId};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Sequence numbers of objects as of a particular listing, as recorded by
+/// `Changeset::snapshot`
+pub type Snapshot = BTreeMap<Handle, SequenceId>;
+
+/// Objects which are new, deleted, or have been replaced since a previous
+/// `Snapshot` was taken.
+///
+/// Changes are detected by comparing sequence numbers, which the HSM only
+/// bumps when an object is (re)created. Objects which have been modified in
+/// place without changing their sequence number (if the HSM ever allows
+/// that) aren't reported, so this can't be relied on to detect every change
+/// to an object's attributes.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, Eq, PartialEq)]
+pub struct Changeset {
+    /// Objects which weren't present in the snapshot
+    pub added: Vec<Entry>,
+
+    /// Objects in the snapshot which no longer exist
+    pub deleted: Vec<Handle>,
+
+    /// Objects whose sequence number differs from the snapshot, i.e. which
+    /// have been deleted and recreated under the same ID
+    pub changed: Vec<Entry>,
+}
+
+impl Changeset {
+    /// Compute the changes between a previous snapshot and the current list
+    /// of objects
+    pub fn new(previous: &Snapshot, current: &[Entry]) -> Self {
+        let mut changeset = Self::default();
+        let mut remaining = previous.clone();
+
+        for entry in current {
+            let handle = Handle::new(entry.object_id, entry.object_type);
+
+            match remaining.remove(&handle) {
+                None => changeset.added.push(entry.clone()),
+                Some(sequence) if sequence != entry.sequence => {
+                    changeset.changed.push(entry.clone())
+                }
+                Some(_) => (),
+            }
+        }
+
+        changeset.deleted = remaining.keys().cloned().collect();
+        changeset
+    }
+
+    /// Record the sequence numbers of the given objects, for passing to a
+    /// later call to `Changeset::new`
+    pub fn snapshot(entries: &[Entry]) -> Snapshot {
+        entries
+            .iter()
+            .map(|entry| {
+                (
+                    Handle::new(entry.object_id, entry.object_type),
+                    entry.sequence,
+                )
+            })
+            .collect()
+    }
+
+    /// Are there no changes?
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.deleted.is_empty() && self.changed.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::object::Type;
+
+    fn entry(object_id: u16, object_type: Type, sequence: SequenceId) -> Entry {
+        Entry {
+            object_id,
+            object_type,
+            sequence,
+        }
+    }
+
+    #[test]
+    fn detects_added_deleted_and_changed_objects() {
+        let before = [
+            entry(1, Type::AuthenticationKey, 0),
+            entry(2, Type::AsymmetricKey, 0),
+            entry(3, Type::Opaque, 4),
+        ];
+
+        let after = [
+            entry(1, Type::AuthenticationKey, 0),
+            entry(3, Type::Opaque, 5),
+            entry(4, Type::HmacKey, 0),
+        ];
+
+        let changeset = Changeset::new(&Changeset::snapshot(&before), &after);
+        assert_eq!(changeset.added, vec![entry(4, Type::HmacKey, 0)]);
+        assert_eq!(changeset.deleted, vec![Handle::new(2, Type::AsymmetricKey)]);
+        assert_eq!(changeset.changed, vec![entry(3, Type::Opaque, 5)]);
+        assert!(Changeset::new(&Changeset::snapshot(&after), &after).is_empty());
+    }
+}
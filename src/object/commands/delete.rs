@@ -3,6 +3,7 @@
 //! <https://developers.yubico.com/YubiHSM2/Commands/Delete_Object.html>
 
 use crate::{
+    capability::Capability,
     command::{self, Command},
     object,
     response::Response,
@@ -21,6 +22,26 @@ pub(crate) struct DeleteObjectCommand {
 
 impl Command for DeleteObjectCommand {
     type ResponseType = DeleteObjectResponse;
+
+    fn object_id(&self) -> Option<object::Id> {
+        Some(self.object_id)
+    }
+
+    fn object_type(&self) -> Option<object::Type> {
+        Some(self.object_type)
+    }
+
+    fn required_capability(&self) -> Capability {
+        match self.object_type {
+            object::Type::AsymmetricKey => Capability::DELETE_ASYMMETRIC_KEY,
+            object::Type::AuthenticationKey => Capability::DELETE_AUTHENTICATION_KEY,
+            object::Type::HmacKey => Capability::DELETE_HMAC_KEY,
+            object::Type::Opaque => Capability::DELETE_OPAQUE,
+            object::Type::OtpAeadKey => Capability::DELETE_OTP_AEAD_KEY,
+            object::Type::Template => Capability::DELETE_TEMPLATE,
+            object::Type::WrapKey => Capability::DELETE_WRAP_KEY,
+        }
+    }
 }
 
 /// Response from `command::delete_object`
@@ -29,4 +50,5 @@ pub(crate) struct DeleteObjectResponse {}
 
 impl Response for DeleteObjectResponse {
     const COMMAND_CODE: command::Code = command::Code::DeleteObject;
+    const MAX_SIZE: usize = 0;
 }
@@ -15,6 +15,14 @@ pub(crate) struct GetObjectInfoCommand(pub(crate) object::Handle);
 
 impl Command for GetObjectInfoCommand {
     type ResponseType = GetObjectInfoResponse;
+
+    fn object_id(&self) -> Option<object::Id> {
+        Some(self.0.object_id)
+    }
+
+    fn object_type(&self) -> Option<object::Type> {
+        Some(self.0.object_type)
+    }
 }
 
 /// Response from `command::get_object_info`
@@ -0,0 +1,115 @@
+//! Configuration drift detection: compare the capabilities and domains of
+//! objects in the HSM against a desired policy.
+
+use crate::{Capability, Domain};
+use serde::{Deserialize, Serialize};
+
+/// Desired capabilities and domains for an object
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Policy {
+    /// Capabilities the object should have
+    pub capabilities: Capability,
+
+    /// Domains the object should be accessible from
+    pub domains: Domain,
+}
+
+/// Differences between an object's actual configuration and its `Policy`.
+///
+/// "Added" entries are present on the object but not in the policy, and
+/// "removed" entries are in the policy but missing from the object.
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Diff {
+    /// Does the object exist in the HSM?
+    pub present: bool,
+
+    /// Capabilities the object has which the policy doesn't grant
+    pub capabilities_added: Capability,
+
+    /// Capabilities the policy grants which the object lacks
+    pub capabilities_removed: Capability,
+
+    /// Domains the object is in which the policy doesn't include
+    pub domains_added: Domain,
+
+    /// Domains the policy includes which the object isn't in
+    pub domains_removed: Domain,
+}
+
+impl Diff {
+    /// Compute the difference between the actual and desired configuration
+    pub fn new(actual: &Policy, desired: &Policy) -> Self {
+        Self {
+            present: true,
+            capabilities_added: actual.capabilities - desired.capabilities,
+            capabilities_removed: desired.capabilities - actual.capabilities,
+            domains_added: actual.domains - desired.domains,
+            domains_removed: desired.domains - actual.domains,
+        }
+    }
+
+    /// Diff for an object which the policy expects but the HSM doesn't have
+    pub fn missing(desired: &Policy) -> Self {
+        Self {
+            present: false,
+            capabilities_added: Capability::empty(),
+            capabilities_removed: desired.capabilities,
+            domains_added: Domain::empty(),
+            domains_removed: desired.domains,
+        }
+    }
+
+    /// Does the object match its policy?
+    pub fn is_empty(&self) -> bool {
+        self.present
+            && self.capabilities_added.is_empty()
+            && self.capabilities_removed.is_empty()
+            && self.domains_added.is_empty()
+            && self.domains_removed.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matching_policy_is_empty() {
+        let policy = Policy {
+            capabilities: Capability::SIGN_EDDSA | Capability::EXPORTABLE_UNDER_WRAP,
+            domains: Domain::DOM1 | Domain::DOM2,
+        };
+
+        assert!(Diff::new(&policy, &policy).is_empty());
+    }
+
+    #[test]
+    fn drifting_policy_reports_changes() {
+        let actual = Policy {
+            capabilities: Capability::SIGN_EDDSA | Capability::EXPORTABLE_UNDER_WRAP,
+            domains: Domain::DOM1 | Domain::DOM3,
+        };
+
+        let desired = Policy {
+            capabilities: Capability::SIGN_EDDSA | Capability::GET_LOG_ENTRIES,
+            domains: Domain::DOM1 | Domain::DOM2,
+        };
+
+        let diff = Diff::new(&actual, &desired);
+        assert!(!diff.is_empty());
+        assert_eq!(diff.capabilities_added, Capability::EXPORTABLE_UNDER_WRAP);
+        assert_eq!(diff.capabilities_removed, Capability::GET_LOG_ENTRIES);
+        assert_eq!(diff.domains_added, Domain::DOM3);
+        assert_eq!(diff.domains_removed, Domain::DOM2);
+    }
+
+    #[test]
+    fn missing_object_is_not_empty() {
+        let desired = Policy {
+            capabilities: Capability::empty(),
+            domains: Domain::empty(),
+        };
+
+        assert!(!Diff::missing(&desired).is_empty());
+    }
+}
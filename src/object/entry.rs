@@ -1,10 +1,14 @@
 //! Object entries in list objects output
 
-use crate::object;
+use crate::{client, object};
 use serde::{Deserialize, Serialize};
 
+/// Entry for an object, along with either its full information or the error
+/// which occurred fetching it (see `Client::list_objects_detailed`)
+pub type DetailedEntry = (Entry, Result<object::Info, client::Error>);
+
 /// Brief information about an object as included in `ListObjectsCommand`
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct Entry {
     /// Object identifier
     pub object_id: object::Id,
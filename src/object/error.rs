@@ -9,6 +9,10 @@ pub type Error = crate::Error<ErrorKind>;
 /// Kinds of `Object`-related errors
 #[derive(Copy, Clone, Debug, Eq, Error, PartialEq)]
 pub enum ErrorKind {
+    /// Invalid object ID
+    #[error("invalid object ID")]
+    IdInvalid,
+
     /// Invalid label
     #[error("invalid label")]
     LabelInvalid,
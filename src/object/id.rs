@@ -0,0 +1,102 @@
+//! Hexadecimal formatting and parsing for object IDs
+
+use super::{Error, ErrorKind, Id};
+use anomaly::format_err;
+use std::{fmt, str::FromStr};
+
+/// Object ID which asks the HSM to assign a free ID when putting or
+/// generating an object. The assigned ID is returned by the respective
+/// `Client` method.
+///
+/// Assignment happens atomically on the device, so unlike picking an unused
+/// ID on the host (e.g. by listing objects first) it's safe when several
+/// clients are provisioning the same HSM concurrently. All YubiHSM 2
+/// firmware versions support it.
+pub const AUTO_ID: Id = 0;
+
+/// Object ID which is displayed in hexadecimal (e.g. `0x0001`), matching the
+/// notation used by `yubihsm-shell` and the Yubico documentation.
+///
+/// Parses from hexadecimal strings either with or without a `0x` prefix.
+#[derive(Copy, Clone, Default, Eq, Hash, PartialEq, PartialOrd, Ord)]
+pub struct HexId(pub Id);
+
+impl HexId {
+    /// Parse an object ID from a hexadecimal string (with or without `0x`)
+    pub fn from_hex(s: &str) -> Result<Self, Error> {
+        let digits = match s.get(..2) {
+            Some("0x") | Some("0X") => &s[2..],
+            _ => s,
+        };
+
+        if digits.is_empty() || digits.len() > 4 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format_err!(ErrorKind::IdInvalid, "invalid object ID: {:?}", s).into());
+        }
+
+        Id::from_str_radix(digits, 16)
+            .map(HexId)
+            .map_err(|_| format_err!(ErrorKind::IdInvalid, "invalid object ID: {:?}", s).into())
+    }
+
+    /// Format this object ID as a hexadecimal string (e.g. `0x0001`)
+    pub fn to_hex(self) -> String {
+        self.to_string()
+    }
+}
+
+impl fmt::Display for HexId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "0x{:04x}", self.0)
+    }
+}
+
+impl fmt::Debug for HexId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "HexId(0x{:04x})", self.0)
+    }
+}
+
+impl FromStr for HexId {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        Self::from_hex(s)
+    }
+}
+
+impl From<Id> for HexId {
+    fn from(id: Id) -> Self {
+        HexId(id)
+    }
+}
+
+impl From<HexId> for Id {
+    fn from(id: HexId) -> Id {
+        id.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display() {
+        assert_eq!(HexId(1).to_string(), "0x0001");
+        assert_eq!(HexId(0xabcd).to_hex(), "0xabcd");
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!("0x0001".parse::<HexId>().unwrap(), HexId(1));
+        assert_eq!("0XFFFE".parse::<HexId>().unwrap(), HexId(0xfffe));
+        assert_eq!("12ab".parse::<HexId>().unwrap(), HexId(0x12ab));
+
+        for s in &["", "0x", "0x10000", "zz", "-1"] {
+            assert_eq!(
+                *s.parse::<HexId>().unwrap_err().kind(),
+                ErrorKind::IdInvalid
+            );
+        }
+    }
+}
@@ -38,3 +38,33 @@ pub struct Info {
     /// Delegated Capabilities (bitfield)
     pub delegated_capabilities: Capability,
 }
+
+impl Info {
+    /// Get the delegated capabilities of this object, if it's a type of
+    /// object which has them (i.e. authentication and wrap keys).
+    ///
+    /// These are the capabilities which objects created or imported using
+    /// this key are allowed to have.
+    pub fn delegated(&self) -> Option<Capability> {
+        match self.object_type {
+            object::Type::AuthenticationKey | object::Type::WrapKey => {
+                Some(self.delegated_capabilities)
+            }
+            _ => None,
+        }
+    }
+
+    /// Get the capabilities and domains of this object as a `Policy`
+    pub fn policy(&self) -> object::Policy {
+        object::Policy {
+            capabilities: self.capabilities,
+            domains: self.domains,
+        }
+    }
+
+    /// Compare this object's capabilities and domains against the desired
+    /// policy
+    pub fn diff(&self, desired: &object::Policy) -> object::Diff {
+        object::Diff::new(&self.policy(), desired)
+    }
+}
@@ -0,0 +1,103 @@
+//! Fingerprints of an HSM's object inventory, for checking that several
+//! HSMs have been provisioned identically (e.g. members of an `HsmCluster`).
+
+use super::{Handle, Info};
+use crate::{Algorithm, Capability, Domain};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+/// Size of an inventory fingerprint (SHA-256)
+pub const FINGERPRINT_SIZE: usize = 32;
+
+/// Configuration of an object which is covered by an inventory fingerprint.
+///
+/// This deliberately excludes key material, as well as attributes which are
+/// expected to differ between identically provisioned HSMs (labels, origins
+/// and sequence numbers).
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Eq, PartialEq)]
+pub struct InventoryEntry {
+    /// Algorithm the object is intended to be used with
+    pub algorithm: Algorithm,
+
+    /// Capabilities of the object
+    pub capabilities: Capability,
+
+    /// Delegated capabilities of the object
+    pub delegated_capabilities: Capability,
+
+    /// Domains the object is accessible from
+    pub domains: Domain,
+}
+
+impl From<&Info> for InventoryEntry {
+    fn from(info: &Info) -> Self {
+        Self {
+            algorithm: info.algorithm,
+            capabilities: info.capabilities,
+            delegated_capabilities: info.delegated_capabilities,
+            domains: info.domains,
+        }
+    }
+}
+
+/// Inventory of the objects in an HSM, along with a fingerprint computed
+/// over it which is stable across HSMs with the same objects.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Inventory {
+    /// Objects in the HSM
+    pub objects: BTreeMap<Handle, InventoryEntry>,
+}
+
+impl Inventory {
+    /// Create an inventory from information about each object
+    pub fn new<'a>(infos: impl IntoIterator<Item = &'a Info>) -> Self {
+        Self {
+            objects: infos
+                .into_iter()
+                .map(|info| (Handle::new(info.object_id, info.object_type), info.into()))
+                .collect(),
+        }
+    }
+
+    /// Compute the SHA-256 fingerprint of this inventory
+    pub fn fingerprint(&self) -> [u8; FINGERPRINT_SIZE] {
+        let mut hasher = Sha256::new();
+        hasher.update((self.objects.len() as u32).to_be_bytes());
+
+        // Objects are hashed in (ID, type) order in a fixed-width encoding
+        for (handle, entry) in &self.objects {
+            hasher.update(handle.object_id.to_be_bytes());
+            hasher.update([handle.object_type.to_u8(), entry.algorithm.to_u8()]);
+            hasher.update(entry.capabilities.bits().to_be_bytes());
+            hasher.update(entry.delegated_capabilities.bits().to_be_bytes());
+            hasher.update(entry.domains.bits().to_be_bytes());
+        }
+
+        let mut fingerprint = [0u8; FINGERPRINT_SIZE];
+        fingerprint.copy_from_slice(&hasher.finalize());
+        fingerprint
+    }
+
+    /// Find the objects which differ between this inventory and another,
+    /// including objects which are only present in one of them
+    pub fn diff(&self, other: &Inventory) -> Vec<Handle> {
+        let mut handles: Vec<Handle> = self
+            .objects
+            .iter()
+            .filter(|(handle, entry)| other.objects.get(handle) != Some(entry))
+            .map(|(handle, _)| handle.clone())
+            .collect();
+
+        handles.extend(
+            other
+                .objects
+                .keys()
+                .filter(|handle| !self.objects.contains_key(handle))
+                .cloned(),
+        );
+
+        handles.sort();
+        handles
+    }
+}
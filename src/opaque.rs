@@ -1,6 +1,12 @@
 //! Opaque object support: store arbitrary data in the HSM
 
 mod algorithm;
+mod certificate;
 pub(crate) mod commands;
+mod error;
 
-pub use self::algorithm::Algorithm;
+pub use self::{
+    algorithm::Algorithm,
+    certificate::validate_certificate,
+    error::{Error, ErrorKind},
+};
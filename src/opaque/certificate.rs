@@ -0,0 +1,128 @@
+//! Structural validation of X.509 certificates stored as opaque objects
+
+use super::{Error, ErrorKind};
+use anomaly::{ensure, fail};
+
+/// DER `SEQUENCE` tag
+const SEQUENCE: u8 = 0x30;
+
+/// DER `BIT STRING` tag
+const BIT_STRING: u8 = 0x03;
+
+/// Check that the given bytes are a well-formed DER-encoded X.509
+/// certificate, i.e.:
+///
+/// ```text
+/// Certificate ::= SEQUENCE {
+///     tbsCertificate       SEQUENCE,
+///     signatureAlgorithm   SEQUENCE,
+///     signatureValue       BIT STRING
+/// }
+/// ```
+///
+/// This only checks the outer structure of the certificate, and doesn't
+/// parse its contents or verify its signature.
+pub fn validate_certificate(der: &[u8]) -> Result<(), Error> {
+    let (certificate, trailing) = read_tlv(der, SEQUENCE, "certificate")?;
+
+    ensure!(
+        trailing.is_empty(),
+        ErrorKind::CertificateInvalid,
+        "{} trailing bytes after certificate",
+        trailing.len()
+    );
+
+    let (_, rest) = read_tlv(certificate, SEQUENCE, "tbsCertificate")?;
+    let (_, rest) = read_tlv(rest, SEQUENCE, "signatureAlgorithm")?;
+    let (signature, rest) = read_tlv(rest, BIT_STRING, "signatureValue")?;
+
+    ensure!(
+        !signature.is_empty(),
+        ErrorKind::CertificateInvalid,
+        "empty signatureValue"
+    );
+
+    ensure!(
+        rest.is_empty(),
+        ErrorKind::CertificateInvalid,
+        "unexpected fields after signatureValue"
+    );
+
+    Ok(())
+}
+
+/// Read a DER tag-length-value with the given tag, returning its value and
+/// the remaining bytes
+fn read_tlv<'a>(bytes: &'a [u8], tag: u8, name: &str) -> Result<(&'a [u8], &'a [u8]), Error> {
+    if bytes.len() < 2 {
+        fail!(ErrorKind::CertificateInvalid, "{} is truncated", name);
+    }
+
+    ensure!(
+        bytes[0] == tag,
+        ErrorKind::CertificateInvalid,
+        "expected {} to have tag 0x{:02x} (got 0x{:02x})",
+        name,
+        tag,
+        bytes[0]
+    );
+
+    let (len, header_len) = match bytes[1] {
+        len if len < 0x80 => (len as usize, 2),
+        0x81..=0x84 => {
+            let num_bytes = (bytes[1] & 0x7f) as usize;
+
+            if bytes.len() < 2 + num_bytes {
+                fail!(ErrorKind::CertificateInvalid, "{} is truncated", name);
+            }
+
+            let len = bytes[2..2 + num_bytes]
+                .iter()
+                .fold(0usize, |len, &b| len << 8 | b as usize);
+
+            (len, 2 + num_bytes)
+        }
+        _ => fail!(
+            ErrorKind::CertificateInvalid,
+            "{} has an unsupported length encoding",
+            name
+        ),
+    };
+
+    if bytes.len() - header_len < len {
+        fail!(ErrorKind::CertificateInvalid, "{} is truncated", name);
+    }
+
+    let (value, rest) = bytes[header_len..].split_at(len);
+    Ok((value, rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Structurally valid (but otherwise meaningless) certificate
+    const MINIMAL_CERTIFICATE: &[u8] = &[
+        0x30, 0x09, 0x30, 0x00, 0x30, 0x00, 0x03, 0x03, 0x00, 0xab, 0xcd,
+    ];
+
+    #[test]
+    fn accepts_well_formed_certificate() {
+        assert!(validate_certificate(MINIMAL_CERTIFICATE).is_ok());
+    }
+
+    #[test]
+    fn rejects_malformed_certificates() {
+        let mut trailing = MINIMAL_CERTIFICATE.to_vec();
+        trailing.push(0);
+
+        let truncated = &MINIMAL_CERTIFICATE[..MINIMAL_CERTIFICATE.len() - 1];
+
+        for der in &[&b"not a certificate"[..], truncated, &trailing, &[]] {
+            assert_eq!(
+                *validate_certificate(der).unwrap_err().kind(),
+                ErrorKind::CertificateInvalid
+            );
+        }
+    }
+}
@@ -18,6 +18,14 @@ pub(crate) struct GetOpaqueCommand {
 
 impl Command for GetOpaqueCommand {
     type ResponseType = GetOpaqueResponse;
+
+    fn object_id(&self) -> Option<object::Id> {
+        Some(self.object_id)
+    }
+
+    fn object_type(&self) -> Option<object::Type> {
+        Some(object::Type::Opaque)
+    }
 }
 
 /// Response from `command::get_opaque`
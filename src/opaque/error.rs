@@ -0,0 +1,22 @@
+//! Opaque object errors
+
+use anomaly::{BoxError, Context};
+use thiserror::Error;
+
+/// Opaque object errors
+pub type Error = crate::Error<ErrorKind>;
+
+/// Kinds of opaque object errors
+#[derive(Copy, Clone, Debug, Eq, Error, PartialEq)]
+pub enum ErrorKind {
+    /// Data isn't a well-formed DER-encoded X.509 certificate
+    #[error("invalid X.509 certificate")]
+    CertificateInvalid,
+}
+
+impl ErrorKind {
+    /// Create an error context from this error
+    pub fn context(self, source: impl Into<BoxError>) -> Context<ErrorKind> {
+        Context::new(self, Some(source.into()))
+    }
+}
@@ -1,6 +1,12 @@
 //! Yubico One Time Password (OTP) functionality
 
+mod aead;
 mod algorithm;
 pub(crate) mod commands;
+mod decrypted;
 
-pub use self::algorithm::Algorithm;
+pub use self::{
+    aead::{Aead, AEAD_SIZE, OTP_KEY_SIZE, PRIVATE_ID_SIZE},
+    algorithm::Algorithm,
+    decrypted::{DecryptedOtp, OTP_SIZE},
+};
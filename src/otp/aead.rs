@@ -0,0 +1,53 @@
+//! Yubico OTP AEADs
+
+use serde::{Deserialize, Serialize};
+
+/// Size of a YubiKey's Yubico OTP key (AES-128)
+pub const OTP_KEY_SIZE: usize = 16;
+
+/// Size of a YubiKey's Yubico OTP private ID
+pub const PRIVATE_ID_SIZE: usize = 6;
+
+/// Size of an OTP AEAD: a 6-byte nonce, the encrypted OTP key (16 bytes)
+/// and private ID (6 bytes), and an 8-byte MAC
+pub const AEAD_SIZE: usize = 36;
+
+/// Yubico OTP AEADs: a YubiKey's OTP key and private ID, encrypted under
+/// an OTP AEAD key stored in the HSM
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Aead(Vec<u8>);
+
+impl Aead {
+    /// Create an `Aead` from its serialized bytes.
+    ///
+    /// Returns `None` unless it's exactly `AEAD_SIZE` bytes.
+    pub fn from_bytes<B>(bytes: B) -> Option<Self>
+    where
+        B: Into<Vec<u8>>,
+    {
+        let bytes = bytes.into();
+
+        if bytes.len() == AEAD_SIZE {
+            Some(Aead(bytes))
+        } else {
+            None
+        }
+    }
+
+    /// Borrow this AEAD as a byte slice
+    pub fn as_slice(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl AsRef<[u8]> for Aead {
+    fn as_ref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl From<Aead> for Vec<u8> {
+    fn from(aead: Aead) -> Vec<u8> {
+        aead.0
+    }
+}
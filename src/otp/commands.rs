@@ -1,5 +1,15 @@
 //! Yubico OTP commands
 
+#[cfg(feature = "untested")]
+mod create;
+#[cfg(feature = "untested")]
+mod decrypt;
 mod put;
+#[cfg(feature = "untested")]
+mod randomize;
+#[cfg(feature = "untested")]
+mod rewrap;
 
 pub(crate) use self::put::*;
+#[cfg(feature = "untested")]
+pub(crate) use self::{create::*, decrypt::*, randomize::*, rewrap::*};
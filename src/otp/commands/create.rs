@@ -0,0 +1,44 @@
+//! Create a Yubico OTP AEAD from a given OTP key and private ID
+//!
+//! <https://developers.yubico.com/YubiHSM2/Commands/Create_Otp_Aead.html>
+
+use crate::{
+    command::{self, Command},
+    object,
+    otp::Aead,
+    response::Response,
+};
+use serde::{Deserialize, Serialize};
+
+/// Request parameters for `command::create_otp_aead`
+#[derive(Serialize, Deserialize, Debug)]
+pub(crate) struct CreateOtpAeadCommand {
+    /// ID of the OTP AEAD key to encrypt the AEAD under
+    pub key_id: object::Id,
+
+    /// YubiKey's OTP key
+    pub otp_key: [u8; 16],
+
+    /// YubiKey's OTP private ID
+    pub private_id: [u8; 6],
+}
+
+impl Command for CreateOtpAeadCommand {
+    type ResponseType = CreateOtpAeadResponse;
+
+    fn object_id(&self) -> Option<object::Id> {
+        Some(self.key_id)
+    }
+
+    fn object_type(&self) -> Option<object::Type> {
+        Some(object::Type::OtpAeadKey)
+    }
+}
+
+/// Response from `command::create_otp_aead`
+#[derive(Serialize, Deserialize, Debug)]
+pub(crate) struct CreateOtpAeadResponse(pub(crate) Aead);
+
+impl Response for CreateOtpAeadResponse {
+    const COMMAND_CODE: command::Code = command::Code::CreateOtpAead;
+}
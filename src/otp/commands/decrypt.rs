@@ -0,0 +1,87 @@
+//! Decrypt a Yubico OTP, obtaining its counters and timestamp
+//!
+//! <https://developers.yubico.com/YubiHSM2/Commands/Decrypt_Otp.html>
+
+use crate::{
+    command::{self, Command},
+    object,
+    otp::DecryptedOtp,
+    response::Response,
+};
+use serde::{Deserialize, Serialize};
+
+/// Request parameters for `command::decrypt_otp`
+#[derive(Serialize, Deserialize, Debug)]
+pub(crate) struct DecryptOtpCommand {
+    /// ID of the OTP AEAD key the AEAD was created with
+    pub key_id: object::Id,
+
+    /// AEAD containing the YubiKey's OTP key and private ID
+    pub aead: Vec<u8>,
+
+    /// OTP to decrypt
+    pub otp: Vec<u8>,
+}
+
+impl Command for DecryptOtpCommand {
+    type ResponseType = DecryptOtpResponse;
+
+    fn object_id(&self) -> Option<object::Id> {
+        Some(self.key_id)
+    }
+
+    fn object_type(&self) -> Option<object::Type> {
+        Some(object::Type::OtpAeadKey)
+    }
+}
+
+/// Response from `command::decrypt_otp`: the use counter (2 bytes), session
+/// counter (1 byte), and then the high (1 byte) and low (2 bytes) parts of
+/// the timestamp.
+///
+/// Multi-byte fields are little endian, as in the OTP itself.
+#[derive(Serialize, Deserialize, Debug)]
+pub(crate) struct DecryptOtpResponse(pub(crate) [u8; 6]);
+
+impl Response for DecryptOtpResponse {
+    const COMMAND_CODE: command::Code = command::Code::DecryptOtp;
+}
+
+impl From<DecryptOtpResponse> for DecryptedOtp {
+    fn from(response: DecryptOtpResponse) -> DecryptedOtp {
+        let bytes = response.0;
+
+        DecryptedOtp {
+            use_counter: u16::from_le_bytes([bytes[0], bytes[1]]),
+            session_counter: bytes[2],
+            timestamp: u32::from(bytes[3]) << 16
+                | u32::from(u16::from_le_bytes([bytes[4], bytes[5]])),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serialization::deserialize;
+
+    #[test]
+    fn response_is_parsed_into_counters_and_timestamp() {
+        let response: DecryptOtpResponse =
+            deserialize(&[0x34, 0x12, 0x07, 0xab, 0xef, 0xcd]).unwrap();
+
+        assert_eq!(
+            DecryptedOtp::from(response),
+            DecryptedOtp {
+                use_counter: 0x1234,
+                session_counter: 0x07,
+                timestamp: 0xab_cdef,
+            }
+        );
+    }
+
+    #[test]
+    fn truncated_response_is_rejected() {
+        assert!(deserialize::<DecryptOtpResponse>(&[0x34, 0x12, 0x07]).is_err());
+    }
+}
@@ -0,0 +1,38 @@
+//! Create a Yubico OTP AEAD from a randomly generated OTP key and private ID
+//!
+//! <https://developers.yubico.com/YubiHSM2/Commands/Randomize_Otp_Aead.html>
+
+use crate::{
+    command::{self, Command},
+    object,
+    otp::Aead,
+    response::Response,
+};
+use serde::{Deserialize, Serialize};
+
+/// Request parameters for `command::randomize_otp_aead`
+#[derive(Serialize, Deserialize, Debug)]
+pub(crate) struct RandomizeOtpAeadCommand {
+    /// ID of the OTP AEAD key to encrypt the AEAD under
+    pub key_id: object::Id,
+}
+
+impl Command for RandomizeOtpAeadCommand {
+    type ResponseType = RandomizeOtpAeadResponse;
+
+    fn object_id(&self) -> Option<object::Id> {
+        Some(self.key_id)
+    }
+
+    fn object_type(&self) -> Option<object::Type> {
+        Some(object::Type::OtpAeadKey)
+    }
+}
+
+/// Response from `command::randomize_otp_aead`
+#[derive(Serialize, Deserialize, Debug)]
+pub(crate) struct RandomizeOtpAeadResponse(pub(crate) Aead);
+
+impl Response for RandomizeOtpAeadResponse {
+    const COMMAND_CODE: command::Code = command::Code::RandomizeOtpAead;
+}
@@ -0,0 +1,63 @@
+//! Re-encrypt a Yubico OTP AEAD from one OTP AEAD key to another
+//!
+//! <https://developers.yubico.com/YubiHSM2/Commands/Rewrap_Otp_Aead.html>
+
+use crate::{
+    command::{self, Command},
+    object,
+    otp::Aead,
+    response::Response,
+};
+use serde::{Deserialize, Serialize};
+
+/// Request parameters for `command::rewrap_otp_aead`
+#[derive(Serialize, Deserialize, Debug)]
+pub(crate) struct RewrapOtpAeadCommand {
+    /// ID of the OTP AEAD key the AEAD is currently encrypted under
+    pub from_key_id: object::Id,
+
+    /// ID of the OTP AEAD key to re-encrypt the AEAD under
+    pub to_key_id: object::Id,
+
+    /// AEAD to re-encrypt
+    pub aead: Aead,
+}
+
+impl Command for RewrapOtpAeadCommand {
+    type ResponseType = RewrapOtpAeadResponse;
+
+    fn object_id(&self) -> Option<object::Id> {
+        Some(self.from_key_id)
+    }
+
+    fn object_type(&self) -> Option<object::Type> {
+        Some(object::Type::OtpAeadKey)
+    }
+}
+
+/// Response from `command::rewrap_otp_aead`
+#[derive(Serialize, Deserialize, Debug)]
+pub(crate) struct RewrapOtpAeadResponse(pub(crate) Aead);
+
+impl Response for RewrapOtpAeadResponse {
+    const COMMAND_CODE: command::Code = command::Code::RewrapOtpAead;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{otp::AEAD_SIZE, serialization::serialize};
+
+    #[test]
+    fn command_is_serialized_as_key_ids_then_aead() {
+        let command = RewrapOtpAeadCommand {
+            from_key_id: 0x0102,
+            to_key_id: 0x0304,
+            aead: Aead::from_bytes(vec![0xaa; AEAD_SIZE]).unwrap(),
+        };
+
+        let bytes = serialize(&command).unwrap();
+        assert_eq!(&bytes[..4], &[0x01, 0x02, 0x03, 0x04]);
+        assert_eq!(&bytes[4..], &[0xaa; AEAD_SIZE][..]);
+    }
+}
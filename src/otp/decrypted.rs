@@ -0,0 +1,24 @@
+//! Decrypted Yubico OTPs
+
+/// Size of a (binary, i.e. modhex-decoded) Yubico OTP
+pub const OTP_SIZE: usize = 16;
+
+/// Counters and timestamp from a Yubico OTP decrypted by the HSM.
+///
+/// The HSM checks the OTP's private ID and CRC, so these are only returned
+/// for valid OTPs. Checking the counters haven't gone backwards (i.e. the
+/// OTP isn't being replayed) is up to the caller.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct DecryptedOtp {
+    /// Non-volatile counter, incremented the first time the YubiKey is
+    /// used after power-up (and when `session_counter` wraps)
+    pub use_counter: u16,
+
+    /// Volatile counter, incremented for each OTP generated since
+    /// power-up
+    pub session_counter: u8,
+
+    /// 24-bit timer value, counting at approximately 8Hz from a random
+    /// value at power-up
+    pub timestamp: u32,
+}
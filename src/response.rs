@@ -19,6 +19,13 @@ pub(crate) trait Response: Serialize + DeserializeOwned + Sized {
     /// Command ID this response is for
     const COMMAND_CODE: command::Code;
 
+    /// Maximum size of the serialized response data, if known in advance.
+    ///
+    /// Connectors use this to pre-size the buffer they read the (encrypted)
+    /// response into. For example, when reading an Ed25519 signature over
+    /// USB the buffer shrinks from `MAX_MSG_SIZE` (2048 bytes) to 128 bytes.
+    const MAX_SIZE: usize = command::MAX_MSG_SIZE;
+
     /// Serialize a response type into a response::Message
     #[cfg(feature = "mockhsm")]
     fn serialize(&self) -> Message {
@@ -220,3 +220,16 @@ fn has_rmac(code: response::Code) -> bool {
         _ => false,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reject_out_of_range_session_id() {
+        // Successful `CreateSession` response carrying session ID 16
+        let bytes = vec![0x83, 0x00, 0x01, 0x10];
+        let err = Message::parse(connector::Message(bytes)).unwrap_err();
+        assert_eq!(*err.kind(), ProtocolError);
+    }
+}
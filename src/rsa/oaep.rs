@@ -1,5 +1,7 @@
 //! RSA encryption with Optimal Asymmetric Encryption Padding (OAEP)
 
 mod algorithm;
+#[cfg(feature = "untested")]
+pub(crate) mod commands;
 
 pub use self::algorithm::Algorithm;
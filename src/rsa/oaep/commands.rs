@@ -0,0 +1,53 @@
+//! RSA OAEP decryption commands
+//!
+//! <https://developers.yubico.com/YubiHSM2/Commands/Decrypt_Oaep.html>
+
+use crate::{
+    command::{self, Command},
+    object,
+    response::Response,
+    rsa,
+};
+use serde::{Deserialize, Serialize};
+
+/// Request parameters for `command::decrypt_oaep`
+#[derive(Serialize, Deserialize, Debug)]
+pub(crate) struct DecryptOaepCommand {
+    /// ID of the key to decrypt with
+    pub key_id: object::Id,
+
+    /// Hash algorithm to use for MGF1
+    pub mgf1_hash_alg: rsa::mgf::Algorithm,
+
+    /// Ciphertext to decrypt (the size of the key's modulus)
+    pub ciphertext: Vec<u8>,
+
+    /// Digest of the OAEP label
+    pub label_hash: Vec<u8>,
+}
+
+impl Command for DecryptOaepCommand {
+    type ResponseType = DecryptOaepResponse;
+
+    fn object_id(&self) -> Option<object::Id> {
+        Some(self.key_id)
+    }
+
+    fn object_type(&self) -> Option<object::Type> {
+        Some(object::Type::AsymmetricKey)
+    }
+}
+
+/// Decrypted plaintext
+#[derive(Serialize, Deserialize, Debug)]
+pub(crate) struct DecryptOaepResponse(Vec<u8>);
+
+impl Response for DecryptOaepResponse {
+    const COMMAND_CODE: command::Code = command::Code::DecryptOaep;
+}
+
+impl From<DecryptOaepResponse> for Vec<u8> {
+    fn from(response: DecryptOaepResponse) -> Vec<u8> {
+        response.0
+    }
+}
@@ -1,4 +1,4 @@
-//! RSASSA-PKCS#1v1.5 commands
+//! RSA PKCS#1v1.5 signing and decryption commands
 
 use crate::{
     command::{self, Command},
@@ -8,6 +8,42 @@ use crate::{
 };
 use serde::{Deserialize, Serialize};
 
+/// Request parameters for `command::decrypt_pkcs1v15`
+#[derive(Serialize, Deserialize, Debug)]
+pub(crate) struct DecryptPkcs1Command {
+    /// ID of the key to decrypt with
+    pub key_id: object::Id,
+
+    /// Ciphertext to decrypt (the size of the key's modulus)
+    pub ciphertext: Vec<u8>,
+}
+
+impl Command for DecryptPkcs1Command {
+    type ResponseType = DecryptPkcs1Response;
+
+    fn object_id(&self) -> Option<object::Id> {
+        Some(self.key_id)
+    }
+
+    fn object_type(&self) -> Option<object::Type> {
+        Some(object::Type::AsymmetricKey)
+    }
+}
+
+/// Decrypted (and unpadded) plaintext
+#[derive(Serialize, Deserialize, Debug)]
+pub(crate) struct DecryptPkcs1Response(Vec<u8>);
+
+impl Response for DecryptPkcs1Response {
+    const COMMAND_CODE: command::Code = command::Code::DecryptPkcs1;
+}
+
+impl From<DecryptPkcs1Response> for Vec<u8> {
+    fn from(response: DecryptPkcs1Response) -> Vec<u8> {
+        response.0
+    }
+}
+
 /// Request parameters for `command::sign_rsa_pkcs1v15*`
 #[derive(Serialize, Deserialize, Debug)]
 pub(crate) struct SignPkcs1Command {
@@ -20,11 +56,19 @@ pub(crate) struct SignPkcs1Command {
 
 impl Command for SignPkcs1Command {
     type ResponseType = SignPkcs1Response;
+
+    fn object_id(&self) -> Option<object::Id> {
+        Some(self.key_id)
+    }
+
+    fn object_type(&self) -> Option<object::Type> {
+        Some(object::Type::AsymmetricKey)
+    }
 }
 
 /// RSASSA-PKCS#1v1.5 signatures (ASN.1 DER encoded)
 #[derive(Serialize, Deserialize, Debug)]
-pub struct SignPkcs1Response(rsa::pkcs1::Signature);
+pub struct SignPkcs1Response(pub rsa::pkcs1::Signature);
 
 impl Response for SignPkcs1Response {
     const COMMAND_CODE: command::Code = command::Code::SignPkcs1;
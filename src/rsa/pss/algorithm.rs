@@ -40,6 +40,16 @@ impl Algorithm {
     pub fn to_u8(self) -> u8 {
         self as u8
     }
+
+    /// Length of a digest produced by this algorithm's hash function
+    pub fn digest_len(self) -> usize {
+        match self {
+            Algorithm::Sha1 => 20,
+            Algorithm::Sha256 => 32,
+            Algorithm::Sha384 => 48,
+            Algorithm::Sha512 => 64,
+        }
+    }
 }
 
 impl_algorithm_serializers!(Algorithm);
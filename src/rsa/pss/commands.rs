@@ -26,11 +26,19 @@ pub(crate) struct SignPssCommand {
 
 impl Command for SignPssCommand {
     type ResponseType = SignPssResponse;
+
+    fn object_id(&self) -> Option<object::Id> {
+        Some(self.key_id)
+    }
+
+    fn object_type(&self) -> Option<object::Type> {
+        Some(object::Type::AsymmetricKey)
+    }
 }
 
 /// RSASSA-PSS signatures (ASN.1 DER encoded)
 #[derive(Serialize, Deserialize, Debug)]
-pub struct SignPssResponse(rsa::pss::Signature);
+pub struct SignPssResponse(pub rsa::pss::Signature);
 
 impl Response for SignPssResponse {
     const COMMAND_CODE: command::Code = command::Code::SignPss;
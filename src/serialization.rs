@@ -10,10 +10,16 @@ use std::io::Cursor;
 /// Serialize a message into a byte vector
 pub fn serialize<T: serde::Serialize>(value: &T) -> Result<Vec<u8>, Error> {
     let mut result = vec![];
-    serde::Serialize::serialize(value, &mut ser::Serializer::new(&mut result))?;
+    serialize_into(&mut result, value)?;
     Ok(result)
 }
 
+/// Serialize a message, appending it to the given buffer
+pub fn serialize_into<T: serde::Serialize>(buffer: &mut Vec<u8>, value: &T) -> Result<(), Error> {
+    serde::Serialize::serialize(value, &mut ser::Serializer::new(buffer))?;
+    Ok(())
+}
+
 /// Deserialize a byte slice into an instance of `T`
 pub fn deserialize<T: serde::de::DeserializeOwned>(bytes: &[u8]) -> Result<T, Error> {
     let mut deserializer = de::Deserializer::new(Cursor::new(bytes));
@@ -40,8 +46,8 @@ macro_rules! impl_array_serializers {
 
                     fn expecting(
                         &self,
-                        formatter: &mut ::std::fmt::Formatter<'_>,
-                    ) -> ::std::fmt::Result {
+                        formatter: &mut ::core::fmt::Formatter<'_>,
+                    ) -> ::core::fmt::Result {
                         write!(formatter, "{}-byte string of arbitrary bytes", $size)
                     }
 
@@ -8,39 +8,48 @@ mod macros;
 
 pub(crate) mod commands;
 mod error;
+mod establishment;
 mod guard;
 mod id;
+#[cfg(feature = "unsafe-session-export")]
+mod keys;
+mod log_policy;
 pub(crate) mod securechannel;
 mod timeout;
+mod transcript;
 
 pub use self::{
     error::{Error, ErrorKind},
+    establishment::Establishment,
     guard::Guard,
     id::Id,
-    timeout::Timeout,
+    log_policy::{LogAction, LogPolicy},
+    securechannel::MAX_COMMANDS_PER_SESSION,
+    timeout::{Timeout, DEFAULT_TIMEOUT_SKEW, SESSION_INACTIVITY_TIMEOUT},
+    transcript::TranscriptEntry,
 };
 
-use self::{commands::CloseSessionCommand, securechannel::SecureChannel};
+#[cfg(feature = "unsafe-session-export")]
+pub use self::keys::SessionKeys;
+
+use self::{commands::CloseSessionCommand, securechannel::SecureChannel, transcript::Transcript};
 use crate::{
     authentication::Credentials,
     command::{self, Command},
     connector::Connector,
-    device, response,
+    device::{self, commands::DeviceInfoCommand},
+    object,
+    response::{self, Response},
     serialization::deserialize,
 };
 use anomaly::{ensure, fail, format_err};
+use rand_core::RngCore;
 use std::{
+    mem,
     panic::{self, AssertUnwindSafe},
     time::{Duration, Instant},
 };
 
-/// Timeout fuzz factor: to avoid races/skew with the YubiHSM's clock,
-/// we consider sessions to be timed out slightly earlier than the actual
-/// timeout. This should (hopefully) ensure we always time out first,
-/// and therefore generate appropriate timeout-related errors rather
-/// than opaque "lost connection to HSM"-style errors.
-const TIMEOUT_FUZZ_FACTOR: Duration = Duration::from_secs(1);
-
 /// Authenticated and encrypted (SCP03) `Session` with the HSM. A `Session` is
 /// needed to perform any command.
 ///
@@ -64,6 +73,24 @@ pub struct Session {
 
     /// Inactivity timeout for this session
     timeout: Timeout,
+
+    /// Policy for logging commands sent over this session
+    log_policy: LogPolicy,
+
+    /// Record of commands sent over this session (if enabled)
+    transcript: Transcript,
+
+    /// ID of the authentication key this session was opened with
+    authentication_key_id: object::Id,
+
+    /// Cached information about the session's authentication key
+    authentication_key_info: Option<object::Info>,
+
+    /// Buffer reused for serializing and encrypting commands
+    command_buffer: Vec<u8>,
+
+    /// Time by which messages must have been answered (if any)
+    deadline: Option<Instant>,
 }
 
 impl Session {
@@ -72,15 +99,18 @@ impl Session {
         connector: Connector,
         credentials: &Credentials,
         timeout: Timeout,
+        log_policy: LogPolicy,
+        rng: &mut dyn RngCore,
+        deadline: Option<Instant>,
     ) -> Result<Self, Error> {
         ensure!(
-            timeout.duration() > TIMEOUT_FUZZ_FACTOR,
+            timeout.duration() > timeout.skew(),
             ErrorKind::CreateFailed,
-            "timeout too low: must be longer than {:?}",
-            TIMEOUT_FUZZ_FACTOR
+            "timeout too low: must be longer than its skew ({:?})",
+            timeout.skew()
         );
 
-        let channel = SecureChannel::open(&connector, credentials)?;
+        let channel = SecureChannel::open(&connector, credentials, rng, deadline)?;
         let now = Instant::now();
 
         let mut session = Session {
@@ -90,9 +120,16 @@ impl Session {
             created_at: now,
             last_active: now,
             timeout,
+            log_policy,
+            transcript: Transcript::default(),
+            authentication_key_id: credentials.authentication_key_id,
+            authentication_key_info: None,
+            command_buffer: Vec::with_capacity(command::MAX_MSG_SIZE),
+            deadline,
         };
 
         session.authenticate(credentials)?;
+        session.verify_serial_number()?;
 
         Ok(session)
     }
@@ -102,7 +139,7 @@ impl Session {
         self.secure_channel.is_some() && !self.is_timed_out()
     }
 
-    /// Session ID value (1-16)
+    /// Session ID value (0-15)
     pub fn id(&self) -> Id {
         self.id
     }
@@ -120,11 +157,65 @@ impl Session {
             .map(SecureChannel::counter)
     }
 
+    /// Export the keys derived for this session, e.g. to decrypt a capture
+    /// of its traffic while debugging a MAC desync.
+    ///
+    /// # Security Warning
+    ///
+    /// **Never use this outside of isolated test or forensic environments.**
+    /// Anyone holding these keys can decrypt and forge this session's
+    /// traffic. See `SessionKeys` for details.
+    #[cfg(feature = "unsafe-session-export")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "unsafe-session-export")))]
+    pub fn export_keys(&self) -> Result<SessionKeys, Error> {
+        self.secure_channel
+            .as_ref()
+            .ok_or_else(|| format_err!(ErrorKind::ClosedError, "session is already closed").into())
+            .map(SecureChannel::export_keys)
+    }
+
     /// Has this session timed out?
     pub fn is_timed_out(&self) -> bool {
         let idle_time = Instant::now().duration_since(self.last_active);
-        let timeout_with_fuzz = self.timeout.duration() - TIMEOUT_FUZZ_FACTOR;
-        idle_time >= timeout_with_fuzz
+        idle_time >= self.timeout.effective_duration()
+    }
+
+    /// Set the time by which messages sent over this session must have been
+    /// answered, after which they fail with `ErrorKind::TimeoutError`
+    pub(crate) fn set_deadline(&mut self, deadline: Option<Instant>) {
+        self.deadline = deadline;
+    }
+
+    /// Set the policy for logging commands sent over this session
+    pub(crate) fn set_log_policy(&mut self, log_policy: LogPolicy) {
+        self.log_policy = log_policy;
+    }
+
+    /// Record up to `capacity` commands sent over this session in its
+    /// transcript, discarding any previously recorded entries. A capacity of
+    /// zero disables recording.
+    pub(crate) fn set_transcript_capacity(&mut self, capacity: usize) {
+        self.transcript = Transcript::new(capacity);
+    }
+
+    /// Get the commands recorded in this session's transcript, oldest first
+    pub fn transcript(&self) -> Vec<TranscriptEntry> {
+        self.transcript.entries()
+    }
+
+    /// ID of the authentication key used to open this session
+    pub fn authentication_key_id(&self) -> object::Id {
+        self.authentication_key_id
+    }
+
+    /// Get cached information about this session's authentication key
+    pub(crate) fn authentication_key_info(&self) -> Option<&object::Info> {
+        self.authentication_key_info.as_ref()
+    }
+
+    /// Cache information about this session's authentication key
+    pub(crate) fn set_authentication_key_info(&mut self, info: Option<object::Info>) {
+        self.authentication_key_info = info;
     }
 
     /// Abort this session, terminating it without closing it
@@ -132,33 +223,51 @@ impl Session {
         self.secure_channel = None;
     }
 
-    /// Encrypt a command, send it to the HSM, then read and decrypt the response
+    /// Encrypt a command, send it to the HSM, then read and decrypt the
+    /// response, recording the outcome in the session transcript
     pub(crate) fn send_command<C: Command>(
         &mut self,
         command: &C,
     ) -> Result<C::ResponseType, Error> {
-        let plaintext_cmd = command::Message::from(command);
-        let cmd_type = plaintext_cmd.command_type;
+        let result = self.send_encrypted_command(command);
+        self.transcript
+            .record(C::COMMAND_CODE, C::EFFECT, command.object_id(), &result);
+        result
+    }
 
-        let encrypted_cmd = self
-            .secure_channel()?
-            .encrypt_command(plaintext_cmd)
+    /// Encrypt a command, send it to the HSM, then read and decrypt the response
+    fn send_encrypted_command<C: Command>(
+        &mut self,
+        command: &C,
+    ) -> Result<C::ResponseType, Error> {
+        let cmd_type = C::COMMAND_CODE;
+
+        // Serialize and encrypt the command in the session's reusable buffer
+        let mut buffer = mem::take(&mut self.command_buffer);
+
+        let encrypted_cmd = command::serialize_into(command, &mut buffer)
+            .and_then(|()| self.secure_channel()?.encrypt_command_buffer(&mut buffer))
             .map_err(|e| {
                 // Abort the session in the event of any cryptographic errors
                 self.abort();
                 e
-            })?;
+            });
+
+        self.command_buffer = buffer;
+        let encrypted_cmd = encrypted_cmd?;
 
         let uuid = encrypted_cmd.uuid;
-        session_debug!(
-            self,
-            "n={} uuid={} cmd={:?}",
-            self.messages_sent()?,
-            uuid,
-            C::COMMAND_CODE
-        );
+        let messages_sent = self.messages_sent()?;
+
+        if let Some(line) = self
+            .log_policy
+            .command_line(C::COMMAND_CODE, messages_sent, &uuid)
+        {
+            session_debug!(self, "{}", line);
+        }
 
-        let encrypted_response = self.send_message(encrypted_cmd)?;
+        let max_response_size = securechannel::encrypted_response_size(C::ResponseType::MAX_SIZE);
+        let encrypted_response = self.send_message(encrypted_cmd, max_response_size)?;
 
         let response = self
             .secure_channel()?
@@ -170,11 +279,17 @@ impl Session {
             })?;
 
         if response.is_err() {
-            if let Some(kind) = device::ErrorKind::from_response_message(&response) {
-                session_debug!(self, "uuid={} failed={:?} error={:?}", uuid, cmd_type, kind);
+            let kind = device::ErrorKind::from_response_message(&response);
+            let error = kind.map(|k| format!("{:?}", k));
+            let error = error.as_ref().map(AsRef::as_ref).unwrap_or("unknown");
+
+            if let Some(line) = self.log_policy.failure_line(cmd_type, &uuid, error) {
+                session_debug!(self, "{}", line);
+            }
+
+            if let Some(kind) = kind {
                 return Err(kind.into());
             } else {
-                session_debug!(self, "uuid={} failed={:?} error=unknown", uuid, cmd_type);
                 fail!(ErrorKind::ResponseError, "{:?} failed: HSM error", cmd_type);
             }
         }
@@ -192,13 +307,19 @@ impl Session {
     }
 
     /// Send a command message to the HSM and parse the response
-    fn send_message(&mut self, cmd: command::Message) -> Result<response::Message, Error> {
+    fn send_message(
+        &mut self,
+        cmd: command::Message,
+        max_response_size: usize,
+    ) -> Result<response::Message, Error> {
         let cmd_type = cmd.command_type;
         let uuid = cmd.uuid;
         self.last_active = Instant::now();
 
         // We log the plaintext of all `SessionMessage` commands, so ignore those
-        if cmd_type != command::Code::SessionMessage {
+        if cmd_type != command::Code::SessionMessage
+            && self.log_policy.action_for(cmd_type) == LogAction::Log
+        {
             session_debug!(
                 self,
                 "n={} uuid={} msg={:?}",
@@ -208,7 +329,12 @@ impl Session {
             );
         }
 
-        let response = match self.connector.send_message(uuid, cmd.into()) {
+        let response = match self.connector.send_message_with_size_hint(
+            uuid,
+            cmd.into(),
+            max_response_size,
+            self.deadline,
+        ) {
             Ok(response_bytes) => response::Message::parse(response_bytes)?,
             Err(e) => {
                 // Abort the session in the event of errors
@@ -219,6 +345,20 @@ impl Session {
 
         if response.is_err() {
             session_error!(self, "uuid={} error={:?}", &uuid, response.code);
+
+            // The HSM no longer recognizes this session, e.g. because it was
+            // closed after being inactive for longer than the HSM's timeout
+            if device::ErrorKind::from_response_message(&response)
+                == Some(device::ErrorKind::InvalidSession)
+            {
+                self.abort();
+                fail!(
+                    ErrorKind::ClosedError,
+                    "session {} closed by HSM",
+                    self.id().to_u8()
+                );
+            }
+
             fail!(
                 ErrorKind::ResponseError,
                 "HSM error (session: {})",
@@ -233,13 +373,13 @@ impl Session {
     fn authenticate(&mut self, credentials: &Credentials) -> Result<(), Error> {
         session_debug!(
             self,
-            "command={:?} key={}",
+            "command={:?} key=0x{:04x}",
             command::Code::AuthenticateSession,
             credentials.authentication_key_id
         );
 
         let command = self.secure_channel()?.authenticate_session()?;
-        let response = self.send_message(command)?;
+        let response = self.send_message(command, command::MAX_MSG_SIZE)?;
 
         if let Err(e) = self
             .secure_channel()?
@@ -247,7 +387,7 @@ impl Session {
         {
             session_error!(
                 self,
-                "failed={:?} key={} err={:?}",
+                "failed={:?} key=0x{:04x} err={:?}",
                 command::Code::AuthenticateSession,
                 credentials.authentication_key_id,
                 e.to_string()
@@ -256,7 +396,32 @@ impl Session {
             return Err(e);
         }
 
-        session_debug!(self, "auth=OK key={}", credentials.authentication_key_id);
+        session_debug!(
+            self,
+            "auth=OK key=0x{:04x}",
+            credentials.authentication_key_id
+        );
+        Ok(())
+    }
+
+    /// Ensure the HSM has the serial number the connector is configured with
+    /// (if any), so a session isn't accidentally opened to the wrong device
+    fn verify_serial_number(&mut self) -> Result<(), Error> {
+        let expected = match self.connector.serial_number() {
+            Some(serial_number) => serial_number,
+            None => return Ok(()),
+        };
+
+        let info: device::Info = self.send_command(&DeviceInfoCommand {})?.into();
+
+        ensure!(
+            info.serial_number == expected,
+            ErrorKind::CreateFailed,
+            "connected to YubiHSM 2 with serial #{} (expected #{})",
+            info.serial_number,
+            expected
+        );
+
         Ok(())
     }
 
@@ -279,6 +444,10 @@ impl Drop for Session {
 
         session_debug!(self, "closing dropped session");
 
+        // The deadline for the last command has likely passed by now, and
+        // shouldn't prevent the session from being closed
+        self.deadline = None;
+
         // TODO: ensure we're really unwind safe.
         // This should still be better than panicking in a drop handler, hopefully
         let result = panic::catch_unwind(AssertUnwindSafe(|| {
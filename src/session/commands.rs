@@ -2,12 +2,18 @@
 //!
 //! <https://developers.yubico.com/YubiHSM2/Commands/Create_Session.html>
 
-use super::securechannel::{Challenge, Cryptogram};
+use super::{
+    securechannel::{Challenge, Cryptogram, CHALLENGE_SIZE, CRYPTOGRAM_SIZE},
+    Error,
+    ErrorKind::{ProtocolError, UnsupportedProtocol},
+};
 use crate::{
     command::{self, Command},
     object,
     response::Response,
+    serialization::deserialize,
 };
+use anomaly::fail;
 use serde::{Deserialize, Serialize};
 
 /// Request parameters for `command::create_session`
@@ -38,11 +44,43 @@ impl Response for CreateSessionResponse {
     const COMMAND_CODE: command::Code = command::Code::CreateSession;
 }
 
+impl CreateSessionResponse {
+    /// Parse a `CreateSession` response, ensuring the card challenge and
+    /// cryptogram are exactly the expected size
+    pub(crate) fn parse(bytes: &[u8]) -> Result<Self, Error> {
+        // Only the response length is checked: one longer than SCP03's
+        // suggests the HSM expects another authentication protocol, so report
+        // that here rather than failing later with a cryptogram mismatch.
+        if bytes.len() > CHALLENGE_SIZE + CRYPTOGRAM_SIZE {
+            fail!(
+                UnsupportedProtocol,
+                "HSM sent a {}-byte CreateSession response, longer than SCP03's {} bytes \
+                 (SCP03 is the only authentication protocol this crate implements)",
+                bytes.len(),
+                CHALLENGE_SIZE + CRYPTOGRAM_SIZE
+            );
+        }
+
+        if bytes.len() != CHALLENGE_SIZE + CRYPTOGRAM_SIZE {
+            fail!(
+                ProtocolError,
+                "malformed CreateSession response: expected {}-byte card challenge and \
+                 {}-byte card cryptogram (got {} bytes total)",
+                CHALLENGE_SIZE,
+                CRYPTOGRAM_SIZE,
+                bytes.len()
+            );
+        }
+
+        Ok(deserialize(bytes)?)
+    }
+}
+
 /// Close the current session and release its resources for reuse
 ///
 /// <https://developers.yubico.com/YubiHSM2/Commands/Close_Session.html>
 #[derive(Serialize, Deserialize, Debug)]
-pub(super) struct CloseSessionCommand {}
+pub(crate) struct CloseSessionCommand {}
 
 impl Command for CloseSessionCommand {
     type ResponseType = CloseSessionResponse;
@@ -53,4 +91,26 @@ pub(crate) struct CloseSessionResponse {}
 
 impl Response for CloseSessionResponse {
     const COMMAND_CODE: command::Code = command::Code::CloseSession;
+    const MAX_SIZE: usize = 0;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reject_short_card_challenge() {
+        // 7-byte card challenge followed by an 8-byte cryptogram
+        let bytes = [0u8; CHALLENGE_SIZE - 1 + CRYPTOGRAM_SIZE];
+        let err = CreateSessionResponse::parse(&bytes).unwrap_err();
+        assert_eq!(*err.kind(), ProtocolError);
+    }
+
+    #[test]
+    fn detect_unsupported_protocol() {
+        // Longer than an SCP03 card challenge and cryptogram
+        let bytes = [0u8; 65 + 16];
+        let err = CreateSessionResponse::parse(&bytes).unwrap_err();
+        assert_eq!(*err.kind(), UnsupportedProtocol);
+    }
 }
@@ -42,6 +42,15 @@ pub enum ErrorKind {
     #[error("HSM response error")]
     ResponseError,
 
+    /// Deadline elapsed before the HSM responded
+    #[error("timed out")]
+    TimeoutError,
+
+    /// HSM's `CreateSession` response is longer than SCP03's, suggesting it
+    /// expects an authentication protocol this crate doesn't implement
+    #[error("unsupported authentication protocol")]
+    UnsupportedProtocol,
+
     /// MAC or cryptogram verify failed
     #[error("cryptographic verification failed")]
     VerifyFailed,
@@ -56,7 +65,12 @@ impl ErrorKind {
 
 impl From<connector::Error> for Error {
     fn from(err: connector::Error) -> Self {
-        ErrorKind::ProtocolError.context(err).into()
+        let kind = match err.kind() {
+            connector::ErrorKind::Timeout => ErrorKind::TimeoutError,
+            _ => ErrorKind::ProtocolError,
+        };
+
+        kind.context(err).into()
     }
 }
 
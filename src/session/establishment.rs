@@ -0,0 +1,42 @@
+//! Host-side records of attempts to establish sessions, for compliance
+//! logging.
+//!
+//! These capture every authentication attempt made by a `Client`, including
+//! failures the HSM may not record in its own audit log. They never include
+//! key material.
+
+use super::{Error, ErrorKind, Id, Session};
+use crate::object;
+use std::time::SystemTime;
+
+/// Record of an attempt to establish an authenticated session
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Establishment {
+    /// ID of the authentication key used
+    pub authentication_key_id: object::Id,
+
+    /// When the attempt completed
+    pub timestamp: SystemTime,
+
+    /// ID of the established session (if successful)
+    pub session_id: Option<Id>,
+
+    /// Did the session authenticate successfully?
+    pub succeeded: bool,
+
+    /// Reason the attempt failed (if it did)
+    pub error: Option<ErrorKind>,
+}
+
+impl Establishment {
+    /// Record the result of opening a session
+    pub(crate) fn new(authentication_key_id: object::Id, result: &Result<Session, Error>) -> Self {
+        Self {
+            authentication_key_id,
+            timestamp: SystemTime::now(),
+            session_id: result.as_ref().ok().map(Session::id),
+            succeeded: result.is_ok(),
+            error: result.as_ref().err().map(|e| *e.kind()),
+        }
+    }
+}
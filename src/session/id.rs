@@ -1,18 +1,22 @@
-//! Session IDs: the YubiHSM2 supports up to 16 concurrent sessions.
+//! Session IDs: the YubiHSM2 supports up to 16 concurrent sessions, which
+//! are identified by the IDs 0-15.
 
 use super::{Error, ErrorKind::ProtocolError};
 use anomaly::fail;
 use std::fmt::{self, Display};
 
 /// Maximum session identifier
-pub const MAX_SESSION_ID: Id = Id(16);
+pub const MAX_SESSION_ID: Id = Id(15);
 
 /// Session/Channel IDs
 #[derive(Copy, Clone, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
 pub struct Id(u8);
 
 impl Id {
-    /// Create a new session ID from a byte value
+    /// Create a new session ID from a byte value.
+    ///
+    /// Returns an error if the ID is outside the range of session IDs
+    /// supported by the HSM (0-15).
     pub fn from_u8(id: u8) -> Result<Self, Error> {
         if id > MAX_SESSION_ID.0 {
             fail!(
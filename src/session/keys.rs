@@ -0,0 +1,50 @@
+//! Exporting session keys (for debugging and forensics only)
+
+use super::securechannel::KEY_SIZE;
+use super::Id;
+use std::fmt;
+use zeroize::Zeroize;
+
+/// Keys derived for an SCP03 session: anyone holding them can decrypt and
+/// forge all traffic in the session they were derived for.
+///
+/// # Security Warning
+///
+/// **Never use this outside of isolated test or forensic environments.**
+/// Exporting session keys defeats the confidentiality and integrity of the
+/// session, including any keys or other secrets sent over it. It's only
+/// available with the `unsafe-session-export` cargo feature, which must
+/// never be enabled in production builds.
+///
+/// The keys are zeroized when this value is dropped, and omitted from its
+/// `Debug` output.
+#[derive(Clone)]
+pub struct SessionKeys {
+    /// ID of the session these keys were derived for
+    pub session_id: Id,
+
+    /// Session encryption key (S-ENC)
+    pub enc_key: [u8; KEY_SIZE],
+
+    /// Session command MAC key (S-MAC)
+    pub mac_key: [u8; KEY_SIZE],
+
+    /// Session response MAC key (S-RMAC)
+    pub rmac_key: [u8; KEY_SIZE],
+}
+
+impl fmt::Debug for SessionKeys {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SessionKeys")
+            .field("session_id", &self.session_id)
+            .finish()
+    }
+}
+
+impl Drop for SessionKeys {
+    fn drop(&mut self) {
+        self.enc_key.zeroize();
+        self.mac_key.zeroize();
+        self.rmac_key.zeroize();
+    }
+}
@@ -0,0 +1,172 @@
+//! Logging policies: control what sessions log about the commands they send.
+//!
+//! By default sessions log the type and UUID of every command sent to the
+//! HSM at the `debug` level. Deployments with strict logging requirements
+//! can use a `LogPolicy` to redact or suppress these lines for particular
+//! commands (e.g. `PutAuthenticationKey`).
+
+use crate::{command, uuid::Uuid};
+use std::collections::BTreeMap;
+
+/// How to log a particular command
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum LogAction {
+    /// Log the command normally
+    Log,
+
+    /// Log that a command was sent, but omit its type and UUID
+    Redact,
+
+    /// Don't log anything about the command
+    Suppress,
+}
+
+/// Policy consulted by sessions before logging information about a command
+#[derive(Clone, Debug)]
+pub struct LogPolicy {
+    /// Action to take for commands without an explicit rule
+    default_action: LogAction,
+
+    /// Per-command log actions
+    rules: BTreeMap<command::Code, LogAction>,
+}
+
+impl Default for LogPolicy {
+    fn default() -> Self {
+        Self {
+            default_action: LogAction::Log,
+            rules: BTreeMap::new(),
+        }
+    }
+}
+
+impl LogPolicy {
+    /// Create a new policy which logs all commands
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the action to take for commands without an explicit rule
+    pub fn default_action(mut self, action: LogAction) -> Self {
+        self.default_action = action;
+        self
+    }
+
+    /// Set the action to take when logging the given command
+    pub fn action(mut self, command: command::Code, action: LogAction) -> Self {
+        self.rules.insert(command, action);
+        self
+    }
+
+    /// Redact log lines for the given command
+    pub fn redact(self, command: command::Code) -> Self {
+        self.action(command, LogAction::Redact)
+    }
+
+    /// Suppress log lines for the given command
+    pub fn suppress(self, command: command::Code) -> Self {
+        self.action(command, LogAction::Suppress)
+    }
+
+    /// Get the action to take when logging the given command
+    pub fn action_for(&self, command: command::Code) -> LogAction {
+        self.rules
+            .get(&command)
+            .cloned()
+            .unwrap_or(self.default_action)
+    }
+
+    /// Format the line logged when sending a command, if any
+    pub(crate) fn command_line(
+        &self,
+        command: command::Code,
+        messages_sent: usize,
+        uuid: &Uuid,
+    ) -> Option<String> {
+        match self.action_for(command) {
+            LogAction::Log => Some(format!(
+                "n={} uuid={} cmd={:?}",
+                messages_sent, uuid, command
+            )),
+            LogAction::Redact => Some(format!("n={} cmd=<redacted>", messages_sent)),
+            LogAction::Suppress => None,
+        }
+    }
+
+    /// Format the line logged when a command fails, if any
+    pub(crate) fn failure_line(
+        &self,
+        command: command::Code,
+        uuid: &Uuid,
+        error: &str,
+    ) -> Option<String> {
+        match self.action_for(command) {
+            LogAction::Log => Some(format!(
+                "uuid={} failed={:?} error={}",
+                uuid, command, error
+            )),
+            LogAction::Redact => Some(format!("failed=<redacted> error={}", error)),
+            LogAction::Suppress => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::uuid;
+
+    #[test]
+    fn default_policy_logs_command_details() {
+        let policy = LogPolicy::default();
+        let uuid = uuid::new_v4();
+
+        assert_eq!(
+            policy.command_line(command::Code::Echo, 3, &uuid),
+            Some(format!("n=3 uuid={} cmd=Echo", uuid))
+        );
+        assert_eq!(
+            policy.failure_line(command::Code::Echo, &uuid, "InvalidData"),
+            Some(format!("uuid={} failed=Echo error=InvalidData", uuid))
+        );
+    }
+
+    #[test]
+    fn redact_hides_command_details() {
+        let policy = LogPolicy::new().redact(command::Code::PutAuthenticationKey);
+        let uuid = uuid::new_v4();
+
+        let line = policy
+            .command_line(command::Code::PutAuthenticationKey, 3, &uuid)
+            .unwrap();
+        assert_eq!(line, "n=3 cmd=<redacted>");
+        assert!(!line.contains(&uuid.to_string()));
+
+        let line = policy
+            .failure_line(command::Code::PutAuthenticationKey, &uuid, "InvalidData")
+            .unwrap();
+        assert_eq!(line, "failed=<redacted> error=InvalidData");
+        assert!(!line.contains("PutAuthenticationKey"));
+
+        // Commands without a rule are still logged normally
+        assert!(policy
+            .command_line(command::Code::Echo, 3, &uuid)
+            .unwrap()
+            .contains("cmd=Echo"));
+    }
+
+    #[test]
+    fn suppress_emits_nothing() {
+        let policy = LogPolicy::new().suppress(command::Code::PutAuthenticationKey);
+        let uuid = uuid::new_v4();
+
+        assert_eq!(
+            policy.command_line(command::Code::PutAuthenticationKey, 3, &uuid),
+            None
+        );
+        assert_eq!(
+            policy.failure_line(command::Code::PutAuthenticationKey, &uuid, "InvalidData"),
+            None
+        );
+    }
+}
@@ -36,7 +36,6 @@ use crate::{
     command,
     connector::Connector,
     device, response,
-    serialization::deserialize,
     session::{self, ErrorKind},
 };
 use aes::{
@@ -47,6 +46,9 @@ use anomaly::{fail, format_err};
 use block_modes::{block_padding::Iso7816, BlockMode, Cbc};
 use cmac::crypto_mac::NewMac;
 use cmac::{crypto_mac::Mac as CryptoMac, Cmac};
+use core::cmp;
+use rand_core::RngCore;
+use std::time::Instant;
 use subtle::ConstantTimeEq;
 use zeroize::{Zeroize, Zeroizing};
 
@@ -66,6 +68,12 @@ pub const MAX_COMMANDS_PER_SESSION: u32 = 0x10_0000;
 /// Size of an AES block (128-bits)
 const AES_BLOCK_SIZE: usize = 16;
 
+/// Maximum amount of command or response data which fits in a
+/// `SessionMessage` without exceeding `MAX_MSG_SIZE` once encrypted
+/// (see `encrypted_response_size`)
+pub(crate) const MAX_SESSION_DATA_SIZE: usize =
+    (command::MAX_MSG_SIZE - 3 - 1 - MAC_SIZE) / AES_BLOCK_SIZE * AES_BLOCK_SIZE - 1 - 3;
+
 /// SCP03 uses AES-128 encryption in CBC mode with ISO 7816 padding
 type Aes128Cbc = Cbc<Aes128, Iso7816>;
 
@@ -99,12 +107,15 @@ pub(crate) struct SecureChannel {
 
 impl SecureChannel {
     /// Open a SecureChannel, performing challenge/response authentication and
-    /// establishing a session key
+    /// establishing a session key. The host challenge is generated using the
+    /// given RNG.
     pub(crate) fn open(
         connector: &Connector,
         credentials: &Credentials,
+        rng: &mut dyn RngCore,
+        deadline: Option<Instant>,
     ) -> Result<Self, session::Error> {
-        let host_challenge = Challenge::new();
+        let host_challenge = Challenge::random(rng);
 
         let command_message = command::Message::from(&CreateSessionCommand {
             authentication_key_id: credentials.authentication_key_id,
@@ -112,7 +123,12 @@ impl SecureChannel {
         });
 
         let uuid = command_message.uuid;
-        let response_body = connector.send_message(uuid, command_message.into())?;
+        let response_body = connector.send_message_with_size_hint(
+            uuid,
+            command_message.into(),
+            command::MAX_MSG_SIZE,
+            deadline,
+        )?;
         let response_message = response::Message::parse(response_body)?;
 
         if response_message.is_err() {
@@ -144,7 +160,7 @@ impl SecureChannel {
             .session_id
             .ok_or_else(|| format_err!(ErrorKind::CreateFailed, "no session ID in response"))?;
 
-        let session_response: CreateSessionResponse = deserialize(response_message.data.as_ref())?;
+        let session_response = CreateSessionResponse::parse(response_message.data.as_ref())?;
 
         // Derive session keys from the combination of host and card challenges.
         // If either of them are incorrect (indicating a key mismatch) it will
@@ -164,7 +180,7 @@ impl SecureChannel {
         {
             fail!(
                 ErrorKind::AuthenticationError,
-                "(session: {}) invalid credentials for authentication key #{} (cryptogram mismatch)",
+                "(session: {}) invalid credentials for authentication key 0x{:04x} (cryptogram mismatch)",
                 channel.id().to_u8(),
                 credentials.authentication_key_id,
             );
@@ -244,10 +260,15 @@ impl SecureChannel {
         let tag = mac.finalize().into_bytes();
         self.mac_chaining_value.copy_from_slice(tag.as_slice());
 
+        // Leave room for the header and MAC so serializing the message
+        // doesn't need to reallocate
+        let mut data = Vec::with_capacity(command::HEADER_SIZE + command_data.len() + MAC_SIZE);
+        data.extend_from_slice(command_data);
+
         Ok(command::Message::new_with_mac(
             command_type,
             self.id,
-            command_data,
+            data,
             &tag,
         )?)
     }
@@ -290,24 +311,36 @@ impl SecureChannel {
     }
 
     /// Encrypt a command to be sent to the card
+    #[cfg(all(test, feature = "mockhsm"))]
     pub fn encrypt_command(
         &mut self,
         command: command::Message,
     ) -> Result<command::Message, session::Error> {
         assert_eq!(self.security_level, SecurityLevel::Authenticated);
 
-        let mut message = command.serialize();
-        let pos = message.len();
+        self.encrypt_command_buffer(&mut command.serialize())
+    }
 
-        // Provide space at the end of the vec for the padding
-        message.extend_from_slice(&[0u8; AES_BLOCK_SIZE]);
+    /// Encrypt a serialized command in place (see `command::serialize_into`),
+    /// leaving the ciphertext in the buffer
+    pub fn encrypt_command_buffer(
+        &mut self,
+        buffer: &mut Vec<u8>,
+    ) -> Result<command::Message, session::Error> {
+        assert_eq!(self.security_level, SecurityLevel::Authenticated);
+
+        let pos = buffer.len();
+
+        // Provide space at the end of the buffer for the padding
+        buffer.resize(pos + AES_BLOCK_SIZE, 0);
 
         let cipher = Aes128::new_varkey(&self.enc_key).unwrap();
         let icv = compute_icv(&cipher, self.counter);
         let cbc_encryptor = Aes128Cbc::new(cipher, &icv);
-        let ciphertext = cbc_encryptor.encrypt(&mut message, pos).unwrap();
+        let ciphertext_len = cbc_encryptor.encrypt(buffer, pos).unwrap().len();
+        buffer.truncate(ciphertext_len);
 
-        self.command_with_mac(command::Code::SessionMessage, ciphertext)
+        self.command_with_mac(command::Code::SessionMessage, buffer)
     }
 
     /// Verify and decrypt a response from the card
@@ -581,6 +614,17 @@ impl SecureChannel {
         });
     }
 
+    /// Copy this channel's session keys
+    #[cfg(feature = "unsafe-session-export")]
+    pub(super) fn export_keys(&self) -> session::SessionKeys {
+        session::SessionKeys {
+            session_id: self.id,
+            enc_key: self.enc_key,
+            mac_key: self.mac_key,
+            rmac_key: self.rmac_key,
+        }
+    }
+
     /// Terminate the session
     fn terminate(&mut self) {
         self.security_level = SecurityLevel::Terminated;
@@ -609,6 +653,18 @@ pub(crate) enum SecurityLevel {
     Terminated,
 }
 
+/// Compute the maximum size of an encrypted `SessionMessage` response which
+/// carries a plaintext response with up to `data_len` bytes of data
+pub(crate) fn encrypted_response_size(data_len: usize) -> usize {
+    // Plaintext response header (code + length) and data, followed by
+    // ISO 7816 padding which always adds at least one byte
+    let plaintext_len = 3 + data_len;
+    let ciphertext_len = (plaintext_len / AES_BLOCK_SIZE + 1) * AES_BLOCK_SIZE;
+
+    // Outer response header (code + length), session ID, ciphertext, and R-MAC
+    cmp::min(3 + 1 + ciphertext_len + MAC_SIZE, command::MAX_MSG_SIZE)
+}
+
 /// Derive a key using the SCP03 KDF
 fn derive_key(parent_key: &[u8], derivation_constant: u8, context: &Context) -> [u8; KEY_SIZE] {
     let mut key = [0u8; KEY_SIZE];
@@ -735,4 +791,42 @@ mod tests {
             "cryptographic verification failed: R-MAC mismatch!"
         );
     }
+
+    #[test]
+    fn encrypted_response_size_test() {
+        let (_, mut card_channel) = create_channel_pair();
+
+        for data_len in 0..64 {
+            let response_ciphertext = card_channel
+                .encrypt_response(response::Message::success(
+                    COMMAND_CODE,
+                    vec![0u8; data_len],
+                ))
+                .unwrap();
+
+            let response_bytes: Vec<u8> = response_ciphertext.into();
+            assert_eq!(response_bytes.len(), encrypted_response_size(data_len));
+        }
+    }
+
+    #[cfg(feature = "unsafe-session-export")]
+    #[test]
+    fn export_keys_test() {
+        let (host_channel, card_channel) = create_channel_pair();
+
+        let host_keys = host_channel.export_keys();
+        let card_keys = card_channel.export_keys();
+
+        assert_eq!(host_keys.enc_key, card_keys.enc_key);
+        assert_eq!(host_keys.mac_key, card_keys.mac_key);
+        assert_eq!(host_keys.rmac_key, card_keys.rmac_key);
+        assert_ne!(host_keys.enc_key, host_keys.mac_key);
+        assert_ne!(host_keys.mac_key, host_keys.rmac_key);
+
+        // Keys are omitted from `Debug` output
+        assert_eq!(
+            format!("{:?}", host_keys),
+            format!("SessionKeys {{ session_id: {:?} }}", host_keys.session_id)
+        );
+    }
 }
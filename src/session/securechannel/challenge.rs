@@ -1,6 +1,6 @@
 //! Challenge messages used as part of SCP03's challenge/response protocol.
 
-use rand_core::{OsRng, RngCore};
+use rand_core::RngCore;
 use serde::{Deserialize, Serialize};
 
 /// Size of a challenge message
@@ -11,10 +11,16 @@ pub const CHALLENGE_SIZE: usize = 8;
 pub struct Challenge([u8; CHALLENGE_SIZE]);
 
 impl Challenge {
-    /// Create a new random `Challenge`
+    /// Create a new random `Challenge` using `OsRng`
+    #[cfg(feature = "mockhsm")]
     pub fn new() -> Self {
+        Self::random(&mut rand_core::OsRng)
+    }
+
+    /// Create a new random `Challenge` using the given RNG
+    pub fn random(rng: &mut dyn RngCore) -> Self {
         let mut challenge = [0u8; CHALLENGE_SIZE];
-        OsRng.fill_bytes(&mut challenge);
+        rng.fill_bytes(&mut challenge);
         Challenge(challenge)
     }
 
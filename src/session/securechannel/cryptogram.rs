@@ -1,7 +1,7 @@
 //! Authentication cryptograms (8-byte MACs) used for session verification
 
+use core::fmt;
 use serde::{Deserialize, Serialize};
-use std::fmt;
 use subtle::{Choice, ConstantTimeEq};
 use zeroize::Zeroize;
 
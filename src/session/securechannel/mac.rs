@@ -9,7 +9,7 @@
 use crate::session;
 use anomaly::fail;
 use cmac::crypto_mac::generic_array::{typenum::U16, GenericArray};
-use std::fmt;
+use core::fmt;
 use subtle::{Choice, ConstantTimeEq};
 use zeroize::Zeroize;
 
@@ -7,14 +7,38 @@ use std::time::Duration;
 /// <https://developers.yubico.com/YubiHSM2/Concepts/Session.html>
 pub const SESSION_INACTIVITY_TIMEOUT: Duration = Duration::from_secs(30);
 
-/// Session timeouts (i.e. YubiHSM's session inactivity timeout)
+/// Default allowance for clock skew and latency between the host and HSM.
+///
+/// Sessions are considered timed out slightly earlier than the HSM's
+/// timeout, so we (hopefully) always time out first and open a new session,
+/// rather than sending commands the HSM rejects.
+pub const DEFAULT_TIMEOUT_SKEW: Duration = Duration::from_secs(1);
+
+/// Session timeouts (i.e. YubiHSM's session inactivity timeout).
+///
+/// Sessions are considered timed out once they've been idle for the timeout
+/// duration minus the skew, so they're replaced before the HSM closes them.
+/// The HSM closes sessions after 30 seconds of inactivity regardless of
+/// this setting: a longer duration doesn't keep sessions open for longer,
+/// but makes it likelier commands are sent in a session the HSM has already
+/// closed (and have to be retried in a new one). A larger skew suits links
+/// with high or variable latency.
 #[derive(Copy, Clone, Debug, Deserialize, Serialize)]
-pub struct Timeout(Duration);
+pub struct Timeout {
+    /// Inactivity timeout
+    duration: Duration,
+
+    /// Allowance for clock skew and latency
+    skew: Duration,
+}
 
 impl Timeout {
-    /// Create a new timeout from the given duration
+    /// Create a new timeout from the given duration, with the default skew
     pub fn new(duration: Duration) -> Self {
-        Timeout(duration)
+        Timeout {
+            duration,
+            skew: DEFAULT_TIMEOUT_SKEW,
+        }
     }
 
     /// Create a new timeout from the given number of secs
@@ -22,9 +46,27 @@ impl Timeout {
         Self::from(Duration::from_secs(secs))
     }
 
+    /// Set the allowance for clock skew and latency (default:
+    /// `DEFAULT_TIMEOUT_SKEW`)
+    pub fn with_skew(mut self, skew: Duration) -> Self {
+        self.skew = skew;
+        self
+    }
+
     /// Get the duration value
     pub fn duration(&self) -> Duration {
-        self.0
+        self.duration
+    }
+
+    /// Get the allowance for clock skew and latency
+    pub fn skew(&self) -> Duration {
+        self.skew
+    }
+
+    /// How long a session may be idle before it's considered timed out,
+    /// i.e. the duration minus the skew
+    pub fn effective_duration(&self) -> Duration {
+        self.duration.checked_sub(self.skew).unwrap_or_default()
     }
 }
 
@@ -40,3 +82,24 @@ impl From<Duration> for Timeout {
         Self::new(duration)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skew_is_subtracted_from_duration() {
+        let timeout = Timeout::from_secs(10).with_skew(Duration::from_secs(3));
+        assert_eq!(timeout.effective_duration(), Duration::from_secs(7));
+        assert_eq!(
+            Timeout::default().effective_duration(),
+            Duration::from_secs(29)
+        );
+    }
+
+    #[test]
+    fn skew_longer_than_duration_leaves_no_window() {
+        let timeout = Timeout::from_secs(1).with_skew(Duration::from_secs(2));
+        assert_eq!(timeout.effective_duration(), Duration::from_secs(0));
+    }
+}
@@ -0,0 +1,90 @@
+//! Session transcripts: host-side record of the commands sent during a
+//! session, for audit purposes.
+//!
+//! Transcripts record command metadata only (never command or response
+//! payloads), and include read-only commands which the HSM's own audit log
+//! doesn't capture.
+
+use super::Error;
+use crate::{command, device, object};
+use std::{collections::VecDeque, error::Error as _, time::SystemTime};
+
+/// Record of a single command sent during a session
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TranscriptEntry {
+    /// Command which was sent
+    pub command: command::Code,
+
+    /// How the command affects the state of the HSM
+    pub effect: command::Effect,
+
+    /// When the command completed
+    pub timestamp: SystemTime,
+
+    /// ID of the object the command operated on (if any)
+    pub object_id: Option<object::Id>,
+
+    /// Did the command succeed?
+    pub succeeded: bool,
+
+    /// Error returned by the HSM (if the command failed on the device)
+    pub device_error: Option<device::ErrorKind>,
+}
+
+/// Bounded buffer of `TranscriptEntry` values. Once full, the oldest entries
+/// are discarded.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Transcript {
+    /// Maximum number of entries to retain (zero disables recording)
+    capacity: usize,
+
+    /// Recorded entries, oldest first
+    entries: VecDeque<TranscriptEntry>,
+}
+
+impl Transcript {
+    /// Create a new transcript which retains up to `capacity` entries
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Record the result of sending a command
+    pub fn record<T>(
+        &mut self,
+        command: command::Code,
+        effect: command::Effect,
+        object_id: Option<object::Id>,
+        result: &Result<T, Error>,
+    ) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+
+        let device_error = result.as_ref().err().and_then(|e| {
+            e.source()
+                .and_then(|source| source.downcast_ref::<device::ErrorKind>())
+                .cloned()
+        });
+
+        self.entries.push_back(TranscriptEntry {
+            command,
+            effect,
+            timestamp: SystemTime::now(),
+            object_id,
+            succeeded: result.is_ok(),
+            device_error,
+        });
+    }
+
+    /// Get the recorded entries, oldest first
+    pub fn entries(&self) -> Vec<TranscriptEntry> {
+        self.entries.iter().cloned().collect()
+    }
+}
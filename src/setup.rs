@@ -66,7 +66,7 @@ pub fn init_with_profile(client: Client, profile: Profile) -> Result<Report, Err
         })?;
 
     info!(
-        "installed temporary setup authentication key into slot {}",
+        "installed temporary setup authentication key into slot 0x{:04x}",
         setup_auth_key_id
     );
 
@@ -109,7 +109,7 @@ pub fn init_with_profile(client: Client, profile: Profile) -> Result<Report, Err
 
     if profile.delete_setup_auth_key {
         warn!(
-            "deleting temporary setup authentication key from slot {}",
+            "deleting temporary setup authentication key from slot 0x{:04x}",
             setup_auth_key_id
         );
         client
@@ -117,7 +117,7 @@ pub fn init_with_profile(client: Client, profile: Profile) -> Result<Report, Err
             .map_err(|e| {
                 format_err!(
                     ErrorKind::SetupFailed,
-                    "error deleting temporary setup authentication key from slot {}: {}",
+                    "error deleting temporary setup authentication key from slot 0x{:04x}: {}",
                     setup_auth_key_id,
                     e
                 )
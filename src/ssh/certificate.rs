@@ -19,6 +19,12 @@ impl Certificate {
     }
 }
 
+impl From<Certificate> for Vec<u8> {
+    fn from(certificate: Certificate) -> Vec<u8> {
+        certificate.0
+    }
+}
+
 impl AsRef<[u8]> for Certificate {
     fn as_ref(&self) -> &[u8] {
         self.as_slice()
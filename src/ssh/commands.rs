@@ -38,6 +38,14 @@ pub(crate) struct SignSshCertificateCommand {
 
 impl Command for SignSshCertificateCommand {
     type ResponseType = SignSshCertificateResponse;
+
+    fn object_id(&self) -> Option<object::Id> {
+        Some(self.key_id)
+    }
+
+    fn object_type(&self) -> Option<object::Type> {
+        Some(object::Type::AsymmetricKey)
+    }
 }
 
 /// Signed SSH certificates
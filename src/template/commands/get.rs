@@ -18,6 +18,14 @@ pub(crate) struct GetTemplateCommand {
 
 impl Command for GetTemplateCommand {
     type ResponseType = GetTemplateResponse;
+
+    fn object_id(&self) -> Option<object::Id> {
+        Some(self.object_id)
+    }
+
+    fn object_type(&self) -> Option<object::Type> {
+        Some(object::Type::Template)
+    }
 }
 
 /// Response from `command::get_template`
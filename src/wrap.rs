@@ -2,16 +2,24 @@
 //! importing existing keys to other derivces.
 
 mod algorithm;
+mod base64;
 pub(crate) mod commands;
 mod error;
+mod import;
 mod key;
 mod message;
 mod nonce;
+#[cfg(feature = "passwords")]
+mod passphrase;
 
 pub use self::{
     algorithm::Algorithm,
     error::{Error, ErrorKind},
+    import::{ImportFailure, ImportMode, ImportReport, RollbackFailure},
     key::Key,
     message::Message,
     nonce::Nonce,
 };
+
+#[cfg(feature = "passwords")]
+pub use self::passphrase::derive_key_from_passphrase;
@@ -0,0 +1,120 @@
+//! Minimal Base64 (RFC 4648, standard alphabet, padded) encoder/decoder used
+//! for wrap message files.
+
+use super::{Error, ErrorKind};
+use anomaly::fail;
+
+/// Base64 alphabet
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encode the given bytes as padded Base64
+pub(crate) fn encode(bytes: &[u8]) -> String {
+    let mut output = String::with_capacity(bytes.len() / 3 * 4 + 4);
+
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).cloned().unwrap_or(0),
+            chunk.get(2).cloned().unwrap_or(0),
+        ];
+
+        let indices = [
+            b[0] >> 2,
+            (b[0] & 0x03) << 4 | b[1] >> 4,
+            (b[1] & 0x0f) << 2 | b[2] >> 6,
+            b[2] & 0x3f,
+        ];
+
+        for (i, &index) in indices.iter().enumerate() {
+            if i <= chunk.len() {
+                output.push(ALPHABET[index as usize] as char);
+            } else {
+                output.push('=');
+            }
+        }
+    }
+
+    output
+}
+
+/// Decode padded Base64, ignoring surrounding whitespace
+pub(crate) fn decode(encoded: &str) -> Result<Vec<u8>, Error> {
+    let encoded = encoded.trim().as_bytes();
+
+    let trailing = encoded.len() % 4;
+
+    if trailing != 0 {
+        fail!(
+            ErrorKind::FormatInvalid,
+            "Base64 length must be a multiple of 4 (got {} trailing bytes)",
+            trailing
+        );
+    }
+
+    let mut output = Vec::with_capacity(encoded.len() / 4 * 3);
+
+    for (n, chunk) in encoded.chunks(4).enumerate() {
+        let is_last = (n + 1) * 4 == encoded.len();
+        let padding = chunk.iter().rev().take_while(|&&c| c == b'=').count();
+
+        if padding > 2 || (padding > 0 && !is_last) {
+            fail!(ErrorKind::FormatInvalid, "invalid Base64 padding");
+        }
+
+        let mut value = 0u32;
+
+        for &c in &chunk[..4 - padding] {
+            let index = match ALPHABET.iter().position(|&a| a == c) {
+                Some(index) => index as u32,
+                None => fail!(
+                    ErrorKind::FormatInvalid,
+                    "invalid Base64 character: {:?}",
+                    c as char
+                ),
+            };
+
+            value = value << 6 | index;
+        }
+
+        value <<= 6 * padding as u32;
+
+        let bytes = [(value >> 16) as u8, (value >> 8) as u8, value as u8];
+        output.extend_from_slice(&bytes[..3 - padding]);
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// RFC 4648 test vectors
+    const VECTORS: &[(&[u8], &str)] = &[
+        (b"", ""),
+        (b"f", "Zg=="),
+        (b"fo", "Zm8="),
+        (b"foo", "Zm9v"),
+        (b"foob", "Zm9vYg=="),
+        (b"fooba", "Zm9vYmE="),
+        (b"foobar", "Zm9vYmFy"),
+    ];
+
+    #[test]
+    fn round_trip() {
+        for &(bytes, encoded) in VECTORS {
+            assert_eq!(encode(bytes), encoded);
+            assert_eq!(decode(encoded).unwrap(), bytes);
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        for &encoded in &["Zm9", "Zm=v", "Zg==Zg==", "Zm9*", "Z==="] {
+            assert_eq!(
+                *decode(encoded).unwrap_err().kind(),
+                ErrorKind::FormatInvalid
+            );
+        }
+    }
+}
@@ -25,6 +25,14 @@ pub(crate) struct ExportWrappedCommand {
 
 impl Command for ExportWrappedCommand {
     type ResponseType = ExportWrappedResponse;
+
+    fn object_id(&self) -> Option<object::Id> {
+        Some(self.object_id)
+    }
+
+    fn object_type(&self) -> Option<object::Type> {
+        Some(self.object_type)
+    }
 }
 
 /// Response from `command::export_wrapped`
@@ -9,6 +9,14 @@ pub type Error = crate::Error<ErrorKind>;
 /// Kinds of wrap-related errors
 #[derive(Copy, Clone, Debug, Eq, Error, PartialEq)]
 pub enum ErrorKind {
+    /// Wrap message file is malformed
+    #[error("invalid message format")]
+    FormatInvalid,
+
+    /// Error reading or writing a wrap message file
+    #[error("I/O error")]
+    IoError,
+
     /// Wrap message is an invalid length
     #[error("invalid message length")]
     LengthInvalid,
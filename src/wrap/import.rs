@@ -0,0 +1,68 @@
+//! Importing batches of wrapped objects (e.g. restoring a backup)
+
+use crate::object;
+
+/// What `Client::import_all` does when importing an object fails
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ImportMode {
+    /// Stop importing, leaving the objects imported so far in the HSM
+    StopOnFailure,
+
+    /// Stop importing, then delete the objects imported so far
+    Rollback,
+}
+
+/// Failure to import one of the objects in a batch
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ImportFailure {
+    /// Position of the object in the batch
+    pub index: usize,
+
+    /// Error importing the object
+    pub error: String,
+}
+
+/// Failure to delete an imported object while rolling back
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RollbackFailure {
+    /// Object which couldn't be deleted (and so remains in the HSM)
+    pub object: object::Handle,
+
+    /// Error deleting the object
+    pub error: String,
+}
+
+/// Final state of the HSM after `Client::import_all`.
+///
+/// Importing a batch isn't atomic: each object is imported with its own
+/// command, and rolling back deletes each object with its own command. If
+/// the connection to the HSM is lost during a rollback, objects can be left
+/// behind; these are listed in `imported` and `rollback_failures`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[must_use]
+pub struct ImportReport {
+    /// Objects which were imported and remain in the HSM, in import order
+    pub imported: Vec<object::Handle>,
+
+    /// The import which failed and stopped the batch (if any)
+    pub failure: Option<ImportFailure>,
+
+    /// Objects which were imported, then deleted by a rollback
+    pub rolled_back: Vec<object::Handle>,
+
+    /// Objects a rollback failed to delete
+    pub rollback_failures: Vec<RollbackFailure>,
+}
+
+impl ImportReport {
+    /// Were all of the objects in the batch imported?
+    pub fn is_complete(&self) -> bool {
+        self.failure.is_none()
+    }
+
+    /// Was the batch either fully imported, or fully rolled back (i.e. the
+    /// HSM isn't left partially restored)?
+    pub fn is_consistent(&self) -> bool {
+        self.is_complete() || self.imported.is_empty()
+    }
+}
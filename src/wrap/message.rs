@@ -1,9 +1,13 @@
 //! Wrap messages
 
 use super::nonce::{self, Nonce};
-use super::{Error, ErrorKind};
-use anomaly::fail;
+use super::{base64, Error, ErrorKind};
+use anomaly::{fail, format_err};
 use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
+
+/// Size of the MAC which follows the encrypted object in the ciphertext
+const MAC_SIZE: usize = 16;
 
 /// Wrap wessage (encrypted HSM object or arbitrary data) encrypted under a wrap key
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -48,6 +52,59 @@ impl Message {
     pub fn into_vec(self) -> Vec<u8> {
         self.into()
     }
+
+    /// Read a `Message` from a file in the Base64 format used by
+    /// `yubihsm-shell` for wrapped object backups (i.e. `.yhw` files)
+    pub fn read_file<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+
+        let contents = fs::read_to_string(path).map_err(|e| {
+            format_err!(
+                ErrorKind::IoError,
+                "couldn't read {}: {}",
+                path.display(),
+                e
+            )
+        })?;
+
+        let bytes = base64::decode(&contents).map_err(|e| {
+            format_err!(
+                ErrorKind::FormatInvalid,
+                "{} is not a wrap message file: {}",
+                path.display(),
+                e
+            )
+        })?;
+
+        if bytes.len() <= nonce::SIZE + MAC_SIZE {
+            fail!(
+                ErrorKind::LengthInvalid,
+                "{} is truncated ({} bytes)",
+                path.display(),
+                bytes.len()
+            );
+        }
+
+        Self::from_vec(bytes)
+    }
+
+    /// Write this `Message` to a file in the Base64 format used by
+    /// `yubihsm-shell` (see `Message::read_file`)
+    pub fn write_file<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let path = path.as_ref();
+        let mut contents = base64::encode(&self.clone().into_vec());
+        contents.push('\n');
+
+        fs::write(path, contents).map_err(|e| {
+            format_err!(
+                ErrorKind::IoError,
+                "couldn't write {}: {}",
+                path.display(),
+                e
+            )
+            .into()
+        })
+    }
 }
 
 impl Into<Vec<u8>> for Message {
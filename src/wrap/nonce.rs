@@ -1,7 +1,6 @@
 //! Nonces used by the YubiHSM 2's AES-CCM encrypted `wrap::Message`
 
-#[cfg(feature = "mockhsm")]
-use rand_core::{OsRng, RngCore};
+use rand_core::RngCore;
 
 /// Number of bytes in a nonce used for "wrapping" (i.e AES-CCM encryption)
 pub const SIZE: usize = 13;
@@ -11,11 +10,16 @@ pub const SIZE: usize = 13;
 pub struct Nonce(pub [u8; SIZE]);
 
 impl Nonce {
-    /// Generate a random `wrap::Nonce`
+    /// Generate a random `wrap::Nonce` using `OsRng`
     #[cfg(feature = "mockhsm")]
     pub fn generate() -> Self {
+        Self::random(&mut rand_core::OsRng)
+    }
+
+    /// Generate a random `wrap::Nonce` using the given RNG
+    pub fn random(rng: &mut dyn RngCore) -> Self {
         let mut bytes = [0u8; SIZE];
-        OsRng.fill_bytes(&mut bytes);
+        rng.fill_bytes(&mut bytes);
         Nonce(bytes)
     }
 }
@@ -0,0 +1,82 @@
+//! Deriving wrap keys from passphrases
+
+use super::Algorithm;
+use scrypt::{scrypt, ScryptParams};
+use zeroize::Zeroizing;
+
+/// Deterministically derive wrap key bytes (of the correct length for the
+/// given algorithm, suitable for `Client::put_wrap_key`) from a passphrase
+/// and salt, using scrypt with its recommended parameters (N = 2^15, r = 8,
+/// p = 1, i.e. 32 MiB of memory per guess).
+///
+/// Re-deriving with the same passphrase, salt and algorithm produces the same
+/// key, so backups wrapped under it can be restored without storing the key
+/// itself. The salt should be random (e.g. 16 bytes), unique per backup, and
+/// stored alongside it.
+///
+/// **WARNING:** anyone with the wrapped backup and a way to test guesses
+/// (e.g. another YubiHSM 2) can mount an offline brute force attack on the
+/// passphrase, which is slowed only by the KDF's work factor. Backups are
+/// only as secure as the passphrase is strong: use a long, randomly
+/// generated one, or a random key stored securely whenever that's practical.
+#[cfg_attr(docsrs, doc(cfg(feature = "passwords")))]
+pub fn derive_key_from_passphrase(
+    passphrase: &[u8],
+    salt: &[u8],
+    algorithm: Algorithm,
+) -> Zeroizing<Vec<u8>> {
+    derive_key(passphrase, salt, algorithm, &ScryptParams::recommended())
+}
+
+/// Derive a wrap key with the given scrypt parameters
+fn derive_key(
+    passphrase: &[u8],
+    salt: &[u8],
+    algorithm: Algorithm,
+    params: &ScryptParams,
+) -> Zeroizing<Vec<u8>> {
+    let mut key = Zeroizing::new(vec![0u8; algorithm.key_len()]);
+    scrypt(passphrase, salt, params, &mut key).expect("wrap key length is valid for scrypt");
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Cheap scrypt parameters for tests (N = 16, r = 1, p = 1)
+    fn test_params() -> ScryptParams {
+        ScryptParams::new(4, 1, 1).unwrap()
+    }
+
+    /// scrypt test vector from RFC 7914 (truncated)
+    #[test]
+    fn scrypt_test_vector() {
+        let key = derive_key(b"", b"", Algorithm::Aes256Ccm, &test_params());
+
+        assert_eq!(
+            key.as_slice(),
+            &[
+                0x77, 0xd6, 0x57, 0x62, 0x38, 0x65, 0x7b, 0x20, 0x3b, 0x19, 0xca, 0x42, 0xc1, 0x8a,
+                0x04, 0x97, 0xf1, 0x6b, 0x48, 0x44, 0xe3, 0x07, 0x4a, 0xe8, 0xdf, 0xdf, 0xfa, 0x3f,
+                0xed, 0xe2, 0x14, 0x42,
+            ][..]
+        );
+    }
+
+    #[test]
+    fn derives_keys_of_algorithm_length() {
+        for &algorithm in &[
+            Algorithm::Aes128Ccm,
+            Algorithm::Aes192Ccm,
+            Algorithm::Aes256Ccm,
+        ] {
+            let key = derive_key(b"correct horse", b"salt", algorithm, &test_params());
+            assert_eq!(key.len(), algorithm.key_len());
+            assert_ne!(
+                key,
+                derive_key(b"battery staple", b"salt", algorithm, &test_params())
+            );
+        }
+    }
+}
@@ -0,0 +1,82 @@
+//! Load balancing commands across several HSMs
+
+use yubihsm::{
+    asymmetric,
+    client::cluster::{Dispatch, HsmCluster},
+    object, Capability, Client, Connector, Credentials, Domain,
+};
+
+/// Ed25519 key shared by the HSMs in the test cluster
+const KEY_ID: object::Id = 100;
+
+fn provisioned_client() -> Client {
+    let client = Client::open(Connector::mockhsm(), Credentials::default(), true).unwrap();
+
+    client
+        .put_asymmetric_key(
+            KEY_ID,
+            "cluster".into(),
+            Domain::DOM1,
+            Capability::SIGN_EDDSA,
+            asymmetric::Algorithm::Ed25519,
+            [0x42; 32].to_vec(),
+        )
+        .unwrap();
+
+    client
+}
+
+/// Client for an HSM which can't be reached
+fn unreachable_client() -> Client {
+    Client::create(Connector::replay(vec![]), Credentials::default()).unwrap()
+}
+
+#[test]
+fn round_robin_spreads_requests() {
+    let cluster = HsmCluster::new(
+        vec![provisioned_client(), provisioned_client()],
+        Dispatch::RoundRobin,
+    );
+
+    let signatures: Vec<_> = (0..4)
+        .map(|_| cluster.sign_ed25519(KEY_ID, b"cluster").unwrap())
+        .collect();
+
+    assert!(signatures.iter().all(|sig| sig == &signatures[0]));
+
+    for health in cluster.health() {
+        assert!(health.up);
+        assert_eq!(health.completed, 2);
+    }
+}
+
+#[test]
+fn routes_around_unreachable_device() {
+    let cluster = HsmCluster::new(
+        vec![unreachable_client(), provisioned_client()],
+        Dispatch::LeastLoaded,
+    );
+
+    for _ in 0..3 {
+        cluster.sign_ed25519(KEY_ID, b"cluster").unwrap();
+    }
+
+    let health = cluster.health();
+    assert!(!health[0].up);
+    assert_eq!(health[0].failures, 1);
+    assert!(health[0].last_error.is_some());
+    assert_eq!(health[1].completed, 3);
+    assert!(cluster.is_degraded());
+    assert_eq!(cluster.available(), 1);
+}
+
+#[test]
+fn device_errors_are_not_routed_around() {
+    let cluster = HsmCluster::new(
+        vec![provisioned_client(), provisioned_client()],
+        Dispatch::RoundRobin,
+    );
+
+    assert!(cluster.sign_ed25519(KEY_ID + 1, b"cluster").is_err());
+    assert!(!cluster.is_degraded());
+}
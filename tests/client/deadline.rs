@@ -0,0 +1,75 @@
+use std::{
+    io::Read,
+    net::TcpListener,
+    thread,
+    time::{Duration, Instant},
+};
+use yubihsm::{client, Client, Connector, Credentials, HttpConfig};
+
+/// Start a server which accepts connections, then never responds
+fn silent_server() -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            thread::spawn(move || while stream.read(&mut [0u8; 1024]).unwrap_or(0) > 0 {});
+        }
+    });
+
+    port
+}
+
+/// `Client::set_deadline` is enforced through the connection's timeouts,
+/// even if the connection's own timeout is much longer
+#[test]
+fn deadline_elapses_while_opening_session() {
+    let config = HttpConfig {
+        addr: "127.0.0.1".to_owned(),
+        port: silent_server(),
+        timeout_ms: 60_000,
+        ..HttpConfig::default()
+    };
+
+    let mut client = Client::create(Connector::http(&config), Credentials::default()).unwrap();
+    client.set_deadline(Some(Duration::from_millis(100)));
+
+    let started_at = Instant::now();
+    let err = client.blink_device(1).unwrap_err();
+
+    assert_eq!(*err.kind(), client::ErrorKind::TimeoutError);
+    assert!(started_at.elapsed() < Duration::from_secs(5));
+}
+
+/// Sessions are still closed when dropped after their deadline has passed,
+/// rather than leaking until the HSM times them out
+#[cfg(feature = "mockhsm")]
+#[test]
+fn expired_deadline_does_not_prevent_closing_session() {
+    let connector = Connector::mockhsm();
+
+    // The MockHsm only has 16 session slots, so leaking a session on each
+    // iteration would exhaust them
+    for _ in 0..20 {
+        let mut client = Client::open(connector.clone(), Credentials::default(), false).unwrap();
+        client.set_deadline(Some(Duration::from_millis(50)));
+        client.echo(b"deadline").unwrap();
+        thread::sleep(Duration::from_millis(60));
+    }
+}
+
+/// Rotating a session closes the old one even if the deadline of the command
+/// which triggered the rotation is too short to do so
+#[cfg(feature = "mockhsm")]
+#[test]
+fn expired_deadline_does_not_prevent_rotating_session() {
+    let mut client = Client::open(Connector::mockhsm(), Credentials::default(), true).unwrap();
+    client.set_session_rotation_threshold(1);
+
+    for _ in 0..20 {
+        client.set_deadline(Some(Duration::from_millis(50)));
+        client.echo(b"deadline").unwrap();
+        thread::sleep(Duration::from_millis(60));
+    }
+}
@@ -0,0 +1,42 @@
+//! Comparing the objects provisioned on HSMs
+
+use yubihsm::{asymmetric, object, Capability, Client, Connector, Credentials, Domain};
+
+fn provision(capabilities: Capability) -> Client {
+    let client = Client::open(Connector::mockhsm(), Credentials::default(), true).unwrap();
+
+    client
+        .generate_asymmetric_key(
+            100,
+            "inventory".into(),
+            Domain::DOM1,
+            capabilities,
+            asymmetric::Algorithm::Ed25519,
+        )
+        .unwrap();
+
+    client
+}
+
+#[test]
+fn identically_provisioned_hsms_match() {
+    let a = provision(Capability::SIGN_EDDSA).inventory().unwrap();
+    let b = provision(Capability::SIGN_EDDSA).inventory().unwrap();
+
+    assert_eq!(a.fingerprint(), b.fingerprint());
+    assert!(a.diff(&b).is_empty());
+}
+
+#[test]
+fn divergently_provisioned_hsms_differ() {
+    let a = provision(Capability::SIGN_EDDSA).inventory().unwrap();
+    let b = provision(Capability::SIGN_EDDSA | Capability::EXPORTABLE_UNDER_WRAP)
+        .inventory()
+        .unwrap();
+
+    assert_ne!(a.fingerprint(), b.fingerprint());
+    assert_eq!(
+        a.diff(&b),
+        vec![object::Handle::new(100, object::Type::AsymmetricKey)]
+    );
+}
@@ -0,0 +1,34 @@
+//! Integration tests for client-side behaviour (i.e. not specific to a
+//! single HSM command)
+
+#[cfg(feature = "mockhsm")]
+pub mod cluster;
+#[cfg(feature = "http")]
+pub mod deadline;
+#[cfg(feature = "mockhsm")]
+pub mod inventory;
+#[cfg(feature = "mockhsm")]
+pub mod open;
+#[cfg(feature = "mockhsm")]
+pub mod permissions;
+#[cfg(feature = "mockhsm")]
+pub mod pool;
+#[cfg(feature = "mockhsm")]
+pub mod protocol;
+#[cfg(feature = "mockhsm")]
+pub mod provision;
+#[cfg(feature = "mockhsm")]
+pub mod read_only;
+#[cfg(feature = "mockhsm")]
+pub mod reconnect;
+pub mod rng;
+#[cfg(feature = "mockhsm")]
+pub mod self_test;
+#[cfg(feature = "mockhsm")]
+pub mod session;
+#[cfg(feature = "mockhsm")]
+pub mod snapshot;
+#[cfg(feature = "mockhsm")]
+pub mod supports;
+#[cfg(all(feature = "mockhsm", feature = "threaded"))]
+pub mod threaded;
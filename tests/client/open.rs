@@ -0,0 +1,58 @@
+//! Opening sessions with the HSM
+
+use std::time::Duration;
+use yubihsm::{
+    authentication, client,
+    mockhsm::{FakeConnector, MockHsm, MOCK_SERIAL_NUMBER},
+    session, Client, Connector, Credentials,
+};
+
+/// Credentials which fail to authenticate are skipped in favor of the next
+#[test]
+fn open_with_fallback_skips_failed_credentials() {
+    let default_credentials = Credentials::default();
+    let wrong_credentials = Credentials::new(
+        default_credentials.authentication_key_id,
+        authentication::Key::random(),
+    );
+
+    let (_, index) = Client::open_with_fallback(
+        Connector::mockhsm(),
+        vec![wrong_credentials, default_credentials],
+        true,
+    )
+    .unwrap();
+
+    assert_eq!(index, 1);
+}
+
+/// Sessions are only opened with the HSM the connector is configured with
+#[test]
+fn open_verifies_the_configured_serial_number() {
+    let mockhsm = MockHsm::new();
+
+    let connector =
+        FakeConnector::new(mockhsm.clone()).with_serial_number(MOCK_SERIAL_NUMBER.parse().unwrap());
+    Client::open(connector.into(), Credentials::default(), true).unwrap();
+
+    let connector = FakeConnector::new(mockhsm).with_serial_number("0000000001".parse().unwrap());
+    let err = Client::open(connector.into(), Credentials::default(), true)
+        .err()
+        .unwrap();
+    assert_eq!(*err.kind(), client::ErrorKind::CreateFailed);
+}
+
+/// Session timeouts must be longer than the allowed clock skew
+#[test]
+fn with_timeout_rejects_timeouts_shorter_than_skew() {
+    let timeout = session::Timeout::from_secs(2).with_skew(Duration::from_secs(2));
+    let err = Client::with_timeout(Connector::mockhsm(), Credentials::default(), true, timeout)
+        .err()
+        .unwrap();
+    assert_eq!(*err.kind(), client::ErrorKind::CreateFailed);
+
+    let timeout = timeout.with_skew(Duration::from_millis(500));
+    let client =
+        Client::with_timeout(Connector::mockhsm(), Credentials::default(), true, timeout).unwrap();
+    client.echo(b"hello").unwrap();
+}
@@ -0,0 +1,172 @@
+use yubihsm::{
+    asymmetric, authentication, client, device, object, Capability, Client, Connector, Credentials,
+    Domain,
+};
+
+/// Objects sharing no domain with the session key are rejected client-side
+#[test]
+fn domain_checks_reject_objects_in_other_domains() {
+    let connector = Connector::mockhsm();
+    let admin = Client::open(connector.clone(), Credentials::default(), true).unwrap();
+    let credentials = Credentials::new(2, authentication::Key::random());
+
+    admin
+        .put_authentication_key(
+            credentials.authentication_key_id,
+            "dom2".into(),
+            Domain::DOM2,
+            Capability::SIGN_EDDSA,
+            Capability::empty(),
+            authentication::Algorithm::YubicoAes,
+            credentials.authentication_key.clone(),
+        )
+        .unwrap();
+
+    for (key_id, domains) in &[(100, Domain::DOM1), (101, Domain::DOM2 | Domain::DOM3)] {
+        admin
+            .generate_asymmetric_key(
+                *key_id,
+                "ed25519".into(),
+                *domains,
+                Capability::SIGN_EDDSA,
+                asymmetric::Algorithm::Ed25519,
+            )
+            .unwrap();
+    }
+
+    let mut client = Client::open(connector, credentials, true).unwrap();
+    client.set_domain_checks(true);
+
+    let err = client.sign_ed25519(100, b"disjoint").unwrap_err();
+    assert_eq!(
+        err.device_error(),
+        Some(device::ErrorKind::InsufficientPermissions)
+    );
+
+    client.sign_ed25519(101, b"overlapping").unwrap();
+}
+
+/// Capability checks use the session key's current capabilities
+#[test]
+fn session_has_capability_checks_session_key() {
+    let connector = Connector::mockhsm();
+    let admin = Client::open(connector.clone(), Credentials::default(), true).unwrap();
+    assert!(admin
+        .session_has_capability(Capability::RESET_DEVICE)
+        .unwrap());
+
+    let key = authentication::Key::random();
+    admin
+        .put_authentication_key(
+            2,
+            "limited".into(),
+            Domain::all(),
+            Capability::GET_OPAQUE,
+            Capability::empty(),
+            authentication::Algorithm::YubicoAes,
+            key.clone(),
+        )
+        .unwrap();
+
+    let limited = Client::open(connector.clone(), Credentials::new(2, key.clone()), true).unwrap();
+    assert!(limited
+        .session_has_capability(Capability::GET_OPAQUE)
+        .unwrap());
+    assert!(!limited
+        .session_has_capability(Capability::RESET_DEVICE)
+        .unwrap());
+
+    // Deleting the key mid-session is reported as lacking the capability
+    let deleted = Client::open(connector, Credentials::new(2, key), true).unwrap();
+    admin
+        .delete_object(2, object::Type::AuthenticationKey)
+        .unwrap();
+
+    assert!(!deleted
+        .session_has_capability(Capability::GET_OPAQUE)
+        .unwrap());
+}
+
+/// Deleting the last authentication key able to manage the HSM is refused
+/// unless lockout protection is disabled
+#[test]
+fn deleting_last_admin_key_is_refused() {
+    let mut client = Client::open(Connector::mockhsm(), Credentials::default(), true).unwrap();
+
+    client
+        .put_authentication_key(
+            2,
+            "limited".into(),
+            Domain::all(),
+            Capability::GET_OPAQUE,
+            Capability::empty(),
+            authentication::Algorithm::YubicoAes,
+            authentication::Key::random(),
+        )
+        .unwrap();
+
+    let err = client
+        .delete_object(1, object::Type::AuthenticationKey)
+        .unwrap_err();
+    assert_eq!(*err.kind(), client::ErrorKind::WouldLockOut);
+
+    // Non-admin keys can be deleted as long as an admin key remains
+    client
+        .delete_object(2, object::Type::AuthenticationKey)
+        .unwrap();
+
+    client.set_lockout_protection(false);
+    client
+        .delete_object(1, object::Type::AuthenticationKey)
+        .unwrap();
+}
+
+/// The MockHsm itself checks the session key's capabilities and domains
+#[test]
+fn mockhsm_enforces_session_key_capabilities_and_domains() {
+    let connector = Connector::mockhsm();
+    let admin = Client::open(connector.clone(), Credentials::default(), true).unwrap();
+    let credentials = Credentials::new(2, authentication::Key::random());
+
+    admin
+        .put_authentication_key(
+            credentials.authentication_key_id,
+            "limited".into(),
+            Domain::DOM1,
+            Capability::SIGN_EDDSA,
+            Capability::empty(),
+            authentication::Algorithm::YubicoAes,
+            credentials.authentication_key.clone(),
+        )
+        .unwrap();
+
+    for (key_id, domains) in &[(100, Domain::DOM1), (101, Domain::DOM2)] {
+        admin
+            .generate_asymmetric_key(
+                *key_id,
+                "ed25519".into(),
+                *domains,
+                Capability::SIGN_EDDSA,
+                asymmetric::Algorithm::Ed25519,
+            )
+            .unwrap();
+    }
+
+    let client = Client::open(connector, credentials, true).unwrap();
+
+    // Missing the `GET_PSEUDO_RANDOM` capability
+    let err = client.get_pseudo_random(32).unwrap_err();
+    assert_eq!(
+        err.device_error(),
+        Some(device::ErrorKind::InsufficientPermissions)
+    );
+
+    // Object in a domain the session key isn't in
+    let err = client.sign_ed25519(101, b"other domain").unwrap_err();
+    assert_eq!(
+        err.device_error(),
+        Some(device::ErrorKind::InsufficientPermissions)
+    );
+
+    client.sign_ed25519(100, b"permitted").unwrap();
+}
@@ -0,0 +1,67 @@
+//! Pooling clients between threads
+
+use std::{sync::Arc, thread, time::Duration};
+use yubihsm::{client::ClientPool, session, Connector, Credentials};
+
+fn pool() -> ClientPool {
+    let connector = Connector::mockhsm();
+    ClientPool::new(move || connector.clone(), Credentials::default())
+}
+
+#[test]
+fn returned_clients_are_reused() {
+    let pool = pool();
+
+    pool.get().unwrap().echo(b"first").unwrap();
+    assert_eq!(pool.size(), 1);
+    assert_eq!(pool.idle(), 1);
+
+    {
+        let a = pool.get().unwrap();
+        let b = pool.get().unwrap();
+        a.echo(b"a").unwrap();
+        b.echo(b"b").unwrap();
+        assert_eq!(pool.idle(), 0);
+    }
+
+    assert_eq!(pool.size(), 2);
+    assert_eq!(pool.idle(), 2);
+}
+
+#[test]
+fn stale_clients_are_discarded() {
+    let mut pool = pool();
+    pool.set_timeout(session::Timeout::from_secs(2).with_skew(Duration::from_millis(1900)));
+
+    let stale_session_id = pool.get().unwrap().session().unwrap().id();
+    thread::sleep(Duration::from_millis(150));
+
+    let client = pool.get().unwrap();
+    client.echo(b"fresh").unwrap();
+    assert_ne!(client.session().unwrap().id(), stale_session_id);
+    assert_eq!(pool.size(), 1);
+}
+
+#[test]
+fn clients_are_shared_between_threads() {
+    let mut pool = pool();
+    pool.set_max_size(2);
+    let pool = Arc::new(pool);
+
+    let threads: Vec<_> = (0..4)
+        .map(|_| {
+            let pool = pool.clone();
+            thread::spawn(move || {
+                for _ in 0..10 {
+                    pool.get().unwrap().echo(b"hello").unwrap();
+                }
+            })
+        })
+        .collect();
+
+    for thread in threads {
+        thread.join().unwrap();
+    }
+
+    assert!(pool.size() <= 2);
+}
@@ -0,0 +1,41 @@
+use yubihsm::{
+    authentication, client, command,
+    connector::{ProtocolVersion, MAX_PROTOCOL_MAJOR_VERSION},
+    mockhsm::{FakeConnector, MockHsm},
+    Client, Credentials,
+};
+
+/// Connectors which report a protocol version from the future are rejected
+/// before any messages are sent
+#[test]
+fn incompatible_connector_version_is_rejected() {
+    let connector = FakeConnector::new(MockHsm::new()).with_protocol_version(ProtocolVersion::new(
+        MAX_PROTOCOL_MAJOR_VERSION + 1,
+        0,
+        0,
+    ));
+    let err = Client::open(connector.into(), Credentials::default(), true)
+        .err()
+        .unwrap();
+
+    assert_eq!(*err.kind(), client::ErrorKind::ConnectorError);
+}
+
+/// `CreateSession` responses longer than SCP03's are reported as an
+/// unsupported authentication protocol, rather than a cryptogram mismatch
+#[test]
+fn unsupported_authentication_protocol_is_detected() {
+    // Session ID, then an EC P-256 public key and 16-byte receipt
+    let data_len = 1 + 65 + 16;
+    let mut response = vec![command::Code::CreateSession as u8 + 0x80, 0, data_len as u8];
+    response.resize(3 + data_len, 0);
+
+    let connector =
+        FakeConnector::new(MockHsm::new()).with_response(command::Code::CreateSession, response);
+    let credentials = Credentials::new(1, authentication::Key::random());
+    let err = Client::open(connector.into(), credentials, true)
+        .err()
+        .unwrap();
+
+    assert_eq!(*err.kind(), client::ErrorKind::UnsupportedProtocol);
+}
@@ -0,0 +1,40 @@
+//! Provisioning a new HSM
+
+use yubihsm::{
+    authentication, device, mockhsm::MockHsm, object, Capability, Client, Credentials, Domain,
+};
+
+/// The default authentication key is replaced, and the client switches to
+/// the new key for subsequent sessions
+#[test]
+fn provision_initial_auth_key_replaces_default_key() {
+    let default_key_id = Credentials::default().authentication_key_id;
+    let new_key_id = 2;
+    let mockhsm = MockHsm::new();
+    let mut client = Client::open(mockhsm.clone().into(), Credentials::default(), true).unwrap();
+
+    client
+        .provision_initial_auth_key(
+            new_key_id,
+            "admin".into(),
+            Domain::all(),
+            Capability::all(),
+            Capability::all(),
+            authentication::Key::random(),
+        )
+        .unwrap();
+
+    let err = client
+        .get_object_info(default_key_id, object::Type::AuthenticationKey)
+        .unwrap_err();
+
+    assert_eq!(err.device_error(), Some(device::ErrorKind::ObjectNotFound));
+    assert_eq!(client.session_key_info().unwrap().object_id, new_key_id);
+
+    // Force a new session to be opened with the cached (i.e. new) credentials
+    mockhsm.expire_sessions();
+    client
+        .get_object_info(new_key_id, object::Type::AuthenticationKey)
+        .unwrap();
+    assert_eq!(client.session_key_info().unwrap().object_id, new_key_id);
+}
@@ -0,0 +1,157 @@
+use yubihsm::{
+    asymmetric, authentication, client, command, hmac, object, opaque, otp, wrap, AuditOption,
+    Capability, Client, Connector, Credentials, Domain,
+};
+
+/// Every mutating command is rejected before being sent to the HSM
+#[test]
+fn read_only_client_rejects_mutating_commands() {
+    let mut client = Client::open(Connector::mockhsm(), Credentials::default(), true).unwrap();
+    client.set_read_only(true);
+
+    let label = || object::Label::from("read-only");
+    let caps = Capability::all();
+
+    let results = vec![
+        (
+            command::Code::PutOpaqueObject,
+            client
+                .put_opaque(
+                    1,
+                    label(),
+                    Domain::DOM1,
+                    caps,
+                    opaque::Algorithm::Data,
+                    vec![0],
+                )
+                .map(|_| ()),
+        ),
+        (
+            command::Code::PutAuthenticationKey,
+            client
+                .put_authentication_key(
+                    2,
+                    label(),
+                    Domain::DOM1,
+                    caps,
+                    caps,
+                    authentication::Algorithm::YubicoAes,
+                    authentication::Key::random(),
+                )
+                .map(|_| ()),
+        ),
+        (
+            command::Code::PutAsymmetricKey,
+            client
+                .put_asymmetric_key(
+                    1,
+                    label(),
+                    Domain::DOM1,
+                    caps,
+                    asymmetric::Algorithm::Ed25519,
+                    vec![0x42; 32],
+                )
+                .map(|_| ()),
+        ),
+        (
+            command::Code::GenerateAsymmetricKey,
+            client
+                .generate_asymmetric_key(
+                    1,
+                    label(),
+                    Domain::DOM1,
+                    caps,
+                    asymmetric::Algorithm::Ed25519,
+                )
+                .map(|_| ()),
+        ),
+        (
+            command::Code::ImportWrapped,
+            client
+                .import_wrapped(1, wrap::Message::new([0u8; 13], vec![0; 32]))
+                .map(|_| ()),
+        ),
+        (
+            command::Code::PutWrapKey,
+            client
+                .put_wrap_key(
+                    1,
+                    label(),
+                    Domain::DOM1,
+                    caps,
+                    caps,
+                    wrap::Algorithm::Aes128Ccm,
+                    vec![0x42; 16],
+                )
+                .map(|_| ()),
+        ),
+        (
+            command::Code::SetOption,
+            client.set_force_audit_option(AuditOption::Off),
+        ),
+        (
+            command::Code::PutHmacKey,
+            client
+                .put_hmac_key(
+                    1,
+                    label(),
+                    Domain::DOM1,
+                    caps,
+                    hmac::Algorithm::Sha256,
+                    vec![0x42; 32],
+                )
+                .map(|_| ()),
+        ),
+        (
+            command::Code::DeleteObject,
+            client.delete_object(1, object::Type::AuthenticationKey),
+        ),
+        (
+            command::Code::GenerateHmacKey,
+            client
+                .generate_hmac_key(1, label(), Domain::DOM1, caps, hmac::Algorithm::Sha256)
+                .map(|_| ()),
+        ),
+        (
+            command::Code::GenerateWrapKey,
+            client
+                .generate_wrap_key(
+                    1,
+                    label(),
+                    Domain::DOM1,
+                    caps,
+                    caps,
+                    wrap::Algorithm::Aes128Ccm,
+                )
+                .map(|_| ()),
+        ),
+        (
+            command::Code::PutOtpAead,
+            client
+                .put_otp_aead_key(
+                    1,
+                    label(),
+                    Domain::DOM1,
+                    caps,
+                    otp::Algorithm::Aes128,
+                    vec![0x42; 16],
+                )
+                .map(|_| ()),
+        ),
+        (command::Code::SetLogIndex, client.set_log_index(1)),
+        (command::Code::ResetDevice, client.reset_device()),
+    ];
+
+    for (code, result) in results {
+        assert!(code.is_mutating());
+        assert_eq!(
+            *result.unwrap_err().kind(),
+            client::ErrorKind::ReadOnly(code)
+        );
+    }
+
+    // Read-only commands are still permitted
+    client
+        .get_object_info(1, object::Type::AuthenticationKey)
+        .unwrap();
+}
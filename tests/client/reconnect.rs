@@ -0,0 +1,66 @@
+use std::time::{Duration, Instant};
+use yubihsm::{
+    client::{self, ReconnectPolicy},
+    mockhsm::{FakeConnector, MockHsm},
+    Client, Credentials,
+};
+
+/// Reconnection attempts are delayed according to the `ReconnectPolicy`
+#[test]
+fn reconnect_backs_off_between_attempts() {
+    let connector = FakeConnector::new(MockHsm::new()).with_connection_failures(3);
+
+    let mut client = Client::create(connector.clone().into(), Credentials::default()).unwrap();
+    client.set_reconnect_policy(ReconnectPolicy::exponential(
+        Duration::from_millis(10),
+        Duration::from_millis(20),
+        5,
+    ));
+
+    let started_at = Instant::now();
+    client.session().unwrap();
+
+    // 10ms + 20ms + 20ms (capped)
+    assert!(started_at.elapsed() >= Duration::from_millis(50));
+    assert_eq!(connector.connection_failures(), 0);
+}
+
+/// Reconnecting stops after the `ReconnectPolicy`'s maximum attempts
+#[test]
+fn reconnect_gives_up_after_max_attempts() {
+    let connector = FakeConnector::new(MockHsm::new()).with_connection_failures(5);
+
+    let mut client = Client::create(connector.clone().into(), Credentials::default()).unwrap();
+    client.set_reconnect_policy(ReconnectPolicy::exponential(
+        Duration::from_millis(1),
+        Duration::from_millis(1),
+        3,
+    ));
+
+    assert!(client.session().is_err());
+    assert_eq!(connector.connection_failures(), 2);
+}
+
+/// Commands are retried in a new session if the HSM closed the old one
+#[test]
+fn commands_are_retried_after_hsm_closes_session() {
+    let mockhsm = MockHsm::new();
+    let client = Client::open(mockhsm.clone().into(), Credentials::default(), true).unwrap();
+
+    client.echo(b"hello").unwrap();
+    mockhsm.expire_sessions();
+
+    assert_eq!(client.echo(b"still here").unwrap(), b"still here");
+}
+
+/// Without cached credentials, closed sessions can't be reopened
+#[test]
+fn closed_sessions_are_not_retried_without_credentials() {
+    let mockhsm = MockHsm::new();
+    let client = Client::open(mockhsm.clone().into(), Credentials::default(), false).unwrap();
+
+    mockhsm.expire_sessions();
+
+    let err = client.echo(b"gone").unwrap_err();
+    assert_eq!(*err.kind(), client::ErrorKind::ClosedSessionError);
+}
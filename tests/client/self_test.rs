@@ -0,0 +1,56 @@
+//! End-to-end self-tests of the connector, session, and HSM
+
+use yubihsm::{
+    client::self_test::{Options, Step},
+    hmac, object, Capability, Client, Connector, Credentials, Domain,
+};
+
+/// ID of the HMAC key used for self-tests
+const HMAC_KEY_ID: object::Id = 100;
+
+#[test]
+fn all_steps_pass() {
+    let client = Client::open(Connector::mockhsm(), Credentials::default(), true).unwrap();
+
+    client
+        .generate_hmac_key(
+            HMAC_KEY_ID,
+            "self-test".into(),
+            Domain::DOM1,
+            Capability::SIGN_HMAC | Capability::VERIFY_HMAC,
+            hmac::Algorithm::Sha256,
+        )
+        .unwrap();
+
+    let options = Options {
+        hmac_key_id: Some(HMAC_KEY_ID),
+        ..Options::default()
+    };
+
+    let report = client.self_test(&options).unwrap();
+    assert!(report.passed(), "{}", report);
+
+    let steps: Vec<_> = report.steps.iter().map(|result| result.step).collect();
+    assert_eq!(
+        steps,
+        [Step::Echo, Step::GetPseudoRandom, Step::SignVerifyHmac]
+    );
+}
+
+#[test]
+fn missing_test_key_fails_its_step() {
+    let client = Client::open(Connector::mockhsm(), Credentials::default(), true).unwrap();
+
+    let options = Options {
+        echo: false,
+        random_bytes: 0,
+        hmac_key_id: Some(HMAC_KEY_ID),
+    };
+
+    let report = client.self_test(&options).unwrap();
+    assert!(!report.passed());
+
+    let failures: Vec<_> = report.failures().collect();
+    assert_eq!(failures.len(), 1);
+    assert_eq!(failures[0].step, Step::SignVerifyHmac);
+}
@@ -0,0 +1,152 @@
+use std::sync::{Arc, Mutex};
+use yubihsm::{
+    authentication, client, command, device, object, session, Capability, Client, Connector,
+    Credentials, Domain,
+};
+
+/// Session establishment records collected by a hook
+type Establishments = Arc<Mutex<Vec<session::Establishment>>>;
+
+/// Record the client's session establishments in the given list
+fn record_establishments(client: &mut Client, establishments: &Establishments) {
+    let establishments = establishments.clone();
+    client.set_session_establishment_hook(move |establishment| {
+        establishments.lock().unwrap().push(establishment.clone());
+    });
+}
+
+/// The session establishment hook records failed and successful attempts
+#[test]
+fn session_establishment_hook_records_attempts() {
+    let connector = Connector::mockhsm();
+    let default_credentials = Credentials::default();
+    let key_id = default_credentials.authentication_key_id;
+    let wrong_credentials = Credentials::new(key_id, authentication::Key::random());
+    let records = Establishments::default();
+
+    let mut client = Client::create(connector.clone(), wrong_credentials).unwrap();
+    record_establishments(&mut client, &records);
+    assert!(client.session().is_err());
+
+    let mut client = Client::create(connector, default_credentials).unwrap();
+    record_establishments(&mut client, &records);
+    let session_id = client.session().unwrap().id();
+
+    let records = records.lock().unwrap();
+    assert_eq!(records.len(), 2);
+
+    assert_eq!(records[0].authentication_key_id, key_id);
+    assert!(!records[0].succeeded);
+    assert!(records[0].error.is_some());
+    assert_eq!(records[0].session_id, None);
+
+    assert_eq!(records[1].authentication_key_id, key_id);
+    assert!(records[1].succeeded);
+    assert_eq!(records[1].error, None);
+    assert_eq!(records[1].session_id, Some(session_id));
+}
+
+/// The session key's info is only looked up once per session
+#[test]
+fn session_key_info_is_fetched_once_per_session() {
+    let mut client = Client::open(Connector::mockhsm(), Credentials::default(), true).unwrap();
+    client.set_transcript_capacity(8);
+
+    let info = client.session_key_info().unwrap();
+    assert_eq!(info.object_id, Credentials::default().authentication_key_id);
+    assert_eq!(client.session_key_info().unwrap().object_id, info.object_id);
+
+    let lookups = client
+        .session_transcript()
+        .iter()
+        .filter(|entry| entry.command == command::Code::GetObjectInfo)
+        .count();
+
+    assert_eq!(lookups, 1);
+}
+
+/// Deleting the session key mid-session is reported as such
+#[test]
+fn session_key_info_reports_deleted_key() {
+    let connector = Connector::mockhsm();
+    let admin = Client::open(connector.clone(), Credentials::default(), true).unwrap();
+    let credentials = Credentials::new(2, authentication::Key::random());
+
+    admin
+        .put_authentication_key(
+            credentials.authentication_key_id,
+            "deleted".into(),
+            Domain::all(),
+            Capability::all(),
+            Capability::empty(),
+            authentication::Algorithm::YubicoAes,
+            credentials.authentication_key.clone(),
+        )
+        .unwrap();
+
+    let client = Client::open(connector, credentials.clone(), true).unwrap();
+
+    admin
+        .delete_object(
+            credentials.authentication_key_id,
+            object::Type::AuthenticationKey,
+        )
+        .unwrap();
+
+    let err = client.session_key_info().unwrap_err();
+    assert_eq!(*err.kind(), client::ErrorKind::SessionKeyNotFound);
+}
+
+/// The transcript records the most recent commands sent in the session
+#[test]
+fn session_transcript_records_commands() {
+    let mut client = Client::open(Connector::mockhsm(), Credentials::default(), true).unwrap();
+    client.set_transcript_capacity(2);
+
+    client
+        .get_object_info(1, object::Type::AuthenticationKey)
+        .unwrap();
+    client
+        .delete_object(1234, object::Type::Opaque)
+        .unwrap_err();
+    client.blink_device(1).unwrap();
+
+    let transcript = client.session_transcript();
+    assert_eq!(transcript.len(), 2);
+
+    assert_eq!(transcript[0].command, command::Code::DeleteObject);
+    assert_eq!(transcript[0].effect, command::Effect::Destructive);
+    assert_eq!(transcript[0].object_id, Some(1234));
+    assert!(!transcript[0].succeeded);
+    assert_eq!(
+        transcript[0].device_error,
+        Some(device::ErrorKind::ObjectNotFound)
+    );
+
+    assert_eq!(transcript[1].command, command::Code::BlinkDevice);
+    assert_eq!(transcript[1].effect, command::Effect::ReadOnly);
+    assert_eq!(transcript[1].object_id, None);
+    assert!(transcript[1].succeeded);
+}
+
+/// Sessions are replaced once they've carried the configured number of
+/// messages, and the replaced sessions are closed
+#[test]
+fn sessions_are_rotated_at_threshold() {
+    let records = Establishments::default();
+
+    let mut client = Client::create(Connector::mockhsm(), Credentials::default()).unwrap();
+    client.set_session_rotation_threshold(4);
+    record_establishments(&mut client, &records);
+
+    // Authenticating counts as the first message, so each session carries
+    // 3 echoes. The MockHsm only supports 16 concurrent sessions, so this
+    // also checks rotated sessions are closed.
+    for _ in 0..60 {
+        client.echo(b"rotate").unwrap();
+    }
+
+    let records = records.lock().unwrap();
+    assert_eq!(records.len(), 20);
+    assert!(records.iter().all(|establishment| establishment.succeeded));
+}
@@ -0,0 +1,61 @@
+//! Saving and restoring MockHsm state
+
+use yubihsm::{
+    asymmetric,
+    mockhsm::{ErrorKind, MockHsm, SNAPSHOT_VERSION},
+    object, Capability, Client, Connector, Credentials, Domain,
+};
+
+#[test]
+fn round_trip() {
+    let mockhsm = MockHsm::new();
+    let client = Client::open(
+        Connector::from(mockhsm.clone()),
+        Credentials::default(),
+        true,
+    )
+    .unwrap();
+
+    client
+        .generate_asymmetric_key(
+            100,
+            "snapshot".into(),
+            Domain::DOM1,
+            Capability::SIGN_EDDSA,
+            asymmetric::Algorithm::Ed25519,
+        )
+        .unwrap();
+
+    let public_key = client.get_public_key(100).unwrap();
+    let snapshot = mockhsm.snapshot();
+    assert_eq!(snapshot[0], SNAPSHOT_VERSION);
+
+    let restored = MockHsm::from_snapshot(&snapshot).unwrap();
+    let client = Client::open(Connector::from(restored), Credentials::default(), true).unwrap();
+
+    assert_eq!(client.get_public_key(100).unwrap(), public_key);
+
+    let info = client
+        .get_object_info(100, object::Type::AsymmetricKey)
+        .unwrap();
+
+    assert_eq!(info.label.to_string(), "snapshot");
+    assert_eq!(info.capabilities, Capability::SIGN_EDDSA);
+}
+
+#[test]
+fn rejects_unknown_version() {
+    let mut snapshot = MockHsm::new().snapshot();
+    snapshot[0] = SNAPSHOT_VERSION + 1;
+
+    let err = MockHsm::from_snapshot(&snapshot).unwrap_err();
+    assert_eq!(*err.kind(), ErrorKind::SnapshotInvalid);
+}
+
+#[test]
+fn rejects_truncated_snapshot() {
+    let snapshot = MockHsm::new().snapshot();
+
+    let err = MockHsm::from_snapshot(&snapshot[..snapshot.len() - 1]).unwrap_err();
+    assert_eq!(*err.kind(), ErrorKind::SnapshotInvalid);
+}
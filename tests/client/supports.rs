@@ -0,0 +1,38 @@
+//! Checking which commands the HSM supports
+
+use yubihsm::{client, command, device, Client, Connector, Credentials};
+
+/// Commands the MockHsm doesn't implement are rejected as invalid, like a
+/// real HSM rejects commands its firmware doesn't implement
+#[test]
+fn mockhsm_rejects_unimplemented_commands_as_invalid() {
+    let client = Client::open(Connector::mockhsm(), Credentials::default(), true).unwrap();
+    let err = client.sign_attestation_certificate(1, None).unwrap_err();
+
+    assert_eq!(*err.kind(), client::ErrorKind::DeviceError);
+    assert_eq!(err.device_error(), Some(device::ErrorKind::InvalidCommand));
+
+    // The session remains usable afterwards
+    client.blink_device(1).unwrap();
+}
+
+/// Supported commands are checked against the device info, which is only
+/// fetched once
+#[test]
+fn supports_checks_the_mockhsm_device_info() {
+    let mut client = Client::open(Connector::mockhsm(), Credentials::default(), true).unwrap();
+    client.set_transcript_capacity(8);
+
+    assert!(client.supports(command::Code::DeriveEcdh).unwrap());
+    assert!(client
+        .supports(command::Code::ChangeAuthenticationKey)
+        .unwrap());
+    assert!(!client.supports(command::Code::Bsl).unwrap());
+
+    let device_info_requests = client
+        .session_transcript()
+        .iter()
+        .filter(|entry| entry.command == command::Code::DeviceInfo)
+        .count();
+    assert_eq!(device_info_requests, 1);
+}
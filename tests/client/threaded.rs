@@ -0,0 +1,102 @@
+//! `ThreadedClient` tests, with a minimal executor to drive its futures
+
+use std::{
+    future::Future,
+    mem::ManuallyDrop,
+    sync::Arc,
+    task::{Context, Poll, RawWaker, RawWakerVTable, Waker},
+    thread,
+};
+use yubihsm::{
+    authentication,
+    client::{self, ThreadedClient},
+    Connector, Credentials,
+};
+
+/// Vtable for wakers which unpark a thread blocked in `block_on`. The
+/// waker's data is an `Arc<thread::Thread>` converted with `Arc::into_raw`.
+static VTABLE: RawWakerVTable = RawWakerVTable::new(clone_waker, wake, wake_by_ref, drop_waker);
+
+unsafe fn clone_waker(data: *const ()) -> RawWaker {
+    let thread = ManuallyDrop::new(Arc::from_raw(data as *const thread::Thread));
+    let data = Arc::into_raw(Arc::clone(&thread)) as *const ();
+    RawWaker::new(data, &VTABLE)
+}
+
+unsafe fn wake(data: *const ()) {
+    Arc::from_raw(data as *const thread::Thread).unpark();
+}
+
+unsafe fn wake_by_ref(data: *const ()) {
+    (*(data as *const thread::Thread)).unpark();
+}
+
+unsafe fn drop_waker(data: *const ()) {
+    drop(Arc::from_raw(data as *const thread::Thread));
+}
+
+/// Poll the future, parking the thread until woken
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = Box::pin(future);
+    let data = Arc::into_raw(Arc::new(thread::current())) as *const ();
+    let waker = unsafe { Waker::from_raw(RawWaker::new(data, &VTABLE)) };
+    let mut cx = Context::from_waker(&waker);
+
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+/// Commands are performed on the worker thread, in the order they're sent
+#[test]
+fn commands_are_performed_on_the_worker() {
+    let client = block_on(ThreadedClient::open(
+        Connector::mockhsm(),
+        Credentials::default(),
+        true,
+    ))
+    .unwrap();
+
+    assert_eq!(block_on(client.echo(b"hello".to_vec())).unwrap(), b"hello");
+
+    // Commands sent without awaiting in between are performed in order
+    let futures: Vec<_> = (0..4u8).map(|i| client.echo(vec![i])).collect();
+
+    for (i, future) in futures.into_iter().enumerate() {
+        assert_eq!(block_on(future).unwrap(), [i as u8]);
+    }
+}
+
+/// Errors opening the session are returned by `ThreadedClient::open`
+#[test]
+fn open_errors_are_returned() {
+    let credentials = Credentials::new(0x1234, authentication::Key::random());
+    let result = block_on(ThreadedClient::open(
+        Connector::mockhsm(),
+        credentials,
+        true,
+    ));
+    assert!(result.is_err());
+}
+
+/// Futures resolve to an error, rather than never completing, if the worker
+/// thread exits (here, because a job panicked)
+#[test]
+fn worker_exiting_resolves_futures() {
+    let client = block_on(ThreadedClient::open(
+        Connector::mockhsm(),
+        Credentials::default(),
+        true,
+    ))
+    .unwrap();
+
+    let err = block_on(client.call(|_| -> Result<(), client::Error> { panic!("job failed") }))
+        .unwrap_err();
+    assert_eq!(*err.kind(), client::ErrorKind::ClosedSessionError);
+
+    let err = block_on(client.echo(b"hello".to_vec())).unwrap_err();
+    assert_eq!(*err.kind(), client::ErrorKind::ClosedSessionError);
+}
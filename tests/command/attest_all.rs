@@ -0,0 +1,55 @@
+use crate::{generate_asymmetric_key, TEST_KEY_ID};
+use yubihsm::{asymmetric, Capability};
+
+/// Attest all generated asymmetric keys in the HSM
+#[test]
+fn attest_all_test() {
+    let client = crate::get_hsm_client();
+
+    generate_asymmetric_key(
+        &client,
+        asymmetric::Algorithm::Ed25519,
+        Capability::SIGN_EDDSA,
+    );
+
+    let certificates = client
+        .attest_all()
+        .unwrap_or_else(|err| panic!("error attesting keys: {}", err));
+
+    let (_, result) = certificates
+        .iter()
+        .find(|(key_id, _)| *key_id == TEST_KEY_ID)
+        .expect("generated key missing from attestations");
+
+    // The MockHsm doesn't support attestation, so the failure should be
+    // reported for the individual key
+    #[cfg(feature = "mockhsm")]
+    assert_eq!(
+        result.as_ref().unwrap_err().device_error(),
+        Some(yubihsm::device::ErrorKind::InvalidCommand)
+    );
+
+    #[cfg(not(feature = "mockhsm"))]
+    assert!(!result.as_ref().unwrap().as_slice().is_empty());
+}
+
+/// Imported keys aren't attested
+#[test]
+fn attest_all_skips_imported_keys_test() {
+    let client = crate::get_hsm_client();
+
+    crate::put_asymmetric_key(
+        &client,
+        asymmetric::Algorithm::Ed25519,
+        Capability::SIGN_EDDSA,
+        [0x42u8; 32].as_ref(),
+    );
+
+    let certificates = client
+        .attest_all()
+        .unwrap_or_else(|err| panic!("error attesting keys: {}", err));
+
+    assert!(certificates
+        .iter()
+        .all(|(key_id, _)| *key_id != TEST_KEY_ID));
+}
@@ -0,0 +1,76 @@
+#[cfg(feature = "mockhsm")]
+use yubihsm::mockhsm::MockHsm;
+use yubihsm::{authentication, object, Capability, Client, Credentials};
+
+use crate::{clear_test_key_slot, TEST_DOMAINS, TEST_KEY_ID, TEST_KEY_LABEL, TEST_MESSAGE};
+
+/// Change the authentication key used to open a session
+#[test]
+fn change_authentication_key() {
+    let client = crate::get_hsm_client();
+    let old_credentials = Credentials::from_password(TEST_KEY_ID, TEST_MESSAGE);
+
+    clear_test_key_slot(&client, object::Type::AuthenticationKey);
+
+    client
+        .put_authentication_key(
+            TEST_KEY_ID,
+            TEST_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            Capability::CHANGE_AUTHENTICATION_KEY,
+            Capability::empty(),
+            authentication::Algorithm::YubicoAes,
+            old_credentials.authentication_key.clone(),
+        )
+        .unwrap_or_else(|err| panic!("error putting auth key: {}", err));
+
+    let mut session_client =
+        Client::open(client.connector().clone(), old_credentials.clone(), true)
+            .unwrap_or_else(|err| panic!("error opening session with old key: {}", err));
+
+    let key_id = session_client
+        .change_authentication_key_from_password(TEST_KEY_ID, b"new password")
+        .unwrap_or_else(|err| panic!("error changing auth key: {}", err));
+
+    assert_eq!(key_id, TEST_KEY_ID);
+    drop(session_client);
+
+    assert!(Client::open(client.connector().clone(), old_credentials, false).is_err());
+
+    let new_credentials = Credentials::from_password(TEST_KEY_ID, b"new password");
+    Client::open(client.connector().clone(), new_credentials, false)
+        .unwrap_or_else(|err| panic!("error opening session with new key: {}", err));
+
+    let object_info = client
+        .get_object_info(TEST_KEY_ID, object::Type::AuthenticationKey)
+        .unwrap_or_else(|err| panic!("error getting object info: {}", err));
+
+    assert_eq!(
+        object_info.capabilities,
+        Capability::CHANGE_AUTHENTICATION_KEY
+    );
+    assert_eq!(&object_info.label.to_string(), TEST_KEY_LABEL);
+}
+
+/// Sessions reopened after changing the authentication key use the new key
+#[cfg(feature = "mockhsm")]
+#[test]
+fn change_authentication_key_updates_cached_credentials() {
+    let mockhsm = MockHsm::new();
+    let key_id = Credentials::default().authentication_key_id;
+    let mut client = Client::open(mockhsm.clone().into(), Credentials::default(), true).unwrap();
+
+    client
+        .change_authentication_key_from_password(key_id, b"rotated")
+        .unwrap_or_else(|err| panic!("error changing auth key: {}", err));
+
+    // Reconnecting must use the new key
+    mockhsm.expire_sessions();
+    client
+        .echo(b"reconnected")
+        .unwrap_or_else(|err| panic!("error reconnecting with new key: {}", err));
+
+    let new_credentials = Credentials::from_password(key_id, b"rotated");
+    Client::open(mockhsm.into(), new_credentials, false)
+        .unwrap_or_else(|err| panic!("error opening session with new key: {}", err));
+}
@@ -0,0 +1,22 @@
+//! OTP AEAD creation tests
+
+use crate::TEST_KEY_ID;
+use yubihsm::client;
+
+/// OTP keys and private IDs are length checked before being sent to the HSM
+#[test]
+fn inputs_are_length_checked() {
+    let client = crate::get_hsm_client();
+
+    let err = client
+        .create_otp_aead(TEST_KEY_ID, &[0; 15], &[0; 6])
+        .unwrap_err();
+    assert_eq!(*err.kind(), client::ErrorKind::ProtocolError);
+    assert!(err.to_string().contains("OTP key"));
+
+    let err = client
+        .create_otp_aead(TEST_KEY_ID, &[0; 16], &[0; 7])
+        .unwrap_err();
+    assert_eq!(*err.kind(), client::ErrorKind::ProtocolError);
+    assert!(err.to_string().contains("private ID"));
+}
@@ -0,0 +1,41 @@
+//! RSA-OAEP decryption tests
+
+use crate::{generate_asymmetric_key, TEST_KEY_ID};
+use sha2::{Digest, Sha256};
+use yubihsm::{asymmetric, client, rsa, Capability};
+
+/// Decryption requests are checked against the key's algorithm and the OAEP
+/// parameters before being sent to the HSM
+#[test]
+fn decryption_is_checked_against_key_algorithm() {
+    let client = crate::get_hsm_client();
+
+    generate_asymmetric_key(
+        &client,
+        asymmetric::Algorithm::Ed25519,
+        Capability::DECRYPT_OAEP,
+    );
+
+    let label_hash = Sha256::digest(b"");
+
+    let err = client
+        .decrypt_oaep(
+            TEST_KEY_ID,
+            rsa::mgf::Algorithm::Sha256,
+            &label_hash,
+            &[0; 256],
+        )
+        .unwrap_err();
+    assert_eq!(*err.kind(), client::ErrorKind::ProtocolError);
+    assert!(err.to_string().contains("is not an RSA key"));
+
+    let err = client
+        .decrypt_oaep(
+            TEST_KEY_ID,
+            rsa::mgf::Algorithm::Sha256,
+            &[0; 31],
+            &[0; 256],
+        )
+        .unwrap_err();
+    assert!(err.to_string().contains("invalid OAEP label hash length"));
+}
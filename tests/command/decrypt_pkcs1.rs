@@ -0,0 +1,21 @@
+//! RSA PKCS#1v1.5 decryption tests
+
+use crate::{generate_asymmetric_key, TEST_KEY_ID};
+use yubihsm::{asymmetric, client, Capability};
+
+/// Decryption requests are checked against the key's algorithm before being
+/// sent to the HSM
+#[test]
+fn decryption_is_checked_against_key_algorithm() {
+    let client = crate::get_hsm_client();
+
+    generate_asymmetric_key(
+        &client,
+        asymmetric::Algorithm::Ed25519,
+        Capability::DECRYPT_PKCS,
+    );
+
+    let err = client.decrypt_pkcs1v15(TEST_KEY_ID, &[0; 256]).unwrap_err();
+    assert_eq!(*err.kind(), client::ErrorKind::ProtocolError);
+    assert!(err.to_string().contains("is not an RSA key"));
+}
@@ -0,0 +1,34 @@
+//! ECDH key derivation tests
+
+use crate::{generate_asymmetric_key, TEST_KEY_ID};
+use yubihsm::{asymmetric, client, ecdh, Capability};
+
+/// Public keys are checked against the key's algorithm before being sent to
+/// the HSM
+#[test]
+fn public_key_length_is_checked_against_key_algorithm() {
+    let client = crate::get_hsm_client();
+    let point = |len| ecdh::UncompressedPoint::from_bytes(vec![0x04; len]).unwrap();
+
+    generate_asymmetric_key(
+        &client,
+        asymmetric::Algorithm::EcP256,
+        Capability::DERIVE_ECDH,
+    );
+
+    let err = client.derive_ecdh(TEST_KEY_ID, point(97)).unwrap_err();
+    assert_eq!(*err.kind(), client::ErrorKind::ProtocolError);
+    assert!(err
+        .to_string()
+        .contains("expected 65-byte public key for Asymmetric(EcP256), got 97"));
+
+    generate_asymmetric_key(
+        &client,
+        asymmetric::Algorithm::Ed25519,
+        Capability::DERIVE_ECDH,
+    );
+
+    let err = client.derive_ecdh(TEST_KEY_ID, point(65)).unwrap_err();
+    assert_eq!(*err.kind(), client::ErrorKind::ProtocolError);
+    assert!(err.to_string().contains("is not an ECDH key"));
+}
@@ -10,4 +10,6 @@ fn device_info_test() {
     // This should always be 2. The minor and patch versions will vary
     // depending on the specific YubiHSM 2 model.
     assert_eq!(device_info.major_version, 2);
+    assert_eq!(device_info.version().major, 2);
+    assert!(device_info.version() >= yubihsm::device::Version::new(2, 0, 0));
 }
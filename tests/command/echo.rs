@@ -1,12 +1,31 @@
-use TEST_MESSAGE;
+use crate::TEST_MESSAGE;
+use yubihsm::client;
 
 /// Send a simple echo request
 #[test]
 fn echo_test() {
-    let client = crate::create_client();
+    let client = crate::get_hsm_client();
 
-    let echo_response = client.echo(TEST_MESSAGE)
+    let echo_response = client
+        .echo(TEST_MESSAGE)
         .unwrap_or_else(|err| panic!("error sending echo: {}", err));
 
     assert_eq!(TEST_MESSAGE, echo_response.as_slice());
 }
+
+/// Echo the largest message which fits in a session message, and make sure
+/// anything larger is rejected before being sent
+#[test]
+fn echo_max_size_test() {
+    let client = crate::get_hsm_client();
+    let message = vec![0x42; 2028];
+
+    let echo_response = client
+        .echo(message.clone())
+        .unwrap_or_else(|err| panic!("error sending echo: {}", err));
+
+    assert_eq!(message, echo_response);
+
+    let err = client.echo(vec![0x42; 2029]).unwrap_err();
+    assert_eq!(*err.kind(), client::ErrorKind::ProtocolError);
+}
@@ -0,0 +1,29 @@
+use crate::{generate_asymmetric_key, TEST_KEY_ID};
+use yubihsm::{asymmetric, Capability};
+
+/// Export the public keys of all asymmetric keys in the HSM
+#[test]
+fn export_public_keys_test() {
+    let client = crate::get_hsm_client();
+
+    generate_asymmetric_key(
+        &client,
+        asymmetric::Algorithm::Ed25519,
+        Capability::SIGN_EDDSA,
+    );
+
+    let expected_key = client
+        .get_public_key(TEST_KEY_ID)
+        .unwrap_or_else(|err| panic!("error getting public key: {}", err));
+
+    let public_keys = client
+        .export_public_keys()
+        .unwrap_or_else(|err| panic!("error exporting public keys: {}", err));
+
+    let (_, public_key) = public_keys
+        .iter()
+        .find(|(key_id, _)| *key_id == TEST_KEY_ID)
+        .expect("generated key missing from exported public keys");
+
+    assert_eq!(public_key, &expected_key);
+}
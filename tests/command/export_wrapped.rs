@@ -2,7 +2,8 @@ use crate::{
     clear_test_key_slot, test_vectors::AESCCM_TEST_VECTORS, TEST_DOMAINS, TEST_EXPORTED_KEY_ID,
     TEST_EXPORTED_KEY_LABEL, TEST_KEY_ID, TEST_KEY_LABEL,
 };
-use yubihsm::{asymmetric, object, wrap, Capability};
+use std::{env, fs, process};
+use yubihsm::{asymmetric, client, device, object, opaque, wrap, Capability, Domain};
 
 /// Test wrap key workflow using randomly generated keys
 // TODO: test against RFC 3610 vectors
@@ -78,3 +79,365 @@ fn wrap_key_test() {
         TEST_EXPORTED_KEY_LABEL
     );
 }
+
+/// Export a wrapped key to a file and import it back
+#[test]
+fn wrap_key_file_test() {
+    let client = crate::get_hsm_client();
+    let exported_key_type = object::Type::AsymmetricKey;
+
+    clear_test_key_slot(&client, object::Type::WrapKey);
+
+    client
+        .put_wrap_key(
+            TEST_KEY_ID,
+            TEST_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            Capability::EXPORT_WRAPPED | Capability::IMPORT_WRAPPED,
+            Capability::all(),
+            wrap::Algorithm::Aes128Ccm,
+            AESCCM_TEST_VECTORS[0].key,
+        )
+        .unwrap_or_else(|err| panic!("error generating wrap key: {}", err));
+
+    let _ = client.delete_object(TEST_EXPORTED_KEY_ID, exported_key_type);
+
+    client
+        .generate_asymmetric_key(
+            TEST_EXPORTED_KEY_ID,
+            TEST_EXPORTED_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            Capability::SIGN_EDDSA | Capability::EXPORTABLE_UNDER_WRAP,
+            asymmetric::Algorithm::Ed25519,
+        )
+        .unwrap_or_else(|err| panic!("error generating asymmetric key: {}", err));
+
+    let path = env::temp_dir().join(format!("yubihsm-test-{}.yhw", process::id()));
+
+    client
+        .export_wrapped_to_file(TEST_KEY_ID, exported_key_type, TEST_EXPORTED_KEY_ID, &path)
+        .unwrap_or_else(|err| panic!("error exporting key: {}", err));
+
+    assert!(client
+        .delete_object(TEST_EXPORTED_KEY_ID, exported_key_type)
+        .is_ok());
+
+    let import_response = client
+        .import_wrapped_from_file(TEST_KEY_ID, &path)
+        .unwrap_or_else(|err| panic!("error importing key: {}", err));
+
+    assert_eq!(import_response.object_type, exported_key_type);
+    assert_eq!(import_response.object_id, TEST_EXPORTED_KEY_ID);
+
+    // Truncate the file and ensure the import is rejected
+    let contents = fs::read_to_string(&path).unwrap();
+    fs::write(&path, &contents[..8]).unwrap();
+
+    let err = wrap::Message::read_file(&path).unwrap_err();
+    assert_eq!(*err.kind(), wrap::ErrorKind::LengthInvalid);
+
+    fs::write(&path, "not a wrap message!\n").unwrap();
+
+    let err = wrap::Message::read_file(&path).unwrap_err();
+    assert_eq!(*err.kind(), wrap::ErrorKind::FormatInvalid);
+
+    fs::remove_file(&path).unwrap();
+}
+
+/// Restore a backup using a wrap key re-derived from a passphrase
+#[cfg(feature = "passwords")]
+#[test]
+fn passphrase_wrap_key_test() {
+    let client = crate::get_hsm_client();
+    let algorithm = wrap::Algorithm::Aes256Ccm;
+    let exported_key_type = object::Type::AsymmetricKey;
+    let key = wrap::derive_key_from_passphrase(
+        b"correct horse battery staple",
+        b"yubihsm.rs salt!",
+        algorithm,
+    );
+
+    let put_wrap_key = || {
+        clear_test_key_slot(&client, object::Type::WrapKey);
+
+        client
+            .put_wrap_key(
+                TEST_KEY_ID,
+                TEST_KEY_LABEL.into(),
+                TEST_DOMAINS,
+                Capability::EXPORT_WRAPPED | Capability::IMPORT_WRAPPED,
+                Capability::all(),
+                algorithm,
+                key.to_vec(),
+            )
+            .unwrap_or_else(|err| panic!("error putting wrap key: {}", err));
+    };
+
+    put_wrap_key();
+
+    let _ = client.delete_object(TEST_EXPORTED_KEY_ID, exported_key_type);
+
+    client
+        .generate_asymmetric_key(
+            TEST_EXPORTED_KEY_ID,
+            TEST_EXPORTED_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            Capability::SIGN_EDDSA | Capability::EXPORTABLE_UNDER_WRAP,
+            asymmetric::Algorithm::Ed25519,
+        )
+        .unwrap_or_else(|err| panic!("error generating asymmetric key: {}", err));
+
+    let public_key = client.get_public_key(TEST_EXPORTED_KEY_ID).unwrap();
+
+    let wrap_data = client
+        .export_wrapped(TEST_KEY_ID, exported_key_type, TEST_EXPORTED_KEY_ID)
+        .unwrap_or_else(|err| panic!("error exporting key: {}", err));
+
+    client
+        .delete_object(TEST_EXPORTED_KEY_ID, exported_key_type)
+        .unwrap();
+
+    // Restore into a fresh wrap key object with the same derived key
+    put_wrap_key();
+
+    let handle = client
+        .import_wrapped(TEST_KEY_ID, wrap_data)
+        .unwrap_or_else(|err| panic!("error importing key: {}", err));
+
+    assert_eq!(handle.object_id, TEST_EXPORTED_KEY_ID);
+    assert_eq!(
+        client.get_public_key(TEST_EXPORTED_KEY_ID).unwrap(),
+        public_key
+    );
+}
+
+/// Exporting an object with domains the wrap key doesn't have fails before
+/// reaching the HSM
+#[test]
+fn wrap_domain_mismatch_test() {
+    let client = crate::get_hsm_client();
+
+    clear_test_key_slot(&client, object::Type::WrapKey);
+
+    client
+        .put_wrap_key(
+            TEST_KEY_ID,
+            TEST_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            Capability::EXPORT_WRAPPED,
+            Capability::all(),
+            wrap::Algorithm::Aes128Ccm,
+            AESCCM_TEST_VECTORS[0].key,
+        )
+        .unwrap_or_else(|err| panic!("error putting wrap key: {}", err));
+
+    let exported_key_type = object::Type::AsymmetricKey;
+    let _ = client.delete_object(TEST_EXPORTED_KEY_ID, exported_key_type);
+
+    client
+        .generate_asymmetric_key(
+            TEST_EXPORTED_KEY_ID,
+            TEST_EXPORTED_KEY_LABEL.into(),
+            TEST_DOMAINS | Domain::DOM2,
+            Capability::SIGN_EDDSA | Capability::EXPORTABLE_UNDER_WRAP,
+            asymmetric::Algorithm::Ed25519,
+        )
+        .unwrap_or_else(|err| panic!("error generating asymmetric key: {}", err));
+
+    assert!(!client
+        .can_wrap(TEST_KEY_ID, exported_key_type, TEST_EXPORTED_KEY_ID)
+        .unwrap());
+
+    let err = client
+        .export_wrapped(TEST_KEY_ID, exported_key_type, TEST_EXPORTED_KEY_ID)
+        .unwrap_err();
+
+    assert_eq!(*err.kind(), client::ErrorKind::DomainMismatch);
+    assert!(err.to_string().contains("DOM2"), "{}", err);
+}
+
+/// Objects can only be wrapped or unwrapped with a wrap key which is
+/// delegated all of their capabilities
+#[test]
+fn wrap_delegated_capabilities_test() {
+    let client = crate::get_hsm_client();
+    let exported_key_type = object::Type::AsymmetricKey;
+
+    let put_wrap_key = |delegated_capabilities| {
+        clear_test_key_slot(&client, object::Type::WrapKey);
+
+        client
+            .put_wrap_key(
+                TEST_KEY_ID,
+                TEST_KEY_LABEL.into(),
+                TEST_DOMAINS,
+                Capability::EXPORT_WRAPPED | Capability::IMPORT_WRAPPED,
+                delegated_capabilities,
+                wrap::Algorithm::Aes128Ccm,
+                AESCCM_TEST_VECTORS[0].key,
+            )
+            .unwrap_or_else(|err| panic!("error putting wrap key: {}", err));
+    };
+
+    let _ = client.delete_object(TEST_EXPORTED_KEY_ID, exported_key_type);
+
+    client
+        .generate_asymmetric_key(
+            TEST_EXPORTED_KEY_ID,
+            TEST_EXPORTED_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            Capability::SIGN_EDDSA | Capability::EXPORTABLE_UNDER_WRAP,
+            asymmetric::Algorithm::Ed25519,
+        )
+        .unwrap_or_else(|err| panic!("error generating asymmetric key: {}", err));
+
+    // Wrap key isn't delegated `sign-eddsa`
+    put_wrap_key(Capability::EXPORTABLE_UNDER_WRAP);
+
+    let err = client
+        .export_wrapped(TEST_KEY_ID, exported_key_type, TEST_EXPORTED_KEY_ID)
+        .unwrap_err();
+
+    assert_eq!(
+        err.device_error(),
+        Some(device::ErrorKind::InsufficientPermissions)
+    );
+
+    put_wrap_key(Capability::all());
+
+    let wrap_data = client
+        .export_wrapped(TEST_KEY_ID, exported_key_type, TEST_EXPORTED_KEY_ID)
+        .unwrap_or_else(|err| panic!("error exporting key: {}", err));
+
+    client
+        .delete_object(TEST_EXPORTED_KEY_ID, exported_key_type)
+        .unwrap();
+
+    // Same key material, without `sign-eddsa` delegated
+    put_wrap_key(Capability::EXPORTABLE_UNDER_WRAP);
+
+    let err = client.import_wrapped(TEST_KEY_ID, wrap_data).unwrap_err();
+
+    assert_eq!(
+        err.device_error(),
+        Some(device::ErrorKind::InsufficientPermissions)
+    );
+}
+
+/// Only objects with the `exportable-under-wrap` capability are exportable
+#[test]
+fn is_exportable_test() {
+    let client = crate::get_hsm_client();
+    let object_type = object::Type::AsymmetricKey;
+
+    for &exportable in &[true, false] {
+        let mut capabilities = Capability::SIGN_EDDSA;
+
+        if exportable {
+            capabilities |= Capability::EXPORTABLE_UNDER_WRAP;
+        }
+
+        let _ = client.delete_object(TEST_EXPORTED_KEY_ID, object_type);
+
+        client
+            .generate_asymmetric_key(
+                TEST_EXPORTED_KEY_ID,
+                TEST_EXPORTED_KEY_LABEL.into(),
+                TEST_DOMAINS,
+                capabilities,
+                asymmetric::Algorithm::Ed25519,
+            )
+            .unwrap_or_else(|err| panic!("error generating asymmetric key: {}", err));
+
+        assert_eq!(
+            client
+                .is_exportable(TEST_EXPORTED_KEY_ID, object_type)
+                .unwrap(),
+            exportable
+        );
+    }
+}
+
+/// Importing a batch stops at the first failure and rolls back the objects
+/// imported before it
+#[test]
+fn import_all_rollback_test() {
+    let client = crate::get_hsm_client();
+
+    clear_test_key_slot(&client, object::Type::WrapKey);
+
+    client
+        .put_wrap_key(
+            TEST_KEY_ID,
+            TEST_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            Capability::EXPORT_WRAPPED | Capability::IMPORT_WRAPPED,
+            Capability::all(),
+            wrap::Algorithm::Aes128Ccm,
+            AESCCM_TEST_VECTORS[0].key,
+        )
+        .unwrap_or_else(|err| panic!("error putting wrap key: {}", err));
+
+    let asymmetric_key = object::Handle::new(TEST_EXPORTED_KEY_ID, object::Type::AsymmetricKey);
+    let opaque_object = object::Handle::new(TEST_EXPORTED_KEY_ID, object::Type::Opaque);
+    let handles = [asymmetric_key.clone(), opaque_object.clone()];
+
+    for handle in &handles {
+        let _ = client.delete_object(handle.object_id, handle.object_type);
+    }
+
+    client
+        .generate_asymmetric_key(
+            TEST_EXPORTED_KEY_ID,
+            TEST_EXPORTED_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            Capability::SIGN_EDDSA | Capability::EXPORTABLE_UNDER_WRAP,
+            asymmetric::Algorithm::Ed25519,
+        )
+        .unwrap_or_else(|err| panic!("error generating asymmetric key: {}", err));
+
+    client
+        .put_opaque(
+            TEST_EXPORTED_KEY_ID,
+            TEST_EXPORTED_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            Capability::EXPORTABLE_UNDER_WRAP,
+            opaque::Algorithm::Data,
+            b"backed up".to_vec(),
+        )
+        .unwrap_or_else(|err| panic!("error putting opaque object: {}", err));
+
+    let mut batch = vec![];
+
+    for handle in &handles {
+        batch.push(
+            client
+                .export_wrapped(TEST_KEY_ID, handle.object_type, handle.object_id)
+                .unwrap_or_else(|err| panic!("error exporting object: {}", err)),
+        );
+
+        client
+            .delete_object(handle.object_id, handle.object_type)
+            .unwrap();
+    }
+
+    // Corrupt a copy of the last message so its import fails
+    let mut corrupted = batch[1].clone();
+    *corrupted.ciphertext.last_mut().unwrap() ^= 0xff;
+    batch.push(corrupted);
+
+    let report = client.import_all(TEST_KEY_ID, batch, wrap::ImportMode::Rollback);
+
+    assert!(!report.is_complete());
+    assert!(report.is_consistent());
+    assert_eq!(report.failure.as_ref().unwrap().index, 2);
+    assert_eq!(report.rolled_back, [opaque_object, asymmetric_key]);
+    assert!(report.imported.is_empty());
+    assert!(report.rollback_failures.is_empty());
+
+    for handle in &handles {
+        assert!(client
+            .get_object_info(handle.object_id, handle.object_type)
+            .is_err());
+    }
+}
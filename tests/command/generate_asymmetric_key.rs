@@ -1,5 +1,7 @@
-use crate::{generate_asymmetric_key, TEST_DOMAINS, TEST_KEY_ID, TEST_KEY_LABEL};
-use yubihsm::{asymmetric, object, Capability};
+use crate::{
+    clear_test_key_slot, generate_asymmetric_key, TEST_DOMAINS, TEST_KEY_ID, TEST_KEY_LABEL,
+};
+use yubihsm::{asymmetric, device, object, Capability};
 
 /// Generate an Ed25519 key
 #[test]
@@ -24,6 +26,27 @@ fn ed25519_key_test() {
     assert_eq!(&object_info.label.to_string(), TEST_KEY_LABEL);
 }
 
+/// Generate an Ed25519 key and fetch its public key
+#[test]
+fn ed25519_key_with_public_test() {
+    let client = crate::get_hsm_client();
+    clear_test_key_slot(&client, object::Type::AsymmetricKey);
+
+    let (key_id, public_key) = client
+        .generate_asymmetric_key_with_public(
+            TEST_KEY_ID,
+            TEST_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            Capability::SIGN_EDDSA,
+            asymmetric::Algorithm::Ed25519,
+        )
+        .unwrap_or_else(|err| panic!("error generating asymmetric key: {}", err));
+
+    assert_eq!(key_id, TEST_KEY_ID);
+    assert_eq!(public_key.algorithm, asymmetric::Algorithm::Ed25519);
+    assert_eq!(public_key, client.get_public_key(TEST_KEY_ID).unwrap());
+}
+
 /// Generate a NIST P-256 key
 #[cfg(not(feature = "mockhsm"))]
 #[test]
@@ -46,3 +69,83 @@ fn nistp256_key_test() {
     assert_eq!(object_info.origin, object::Origin::Generated);
     assert_eq!(&object_info.label.to_string(), TEST_KEY_LABEL);
 }
+
+/// Generate a NIST P-256 key and fetch its compressed public key
+#[test]
+fn nistp256_key_with_compressed_public_test() {
+    let client = crate::get_hsm_client();
+    clear_test_key_slot(&client, object::Type::AsymmetricKey);
+
+    let (key_id, public_key) = client
+        .generate_asymmetric_key_with_encoded_public(
+            TEST_KEY_ID,
+            TEST_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            Capability::SIGN_ECDSA,
+            asymmetric::Algorithm::EcP256,
+            asymmetric::PointFormat::Compressed,
+        )
+        .unwrap_or_else(|err| panic!("error generating asymmetric key: {}", err));
+
+    assert_eq!(key_id, TEST_KEY_ID);
+    assert_eq!(public_key.len(), 33);
+    assert!(public_key[0] == 0x02 || public_key[0] == 0x03);
+}
+
+/// Generate keys with device-assigned IDs
+#[test]
+fn auto_id_test() {
+    let client = crate::get_hsm_client();
+
+    let mut key_ids = vec![];
+
+    for _ in 0..2 {
+        let key_id = client
+            .generate_asymmetric_key(
+                object::AUTO_ID,
+                TEST_KEY_LABEL.into(),
+                TEST_DOMAINS,
+                Capability::SIGN_EDDSA,
+                asymmetric::Algorithm::Ed25519,
+            )
+            .unwrap_or_else(|err| panic!("error generating asymmetric key: {}", err));
+
+        assert_ne!(key_id, object::AUTO_ID);
+
+        let object_info = client
+            .get_object_info(key_id, object::Type::AsymmetricKey)
+            .unwrap();
+
+        assert_eq!(object_info.object_id, key_id);
+        key_ids.push(key_id);
+    }
+
+    assert_ne!(key_ids[0], key_ids[1]);
+
+    for key_id in key_ids {
+        client
+            .delete_object(key_id, object::Type::AsymmetricKey)
+            .unwrap();
+    }
+}
+
+/// Generating keys the MockHsm doesn't support fails without poisoning it
+#[cfg(feature = "mockhsm")]
+#[test]
+fn unsupported_algorithm_test() {
+    let client = crate::get_hsm_client();
+    clear_test_key_slot(&client, object::Type::AsymmetricKey);
+
+    let err = client
+        .generate_asymmetric_key(
+            TEST_KEY_ID,
+            TEST_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            Capability::SIGN_PKCS,
+            asymmetric::Algorithm::Rsa4096,
+        )
+        .unwrap_err();
+
+    assert_eq!(err.device_error(), Some(device::ErrorKind::InvalidData));
+    client.blink_device(1).unwrap();
+}
@@ -8,3 +8,84 @@ fn get_audit_logs_test() {
         .get_log_entries()
         .unwrap_or_else(|err| panic!("error getting logs: {}", err));
 }
+
+/// Detect whether the audit log is being written
+#[cfg(feature = "mockhsm")]
+#[test]
+fn get_audit_state_test() {
+    use yubihsm::audit::{AuditOption, AuditState};
+
+    let client = crate::get_hsm_client();
+
+    // MockHsm audits most commands by default
+    client.get_pseudo_random(1).unwrap();
+    let state = client.get_audit_state().unwrap();
+    assert!(state.is_enabled());
+    assert_eq!(state.force(), AuditOption::Off);
+    assert!(!state.log_entries().entries.is_empty());
+
+    let audit_options = client.get_commands_audit_options().unwrap();
+
+    for audit_command in &audit_options {
+        client
+            .set_command_audit_option(audit_command.command_type(), AuditOption::Off)
+            .unwrap();
+    }
+
+    match client.get_audit_state().unwrap() {
+        AuditState::Disabled(log_entries) => {
+            assert_eq!(log_entries.num_entries as usize, log_entries.entries.len())
+        }
+        other => panic!("expected auditing to be disabled: {:?}", other),
+    }
+
+    // Forcing auditing enables it even if no commands are audited
+    client.set_force_audit_option(AuditOption::On).unwrap();
+    let state = client.get_audit_state().unwrap();
+    assert!(state.is_enabled());
+    assert_eq!(state.force(), AuditOption::On);
+    client.set_force_audit_option(AuditOption::Off).unwrap();
+
+    for audit_command in &audit_options {
+        client
+            .set_command_audit_option(audit_command.command_type(), audit_command.audit_option())
+            .unwrap();
+    }
+
+    assert!(client.get_audit_state().unwrap().is_enabled());
+}
+
+/// Drain the audit log, verifying its digest chain
+#[cfg(feature = "mockhsm")]
+#[test]
+fn drain_audit_log_test() {
+    use yubihsm::{audit::LogArchive, command};
+
+    let client = crate::get_hsm_client();
+    client.get_pseudo_random(32).unwrap();
+
+    let log_entries = client.get_log_entries().unwrap();
+    assert_eq!(log_entries.num_entries as usize, log_entries.entries.len());
+
+    let last_entry = log_entries.entries.last().unwrap().clone();
+    assert_eq!(last_entry.cmd, command::Code::GetPseudoRandom);
+    assert_eq!(
+        last_entry.session_key,
+        yubihsm::authentication::DEFAULT_AUTHENTICATION_KEY_ID
+    );
+
+    let mut archive = LogArchive::new();
+    archive.append(log_entries).unwrap();
+    let head = archive.verify(None).unwrap();
+
+    client.set_log_index(last_entry.item).unwrap();
+
+    // Only the `SetLogIndex` command remains, chained to the drained entries
+    let log_entries = client.get_log_entries().unwrap();
+    assert_eq!(log_entries.num_entries, 1);
+    assert_eq!(log_entries.entries[0].cmd, command::Code::SetLogIndex);
+
+    let mut archive = LogArchive::new();
+    archive.append(log_entries).unwrap();
+    archive.verify(Some(&head)).unwrap();
+}
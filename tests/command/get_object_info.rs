@@ -1,7 +1,10 @@
-use crate::DEFAULT_AUTHENTICATION_KEY_LABEL;
+use crate::{generate_asymmetric_key, DEFAULT_AUTHENTICATION_KEY_LABEL, TEST_KEY_ID};
+use std::collections::BTreeMap;
 use yubihsm::{
+    asymmetric,
     authentication::{self, DEFAULT_AUTHENTICATION_KEY_ID},
-    object, Capability, Domain,
+    client::diagnosis::{Check, Layer},
+    command, object, wrap, Capability, Domain,
 };
 
 /// Get object info on default auth key
@@ -30,3 +33,143 @@ fn default_authkey_test() {
         DEFAULT_AUTHENTICATION_KEY_LABEL
     );
 }
+
+/// Diff the default auth key against matching and drifting policies
+#[test]
+fn diff_objects_test() {
+    let client = crate::get_hsm_client();
+
+    let handle = object::Handle::new(
+        DEFAULT_AUTHENTICATION_KEY_ID,
+        object::Type::AuthenticationKey,
+    );
+
+    let mut policies = BTreeMap::new();
+    policies.insert(
+        handle.clone(),
+        object::Policy {
+            capabilities: Capability::all(),
+            domains: Domain::all(),
+        },
+    );
+
+    let diffs = client
+        .diff_objects(&policies)
+        .unwrap_or_else(|err| panic!("error diffing objects: {}", err));
+
+    assert!(diffs.is_empty());
+
+    let missing = object::Handle::new(0xfffe, object::Type::Opaque);
+
+    policies.insert(
+        handle.clone(),
+        object::Policy {
+            capabilities: Capability::all() - Capability::RESET_DEVICE,
+            domains: Domain::DOM1,
+        },
+    );
+    policies.insert(
+        missing.clone(),
+        object::Policy {
+            capabilities: Capability::empty(),
+            domains: Domain::DOM1,
+        },
+    );
+
+    let diffs = client
+        .diff_objects(&policies)
+        .unwrap_or_else(|err| panic!("error diffing objects: {}", err));
+
+    assert_eq!(diffs.len(), 2);
+
+    let diff = &diffs[&handle];
+    assert!(diff.present);
+    assert_eq!(diff.capabilities_added, Capability::RESET_DEVICE);
+    assert!(diff.capabilities_removed.is_empty());
+    assert_eq!(diff.domains_added, Domain::all() - Domain::DOM1);
+    assert!(diff.domains_removed.is_empty());
+
+    assert!(!diffs[&missing].present);
+}
+
+/// Diagnose signing with a key which only has some of the capabilities
+#[test]
+fn diagnose_operation_test() {
+    let client = crate::get_hsm_client();
+
+    generate_asymmetric_key(
+        &client,
+        asymmetric::Algorithm::Ed25519,
+        Capability::SIGN_EDDSA,
+    );
+
+    let diagnosis = client
+        .diagnose_operation(
+            command::Code::SignEddsa,
+            TEST_KEY_ID,
+            object::Type::AsymmetricKey,
+        )
+        .unwrap_or_else(|err| panic!("error diagnosing operation: {}", err));
+
+    assert!(diagnosis.is_permitted(), "{}", diagnosis);
+    assert_eq!(
+        diagnosis.authentication_key_id,
+        DEFAULT_AUTHENTICATION_KEY_ID
+    );
+
+    let diagnosis = client
+        .diagnose_operation(
+            command::Code::ExportWrapped,
+            TEST_KEY_ID,
+            object::Type::AsymmetricKey,
+        )
+        .unwrap_or_else(|err| panic!("error diagnosing operation: {}", err));
+
+    assert_eq!(
+        diagnosis.failures(),
+        vec![(
+            Layer::Object,
+            Check::MissingCapabilities(Capability::EXPORTABLE_UNDER_WRAP)
+        )]
+    );
+}
+
+/// Read back the delegated capabilities of a wrap key
+#[test]
+fn delegated_capabilities_test() {
+    let client = crate::get_hsm_client();
+    let delegated_capabilities = Capability::SIGN_EDDSA | Capability::EXPORTABLE_UNDER_WRAP;
+
+    crate::clear_test_key_slot(&client, object::Type::WrapKey);
+
+    client
+        .put_wrap_key(
+            TEST_KEY_ID,
+            crate::TEST_KEY_LABEL.into(),
+            crate::TEST_DOMAINS,
+            Capability::EXPORT_WRAPPED | Capability::IMPORT_WRAPPED,
+            delegated_capabilities,
+            wrap::Algorithm::Aes128Ccm,
+            [0x42; 16].to_vec(),
+        )
+        .unwrap_or_else(|err| panic!("error putting wrap key: {}", err));
+
+    let object_info = client
+        .get_object_info(TEST_KEY_ID, object::Type::WrapKey)
+        .unwrap_or_else(|err| panic!("error getting object info: {}", err));
+
+    assert_eq!(object_info.delegated_capabilities, delegated_capabilities);
+    assert_eq!(object_info.delegated(), Some(delegated_capabilities));
+
+    generate_asymmetric_key(
+        &client,
+        asymmetric::Algorithm::Ed25519,
+        Capability::SIGN_EDDSA,
+    );
+
+    let object_info = client
+        .get_object_info(TEST_KEY_ID, object::Type::AsymmetricKey)
+        .unwrap();
+
+    assert_eq!(object_info.delegated(), None);
+}
@@ -9,3 +9,15 @@ fn get_pseudo_random_test() {
 
     assert_eq!(32, bytes.len());
 }
+
+/// Run the statistical RNG tests
+#[test]
+fn rng_self_test() {
+    let client = crate::get_hsm_client();
+
+    let status = client
+        .rng_self_test()
+        .unwrap_or_else(|err| panic!("error running RNG self-test: {}", err));
+
+    assert!(status.long_run_passed());
+}
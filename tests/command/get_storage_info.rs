@@ -12,3 +12,40 @@ fn get_storage_info_test() {
     assert_eq!(response.total_pages, 1024);
     assert_eq!(response.page_size, 126);
 }
+
+/// Storing an object uses a record and enough pages for its data, which are
+/// freed again when it's deleted
+#[cfg(feature = "mockhsm")]
+#[test]
+fn storage_info_tracks_objects_test() {
+    use crate::{clear_test_key_slot, TEST_DOMAINS, TEST_KEY_ID, TEST_KEY_LABEL};
+    use yubihsm::{object, opaque, Capability};
+
+    let client = crate::get_hsm_client();
+    clear_test_key_slot(&client, object::Type::Opaque);
+
+    let before = client.get_storage_info().unwrap();
+
+    client
+        .put_opaque(
+            TEST_KEY_ID,
+            TEST_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            Capability::default(),
+            opaque::Algorithm::Data,
+            vec![0x42; 300],
+        )
+        .unwrap();
+
+    let after_put = client.get_storage_info().unwrap();
+    assert_eq!(after_put.free_records, before.free_records - 1);
+    assert_eq!(after_put.free_pages, before.free_pages - 3);
+
+    client
+        .delete_object(TEST_KEY_ID, object::Type::Opaque)
+        .unwrap();
+
+    let after_delete = client.get_storage_info().unwrap();
+    assert_eq!(after_delete.free_records, before.free_records);
+    assert_eq!(after_delete.free_pages, before.free_pages);
+}
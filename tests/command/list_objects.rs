@@ -1,5 +1,7 @@
-use crate::{generate_asymmetric_key, TEST_KEY_ID};
-use yubihsm::{asymmetric, object, Capability};
+use crate::{
+    clear_test_key_slot, generate_asymmetric_key, TEST_DOMAINS, TEST_KEY_ID, TEST_KEY_LABEL,
+};
+use yubihsm::{asymmetric, object, opaque, Capability};
 
 /// List the objects in the YubiHSM 2
 #[test]
@@ -36,3 +38,108 @@ fn list_objects_with_filter() {
         .iter()
         .all(|obj| obj.object_type == object::Type::AuthenticationKey));
 }
+
+/// List objects along with their full object info
+#[test]
+fn list_objects_detailed_test() {
+    let client = crate::get_hsm_client();
+
+    generate_asymmetric_key(
+        &client,
+        asymmetric::Algorithm::Ed25519,
+        Capability::SIGN_EDDSA,
+    );
+
+    let objects = client
+        .list_objects_detailed(&[object::Filter::Type(object::Type::AsymmetricKey)])
+        .unwrap_or_else(|err| panic!("error listing objects: {}", err));
+
+    let (_, info) = objects
+        .iter()
+        .find(|(entry, _)| entry.object_id == TEST_KEY_ID)
+        .expect("generated key not listed");
+
+    let info = info
+        .as_ref()
+        .unwrap_or_else(|err| panic!("error getting object info: {}", err));
+
+    assert_eq!(info.object_type, object::Type::AsymmetricKey);
+    assert_eq!(info.algorithm, asymmetric::Algorithm::Ed25519.into());
+    assert_eq!(info.capabilities, Capability::SIGN_EDDSA);
+}
+
+/// Report object IDs which are shared across object types
+#[test]
+fn list_shared_object_ids_test() {
+    let client = crate::get_hsm_client();
+
+    generate_asymmetric_key(
+        &client,
+        asymmetric::Algorithm::Ed25519,
+        Capability::SIGN_EDDSA,
+    );
+
+    clear_test_key_slot(&client, object::Type::Opaque);
+
+    client
+        .put_opaque(
+            TEST_KEY_ID,
+            TEST_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            Capability::default(),
+            opaque::Algorithm::Data,
+            b"shares an ID with an asymmetric key".to_vec(),
+        )
+        .unwrap_or_else(|err| panic!("error putting opaque object: {}", err));
+
+    let shared_ids = client
+        .list_shared_object_ids()
+        .unwrap_or_else(|err| panic!("error listing shared object IDs: {}", err));
+
+    let types = &shared_ids[&TEST_KEY_ID];
+    assert!(types.contains(&object::Type::AsymmetricKey));
+    assert!(types.contains(&object::Type::Opaque));
+    assert!(shared_ids.values().all(|types| types.len() > 1));
+
+    client
+        .delete_object(TEST_KEY_ID, object::Type::Opaque)
+        .unwrap();
+}
+
+/// Detect objects deleted and added since a snapshot was taken
+#[test]
+fn list_changed_objects_test() {
+    let client = crate::get_hsm_client();
+    let handle = object::Handle::new(TEST_KEY_ID, object::Type::AsymmetricKey);
+
+    generate_asymmetric_key(
+        &client,
+        asymmetric::Algorithm::Ed25519,
+        Capability::SIGN_EDDSA,
+    );
+
+    let snapshot = object::Changeset::snapshot(&client.list_objects(&[]).unwrap());
+    assert!(client.list_changed_objects(&snapshot).unwrap().is_empty());
+
+    clear_test_key_slot(&client, object::Type::AsymmetricKey);
+
+    let changeset = client
+        .list_changed_objects(&snapshot)
+        .unwrap_or_else(|err| panic!("error listing changed objects: {}", err));
+
+    assert_eq!(changeset.deleted, vec![handle.clone()]);
+    assert!(changeset.added.is_empty());
+
+    let snapshot = object::Changeset::snapshot(&client.list_objects(&[]).unwrap());
+
+    generate_asymmetric_key(
+        &client,
+        asymmetric::Algorithm::Ed25519,
+        Capability::SIGN_EDDSA,
+    );
+
+    let changeset = client.list_changed_objects(&snapshot).unwrap();
+    assert_eq!(changeset.added.len(), 1);
+    assert_eq!(changeset.added[0].object_id, handle.object_id);
+    assert!(changeset.deleted.is_empty());
+}
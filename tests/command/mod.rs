@@ -1,8 +1,20 @@
 //! Integration tests for YubiHSM 2 commands
 
+pub mod attest_all;
 pub mod blink_device;
+pub mod change_authentication_key;
+#[cfg(feature = "untested")]
+pub mod create_otp_aead;
+#[cfg(feature = "untested")]
+pub mod decrypt_oaep;
+#[cfg(feature = "untested")]
+pub mod decrypt_pkcs1;
 pub mod delete_object;
+#[cfg(feature = "untested")]
+pub mod derive_ecdh;
 pub mod device_info;
+pub mod echo;
+pub mod export_public_keys;
 pub mod export_wrapped;
 pub mod generate_asymmetric_key;
 pub mod generate_hmac_key;
@@ -16,12 +28,18 @@ pub mod list_objects;
 pub mod put_asymmetric_key;
 pub mod put_authentication_key;
 pub mod put_opaque;
+pub mod put_template;
 #[cfg(feature = "mockhsm")]
 pub mod reset_device;
 pub mod set_option;
 #[cfg(not(feature = "mockhsm"))]
 pub mod sign_attestation_certificate;
-#[cfg(not(feature = "mockhsm"))]
 pub mod sign_ecdsa;
 pub mod sign_eddsa;
+#[cfg(all(feature = "rsa", feature = "untested"))]
+pub mod sign_rsa_pkcs1;
+#[cfg(all(feature = "rsa", feature = "untested"))]
+pub mod sign_rsa_pss;
+#[cfg(all(feature = "untested", not(feature = "mockhsm")))]
+pub mod sign_ssh_certificate;
 pub mod verify_hmac;
@@ -1,7 +1,7 @@
-use yubihsm::{asymmetric, object, Capability};
+use yubihsm::{asymmetric, client, device, object, Capability};
 
 use crate::test_vectors::ED25519_TEST_VECTORS;
-use crate::{put_asymmetric_key, TEST_DOMAINS, TEST_KEY_ID, TEST_KEY_LABEL};
+use crate::{clear_test_key_slot, put_asymmetric_key, TEST_DOMAINS, TEST_KEY_ID, TEST_KEY_LABEL};
 
 /// Put an Ed25519 key
 #[test]
@@ -25,3 +25,67 @@ fn ed25519_key_test() {
     assert_eq!(object_info.origin, object::Origin::Imported);
     assert_eq!(&object_info.label.to_string(), TEST_KEY_LABEL);
 }
+
+/// Put an Ed25519 key and verify the public key the HSM derived from it
+#[test]
+fn ed25519_key_verified_test() {
+    let client = crate::get_hsm_client();
+    let algorithm = asymmetric::Algorithm::Ed25519;
+    let test_vector = &ED25519_TEST_VECTORS[0];
+
+    clear_test_key_slot(&client, object::Type::AsymmetricKey);
+
+    let expected_public_key = asymmetric::PublicKey {
+        algorithm,
+        bytes: test_vector.pk.to_vec(),
+    };
+
+    let key_id = client
+        .put_asymmetric_key_verified(
+            TEST_KEY_ID,
+            TEST_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            Capability::SIGN_EDDSA,
+            algorithm,
+            test_vector.sk,
+            &expected_public_key,
+        )
+        .unwrap_or_else(|err| panic!("error putting asymmetric key: {}", err));
+
+    assert_eq!(key_id, TEST_KEY_ID);
+}
+
+/// Putting a key whose public key doesn't match the expected one fails and
+/// removes the imported key
+#[test]
+fn ed25519_key_verified_mismatch_test() {
+    let client = crate::get_hsm_client();
+    let algorithm = asymmetric::Algorithm::Ed25519;
+
+    clear_test_key_slot(&client, object::Type::AsymmetricKey);
+
+    let wrong_public_key = asymmetric::PublicKey {
+        algorithm,
+        bytes: ED25519_TEST_VECTORS[1].pk.to_vec(),
+    };
+
+    let err = client
+        .put_asymmetric_key_verified(
+            TEST_KEY_ID,
+            TEST_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            Capability::SIGN_EDDSA,
+            algorithm,
+            ED25519_TEST_VECTORS[0].sk,
+            &wrong_public_key,
+        )
+        .unwrap_err();
+
+    assert_eq!(*err.kind(), client::ErrorKind::VerifyFailed);
+
+    let err = client
+        .get_object_info(TEST_KEY_ID, object::Type::AsymmetricKey)
+        .unwrap_err();
+
+    assert_eq!(err.device_error(), Some(device::ErrorKind::ObjectNotFound));
+}
@@ -28,3 +28,72 @@ fn opaque_object_test() {
 
     assert_eq!(opaque_data, TEST_MESSAGE);
 }
+
+/// Put an attestation certificate template and read it back
+#[test]
+fn attestation_template_test() {
+    let client = crate::get_hsm_client();
+
+    clear_test_key_slot(&client, object::Type::Opaque);
+
+    client
+        .put_attestation_template(
+            TEST_KEY_ID,
+            TEST_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            Capability::default(),
+            TEST_MESSAGE,
+        )
+        .unwrap_or_else(|err| panic!("error putting attestation template: {}", err));
+
+    let template = client
+        .get_attestation_template(TEST_KEY_ID)
+        .unwrap_or_else(|err| panic!("error getting attestation template: {}", err));
+
+    assert_eq!(template, TEST_MESSAGE);
+}
+
+/// Opaque objects which aren't X.509 certificates can't be used as templates
+#[test]
+fn attestation_template_wrong_type_test() {
+    let client = crate::get_hsm_client();
+
+    clear_test_key_slot(&client, object::Type::Opaque);
+
+    client
+        .put_opaque(
+            TEST_KEY_ID,
+            TEST_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            Capability::default(),
+            opaque::Algorithm::Data,
+            TEST_MESSAGE,
+        )
+        .unwrap_or_else(|err| panic!("error putting opaque object: {}", err));
+
+    let err = client.get_attestation_template(TEST_KEY_ID).unwrap_err();
+    assert_eq!(*err.kind(), yubihsm::client::ErrorKind::ProtocolError);
+}
+
+/// Malformed certificates are rejected before being stored
+#[test]
+fn malformed_certificate_test() {
+    let client = crate::get_hsm_client();
+
+    clear_test_key_slot(&client, object::Type::Opaque);
+
+    let err = client
+        .put_certificate(
+            TEST_KEY_ID,
+            TEST_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            Capability::default(),
+            TEST_MESSAGE,
+        )
+        .unwrap_err();
+
+    assert_eq!(*err.kind(), yubihsm::client::ErrorKind::ProtocolError);
+    assert!(client
+        .get_object_info(TEST_KEY_ID, object::Type::Opaque)
+        .is_err());
+}
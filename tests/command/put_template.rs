@@ -0,0 +1,36 @@
+use yubihsm::{object, ssh, template, Capability};
+
+use crate::{clear_test_key_slot, TEST_DOMAINS, TEST_KEY_ID, TEST_KEY_LABEL, TEST_MESSAGE};
+
+/// Put an SSH certificate template and read it back
+#[test]
+fn template_test() {
+    let client = crate::get_hsm_client();
+
+    clear_test_key_slot(&client, object::Type::Template);
+
+    let object_id = client
+        .put_template(
+            TEST_KEY_ID,
+            TEST_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            Capability::default(),
+            ssh::Template::from_bytes(TEST_MESSAGE),
+        )
+        .unwrap_or_else(|err| panic!("error putting template: {}", err));
+
+    assert_eq!(object_id, TEST_KEY_ID);
+
+    let object_info = client
+        .get_object_info(TEST_KEY_ID, object::Type::Template)
+        .unwrap_or_else(|err| panic!("error getting object info: {}", err));
+
+    assert_eq!(object_info.algorithm, template::Algorithm::Ssh.into());
+    assert_eq!(object_info.length as usize, TEST_MESSAGE.len());
+
+    let template_data = client
+        .get_template(TEST_KEY_ID)
+        .unwrap_or_else(|err| panic!("error getting template: {}", err));
+
+    assert_eq!(template_data, TEST_MESSAGE);
+}
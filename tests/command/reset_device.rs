@@ -1,6 +1,36 @@
+use yubihsm::{authentication::DEFAULT_AUTHENTICATION_KEY_ID, object};
+
 /// Reset the YubiHSM 2 to a factory default state
 #[test]
 fn reset_test() {
     let client = crate::get_hsm_client();
     client.reset_device().unwrap();
 }
+
+/// Reset the YubiHSM 2 using a confirmation token
+#[test]
+fn confirm_reset_test() {
+    let client = crate::get_hsm_client();
+    let token = client.prepare_reset().unwrap();
+    client.confirm_reset(token).unwrap();
+}
+
+/// Report what a reset would delete without resetting the HSM
+#[test]
+fn reset_impact_test() {
+    let client = crate::get_hsm_client();
+    let impact = client.reset_impact().unwrap();
+
+    assert!(impact.object_counts[&object::Type::AuthenticationKey] >= 1);
+    assert!(impact
+        .authentication_keys
+        .iter()
+        .any(|key| key.object_id == DEFAULT_AUTHENTICATION_KEY_ID));
+    assert!(impact.warning().contains("authentication key 0x0001"));
+
+    // Nothing was deleted
+    assert_eq!(
+        client.reset_impact().unwrap().total_objects(),
+        impact.total_objects()
+    );
+}
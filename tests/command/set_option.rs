@@ -1,4 +1,4 @@
-use yubihsm::{command, AuditOption};
+use yubihsm::{asymmetric, command, hmac, Algorithm, AuditOption};
 
 /// Set the auditing options for a particular command
 #[test]
@@ -50,3 +50,26 @@ fn force_audit_option_test() {
         assert_eq!(hsm_option, *audit_option);
     }
 }
+
+/// Disable and re-enable an algorithm with the algorithm toggle
+#[test]
+fn algorithm_toggle_test() {
+    let client = crate::get_hsm_client();
+    let ed25519 = Algorithm::Asymmetric(asymmetric::Algorithm::Ed25519);
+
+    assert!(client.get_algorithm_toggle().unwrap().contains(&ed25519));
+
+    client
+        .put_algorithm_toggle(ed25519, false)
+        .unwrap_or_else(|err| panic!("error disabling {:?}: {}", ed25519, err));
+
+    let enabled = client.get_algorithm_toggle().unwrap();
+    assert!(!enabled.contains(&ed25519));
+    assert!(enabled.contains(&Algorithm::Hmac(hmac::Algorithm::Sha256)));
+
+    client
+        .put_algorithm_toggle(ed25519, true)
+        .unwrap_or_else(|err| panic!("error enabling {:?}: {}", ed25519, err));
+
+    assert!(client.get_algorithm_toggle().unwrap().contains(&ed25519));
+}
@@ -1,4 +1,7 @@
 //! ECDSA signing test
+//!
+//! The MockHsm only supports ECDSA with NIST P-256 (and secp256k1) keys, so
+//! tests of other curves only run against a real HSM.
 
 use crate::{generate_asymmetric_key, TEST_KEY_ID, TEST_MESSAGE};
 use p256::{
@@ -6,7 +9,7 @@ use p256::{
     NistP256,
 };
 use sha2::{Digest, Sha256};
-use yubihsm::{asymmetric, Capability};
+use yubihsm::{asymmetric, client, Capability};
 
 /// Test ECDSA signatures (using NIST P-256)
 #[test]
@@ -39,3 +42,126 @@ fn generated_nistp256_key_test() {
     let verify_key = VerifyKey::from_encoded_point(&public_key).unwrap();
     assert!(verify_key.verify(TEST_MESSAGE, &signature).is_ok());
 }
+
+/// Test ECDSA signatures of SHA-384 digests (using NIST P-384)
+#[cfg(not(feature = "mockhsm"))]
+#[test]
+fn nistp384_sha384_test() {
+    let client = crate::get_hsm_client();
+
+    generate_asymmetric_key(
+        &client,
+        asymmetric::Algorithm::EcP384,
+        Capability::SIGN_ECDSA,
+    );
+
+    let signature = client
+        .sign_ecdsa_sha384(TEST_KEY_ID, TEST_MESSAGE)
+        .unwrap_or_else(|err| panic!("error performing ECDSA signature: {}", err));
+
+    assert!(yubihsm::ecdsa::Signature::<yubihsm::ecdsa::NistP384>::from_asn1(&signature).is_ok());
+}
+
+/// Test ECDSA signatures of SHA-512 digests (using NIST P-521)
+#[cfg(not(feature = "mockhsm"))]
+#[test]
+fn nistp521_sha512_test() {
+    let client = crate::get_hsm_client();
+
+    generate_asymmetric_key(
+        &client,
+        asymmetric::Algorithm::EcP521,
+        Capability::SIGN_ECDSA,
+    );
+
+    let signature = client
+        .sign_ecdsa_sha512(TEST_KEY_ID, TEST_MESSAGE)
+        .unwrap_or_else(|err| panic!("error performing ECDSA signature: {}", err));
+
+    // ASN.1 DER SEQUENCE
+    assert_eq!(signature[0], 0x30);
+}
+
+/// SHA-384 digests can't be signed with a NIST P-256 key
+#[test]
+fn nistp256_sha384_mismatch_test() {
+    let client = crate::get_hsm_client();
+
+    generate_asymmetric_key(
+        &client,
+        asymmetric::Algorithm::EcP256,
+        Capability::SIGN_ECDSA,
+    );
+
+    let err = client
+        .sign_ecdsa_sha384(TEST_KEY_ID, TEST_MESSAGE)
+        .unwrap_err();
+
+    assert_eq!(*err.kind(), client::ErrorKind::ProtocolError);
+}
+
+/// Raw digests must match the length expected for the key's curve
+#[test]
+fn prehash_digest_length_mismatch_test() {
+    let client = crate::get_hsm_client();
+
+    let mut curves = vec![(asymmetric::Algorithm::EcP256, 32)];
+
+    if cfg!(not(feature = "mockhsm")) {
+        curves.push((asymmetric::Algorithm::EcP384, 48));
+        curves.push((asymmetric::Algorithm::EcP521, 64));
+    }
+
+    for &(algorithm, expected_len) in &curves {
+        generate_asymmetric_key(&client, algorithm, Capability::SIGN_ECDSA);
+
+        for &digest_len in &[20, 32, 48, 64] {
+            let result = client.sign_ecdsa_prehash_raw(TEST_KEY_ID, vec![0x42; digest_len]);
+
+            if digest_len == expected_len {
+                assert!(result.is_ok(), "{:?} {}", algorithm, digest_len);
+                continue;
+            }
+
+            let err = result.unwrap_err();
+            assert_eq!(*err.kind(), client::ErrorKind::ProtocolError);
+            assert!(
+                err.to_string().contains(&format!(
+                    "expected {}-byte digest for Asymmetric({:?}), got {}",
+                    expected_len, algorithm, digest_len
+                )),
+                "{}",
+                err
+            );
+        }
+    }
+}
+
+/// Digests are checked against the key's (cached) algorithm before being
+/// sent to the HSM
+#[test]
+fn digest_length_is_checked_against_cached_key_info() {
+    let client = crate::get_hsm_client();
+    let capabilities = Capability::SIGN_ECDSA | Capability::SIGN_EDDSA;
+
+    generate_asymmetric_key(&client, asymmetric::Algorithm::EcP256, capabilities);
+    client
+        .sign_ecdsa_prehash_raw(TEST_KEY_ID, vec![0x42; 32])
+        .unwrap();
+
+    let err = client
+        .sign_ecdsa_prehash_raw(TEST_KEY_ID, vec![0x42; 48])
+        .unwrap_err();
+    assert_eq!(*err.kind(), client::ErrorKind::ProtocolError);
+    assert!(err
+        .to_string()
+        .contains("expected 32-byte digest for Asymmetric(EcP256), got 48"));
+
+    // Replacing the key invalidates the cached algorithm
+    generate_asymmetric_key(&client, asymmetric::Algorithm::Ed25519, capabilities);
+
+    let err = client
+        .sign_ecdsa_prehash_raw(TEST_KEY_ID, vec![0x42; 32])
+        .unwrap_err();
+    assert!(err.to_string().contains("is not an ECDSA key"));
+}
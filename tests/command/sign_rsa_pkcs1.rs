@@ -0,0 +1,87 @@
+//! RSASSA-PKCS#1v1.5 signing test
+
+use crate::{generate_asymmetric_key, put_asymmetric_key, TEST_KEY_ID, TEST_MESSAGE};
+use rand_core::OsRng;
+use rsa::{BigUint, Hash, PaddingScheme, PublicKey, PublicKeyParts, RSAPrivateKey, RSAPublicKey};
+use sha2::{Digest, Sha256};
+use yubihsm::{asymmetric, Capability};
+
+/// Test RSASSA-PKCS#1v1.5 signatures of SHA-256 digests (using RSA-2048)
+#[test]
+fn rsa2048_sha256_test() {
+    let client = crate::get_hsm_client();
+
+    generate_asymmetric_key(
+        &client,
+        asymmetric::Algorithm::Rsa2048,
+        Capability::SIGN_PKCS,
+    );
+
+    let public_key = client
+        .get_public_key(TEST_KEY_ID)
+        .unwrap_or_else(|err| panic!("error getting public key: {}", err));
+
+    assert_eq!(public_key.algorithm, asymmetric::Algorithm::Rsa2048);
+    assert_eq!(public_key.bytes.len(), 256);
+
+    let signature = client
+        .sign_rsa_pkcs1v15_sha256(TEST_KEY_ID, TEST_MESSAGE)
+        .unwrap_or_else(|err| panic!("error performing RSASSA-PKCS#1v1.5 signature: {}", err));
+
+    let verify_key = RSAPublicKey::new(
+        BigUint::from_bytes_be(public_key.as_ref()),
+        BigUint::from(65537u32),
+    )
+    .unwrap();
+
+    assert!(verify_key
+        .verify(
+            PaddingScheme::new_pkcs1v15_sign(Some(Hash::SHA2_256)),
+            Sha256::digest(TEST_MESSAGE).as_slice(),
+            signature.as_ref(),
+        )
+        .is_ok());
+}
+
+/// Test RSASSA-PKCS#1v1.5 signatures with an RSA-2048 key put into the HSM
+/// as its primes `p` and `q`
+#[test]
+fn put_rsa2048_key_test() {
+    let client = crate::get_hsm_client();
+    let private_key = RSAPrivateKey::new(&mut OsRng, 2048).unwrap();
+
+    let primes: Vec<u8> = private_key
+        .primes()
+        .iter()
+        .flat_map(|p| p.to_bytes_be())
+        .collect();
+
+    put_asymmetric_key(
+        &client,
+        asymmetric::Algorithm::Rsa2048,
+        Capability::SIGN_PKCS,
+        primes,
+    );
+
+    let public_key = client
+        .get_public_key(TEST_KEY_ID)
+        .unwrap_or_else(|err| panic!("error getting public key: {}", err));
+
+    assert_eq!(
+        public_key.as_ref(),
+        private_key.n().to_bytes_be().as_slice()
+    );
+
+    let signature = client
+        .sign_rsa_pkcs1v15_sha256(TEST_KEY_ID, TEST_MESSAGE)
+        .unwrap_or_else(|err| panic!("error performing RSASSA-PKCS#1v1.5 signature: {}", err));
+
+    assert!(private_key
+        .to_public_key()
+        .verify(
+            PaddingScheme::new_pkcs1v15_sign(Some(Hash::SHA2_256)),
+            Sha256::digest(TEST_MESSAGE).as_slice(),
+            signature.as_ref(),
+        )
+        .is_ok());
+}
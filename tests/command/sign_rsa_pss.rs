@@ -0,0 +1,43 @@
+//! RSASSA-PSS signing test
+
+use crate::{generate_asymmetric_key, TEST_KEY_ID, TEST_MESSAGE};
+use rand_core::OsRng;
+use rsa::{BigUint, PaddingScheme, PublicKey, RSAPublicKey};
+use sha2::{Digest, Sha256};
+use yubihsm::{asymmetric, Capability};
+
+/// Test RSASSA-PSS signatures of SHA-256 digests (using RSA-2048)
+#[test]
+fn rsa2048_sha256_test() {
+    let client = crate::get_hsm_client();
+
+    generate_asymmetric_key(
+        &client,
+        asymmetric::Algorithm::Rsa2048,
+        Capability::SIGN_PSS,
+    );
+
+    let public_key = client
+        .get_public_key(TEST_KEY_ID)
+        .unwrap_or_else(|err| panic!("error getting public key: {}", err));
+
+    assert_eq!(public_key.algorithm, asymmetric::Algorithm::Rsa2048);
+
+    let signature = client
+        .sign_rsa_pss_sha256(TEST_KEY_ID, TEST_MESSAGE)
+        .unwrap_or_else(|err| panic!("error performing RSASSA-PSS signature: {}", err));
+
+    let verify_key = RSAPublicKey::new(
+        BigUint::from_bytes_be(public_key.as_ref()),
+        BigUint::from(65537u32),
+    )
+    .unwrap();
+
+    assert!(verify_key
+        .verify(
+            PaddingScheme::new_pss::<Sha256, _>(OsRng),
+            Sha256::digest(TEST_MESSAGE).as_slice(),
+            signature.as_ref(),
+        )
+        .is_ok());
+}
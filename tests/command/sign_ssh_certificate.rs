@@ -0,0 +1,80 @@
+//! SSH certificate signing test
+//!
+//! The MockHsm doesn't support SSH certificates, and signing one requires a
+//! template and a request signed by the template's timestamp key, so this
+//! test needs a real HSM and inputs prepared out of band (e.g. with
+//! `yubihsm-shell` and Yubico's SSH CA tooling). The `YUBIHSM_SSH_TEST_DIR`
+//! environment variable must name a directory containing:
+//!
+//! - `template.bin`: SSH certificate template whitelisting `TEST_KEY_ID`
+//! - `request.bin`: certificate request to sign
+//! - `timestamp.txt`: timestamp of the request (as a decimal integer)
+//! - `signature.bin`: 32-byte signature over the request and timestamp
+
+use crate::{
+    clear_test_key_slot, generate_asymmetric_key, TEST_DOMAINS, TEST_KEY_ID, TEST_KEY_LABEL,
+};
+use std::{convert::TryInto, env, fs, path::PathBuf};
+use yubihsm::{asymmetric, ecdsa, object, ssh, Capability};
+
+/// Environment variable naming the directory containing test inputs
+const TEST_DIR_VAR: &str = "YUBIHSM_SSH_TEST_DIR";
+
+/// Read one of the test inputs
+fn read_input(name: &str) -> Vec<u8> {
+    let dir = env::var(TEST_DIR_VAR).unwrap_or_else(|_| panic!("{} not set", TEST_DIR_VAR));
+    let path = PathBuf::from(dir).join(name);
+    fs::read(&path).unwrap_or_else(|err| panic!("error reading {}: {}", path.display(), err))
+}
+
+/// Sign an SSH certificate request with a template stored on the HSM
+#[test]
+#[ignore]
+fn sign_ssh_certificate_test() {
+    let client = crate::get_hsm_client();
+
+    let template = read_input("template.bin");
+    let request = read_input("request.bin");
+    let timestamp = String::from_utf8(read_input("timestamp.txt"))
+        .unwrap()
+        .trim()
+        .parse()
+        .unwrap_or_else(|err| panic!("invalid timestamp: {}", err));
+    let signature: [u8; 32] = read_input("signature.bin")
+        .as_slice()
+        .try_into()
+        .unwrap_or_else(|_| panic!("signature.bin must be 32 bytes"));
+
+    generate_asymmetric_key(
+        &client,
+        asymmetric::Algorithm::EcP256,
+        Capability::SIGN_SSH_CERTIFICATE,
+    );
+
+    clear_test_key_slot(&client, object::Type::Template);
+
+    client
+        .put_template(
+            TEST_KEY_ID,
+            TEST_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            Capability::SIGN_SSH_CERTIFICATE,
+            ssh::Template::from_bytes(template.clone()),
+        )
+        .unwrap_or_else(|err| panic!("error putting template: {}", err));
+
+    assert_eq!(client.get_template(TEST_KEY_ID).unwrap(), template);
+
+    let certificate = client
+        .sign_ssh_certificate(
+            TEST_KEY_ID,
+            TEST_KEY_ID,
+            ecdsa::Algorithm::Sha256,
+            timestamp,
+            signature,
+            request.clone(),
+        )
+        .unwrap_or_else(|err| panic!("error signing SSH certificate: {}", err));
+
+    assert!(certificate.as_slice().len() > request.len());
+}
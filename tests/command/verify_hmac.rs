@@ -2,7 +2,7 @@ use crate::{
     clear_test_key_slot, object, test_vectors::HMAC_SHA256_TEST_VECTORS, TEST_DOMAINS, TEST_KEY_ID,
     TEST_KEY_LABEL,
 };
-use yubihsm::{hmac, Capability};
+use yubihsm::{client, hmac, Capability};
 
 /// Test HMAC against RFC 4231 test vectors
 #[test]
@@ -45,3 +45,85 @@ fn hmac_test_vectors() {
             .is_err());
     }
 }
+
+/// Tags of the wrong length are reported differently from mismatched tags
+#[test]
+fn hmac_tag_length_test() {
+    let client = crate::get_hsm_client();
+    let vector = &HMAC_SHA256_TEST_VECTORS[0];
+
+    clear_test_key_slot(&client, object::Type::HmacKey);
+
+    client
+        .put_hmac_key(
+            TEST_KEY_ID,
+            TEST_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            Capability::VERIFY_HMAC,
+            hmac::Algorithm::Sha256,
+            vector.key,
+        )
+        .unwrap_or_else(|err| panic!("error putting HMAC key: {}", err));
+
+    let truncated_tag = &vector.tag[..16];
+    let err = client
+        .verify_hmac(TEST_KEY_ID, vector.msg, truncated_tag)
+        .unwrap_err();
+
+    assert_eq!(*err.kind(), client::ErrorKind::ProtocolError);
+    assert!(
+        err.to_string().contains("expected 32 bytes, got 16"),
+        "{}",
+        err
+    );
+
+    let mut bad_tag = Vec::from(vector.tag);
+    bad_tag[31] ^= 1;
+
+    let err = client
+        .verify_hmac(TEST_KEY_ID, vector.msg, bad_tag)
+        .unwrap_err();
+
+    assert_eq!(*err.kind(), client::ErrorKind::ResponseError);
+}
+
+/// Compute and verify tags with a signer bound to the key
+#[test]
+fn hmac_signer_test() {
+    use yubihsm::asymmetric::signature::{Signer as _, Verifier};
+
+    let client = crate::get_hsm_client();
+    let vector = &HMAC_SHA256_TEST_VECTORS[0];
+
+    clear_test_key_slot(&client, object::Type::HmacKey);
+
+    client
+        .put_hmac_key(
+            TEST_KEY_ID,
+            TEST_KEY_LABEL.into(),
+            TEST_DOMAINS,
+            Capability::SIGN_HMAC | Capability::VERIFY_HMAC,
+            hmac::Algorithm::Sha256,
+            vector.key,
+        )
+        .unwrap_or_else(|err| panic!("error putting HMAC key: {}", err));
+
+    let signer = client
+        .hmac_signer(TEST_KEY_ID)
+        .unwrap_or_else(|err| panic!("error creating HMAC signer: {}", err));
+
+    assert_eq!(signer.algorithm(), hmac::Algorithm::Sha256);
+
+    let tag = signer.sign(vector.msg).unwrap();
+    assert_eq!(tag.as_slice(), vector.tag);
+    assert!(signer.verify(vector.msg, &tag).is_ok());
+
+    let truncated_tag = hmac::Tag::from(&vector.tag[..16]);
+    let err = signer.verify(vector.msg, &truncated_tag).unwrap_err();
+    assert_eq!(*err.kind(), client::ErrorKind::ProtocolError);
+
+    // `signature` crate traits
+    let tag: hmac::Tag = signer.try_sign(vector.msg).unwrap();
+    assert!(Verifier::verify(&signer, vector.msg, &tag).is_ok());
+    assert!(Verifier::verify(&signer, vector.msg, &truncated_tag).is_err());
+}
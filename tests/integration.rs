@@ -4,6 +4,9 @@ use lazy_static::lazy_static;
 use std::sync::{Mutex, MutexGuard};
 use yubihsm::{asymmetric, device, object, Capability, Client, Connector, Domain};
 
+/// Integration tests for client-side behaviour
+mod client;
+
 /// Integration tests for individual YubiHSM 2 commands
 mod command;
 